@@ -1,71 +1,346 @@
 use crate::error::{OktofetchError, Result};
 use std::fs::File;
+use std::io::Read;
 use std::path::Path;
 
-pub fn extract_archive(archive_path: &Path, dest_dir: &Path) -> Result<Vec<String>> {
-    let file_name = archive_path
-        .file_name()
-        .and_then(|n| n.to_str())
-        .ok_or_else(|| OktofetchError::ExtractionFailed("Invalid archive name".to_string()))?;
+/// Default cap on the total number of bytes written during extraction (1 GiB).
+pub const DEFAULT_MAX_EXTRACT_SIZE: u64 = 1024 * 1024 * 1024;
+/// Default cap on the size of a single extracted file (512 MiB).
+pub const DEFAULT_MAX_EXTRACT_FILE_SIZE: u64 = 512 * 1024 * 1024;
+/// Default cap on the number of entries extracted from one archive.
+pub const DEFAULT_MAX_EXTRACT_FILES: usize = 10_000;
+
+/// Limits enforced while extracting an archive, guarding against zip bombs
+/// and archives that would fill the disk or exhaust inodes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExtractLimits {
+    pub max_total_size: u64,
+    pub max_file_size: u64,
+    pub max_files: usize,
+}
 
-    if file_name.ends_with(".tar.gz") || file_name.ends_with(".tgz") {
-        extract_tar_gz(archive_path, dest_dir)
-    } else if file_name.ends_with(".tar.bz2") || file_name.ends_with(".tbz") {
-        extract_tar_bz2(archive_path, dest_dir)
-    } else if file_name.ends_with(".zip") {
-        extract_zip(archive_path, dest_dir)
-    } else {
-        // Not a recognized archive format, check if it's a standalone binary
-        handle_standalone_binary(archive_path, dest_dir, file_name)
+impl Default for ExtractLimits {
+    fn default() -> Self {
+        Self {
+            max_total_size: DEFAULT_MAX_EXTRACT_SIZE,
+            max_file_size: DEFAULT_MAX_EXTRACT_FILE_SIZE,
+            max_files: DEFAULT_MAX_EXTRACT_FILES,
+        }
     }
 }
 
-fn extract_tar_gz(archive_path: &Path, dest_dir: &Path) -> Result<Vec<String>> {
-    use flate2::read::GzDecoder;
-    use tar::Archive;
-
-    let file = File::open(archive_path)?;
-    let gz = GzDecoder::new(file);
-    let mut archive = Archive::new(gz);
+/// Running totals for a single extraction, checked against `ExtractLimits`.
+struct ExtractBudget<'a> {
+    limits: &'a ExtractLimits,
+    total_size: u64,
+    files: usize,
+}
 
-    let mut extracted_files = Vec::new();
+impl<'a> ExtractBudget<'a> {
+    fn new(limits: &'a ExtractLimits) -> Self {
+        Self {
+            limits,
+            total_size: 0,
+            files: 0,
+        }
+    }
 
-    for entry in archive.entries()? {
-        let mut entry = entry?;
-        let path = entry.path()?.to_path_buf();
+    fn add_entry(&mut self, name: &str) -> Result<()> {
+        self.files += 1;
+        if self.files > self.limits.max_files {
+            return Err(OktofetchError::ExtractionFailed(format!(
+                "Archive contains more than {} entries (at {})",
+                self.limits.max_files, name
+            )));
+        }
+        Ok(())
+    }
 
-        // Security: prevent path traversal
-        if path
-            .components()
-            .any(|c| matches!(c, std::path::Component::ParentDir))
-        {
-            continue;
+    fn add_size(&mut self, name: &str, size: u64) -> Result<()> {
+        if size > self.limits.max_file_size {
+            return Err(OktofetchError::ExtractionFailed(format!(
+                "File {} exceeds the per-file size limit of {} bytes",
+                name, self.limits.max_file_size
+            )));
+        }
+        self.total_size = self.total_size.saturating_add(size);
+        if self.total_size > self.limits.max_total_size {
+            return Err(OktofetchError::ExtractionFailed(format!(
+                "Archive exceeds the total extraction size limit of {} bytes",
+                self.limits.max_total_size
+            )));
         }
+        Ok(())
+    }
 
-        let dest_path = dest_dir.join(&path);
+    /// Bytes that may still be written for a single file before a limit trips.
+    fn remaining_for_file(&self) -> u64 {
+        self.limits
+            .max_file_size
+            .min(self.limits.max_total_size - self.total_size)
+    }
+}
 
-        // Create parent directories if needed
-        if let Some(parent) = dest_path.parent() {
-            std::fs::create_dir_all(parent)?;
+/// Container and compression formats recognised in downloaded assets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Gzip,
+    Bzip2,
+    Xz,
+    Zstd,
+    Zip,
+    Tar,
+    Elf,
+}
+
+/// Bytes needed to see the `ustar` marker of a tar header.
+const SNIFF_LEN: usize = 262;
+
+/// Extensions stripped from a compressed single-file asset to name the binary.
+const COMPRESSED_SUFFIXES: &[&str] = &[".gz", ".bz2", ".xz", ".zst"];
+
+impl Format {
+    /// Identifies a file by its leading magic bytes.
+    fn sniff(header: &[u8]) -> Option<Self> {
+        const MAGIC: &[(&[u8], Format)] = &[
+            (&[0x1F, 0x8B], Format::Gzip),
+            (b"BZh", Format::Bzip2),
+            (&[0xFD, b'7', b'z', b'X', b'Z', 0x00], Format::Xz),
+            (&[0x28, 0xB5, 0x2F, 0xFD], Format::Zstd),
+            (b"PK\x03\x04", Format::Zip),
+            (b"PK\x05\x06", Format::Zip),
+            (&[0x7F, b'E', b'L', b'F'], Format::Elf),
+        ];
+
+        MAGIC
+            .iter()
+            .find(|(magic, _)| header.starts_with(magic))
+            .map(|&(_, format)| format)
+            .or_else(|| {
+                header
+                    .get(257..262)
+                    .filter(|marker| *marker == b"ustar")
+                    .map(|_| Format::Tar)
+            })
+    }
+
+    /// Format suggested by the file name, used when the content isn't
+    /// conclusive (e.g. old tarballs without the `ustar` marker).
+    fn from_extension(file_name: &str) -> Option<Self> {
+        const EXTENSIONS: &[(&str, Format)] = &[
+            (".tar.gz", Format::Gzip),
+            (".tgz", Format::Gzip),
+            (".tar.bz2", Format::Bzip2),
+            (".tbz", Format::Bzip2),
+            (".tar.xz", Format::Xz),
+            (".txz", Format::Xz),
+            (".tar.zst", Format::Zstd),
+            (".tzst", Format::Zstd),
+            (".zip", Format::Zip),
+            (".tar", Format::Tar),
+        ];
+
+        EXTENSIONS
+            .iter()
+            .find(|(ext, _)| file_name.ends_with(ext))
+            .map(|&(_, format)| format)
+    }
+}
+
+/// Detects the asset's format from its content, falling back to the extension.
+fn detect_format(path: &Path, file_name: &str) -> Result<Option<Format>> {
+    let mut header = Vec::with_capacity(SNIFF_LEN);
+    File::open(path)?
+        .take(SNIFF_LEN as u64)
+        .read_to_end(&mut header)?;
+
+    Ok(Format::sniff(&header).or_else(|| Format::from_extension(file_name)))
+}
+
+pub fn extract_archive(
+    archive_path: &Path,
+    dest_dir: &Path,
+    limits: &ExtractLimits,
+) -> Result<Vec<String>> {
+    let file_name = archive_path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or_else(|| OktofetchError::ExtractionFailed("Invalid archive name".to_string()))?;
+
+    let file = || File::open(archive_path);
+    match detect_format(archive_path, file_name)? {
+        Some(Format::Gzip) => extract_compressed(
+            flate2::read::GzDecoder::new(file()?),
+            dest_dir,
+            file_name,
+            limits,
+        ),
+        Some(Format::Bzip2) => extract_compressed(
+            bzip2::read::BzDecoder::new(file()?),
+            dest_dir,
+            file_name,
+            limits,
+        ),
+        Some(Format::Xz) => extract_compressed(
+            xz2::read::XzDecoder::new(file()?),
+            dest_dir,
+            file_name,
+            limits,
+        ),
+        Some(Format::Zstd) => {
+            extract_compressed(zstd::Decoder::new(file()?)?, dest_dir, file_name, limits)
         }
+        Some(Format::Tar) => extract_tar(file()?, dest_dir, limits),
+        Some(Format::Zip) => extract_zip(archive_path, dest_dir, limits),
+        // Not an archive; accepted if it is a standalone binary
+        Some(Format::Elf) | None => {
+            handle_standalone_binary(archive_path, dest_dir, file_name, limits)
+        }
+    }
+}
 
-        entry.unpack(&dest_path)?;
+/// Decodes an asset that holds nothing but a binary, whether bare, compressed
+/// (e.g. `tool.gz`) or the only entry of a zip, into an executable temp file
+/// in `dest_dir`. Decoding next to the final location lets the binary be
+/// renamed into place instead of copied again. `None` if the asset needs a
+/// full extraction.
+pub fn decode_single_binary(
+    archive_path: &Path,
+    dest_dir: &Path,
+    limits: &ExtractLimits,
+) -> Result<Option<tempfile::NamedTempFile>> {
+    let file_name = archive_path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or_else(|| OktofetchError::ExtractionFailed("Invalid archive name".to_string()))?;
 
-        if let Some(path_str) = path.to_str() {
-            extracted_files.push(path_str.to_string());
+    let file = || File::open(archive_path);
+    match detect_format(archive_path, file_name)? {
+        Some(Format::Gzip) => decode_binary(
+            flate2::read::GzDecoder::new(file()?),
+            dest_dir,
+            file_name,
+            limits,
+        ),
+        Some(Format::Bzip2) => decode_binary(
+            bzip2::read::BzDecoder::new(file()?),
+            dest_dir,
+            file_name,
+            limits,
+        ),
+        Some(Format::Xz) => decode_binary(
+            xz2::read::XzDecoder::new(file()?),
+            dest_dir,
+            file_name,
+            limits,
+        ),
+        Some(Format::Zstd) => {
+            decode_binary(zstd::Decoder::new(file()?)?, dest_dir, file_name, limits)
         }
+        Some(Format::Elf) => decode_binary(file()?, dest_dir, file_name, limits),
+        Some(Format::Zip) => {
+            let mut archive = zip::ZipArchive::new(file()?).map_err(|e| {
+                OktofetchError::ExtractionFailed(format!("Failed to open zip archive: {}", e))
+            })?;
+            if archive.len() != 1 {
+                return Ok(None);
+            }
+            let entry = archive.by_index(0).map_err(|e| {
+                OktofetchError::ExtractionFailed(format!("Failed to extract file: {}", e))
+            })?;
+            if !entry.is_file() {
+                return Ok(None);
+            }
+            let name = entry.name().to_string();
+            decode_binary(entry, dest_dir, &name, limits)
+        }
+        Some(Format::Tar) | None => Ok(None),
+    }
+}
+
+/// Writes the decoded stream `reader` to a temp file in `dest_dir` if it is
+/// an ELF binary.
+fn decode_binary<R: Read>(
+    mut reader: R,
+    dest_dir: &Path,
+    name: &str,
+    limits: &ExtractLimits,
+) -> Result<Option<tempfile::NamedTempFile>> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mut header = Vec::with_capacity(SNIFF_LEN);
+    (&mut reader)
+        .take(SNIFF_LEN as u64)
+        .read_to_end(&mut header)?;
+    if Format::sniff(&header) != Some(Format::Elf) {
+        return Ok(None);
     }
 
-    Ok(extracted_files)
+    let mut temp = tempfile::NamedTempFile::new_in(dest_dir)?;
+    let max_size = limits.max_file_size.min(limits.max_total_size);
+    let reader = std::io::Cursor::new(header).chain(reader);
+    let size = std::io::copy(&mut reader.take(max_size + 1), &mut temp)?;
+    ExtractBudget::new(limits).add_size(name, size)?;
+
+    temp.as_file()
+        .set_permissions(std::fs::Permissions::from_mode(0o755))?;
+    Ok(Some(temp))
 }
 
-fn extract_tar_bz2(archive_path: &Path, dest_dir: &Path) -> Result<Vec<String>> {
-    use bzip2::read::BzDecoder;
+/// Extracts a compressed asset, which holds either a tarball or a single
+/// binary such as `tool.bz2`.
+fn extract_compressed<R: Read>(
+    mut decoder: R,
+    dest_dir: &Path,
+    file_name: &str,
+    limits: &ExtractLimits,
+) -> Result<Vec<String>> {
+    let mut header = Vec::with_capacity(SNIFF_LEN);
+    (&mut decoder)
+        .take(SNIFF_LEN as u64)
+        .read_to_end(&mut header)?;
+
+    let is_tarball =
+        Format::sniff(&header) == Some(Format::Tar) || Format::from_extension(file_name).is_some();
+    let reader = std::io::Cursor::new(header).chain(decoder);
+    if is_tarball {
+        extract_tar(reader, dest_dir, limits)
+    } else {
+        extract_single_file(reader, dest_dir, file_name, limits)
+    }
+}
+
+/// Decompresses a single-file asset into `dest_dir` and accepts it if it is
+/// a standalone binary.
+fn extract_single_file<R: Read>(
+    reader: R,
+    dest_dir: &Path,
+    file_name: &str,
+    limits: &ExtractLimits,
+) -> Result<Vec<String>> {
+    let name = COMPRESSED_SUFFIXES
+        .iter()
+        .find_map(|suffix| file_name.strip_suffix(suffix))
+        .filter(|name| !name.is_empty())
+        .unwrap_or(file_name);
+
+    // Decompress to a temp file first: `dest_dir` may hold the asset itself
+    std::fs::create_dir_all(dest_dir)?;
+    let mut temp = tempfile::NamedTempFile::new_in(dest_dir)?;
+    let max_size = limits.max_file_size.min(limits.max_total_size);
+    let size = std::io::copy(&mut reader.take(max_size + 1), &mut temp)?;
+    ExtractBudget::new(limits).add_size(name, size)?;
+
+    let dest_path = dest_dir.join(name);
+    temp.persist(&dest_path).map_err(|e| e.error)?;
+    handle_standalone_binary(&dest_path, dest_dir, name, limits)
+}
+
+fn extract_tar<R: Read>(reader: R, dest_dir: &Path, limits: &ExtractLimits) -> Result<Vec<String>> {
     use tar::Archive;
 
-    let file = File::open(archive_path)?;
-    let bz = BzDecoder::new(file);
-    let mut archive = Archive::new(bz);
+    let mut archive = Archive::new(reader);
+    let mut budget = ExtractBudget::new(limits);
 
     let mut extracted_files = Vec::new();
 
@@ -81,6 +356,9 @@ fn extract_tar_bz2(archive_path: &Path, dest_dir: &Path) -> Result<Vec<String>>
             continue;
         }
 
+        let name = path.to_string_lossy().to_string();
+        budget.add_entry(&name)?;
+
         let dest_path = dest_dir.join(&path);
 
         // Create parent directories if needed
@@ -88,7 +366,20 @@ fn extract_tar_bz2(archive_path: &Path, dest_dir: &Path) -> Result<Vec<String>>
             std::fs::create_dir_all(parent)?;
         }
 
-        entry.unpack(&dest_path)?;
+        let kind = entry.header().entry_type();
+        if kind.is_file() || kind.is_gnu_sparse() {
+            // Count the bytes written rather than the header size, which for
+            // a sparse entry leaves out the holes it expands to
+            let remaining = budget.remaining_for_file();
+            let mode = entry.header().mode()? & 0o777;
+            let written = write_capped(&mut entry, &dest_path, remaining, mode)?;
+            if let Err(e) = budget.add_size(&name, written) {
+                std::fs::remove_file(&dest_path)?;
+                return Err(e);
+            }
+        } else {
+            entry.unpack(&dest_path)?;
+        }
 
         if let Some(path_str) = path.to_str() {
             extracted_files.push(path_str.to_string());
@@ -98,7 +389,30 @@ fn extract_tar_bz2(archive_path: &Path, dest_dir: &Path) -> Result<Vec<String>>
     Ok(extracted_files)
 }
 
-fn extract_zip(archive_path: &Path, dest_dir: &Path) -> Result<Vec<String>> {
+/// Writes at most `cap + 1` bytes of `reader` to a new file at `dest` with
+/// permissions `mode`, replacing whatever was there, and returns how many
+/// were written. Writing more than `cap` means the entry is over its limit.
+fn write_capped(reader: &mut impl Read, dest: &Path, cap: u64, mode: u32) -> Result<u64> {
+    use std::os::unix::fs::PermissionsExt;
+
+    // Don't write through a symlink an earlier entry put here
+    if dest.symlink_metadata().is_ok_and(|m| !m.is_dir()) {
+        std::fs::remove_file(dest)?;
+    }
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(dest)?;
+    let written = std::io::copy(&mut reader.take(cap.saturating_add(1)), &mut file)?;
+    file.set_permissions(std::fs::Permissions::from_mode(mode))?;
+    Ok(written)
+}
+
+fn extract_zip(
+    archive_path: &Path,
+    dest_dir: &Path,
+    limits: &ExtractLimits,
+) -> Result<Vec<String>> {
     use std::os::unix::fs::PermissionsExt;
     use zip::ZipArchive;
 
@@ -107,6 +421,7 @@ fn extract_zip(archive_path: &Path, dest_dir: &Path) -> Result<Vec<String>> {
         OktofetchError::ExtractionFailed(format!("Failed to open zip archive: {}", e))
     })?;
 
+    let mut budget = ExtractBudget::new(limits);
     let mut extracted_files = Vec::new();
 
     for i in 0..archive.len() {
@@ -118,14 +433,29 @@ fn extract_zip(archive_path: &Path, dest_dir: &Path) -> Result<Vec<String>> {
             None => continue, // Skip invalid paths
         };
 
+        let name = file.name().to_string();
+        budget.add_entry(&name)?;
+
         if file.is_dir() {
             std::fs::create_dir_all(&outpath)?;
         } else {
+            // The declared size can lie, so also cap the bytes actually written.
+            // The cap is what was left before this file was counted.
+            let remaining = budget.remaining_for_file();
+            budget.add_size(&name, file.size())?;
+
             if let Some(parent) = outpath.parent() {
                 std::fs::create_dir_all(parent)?;
             }
             let mut outfile = File::create(&outpath)?;
-            std::io::copy(&mut file, &mut outfile)?;
+            let written = std::io::copy(&mut (&mut file).take(remaining + 1), &mut outfile)?;
+            if written > remaining {
+                return Err(OktofetchError::ExtractionFailed(format!(
+                    "File {} decompresses beyond its declared size",
+                    name
+                )));
+            }
+            budget.add_size(&name, written.saturating_sub(file.size()))?;
 
             // Check if the file is a binary and set executable permissions
             if is_elf_binary(&outpath)? {
@@ -135,15 +465,13 @@ fn extract_zip(archive_path: &Path, dest_dir: &Path) -> Result<Vec<String>> {
             }
         }
 
-        extracted_files.push(file.name().to_string());
+        extracted_files.push(name);
     }
 
     Ok(extracted_files)
 }
 
 fn is_elf_binary(path: &Path) -> Result<bool> {
-    use std::io::Read;
-
     let mut file = File::open(path)?;
     let mut header = [0u8; 4];
 
@@ -161,8 +489,8 @@ fn handle_standalone_binary(
     binary_path: &Path,
     dest_dir: &Path,
     file_name: &str,
+    limits: &ExtractLimits,
 ) -> Result<Vec<String>> {
-    use std::io::Read;
     use std::os::unix::fs::PermissionsExt;
 
     // Check file size first
@@ -173,6 +501,7 @@ fn handle_standalone_binary(
             file_name
         )));
     }
+    ExtractBudget::new(limits).add_size(file_name, metadata.len())?;
 
     // Check if it's a binary file by looking for ELF header (Linux/Unix)
     let mut file = File::open(binary_path)?;
@@ -240,7 +569,7 @@ mod tests {
         // Extract
         let extract_dir = temp_dir.path().join("extracted");
         fs::create_dir(&extract_dir).unwrap();
-        let result = extract_archive(&archive_path, &extract_dir);
+        let result = extract_archive(&archive_path, &extract_dir, &ExtractLimits::default());
 
         assert!(result.is_ok());
         let files = result.unwrap();
@@ -269,7 +598,7 @@ mod tests {
         // Extract
         let extract_dir = temp_dir.path().join("extracted");
         fs::create_dir(&extract_dir).unwrap();
-        let result = extract_archive(&archive_path, &extract_dir);
+        let result = extract_archive(&archive_path, &extract_dir, &ExtractLimits::default());
 
         assert!(result.is_ok());
         assert!(extract_dir.join("test.txt").exists());
@@ -279,6 +608,176 @@ mod tests {
         );
     }
 
+    /// Uncompressed tarball holding a single `name` entry.
+    fn tar_bytes(name: &str, content: &[u8]) -> Vec<u8> {
+        let mut tar = tar::Builder::new(Vec::new());
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        tar.append_data(&mut header, name, content).unwrap();
+        tar.into_inner().unwrap()
+    }
+
+    #[test]
+    fn test_format_sniff() {
+        assert_eq!(Format::sniff(&[0x1F, 0x8B, 8, 0]), Some(Format::Gzip));
+        assert_eq!(Format::sniff(b"BZh91AY"), Some(Format::Bzip2));
+        assert_eq!(
+            Format::sniff(&[0xFD, b'7', b'z', b'X', b'Z', 0, 0]),
+            Some(Format::Xz)
+        );
+        assert_eq!(Format::sniff(&[0x28, 0xB5, 0x2F, 0xFD]), Some(Format::Zstd));
+        assert_eq!(Format::sniff(b"PK\x03\x04rest"), Some(Format::Zip));
+        assert_eq!(Format::sniff(b"\x7fELF\x02\x01"), Some(Format::Elf));
+        assert_eq!(Format::sniff(&tar_bytes("a", b"x")), Some(Format::Tar));
+        assert_eq!(Format::sniff(b"#!/bin/sh"), None);
+        assert_eq!(Format::sniff(b""), None);
+    }
+
+    #[test]
+    fn test_decode_single_binary() {
+        use std::io::Write;
+        use std::os::unix::fs::PermissionsExt;
+
+        let elf = b"\x7fELF\x02\x01\x01binary".to_vec();
+        let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gz.write_all(&elf).unwrap();
+        let zip = {
+            let mut writer = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+            writer
+                .start_file("tool", zip::write::FileOptions::default())
+                .unwrap();
+            writer.write_all(&elf).unwrap();
+            writer.finish().unwrap().into_inner()
+        };
+
+        let temp_dir = TempDir::new().unwrap();
+        let install_dir = temp_dir.path().join("bin");
+        fs::create_dir(&install_dir).unwrap();
+        for (name, bytes) in [
+            ("tool", elf.clone()),
+            ("tool.gz", gz.finish().unwrap()),
+            ("tool.zip", zip),
+        ] {
+            let asset = temp_dir.path().join(name);
+            fs::write(&asset, bytes).unwrap();
+
+            let decoded = decode_single_binary(&asset, &install_dir, &ExtractLimits::default())
+                .unwrap()
+                .unwrap_or_else(|| panic!("{} was not decoded", name));
+            assert_eq!(decoded.path().parent(), Some(install_dir.as_path()));
+            assert_eq!(fs::read(decoded.path()).unwrap(), elf);
+            let mode = decoded.as_file().metadata().unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o755);
+        }
+
+        // Anything else takes the full extraction path
+        let tarball = temp_dir.path().join("tool.tar");
+        fs::write(&tarball, tar_bytes("tool", &elf)).unwrap();
+        let script = temp_dir.path().join("tool.sh");
+        fs::write(&script, b"#!/bin/sh\n").unwrap();
+        for path in [tarball, script] {
+            let decoded =
+                decode_single_binary(&path, &install_dir, &ExtractLimits::default()).unwrap();
+            assert!(decoded.is_none());
+        }
+        assert_eq!(fs::read_dir(&install_dir).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_format_from_extension() {
+        assert_eq!(Format::from_extension("a.tgz"), Some(Format::Gzip));
+        assert_eq!(Format::from_extension("a.tar.zst"), Some(Format::Zstd));
+        assert_eq!(Format::from_extension("a.tar"), Some(Format::Tar));
+        assert_eq!(Format::from_extension("a.rar"), None);
+    }
+
+    #[test]
+    fn test_extract_detects_format_from_content() {
+        use flate2::Compression;
+        use flate2::write::GzEncoder;
+        use std::io::Write;
+
+        let tar = tar_bytes("tool", b"payload");
+        let mut gz = GzEncoder::new(Vec::new(), Compression::default());
+        gz.write_all(&tar).unwrap();
+        let xz = {
+            let mut enc = xz2::write::XzEncoder::new(Vec::new(), 6);
+            enc.write_all(&tar).unwrap();
+            enc.finish().unwrap()
+        };
+        let zst = zstd::encode_all(&tar[..], 0).unwrap();
+
+        let cases = [
+            ("tool-linux-amd64", gz.finish().unwrap()),
+            ("tool.tar.xz", xz),
+            ("tool.tar.zst", zst),
+            ("tool-plain", tar),
+        ];
+        for (name, bytes) in cases {
+            let temp_dir = TempDir::new().unwrap();
+            let archive_path = temp_dir.path().join(name);
+            fs::write(&archive_path, bytes).unwrap();
+            let extract_dir = temp_dir.path().join("extracted");
+            fs::create_dir(&extract_dir).unwrap();
+
+            let files =
+                extract_archive(&archive_path, &extract_dir, &ExtractLimits::default()).unwrap();
+            assert_eq!(files, vec!["tool".to_string()], "{}", name);
+            assert_eq!(fs::read(extract_dir.join("tool")).unwrap(), b"payload");
+        }
+    }
+
+    #[test]
+    fn test_extract_compressed_single_binary() {
+        use flate2::Compression;
+        use flate2::write::GzEncoder;
+        use std::io::Write;
+
+        let binary = b"\x7fELF fake binary";
+        let mut gz = GzEncoder::new(Vec::new(), Compression::default());
+        gz.write_all(binary).unwrap();
+        let mut bz = bzip2::write::BzEncoder::new(Vec::new(), bzip2::Compression::default());
+        bz.write_all(binary).unwrap();
+
+        let mut xz = xz2::write::XzEncoder::new(Vec::new(), 6);
+        xz.write_all(binary).unwrap();
+
+        for (name, bytes) in [
+            ("tool.bz2", bz.finish().unwrap()),
+            ("tool.gz", gz.finish().unwrap()),
+            ("tool.xz", xz.finish().unwrap()),
+            ("tool.zst", zstd::encode_all(&binary[..], 0).unwrap()),
+        ] {
+            let temp_dir = TempDir::new().unwrap();
+            let archive_path = temp_dir.path().join(name);
+            fs::write(&archive_path, bytes).unwrap();
+            let extract_dir = temp_dir.path().join("extracted");
+
+            let files =
+                extract_archive(&archive_path, &extract_dir, &ExtractLimits::default()).unwrap();
+            assert_eq!(files, vec!["tool".to_string()], "{}", name);
+            assert_eq!(fs::read(extract_dir.join("tool")).unwrap(), binary);
+        }
+    }
+
+    #[test]
+    fn test_extract_compressed_non_binary() {
+        use std::io::Write;
+
+        let mut bz = bzip2::write::BzEncoder::new(Vec::new(), bzip2::Compression::default());
+        bz.write_all(b"just some text").unwrap();
+
+        let temp_dir = TempDir::new().unwrap();
+        let archive_path = temp_dir.path().join("notes.bz2");
+        fs::write(&archive_path, bz.finish().unwrap()).unwrap();
+
+        let err =
+            extract_archive(&archive_path, temp_dir.path(), &ExtractLimits::default()).unwrap_err();
+        assert!(err.to_string().contains("Unsupported archive format"));
+    }
+
     #[test]
     fn test_extract_unsupported_format() {
         let temp_dir = TempDir::new().unwrap();
@@ -288,7 +787,7 @@ mod tests {
         let extract_dir = temp_dir.path().join("extracted");
         fs::create_dir(&extract_dir).unwrap();
 
-        let result = extract_archive(&archive_path, &extract_dir);
+        let result = extract_archive(&archive_path, &extract_dir, &ExtractLimits::default());
         assert!(result.is_err());
         assert!(format!("{}", result.unwrap_err()).contains("Unsupported archive format"));
     }
@@ -299,7 +798,7 @@ mod tests {
         let archive_path = temp_dir.path().join("nonexistent.tar.gz");
         let extract_dir = temp_dir.path().join("extracted");
 
-        let result = extract_archive(&archive_path, &extract_dir);
+        let result = extract_archive(&archive_path, &extract_dir, &ExtractLimits::default());
         assert!(result.is_err());
     }
 
@@ -333,7 +832,7 @@ mod tests {
         // Extract
         let extract_dir = temp_dir.path().join("extracted");
         fs::create_dir(&extract_dir).unwrap();
-        let result = extract_archive(&archive_path, &extract_dir);
+        let result = extract_archive(&archive_path, &extract_dir, &ExtractLimits::default());
 
         assert!(result.is_ok());
         for i in 1..=3 {
@@ -365,7 +864,7 @@ mod tests {
         // Extract
         let extract_dir = temp_dir.path().join("extracted");
         fs::create_dir(&extract_dir).unwrap();
-        let result = extract_archive(&archive_path, &extract_dir);
+        let result = extract_archive(&archive_path, &extract_dir, &ExtractLimits::default());
 
         assert!(result.is_ok());
         assert!(extract_dir.join("testdir").exists());
@@ -383,7 +882,7 @@ mod tests {
         let extract_dir = temp_dir.path().join("extracted");
         fs::create_dir(&extract_dir).unwrap();
 
-        let result = extract_archive(&archive_path, &extract_dir);
+        let result = extract_archive(&archive_path, &extract_dir, &ExtractLimits::default());
         assert!(result.is_err());
     }
 
@@ -398,7 +897,7 @@ mod tests {
         let extract_dir = temp_dir.path().join("extracted");
         fs::create_dir(&extract_dir).unwrap();
 
-        let result = extract_archive(&archive_path, &extract_dir);
+        let result = extract_archive(&archive_path, &extract_dir, &ExtractLimits::default());
         assert!(result.is_err());
     }
 
@@ -413,7 +912,7 @@ mod tests {
         let extract_dir = temp_dir.path().join("extracted");
         fs::create_dir(&extract_dir).unwrap();
 
-        let result = extract_archive(&archive_path, &extract_dir);
+        let result = extract_archive(&archive_path, &extract_dir, &ExtractLimits::default());
         assert!(result.is_err());
         assert!(format!("{}", result.unwrap_err()).contains("Failed to open zip archive"));
     }
@@ -445,7 +944,7 @@ mod tests {
         // Extract
         let extract_dir = temp_dir.path().join("extracted");
         fs::create_dir(&extract_dir).unwrap();
-        let result = extract_archive(&archive_path, &extract_dir);
+        let result = extract_archive(&archive_path, &extract_dir, &ExtractLimits::default());
 
         assert!(result.is_ok());
         assert!(extract_dir.join("test.txt").exists());
@@ -478,7 +977,7 @@ mod tests {
         // Extract
         let extract_dir = temp_dir.path().join("extracted");
         fs::create_dir(&extract_dir).unwrap();
-        let result = extract_archive(&archive_path, &extract_dir);
+        let result = extract_archive(&archive_path, &extract_dir, &ExtractLimits::default());
 
         assert!(result.is_ok());
         let files = result.unwrap();
@@ -517,7 +1016,7 @@ mod tests {
         // Extract
         let extract_dir = temp_dir.path().join("extracted");
         fs::create_dir(&extract_dir).unwrap();
-        let result = extract_archive(&archive_path, &extract_dir);
+        let result = extract_archive(&archive_path, &extract_dir, &ExtractLimits::default());
 
         assert!(result.is_ok());
         assert!(extract_dir.join("test.txt").exists());
@@ -542,7 +1041,7 @@ mod tests {
         // Extract
         let extract_dir = temp_dir.path().join("extracted");
         fs::create_dir(&extract_dir).unwrap();
-        let result = extract_archive(&binary_path, &extract_dir);
+        let result = extract_archive(&binary_path, &extract_dir, &ExtractLimits::default());
 
         assert!(result.is_ok());
         let extracted_files = result.unwrap();
@@ -569,8 +1068,183 @@ mod tests {
         let extract_dir = temp_dir.path().join("extracted");
         fs::create_dir(&extract_dir).unwrap();
 
-        let result = extract_archive(&file_path, &extract_dir);
+        let result = extract_archive(&file_path, &extract_dir, &ExtractLimits::default());
         assert!(result.is_err());
         assert!(format!("{}", result.unwrap_err()).contains("Unsupported archive format"));
     }
+
+    fn write_tar_gz(path: &Path, entries: &[(&str, &[u8])]) {
+        use flate2::Compression;
+        use flate2::write::GzEncoder;
+        use tar::Builder;
+
+        let tar_gz = fs::File::create(path).unwrap();
+        let enc = GzEncoder::new(tar_gz, Compression::default());
+        let mut tar = Builder::new(enc);
+        for (name, content) in entries {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            tar.append_data(&mut header, name, *content).unwrap();
+        }
+        tar.into_inner().unwrap().finish().unwrap();
+    }
+
+    #[test]
+    fn test_extract_tar_gz_file_count_limit() {
+        let temp_dir = TempDir::new().unwrap();
+        let archive_path = temp_dir.path().join("many.tar.gz");
+        write_tar_gz(
+            &archive_path,
+            &[("a.txt", b"a"), ("b.txt", b"b"), ("c.txt", b"c")],
+        );
+
+        let limits = ExtractLimits {
+            max_files: 2,
+            ..ExtractLimits::default()
+        };
+        let result = extract_archive(&archive_path, temp_dir.path(), &limits);
+        assert!(matches!(result, Err(OktofetchError::ExtractionFailed(_))));
+        assert!(format!("{}", result.unwrap_err()).contains("more than 2 entries"));
+    }
+
+    #[test]
+    fn test_extract_tar_gz_file_size_limit() {
+        let temp_dir = TempDir::new().unwrap();
+        let archive_path = temp_dir.path().join("big.tar.gz");
+        write_tar_gz(&archive_path, &[("big.bin", &[0u8; 4096])]);
+
+        let limits = ExtractLimits {
+            max_file_size: 1024,
+            ..ExtractLimits::default()
+        };
+        let result = extract_archive(&archive_path, temp_dir.path(), &limits);
+        assert!(format!("{}", result.unwrap_err()).contains("per-file size limit"));
+        assert!(!temp_dir.path().join("big.bin").exists());
+    }
+
+    #[test]
+    fn test_extract_tar_gz_total_size_limit() {
+        let temp_dir = TempDir::new().unwrap();
+        let archive_path = temp_dir.path().join("total.tar.gz");
+        write_tar_gz(
+            &archive_path,
+            &[("one.bin", &[0u8; 600]), ("two.bin", &[0u8; 600])],
+        );
+
+        let limits = ExtractLimits {
+            max_total_size: 1000,
+            ..ExtractLimits::default()
+        };
+        let result = extract_archive(&archive_path, temp_dir.path(), &limits);
+        assert!(format!("{}", result.unwrap_err()).contains("total extraction size limit"));
+    }
+
+    #[test]
+    fn test_extract_tar_sparse_entry_counts_expanded_size() {
+        let temp_dir = TempDir::new().unwrap();
+        let archive_path = temp_dir.path().join("sparse.tar");
+        let octal = |field: &mut [u8; 12], value: u64| {
+            field.copy_from_slice(format!("{:011o}\0", value).as_bytes())
+        };
+
+        // 1 KiB stored, expanding to a 1 MiB file that is mostly a hole
+        let mut header = tar::Header::new_gnu();
+        header.set_path("sparse.bin").unwrap();
+        header.set_entry_type(tar::EntryType::GNUSparse);
+        header.set_size(1024);
+        header.set_mode(0o644);
+        let gnu = header.as_gnu_mut().unwrap();
+        octal(&mut gnu.sparse[0].offset, 0);
+        octal(&mut gnu.sparse[0].numbytes, 512);
+        octal(&mut gnu.sparse[1].offset, 1024 * 1024 - 512);
+        octal(&mut gnu.sparse[1].numbytes, 512);
+        octal(&mut gnu.realsize, 1024 * 1024);
+        header.set_cksum();
+        let mut tar = tar::Builder::new(fs::File::create(&archive_path).unwrap());
+        tar.append(&header, &[1u8; 1024][..]).unwrap();
+        tar.finish().unwrap();
+
+        let limits = ExtractLimits {
+            max_file_size: 4096,
+            ..ExtractLimits::default()
+        };
+        let result = extract_archive(&archive_path, temp_dir.path(), &limits);
+        assert!(format!("{}", result.unwrap_err()).contains("per-file size limit"));
+        assert!(!temp_dir.path().join("sparse.bin").exists());
+
+        let extracted =
+            extract_archive(&archive_path, temp_dir.path(), &ExtractLimits::default()).unwrap();
+        assert_eq!(extracted, vec!["sparse.bin"]);
+        let metadata = fs::metadata(temp_dir.path().join("sparse.bin")).unwrap();
+        assert_eq!(metadata.len(), 1024 * 1024);
+    }
+
+    #[test]
+    fn test_extract_zip_size_limit() {
+        use zip::write::{FileOptions, ZipWriter};
+
+        let temp_dir = TempDir::new().unwrap();
+        let archive_path = temp_dir.path().join("bomb.zip");
+
+        let file = fs::File::create(&archive_path).unwrap();
+        let mut zip = ZipWriter::new(file);
+        zip.start_file("zeros.bin", FileOptions::default()).unwrap();
+        zip.write_all(&[0u8; 64 * 1024]).unwrap();
+        zip.finish().unwrap();
+
+        let limits = ExtractLimits {
+            max_file_size: 1024,
+            ..ExtractLimits::default()
+        };
+        let extract_dir = temp_dir.path().join("extracted");
+        fs::create_dir(&extract_dir).unwrap();
+        let result = extract_archive(&archive_path, &extract_dir, &limits);
+        assert!(matches!(result, Err(OktofetchError::ExtractionFailed(_))));
+    }
+
+    #[test]
+    fn test_extract_zip_exactly_at_limits() {
+        use zip::write::{FileOptions, ZipWriter};
+
+        let temp_dir = TempDir::new().unwrap();
+        let archive_path = temp_dir.path().join("full.zip");
+
+        let file = fs::File::create(&archive_path).unwrap();
+        let mut zip = ZipWriter::new(file);
+        zip.start_file("one.bin", FileOptions::default()).unwrap();
+        zip.write_all(&[1u8; 512]).unwrap();
+        zip.start_file("two.bin", FileOptions::default()).unwrap();
+        zip.write_all(&[2u8; 512]).unwrap();
+        zip.finish().unwrap();
+
+        let limits = ExtractLimits {
+            max_total_size: 1024,
+            max_file_size: 512,
+            max_files: 2,
+        };
+        let extract_dir = temp_dir.path().join("extracted");
+        fs::create_dir(&extract_dir).unwrap();
+        let files = extract_archive(&archive_path, &extract_dir, &limits).unwrap();
+        assert_eq!(files.len(), 2);
+        assert_eq!(fs::read(extract_dir.join("two.bin")).unwrap(), [2u8; 512]);
+    }
+
+    #[test]
+    fn test_extract_within_limits() {
+        let temp_dir = TempDir::new().unwrap();
+        let archive_path = temp_dir.path().join("ok.tar.gz");
+        write_tar_gz(&archive_path, &[("a.txt", b"hello"), ("b.txt", b"world")]);
+
+        let limits = ExtractLimits {
+            max_total_size: 10,
+            max_file_size: 5,
+            max_files: 2,
+        };
+        let extract_dir = temp_dir.path().join("extracted");
+        fs::create_dir(&extract_dir).unwrap();
+        let files = extract_archive(&archive_path, &extract_dir, &limits).unwrap();
+        assert_eq!(files.len(), 2);
+    }
 }
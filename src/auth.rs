@@ -0,0 +1,176 @@
+//! Where the GitHub token comes from: `--token`, then `$GITHUB_TOKEN`, then
+//! the OS keyring that `oktofetch auth login` stores it in.
+
+use crate::error::{OktofetchError, Result};
+use std::fmt;
+use std::io::BufRead;
+use std::sync::OnceLock;
+
+const KEYRING_SERVICE: &str = "oktofetch";
+const KEYRING_USER: &str = "github";
+
+static TOKEN_OVERRIDE: OnceLock<String> = OnceLock::new();
+static STORED_TOKEN: OnceLock<Option<String>> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenSource {
+    Flag,
+    Env,
+    Keyring,
+}
+
+impl fmt::Display for TokenSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            TokenSource::Flag => "--token",
+            TokenSource::Env => "GITHUB_TOKEN",
+            TokenSource::Keyring => "OS keyring",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub value: String,
+    pub source: TokenSource,
+}
+
+/// Makes [`token`] return `token` for the rest of the process.
+pub fn use_token(token: String) {
+    let _ = TOKEN_OVERRIDE.set(token);
+}
+
+/// The token to send to the GitHub API, if any. The keyring is only asked
+/// when neither `--token` nor `$GITHUB_TOKEN` is set.
+pub fn token() -> Option<Token> {
+    pick(
+        TOKEN_OVERRIDE.get().cloned(),
+        std::env::var("GITHUB_TOKEN").ok(),
+        || STORED_TOKEN.get_or_init(stored_token).clone(),
+    )
+}
+
+fn pick(
+    flag: Option<String>,
+    env: Option<String>,
+    stored: impl FnOnce() -> Option<String>,
+) -> Option<Token> {
+    let non_empty = |value: Option<String>| value.filter(|v| !v.trim().is_empty());
+
+    if let Some(value) = non_empty(flag) {
+        return Some(Token {
+            value,
+            source: TokenSource::Flag,
+        });
+    }
+    if let Some(value) = non_empty(env) {
+        return Some(Token {
+            value,
+            source: TokenSource::Env,
+        });
+    }
+    non_empty(stored()).map(|value| Token {
+        value,
+        source: TokenSource::Keyring,
+    })
+}
+
+/// Runs `f` on oktofetch's keyring entry in a thread of its own. The Secret
+/// Service backend drives D-Bus on a tokio runtime of its own, which can't
+/// be started from a task of ours.
+fn with_entry<T: Send>(
+    f: impl FnOnce(&keyring::Entry) -> keyring::Result<T> + Send,
+) -> keyring::Result<T> {
+    std::thread::scope(|scope| {
+        scope
+            .spawn(|| f(&keyring::Entry::new(KEYRING_SERVICE, KEYRING_USER)?))
+            .join()
+            .unwrap_or_else(|_| {
+                Err(keyring::Error::PlatformFailure(
+                    "keyring access panicked".into(),
+                ))
+            })
+    })
+}
+
+fn keyring_error(e: keyring::Error) -> OktofetchError {
+    OktofetchError::Other(format!("OS keyring: {}", e))
+}
+
+/// Token saved by `auth login`. A missing or unreachable keyring counts as
+/// no token. Tests never look, so a developer's own token can't leak in.
+#[cfg(not(test))]
+fn stored_token() -> Option<String> {
+    with_entry(|entry| entry.get_password()).ok()
+}
+
+#[cfg(test)]
+fn stored_token() -> Option<String> {
+    None
+}
+
+/// Saves `token` in the OS keyring, replacing any earlier one.
+pub fn login(token: &str) -> Result<()> {
+    with_entry(|entry| entry.set_password(token.trim())).map_err(keyring_error)
+}
+
+/// Removes the saved token. Returns false when there was none.
+pub fn logout() -> Result<bool> {
+    match with_entry(|entry| entry.delete_credential()) {
+        Ok(()) => Ok(true),
+        Err(keyring::Error::NoEntry) => Ok(false),
+        Err(e) => Err(keyring_error(e)),
+    }
+}
+
+/// Reads a token from the first line of `input`.
+pub fn read_token(input: &mut impl BufRead) -> Result<String> {
+    let mut line = String::new();
+    input.read_line(&mut line)?;
+    let token = line.trim();
+    if token.is_empty() {
+        return Err(OktofetchError::Other("no token given".to_string()));
+    }
+    Ok(token.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_flag_wins_over_env_and_keyring() {
+        let token = pick(Some("flag".into()), Some("env".into()), || {
+            panic!("keyring consulted")
+        })
+        .unwrap();
+        assert_eq!(token.value, "flag");
+        assert_eq!(token.source, TokenSource::Flag);
+    }
+
+    #[test]
+    fn test_env_skips_keyring() {
+        let token = pick(None, Some("env".into()), || panic!("keyring consulted")).unwrap();
+        assert_eq!(token.source, TokenSource::Env);
+        assert_eq!(token.source.to_string(), "GITHUB_TOKEN");
+    }
+
+    #[test]
+    fn test_keyring_is_last_resort() {
+        let token = pick(Some(String::new()), Some(" ".into()), || Some("kr".into())).unwrap();
+        assert_eq!(token.value, "kr");
+        assert_eq!(token.source, TokenSource::Keyring);
+
+        assert_eq!(pick(None, None, || None), None);
+    }
+
+    #[test]
+    fn test_read_token() {
+        let mut input = Cursor::new("  ghp_abc123\nrest\n");
+        assert_eq!(read_token(&mut input).unwrap(), "ghp_abc123");
+
+        assert!(read_token(&mut Cursor::new("\n")).is_err());
+        assert!(read_token(&mut Cursor::new("")).is_err());
+    }
+}
@@ -1,7 +1,46 @@
 use crate::error::{OktofetchError, Result};
 use std::fs;
+use std::io;
 use std::path::{Path, PathBuf};
 
+/// Places a copy of `src` at `dest`, avoiding a full data copy where the
+/// filesystem allows it: a reflink (copy-on-write clone) is tried first, then
+/// a regular copy. Never a hardlink, which would let a change to one file
+/// show up in the other. `dest` must not exist.
+pub fn clone_or_copy(src: &Path, dest: &Path) -> Result<()> {
+    if reflink(src, dest).is_ok() {
+        return Ok(());
+    }
+    fs::copy(src, dest)?;
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn reflink(_src: &Path, _dest: &Path) -> io::Result<()> {
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn reflink(src: &Path, dest: &Path) -> io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+
+    let src_file = fs::File::open(src)?;
+    let dest_file = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(dest)?;
+
+    // SAFETY: both descriptors are valid for the duration of the call
+    let ret = unsafe { libc::ioctl(dest_file.as_raw_fd(), libc::FICLONE, src_file.as_raw_fd()) };
+    if ret == -1 {
+        let err = io::Error::last_os_error();
+        drop(dest_file);
+        let _ = fs::remove_file(dest);
+        return Err(err);
+    }
+    Ok(())
+}
+
 pub fn find_binary(
     extracted_files: &[String],
     extract_dir: &Path,
@@ -57,6 +96,11 @@ pub fn find_binary(
     )))
 }
 
+/// Path the previously installed binary is moved to before an overwrite.
+pub fn backup_path(install_dir: &Path, name: &str) -> PathBuf {
+    install_dir.join(format!("{}.previous", name))
+}
+
 pub fn install_binary(binary_path: &Path, install_dir: &Path, name: &str) -> Result<PathBuf> {
     use std::os::unix::fs::PermissionsExt;
 
@@ -64,23 +108,480 @@ pub fn install_binary(binary_path: &Path, install_dir: &Path, name: &str) -> Res
         fs::create_dir_all(install_dir)?;
     }
 
+    replace_backed_up(install_dir, name, |dest| {
+        clone_or_copy(binary_path, dest)?;
+
+        // Make executable
+        let mut perms = fs::metadata(dest)?.permissions();
+        perms.set_mode(0o755);
+        fs::set_permissions(dest, perms)?;
+        Ok(())
+    })
+}
+
+/// Renames `decoded`, an executable temp file in `install_dir`, into place as
+/// `name`, backing up the binary it replaces like `install_binary` does.
+pub fn install_decoded(
+    decoded: tempfile::NamedTempFile,
+    install_dir: &Path,
+    name: &str,
+) -> Result<PathBuf> {
+    replace_backed_up(install_dir, name, |dest| {
+        decoded.persist(dest).map_err(|e| e.error)?;
+        Ok(())
+    })
+}
+
+/// Writes a launcher script `name` into `install_dir` that runs `target` with
+/// the arguments it was given, backing up what it replaces like
+/// `install_binary` does.
+pub fn install_wrapper(target: &Path, install_dir: &Path, name: &str) -> Result<PathBuf> {
+    use std::os::unix::fs::PermissionsExt;
+
+    fs::create_dir_all(install_dir)?;
+    replace_backed_up(install_dir, name, |dest| {
+        fs::write(dest, wrapper_script(target))?;
+        fs::set_permissions(dest, fs::Permissions::from_mode(0o755))?;
+        Ok(())
+    })
+}
+
+const WRAPPER_MARKER: &str = "# Installed by oktofetch: runs the binary of a bundled release";
+
+fn wrapper_script(target: &Path) -> String {
+    // Single-quoted for sh, with embedded quotes closed and escaped
+    let quoted = target.to_string_lossy().replace('\'', r"'\''");
+    format!("#!/bin/sh\n{}\nexec '{}' \"$@\"\n", WRAPPER_MARKER, quoted)
+}
+
+/// Binary a wrapper from [`install_wrapper`] at `path` runs, or `None` when
+/// `path` isn't such a wrapper.
+pub fn wrapper_target(path: &Path) -> Option<PathBuf> {
+    let script = fs::read_to_string(path).ok()?;
+    let mut lines = script.lines();
+    if lines.next() != Some("#!/bin/sh") || lines.next() != Some(WRAPPER_MARKER) {
+        return None;
+    }
+    let quoted = lines
+        .next()?
+        .strip_prefix("exec '")?
+        .strip_suffix("' \"$@\"")?;
+    Some(PathBuf::from(quoted.replace(r"'\''", "'")))
+}
+
+/// Moves an installed `name` aside so a bad release can be restored by hand,
+/// then lets `write` put the new file at the returned path. When `write`
+/// fails, whatever it left there is removed and the backup put back.
+fn replace_backed_up(
+    install_dir: &Path,
+    name: &str,
+    write: impl FnOnce(&Path) -> Result<()>,
+) -> Result<PathBuf> {
     let dest = install_dir.join(name);
-    fs::copy(binary_path, &dest)?;
+    let backed_up = dest.is_file();
+    if backed_up {
+        fs::rename(&dest, backup_path(install_dir, name))?;
+    }
 
-    // Make executable
-    let mut perms = fs::metadata(&dest)?.permissions();
-    perms.set_mode(0o755);
-    fs::set_permissions(&dest, perms)?;
+    if let Err(e) = write(&dest) {
+        let _ = fs::remove_file(&dest);
+        if backed_up {
+            restore_backup(install_dir, name)?;
+        }
+        return Err(e);
+    }
+    Ok(dest)
+}
+
+/// Puts the binary `install_binary` backed up back in place of `name`.
+pub fn restore_backup(install_dir: &Path, name: &str) -> Result<()> {
+    fs::rename(backup_path(install_dir, name), install_dir.join(name))?;
+    Ok(())
+}
+
+/// Another executable with the same name as an installed tool.
+#[derive(Debug, PartialEq, Eq)]
+pub struct PathCollision {
+    pub path: PathBuf,
+    /// It comes before the install directory on PATH (or the install
+    /// directory isn't on PATH), so it's the one that runs
+    pub shadows_install: bool,
+}
 
+/// Finds executables called `name` on the PATH-style list `search_path`,
+/// other than the one in `install_dir`, in lookup order.
+pub fn path_collisions(
+    name: &str,
+    install_dir: &Path,
+    search_path: &std::ffi::OsStr,
+) -> Vec<PathCollision> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let canonical = |path: &Path| fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let install_dir = canonical(install_dir);
+    let mut seen_dirs = Vec::new();
+    let mut past_install_dir = false;
+    let mut collisions = Vec::new();
+
+    for dir in std::env::split_paths(search_path) {
+        let dir = canonical(&dir);
+        if seen_dirs.contains(&dir) {
+            continue;
+        }
+        seen_dirs.push(dir.clone());
+        if dir == install_dir {
+            past_install_dir = true;
+            continue;
+        }
+
+        let candidate = dir.join(name);
+        let executable = fs::metadata(&candidate)
+            .is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0);
+        // A symlink to the managed binary isn't a collision
+        if executable && canonical(&candidate) != install_dir.join(name) {
+            collisions.push(PathCollision {
+                path: candidate,
+                shadows_install: !past_install_dir,
+            });
+        }
+    }
+
+    collisions
+}
+
+/// Name of the directory a version is stored in. Tags may contain `/`, e.g.
+/// `cli/v1.2.0`, so it is percent-encoded along with `%` itself, and `.` and
+/// `..` are encoded so that they can't refer to another directory.
+pub fn version_dir_name(version: &str) -> String {
+    match version {
+        "." => "%2E".to_string(),
+        ".." => "%2E%2E".to_string(),
+        _ => version.replace('%', "%25").replace('/', "%2F"),
+    }
+}
+
+/// Directory under a tool's `versions_dir` that `version` is stored in.
+pub fn version_dir(versions_dir: &Path, version: &str) -> PathBuf {
+    versions_dir.join(version_dir_name(version))
+}
+
+/// Copies an installed binary into the version's directory under
+/// `versions_dir`, replacing any earlier copy of the same version.
+pub fn store_version(
+    binary_path: &Path,
+    versions_dir: &Path,
+    version: &str,
+    name: &str,
+) -> Result<PathBuf> {
+    let version_dir = version_dir(versions_dir, version);
+    if version_dir.exists() {
+        fs::remove_dir_all(&version_dir)?;
+    }
+    fs::create_dir_all(&version_dir)?;
+
+    let dest = version_dir.join(name);
+    clone_or_copy(binary_path, &dest)?;
     Ok(dest)
 }
 
+/// A bundle moved into place. The tree it replaced is kept aside until
+/// [`commit`](Self::commit) or put back by [`roll_back`](Self::roll_back).
+#[derive(Debug)]
+pub struct StoredBundle {
+    /// Where the bundle's binary ended up
+    pub binary: PathBuf,
+    dir: PathBuf,
+    previous: Option<PathBuf>,
+}
+
+impl StoredBundle {
+    /// Deletes the tree the bundle replaced, once the new one works.
+    pub fn commit(self) -> Result<()> {
+        if let Some(previous) = self.previous {
+            fs::remove_dir_all(previous)?;
+        }
+        Ok(())
+    }
+
+    /// Removes the bundle and puts back the tree it replaced, if any.
+    pub fn roll_back(self) -> Result<()> {
+        fs::remove_dir_all(&self.dir)?;
+        if let Some(previous) = self.previous {
+            fs::rename(previous, &self.dir)?;
+        }
+        Ok(())
+    }
+}
+
+/// Moves everything extracted into `scratch` to `bundle_dir` and tells where
+/// `binary`, a file in `scratch`, ended up. The tree is assembled next to
+/// `bundle_dir` and renamed into place, so an earlier bundle there keeps
+/// working until the swap and can be restored after it.
+pub fn store_bundle(scratch: &Path, binary: &Path, bundle_dir: &Path) -> Result<StoredBundle> {
+    let relative = binary.strip_prefix(scratch).map_err(|_| {
+        OktofetchError::Other(format!(
+            "{} is outside the extracted release",
+            binary.display()
+        ))
+    })?;
+    let parent = bundle_dir
+        .parent()
+        .ok_or_else(|| OktofetchError::Other(format!("{} has no parent", bundle_dir.display())))?;
+    fs::create_dir_all(parent)?;
+
+    let staging = tempfile::Builder::new()
+        .prefix(".bundle-new-")
+        .tempdir_in(parent)?;
+    for entry in fs::read_dir(scratch)? {
+        let entry = entry?;
+        let dest = staging.path().join(entry.file_name());
+        // The cache and data directories can be on different filesystems
+        if fs::rename(entry.path(), &dest).is_err() {
+            copy_tree(&entry.path(), &dest)?;
+        }
+    }
+
+    let previous = if bundle_dir.exists() {
+        let aside = tempfile::Builder::new()
+            .prefix(".bundle-old-")
+            .tempdir_in(parent)?
+            .keep();
+        // Renaming onto the empty placeholder is atomic
+        fs::rename(bundle_dir, &aside)?;
+        Some(aside)
+    } else {
+        None
+    };
+    if let Err(e) = fs::rename(staging.path(), bundle_dir) {
+        if let Some(previous) = &previous {
+            fs::rename(previous, bundle_dir)?;
+        }
+        return Err(e.into());
+    }
+    // Now in place, so nothing is left for the guard to delete
+    let _ = staging.keep();
+
+    Ok(StoredBundle {
+        binary: bundle_dir.join(relative),
+        dir: bundle_dir.to_path_buf(),
+        previous,
+    })
+}
+
+/// Copies `src`, a file, symlink or directory, to `dest` recursively.
+fn copy_tree(src: &Path, dest: &Path) -> Result<()> {
+    let file_type = fs::symlink_metadata(src)?.file_type();
+    if file_type.is_symlink() {
+        std::os::unix::fs::symlink(fs::read_link(src)?, dest)?;
+    } else if file_type.is_dir() {
+        fs::create_dir_all(dest)?;
+        for entry in fs::read_dir(src)? {
+            let entry = entry?;
+            copy_tree(&entry.path(), &dest.join(entry.file_name()))?;
+        }
+    } else {
+        fs::copy(src, dest)?;
+    }
+    Ok(())
+}
+
+/// Removes all but the `keep` most recently stored versions, returning the
+/// directories that were deleted. Versions named in `exempt` are neither
+/// removed nor counted.
+pub fn prune_versions(versions_dir: &Path, keep: usize, exempt: &[String]) -> Result<Vec<PathBuf>> {
+    if !versions_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let exempt: Vec<String> = exempt.iter().map(|v| version_dir_name(v)).collect();
+    let mut versions = Vec::new();
+    for entry in fs::read_dir(versions_dir)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        if metadata.is_dir() && !exempt.contains(&name) {
+            versions.push((metadata.modified()?, entry.path()));
+        }
+    }
+
+    // Newest first
+    versions.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+
+    let mut removed = Vec::new();
+    for (_, path) in versions.into_iter().skip(keep) {
+        fs::remove_dir_all(&path)?;
+        removed.push(path);
+    }
+
+    Ok(removed)
+}
+
+/// Runs `<binary> --version` and returns the first version-looking token of
+/// its output, or `None` if the binary can't be run or prints no version.
+pub fn probe_version(path: &Path) -> Option<String> {
+    let output = run_version(path).ok()??;
+    extract_version(&String::from_utf8_lossy(&output.stdout))
+        .or_else(|| extract_version(&String::from_utf8_lossy(&output.stderr)))
+}
+
+/// Checks that a freshly installed binary runs at all by calling it with
+/// `--version`. It fails when it can't be executed (wrong architecture,
+/// missing interpreter or libraries) or dies from a signal; a binary that
+/// merely rejects `--version` or keeps running passes.
+pub fn smoke_test(path: &Path) -> std::result::Result<(), String> {
+    use std::os::unix::process::ExitStatusExt;
+
+    let output = match run_version(path) {
+        Ok(Some(output)) => output,
+        Ok(None) => return Ok(()),
+        Err(e) => return Err(e.to_string()),
+    };
+
+    if let Some(signal) = output.status.signal() {
+        return Err(match signal {
+            libc::SIGSEGV => "segmentation fault".to_string(),
+            libc::SIGILL => "illegal instruction".to_string(),
+            libc::SIGBUS => "bus error".to_string(),
+            libc::SIGABRT => "aborted".to_string(),
+            _ => format!("killed by signal {}", signal),
+        });
+    }
+
+    // The shell's codes for "found but not executable" and "not found",
+    // e.g. a foreign binary handed to sh or a missing dynamic loader
+    match output.status.code() {
+        Some(code @ (126 | 127)) => {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            match stderr.lines().next().filter(|line| !line.is_empty()) {
+                Some(line) => Err(format!("exited with status {}: {}", code, line)),
+                None => Err(format!("exited with status {}", code)),
+            }
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Runs `<binary> --version`, returning `None` when it is still running
+/// after a few seconds (it is killed then).
+fn run_version(path: &Path) -> io::Result<Option<std::process::Output>> {
+    use std::process::{Command, Stdio};
+    use std::time::{Duration, Instant};
+
+    let mut child = Command::new(path)
+        .arg("--version")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    // Drain both pipes while waiting, so a binary printing more than a pipe
+    // holds doesn't block until it is killed
+    let stdout = drain(child.stdout.take());
+    let stderr = drain(child.stderr.take());
+
+    // Don't let a binary that ignores --version hang the caller
+    let deadline = Instant::now() + Duration::from_secs(5);
+    let status = loop {
+        match child.try_wait()? {
+            Some(status) => break status,
+            None if Instant::now() < deadline => std::thread::sleep(Duration::from_millis(20)),
+            None => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Ok(None);
+            }
+        }
+    };
+
+    Ok(Some(std::process::Output {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    }))
+}
+
+/// Reads `pipe` to the end on a thread of its own.
+fn drain(pipe: Option<impl io::Read + Send + 'static>) -> std::thread::JoinHandle<Vec<u8>> {
+    std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buf);
+        }
+        buf
+    })
+}
+
+/// Finds the first `X.Y` or `X.Y.Z`-style token in `text`, without any leading `v`.
+pub fn extract_version(text: &str) -> Option<String> {
+    text.split(|c: char| c.is_whitespace() || c == ',' || c == '(' || c == ')')
+        .map(|token| token.trim_start_matches(['v', 'V']))
+        .find(|token| {
+            let mut parts = token.split('.');
+            let major = parts.next().unwrap_or_default();
+            let minor = parts.next().unwrap_or_default();
+            !major.is_empty()
+                && major.chars().all(|c| c.is_ascii_digit())
+                && minor.starts_with(|c: char| c.is_ascii_digit())
+        })
+        .map(|token| token.trim_end_matches('.').to_string())
+}
+
+/// Returns the lowercase hex SHA256 digest of a file.
+pub fn sha256_file(path: &Path) -> Result<String> {
+    use sha2::{Digest, Sha256};
+
+    let mut file = fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::fs::File;
     use tempfile::TempDir;
 
+    #[test]
+    fn test_path_collisions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = TempDir::new().unwrap();
+        let dirs: Vec<PathBuf> = ["usr", "local", "cargo", "empty"]
+            .iter()
+            .map(|d| temp_dir.path().join(d))
+            .collect();
+        for dir in &dirs {
+            fs::create_dir(dir).unwrap();
+        }
+        for dir in &dirs[..3] {
+            let path = dir.join("k9s");
+            fs::write(&path, "#!/bin/sh").unwrap();
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        }
+        // Not executable, so not something a shell would run
+        fs::write(dirs[3].join("k9s"), "").unwrap();
+
+        let install_dir = &dirs[1];
+        let search_path = std::env::join_paths(&dirs).unwrap();
+        let collisions = path_collisions("k9s", install_dir, &search_path);
+
+        assert_eq!(
+            collisions,
+            vec![
+                PathCollision {
+                    path: dirs[0].join("k9s"),
+                    shadows_install: true
+                },
+                PathCollision {
+                    path: dirs[2].join("k9s"),
+                    shadows_install: false
+                },
+            ]
+        );
+        assert!(path_collisions("fzf", install_dir, &search_path).is_empty());
+    }
+
     #[test]
     fn test_find_binary_no_executables() {
         let temp_dir = TempDir::new().unwrap();
@@ -159,6 +660,22 @@ mod tests {
         assert_ne!(perms.mode() & 0o111, 0);
     }
 
+    #[test]
+    fn test_install_decoded() {
+        let temp_dir = TempDir::new().unwrap();
+        let install_dir = temp_dir.path();
+        fs::write(install_dir.join("myapp"), b"old").unwrap();
+
+        let mut decoded = tempfile::NamedTempFile::new_in(install_dir).unwrap();
+        io::Write::write_all(&mut decoded, b"new").unwrap();
+        let dest = install_decoded(decoded, install_dir, "myapp").unwrap();
+
+        assert_eq!(dest, install_dir.join("myapp"));
+        assert_eq!(fs::read(&dest).unwrap(), b"new");
+        assert_eq!(fs::read(backup_path(install_dir, "myapp")).unwrap(), b"old");
+        assert_eq!(fs::read_dir(install_dir).unwrap().count(), 2);
+    }
+
     #[test]
     fn test_install_binary_creates_dir() {
         let temp_dir = TempDir::new().unwrap();
@@ -250,4 +767,362 @@ mod tests {
         let perms = fs::metadata(&dest).unwrap().permissions();
         assert_ne!(perms.mode() & 0o111, 0);
     }
+
+    #[test]
+    fn test_install_binary_backs_up_previous() {
+        let temp_dir = TempDir::new().unwrap();
+        let install_dir = temp_dir.path().join("bin");
+        fs::create_dir(&install_dir).unwrap();
+
+        let source_path = temp_dir.path().join("source");
+        fs::write(&source_path, b"new content").unwrap();
+        fs::write(install_dir.join("myapp"), b"old content").unwrap();
+
+        install_binary(&source_path, &install_dir, "myapp").unwrap();
+
+        let backup = backup_path(&install_dir, "myapp");
+        assert_eq!(backup.file_name().unwrap(), "myapp.previous");
+        assert_eq!(fs::read_to_string(&backup).unwrap(), "old content");
+        assert_eq!(
+            fs::read_to_string(install_dir.join("myapp")).unwrap(),
+            "new content"
+        );
+    }
+
+    #[test]
+    fn test_install_binary_restores_backup_on_failure() {
+        let temp_dir = TempDir::new().unwrap();
+        let install_dir = temp_dir.path().join("bin");
+        fs::create_dir(&install_dir).unwrap();
+        fs::write(install_dir.join("myapp"), b"old content").unwrap();
+
+        // The copy fails: there's nothing to copy
+        let missing = temp_dir.path().join("missing");
+        assert!(install_binary(&missing, &install_dir, "myapp").is_err());
+        assert_eq!(
+            fs::read_to_string(install_dir.join("myapp")).unwrap(),
+            "old content"
+        );
+        assert!(!backup_path(&install_dir, "myapp").exists());
+
+        // Nothing is left behind on a fresh install either
+        assert!(install_binary(&missing, &install_dir, "other").is_err());
+        assert!(!install_dir.join("other").exists());
+    }
+
+    #[test]
+    fn test_install_binary_no_backup_on_fresh_install() {
+        let temp_dir = TempDir::new().unwrap();
+        let install_dir = temp_dir.path().join("bin");
+        let source_path = temp_dir.path().join("source");
+        fs::write(&source_path, b"content").unwrap();
+
+        install_binary(&source_path, &install_dir, "myapp").unwrap();
+
+        assert!(!backup_path(&install_dir, "myapp").exists());
+    }
+
+    #[test]
+    fn test_store_version() {
+        let temp_dir = TempDir::new().unwrap();
+        let versions_dir = temp_dir.path().join("versions/myapp");
+        let source_path = temp_dir.path().join("source");
+        fs::write(&source_path, b"v1").unwrap();
+
+        let stored = store_version(&source_path, &versions_dir, "v1.0.0", "myapp").unwrap();
+        assert_eq!(stored, versions_dir.join("v1.0.0/myapp"));
+        assert_eq!(fs::read_to_string(&stored).unwrap(), "v1");
+
+        // Storing the same version again replaces it
+        fs::write(&source_path, b"v1 rebuilt").unwrap();
+        store_version(&source_path, &versions_dir, "v1.0.0", "myapp").unwrap();
+        assert_eq!(fs::read_to_string(&stored).unwrap(), "v1 rebuilt");
+
+        // A tag with a slash stays one directory
+        let stored = store_version(&source_path, &versions_dir, "cli/v2.0.0", "myapp").unwrap();
+        assert_eq!(stored, versions_dir.join("cli%2Fv2.0.0/myapp"));
+        let stored = store_version(&source_path, &versions_dir, "..", "myapp").unwrap();
+        assert_eq!(stored, versions_dir.join("%2E%2E/myapp"));
+    }
+
+    #[test]
+    fn test_version_dir_name() {
+        assert_eq!(version_dir_name("v1.0.0"), "v1.0.0");
+        assert_eq!(version_dir_name("cli/v1.2.0"), "cli%2Fv1.2.0");
+        assert_eq!(version_dir_name("100%/v1"), "100%25%2Fv1");
+        assert_eq!(version_dir_name("."), "%2E");
+        assert_eq!(version_dir_name(".."), "%2E%2E");
+    }
+
+    #[test]
+    fn test_prune_versions_keeps_newest() {
+        use std::time::{Duration, SystemTime};
+
+        let temp_dir = TempDir::new().unwrap();
+        let versions_dir = temp_dir.path().join("versions");
+        let source_path = temp_dir.path().join("source");
+        fs::write(&source_path, b"bin").unwrap();
+
+        let now = SystemTime::now();
+        for (i, version) in ["v1", "v2", "v3"].iter().enumerate() {
+            store_version(&source_path, &versions_dir, version, "myapp").unwrap();
+            let dir = File::open(versions_dir.join(version)).unwrap();
+            dir.set_modified(now - Duration::from_secs(100 * (3 - i as u64)))
+                .unwrap();
+        }
+
+        let removed = prune_versions(&versions_dir, 2, &[]).unwrap();
+        assert_eq!(removed, vec![versions_dir.join("v1")]);
+        assert!(!versions_dir.join("v1").exists());
+        assert!(versions_dir.join("v2").exists());
+        assert!(versions_dir.join("v3").exists());
+
+        // Kept versions survive and leave room for the others
+        let removed = prune_versions(&versions_dir, 1, &["v2".to_string()]).unwrap();
+        assert!(removed.is_empty());
+        assert!(versions_dir.join("v2").exists());
+
+        // Kept versions are matched by their directory name
+        store_version(&source_path, &versions_dir, "cli/v4", "myapp").unwrap();
+        let removed = prune_versions(&versions_dir, 1, &["cli/v4".to_string()]).unwrap();
+        assert_eq!(removed, vec![versions_dir.join("v2")]);
+        assert!(versions_dir.join("cli%2Fv4").exists());
+    }
+
+    #[test]
+    fn test_prune_versions_missing_dir() {
+        let temp_dir = TempDir::new().unwrap();
+        let removed = prune_versions(&temp_dir.path().join("missing"), 1, &[]).unwrap();
+        assert!(removed.is_empty());
+    }
+
+    #[test]
+    fn test_clone_or_copy_same_filesystem() {
+        let temp_dir = TempDir::new().unwrap();
+        let src = temp_dir.path().join("src");
+        let dest = temp_dir.path().join("dest");
+        fs::write(&src, b"payload").unwrap();
+
+        clone_or_copy(&src, &dest).unwrap();
+        assert_eq!(fs::read_to_string(&dest).unwrap(), "payload");
+    }
+
+    #[test]
+    fn test_clone_or_copy_missing_source() {
+        let temp_dir = TempDir::new().unwrap();
+        let result = clone_or_copy(
+            &temp_dir.path().join("missing"),
+            &temp_dir.path().join("dest"),
+        );
+        assert!(result.is_err());
+        assert!(!temp_dir.path().join("dest").exists());
+    }
+
+    #[test]
+    fn test_store_version_copies_installed_file() {
+        use std::os::unix::fs::MetadataExt;
+
+        let temp_dir = TempDir::new().unwrap();
+        let installed = temp_dir.path().join("myapp");
+        fs::write(&installed, b"bin").unwrap();
+
+        let stored =
+            store_version(&installed, &temp_dir.path().join("versions"), "v1", "myapp").unwrap();
+
+        // A reflink or a copy, never a hardlink to the installed file
+        assert_eq!(fs::read(&stored).unwrap(), b"bin");
+        let installed_meta = fs::metadata(&installed).unwrap();
+        let stored_meta = fs::metadata(&stored).unwrap();
+        assert_ne!(installed_meta.ino(), stored_meta.ino());
+        assert_eq!(installed_meta.nlink(), 1);
+    }
+
+    #[test]
+    fn test_store_bundle() {
+        let temp_dir = TempDir::new().unwrap();
+        let scratch = temp_dir.path().join("scratch");
+        fs::create_dir_all(scratch.join("app-1.0/bin")).unwrap();
+        fs::create_dir_all(scratch.join("app-1.0/lib")).unwrap();
+        fs::write(scratch.join("app-1.0/bin/app"), b"bin").unwrap();
+        fs::write(scratch.join("app-1.0/lib/app.jar"), b"jar").unwrap();
+
+        let bundle_dir = temp_dir.path().join("versions/app/1.0");
+        fs::create_dir_all(&bundle_dir).unwrap();
+        fs::write(bundle_dir.join("stale"), b"old").unwrap();
+
+        let stored = store_bundle(&scratch, &scratch.join("app-1.0/bin/app"), &bundle_dir).unwrap();
+        assert_eq!(stored.binary, bundle_dir.join("app-1.0/bin/app"));
+        assert_eq!(fs::read(&stored.binary).unwrap(), b"bin");
+        assert_eq!(
+            fs::read(bundle_dir.join("app-1.0/lib/app.jar")).unwrap(),
+            b"jar"
+        );
+        assert!(!bundle_dir.join("stale").exists());
+
+        // Rolling back brings the earlier tree back
+        stored.roll_back().unwrap();
+        assert_eq!(fs::read(bundle_dir.join("stale")).unwrap(), b"old");
+        assert!(!bundle_dir.join("app-1.0").exists());
+        assert_eq!(
+            fs::read_dir(bundle_dir.parent().unwrap()).unwrap().count(),
+            1
+        );
+
+        assert!(store_bundle(&scratch, temp_dir.path(), &bundle_dir).is_err());
+        assert_eq!(fs::read(bundle_dir.join("stale")).unwrap(), b"old");
+    }
+
+    #[test]
+    fn test_store_bundle_commit() {
+        let temp_dir = TempDir::new().unwrap();
+        let scratch = temp_dir.path().join("scratch");
+        fs::create_dir_all(&scratch).unwrap();
+        fs::write(scratch.join("app"), b"new").unwrap();
+
+        let bundle_dir = temp_dir.path().join("versions/app/1.0");
+        fs::create_dir_all(&bundle_dir).unwrap();
+        fs::write(bundle_dir.join("app"), b"old").unwrap();
+
+        let stored = store_bundle(&scratch, &scratch.join("app"), &bundle_dir).unwrap();
+        stored.commit().unwrap();
+        assert_eq!(fs::read(bundle_dir.join("app")).unwrap(), b"new");
+        // Neither the staging tree nor the old one is left behind
+        assert_eq!(
+            fs::read_dir(bundle_dir.parent().unwrap()).unwrap().count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_copy_tree() {
+        let temp_dir = TempDir::new().unwrap();
+        let src = temp_dir.path().join("src");
+        fs::create_dir_all(src.join("lib")).unwrap();
+        fs::write(src.join("lib/data"), b"data").unwrap();
+        std::os::unix::fs::symlink("lib/data", src.join("link")).unwrap();
+
+        let dest = temp_dir.path().join("dest");
+        copy_tree(&src, &dest).unwrap();
+        assert_eq!(fs::read(dest.join("lib/data")).unwrap(), b"data");
+        assert_eq!(
+            fs::read_link(dest.join("link")).unwrap(),
+            Path::new("lib/data")
+        );
+    }
+
+    #[test]
+    fn test_install_wrapper() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = TempDir::new().unwrap();
+        let bundle = temp_dir.path().join("it's bundled");
+        fs::create_dir_all(&bundle).unwrap();
+        let target = bundle.join("app");
+        fs::write(&target, "#!/bin/sh\necho \"ran with $*\"\n").unwrap();
+        fs::set_permissions(&target, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let install_dir = temp_dir.path().join("bin");
+        fs::create_dir_all(&install_dir).unwrap();
+        fs::write(install_dir.join("app"), b"old").unwrap();
+
+        let wrapper = install_wrapper(&target, &install_dir, "app").unwrap();
+        assert_eq!(fs::read(backup_path(&install_dir, "app")).unwrap(), b"old");
+        let output = std::process::Command::new(&wrapper)
+            .args(["a", "b c"])
+            .output()
+            .unwrap();
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "ran with a b c\n");
+
+        assert_eq!(wrapper_target(&wrapper), Some(target));
+        assert_eq!(wrapper_target(&backup_path(&install_dir, "app")), None);
+    }
+
+    #[test]
+    fn test_sha256_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("data");
+        fs::write(&path, b"hello").unwrap();
+
+        assert_eq!(
+            sha256_file(&path).unwrap(),
+            "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+        );
+        assert!(sha256_file(&temp_dir.path().join("missing")).is_err());
+    }
+
+    #[test]
+    fn test_extract_version() {
+        assert_eq!(
+            extract_version("ripgrep 14.1.0 (rev abc)"),
+            Some("14.1.0".to_string())
+        );
+        assert_eq!(extract_version("k9s v0.32.5"), Some("0.32.5".to_string()));
+        assert_eq!(extract_version("tool version 1.2"), Some("1.2".to_string()));
+        assert_eq!(extract_version("no version here"), None);
+        assert_eq!(extract_version("build 42"), None);
+    }
+
+    #[test]
+    fn test_probe_version_script() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = TempDir::new().unwrap();
+        let script = temp_dir.path().join("fake");
+        fs::write(&script, "#!/bin/sh\necho \"fake version v2.3.4\"\n").unwrap();
+        let mut perms = fs::metadata(&script).unwrap().permissions();
+        perms.set_mode(0o755);
+        fs::set_permissions(&script, perms).unwrap();
+
+        assert_eq!(probe_version(&script), Some("2.3.4".to_string()));
+        assert_eq!(probe_version(&temp_dir.path().join("missing")), None);
+
+        // More output than a pipe holds doesn't stall it until the timeout
+        fs::write(
+            &script,
+            "#!/bin/sh\necho \"chatty 1.5.0\"\nhead -c 300000 /dev/zero | tr '\\0' x\n\
+             head -c 300000 /dev/zero | tr '\\0' y >&2\n",
+        )
+        .unwrap();
+        let started = std::time::Instant::now();
+        assert_eq!(probe_version(&script), Some("1.5.0".to_string()));
+        assert!(started.elapsed() < std::time::Duration::from_secs(4));
+    }
+
+    #[test]
+    fn test_smoke_test() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = TempDir::new().unwrap();
+        let script = |name: &str, body: &str| {
+            let path = temp_dir.path().join(name);
+            fs::write(&path, format!("#!/bin/sh\n{}\n", body)).unwrap();
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+            path
+        };
+
+        assert!(smoke_test(&script("ok", "echo 1.0.0")).is_ok());
+        // Not knowing --version is fine
+        assert!(smoke_test(&script("picky", "echo unknown flag >&2; exit 2")).is_ok());
+
+        let err = smoke_test(&script("crash", "kill -SEGV $$")).unwrap_err();
+        assert_eq!(err, "segmentation fault");
+        let err = smoke_test(&script("noexec", "echo cannot execute >&2; exit 126")).unwrap_err();
+        assert_eq!(err, "exited with status 126: cannot execute");
+        assert!(smoke_test(&temp_dir.path().join("missing")).is_err());
+    }
+
+    #[test]
+    fn test_restore_backup() {
+        let temp_dir = TempDir::new().unwrap();
+        let install_dir = temp_dir.path();
+        fs::write(install_dir.join("myapp"), "new").unwrap();
+        fs::write(backup_path(install_dir, "myapp"), "old").unwrap();
+
+        restore_backup(install_dir, "myapp").unwrap();
+        assert_eq!(
+            fs::read_to_string(install_dir.join("myapp")).unwrap(),
+            "old"
+        );
+        assert!(!backup_path(install_dir, "myapp").exists());
+    }
 }
@@ -0,0 +1,218 @@
+//! Asset names from cargo-binstall metadata. Rust projects can spell out
+//! their release asset URLs under `[package.metadata.binstall]` in
+//! Cargo.toml, which makes matching assets by name unnecessary. Crates
+//! without usable releases may have a build from the quickinstall service.
+
+use toml::Value;
+
+/// Targets tried in order, matching what cargo-binstall picks on Linux x86_64.
+const TARGETS: &[&str] = &["x86_64-unknown-linux-gnu", "x86_64-unknown-linux-musl"];
+
+/// Repository the quickinstall service publishes its prebuilt crates in.
+pub const QUICKINSTALL_REPO: &str = "cargo-bins/cargo-quickinstall";
+
+/// File name suffixes binstall tries for a `pkg-fmt`, preferred first.
+fn archive_suffixes(pkg_fmt: &str) -> &'static [&'static str] {
+    match pkg_fmt {
+        "tgz" => &[".tgz", ".tar.gz"],
+        "tar" => &[".tar"],
+        "tbz2" => &[".tbz2", ".tar.bz2"],
+        "txz" => &[".txz", ".tar.xz"],
+        "tzstd" => &[".tzstd", ".tzst", ".tar.zst"],
+        "zip" => &[".zip"],
+        "bin" => &[".bin", ""],
+        _ => &[],
+    }
+}
+
+/// Fills in a `{ name }` style template. `None` if it uses a variable
+/// that isn't in `vars`.
+fn render(template: &str, vars: &[(&str, &str)]) -> Option<String> {
+    let mut out = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let end = rest[start..].find('}')? + start;
+        let key = rest[start + 1..end].trim();
+        let (_, value) = vars.iter().find(|(k, _)| *k == key)?;
+        out.push_str(value);
+        rest = &rest[end + 1..];
+    }
+    out.push_str(rest);
+    Some(out)
+}
+
+fn string_at<'a>(table: &'a Value, key: &str) -> Option<&'a str> {
+    table.get(key).and_then(Value::as_str)
+}
+
+/// The `[package]` table of a manifest.
+fn package(cargo_toml: &str) -> Option<Value> {
+    cargo_toml.parse::<Value>().ok()?.get("package").cloned()
+}
+
+/// Release asset file names the binstall metadata in `cargo_toml` describes
+/// for this platform, most preferred first. `version` stands in when the
+/// package inherits its version from a workspace. Empty when the manifest
+/// has no `pkg-url`.
+pub fn asset_names(cargo_toml: &str, version: &str) -> Vec<String> {
+    let Some(package) = package(cargo_toml) else {
+        return Vec::new();
+    };
+    let Some(meta) = package.get("metadata").and_then(|m| m.get("binstall")) else {
+        return Vec::new();
+    };
+    let name = string_at(&package, "name").unwrap_or_default();
+    let version = string_at(&package, "version").unwrap_or(version);
+    let repo = string_at(&package, "repository").unwrap_or_default();
+
+    let mut names = Vec::new();
+    for target in TARGETS {
+        let overrides = meta.get("overrides").and_then(|o| o.get(*target));
+        let setting = |key: &str| {
+            overrides
+                .and_then(|o| string_at(o, key))
+                .or_else(|| string_at(meta, key))
+        };
+        let Some(pkg_url) = setting("pkg-url") else {
+            continue;
+        };
+        let pkg_fmt = setting("pkg-fmt").unwrap_or("tgz");
+        let libc = target.rsplit('-').next().unwrap_or_default();
+
+        for suffix in archive_suffixes(pkg_fmt) {
+            let format = suffix.trim_start_matches('.');
+            let vars = [
+                ("name", name),
+                ("version", version),
+                ("repo", repo),
+                ("target", target),
+                ("target-arch", "x86_64"),
+                ("target-family", "unix"),
+                ("target-libc", libc),
+                ("archive-format", format),
+                ("format", format),
+                ("archive-suffix", suffix),
+                ("binary-ext", ""),
+            ];
+            if let Some(url) = render(pkg_url, &vars)
+                && let Some(file) = url.rsplit('/').next().filter(|f| !f.is_empty())
+                && !names.iter().any(|n| n == file)
+            {
+                names.push(file.to_string());
+            }
+        }
+    }
+    names
+}
+
+/// Tag of quickinstall's release of a crate, and the asset names it uses
+/// for this platform, preferred first. The crate is named in `cargo_toml`,
+/// or else assumed to be `fallback_name`.
+pub fn quickinstall_release(
+    cargo_toml: &str,
+    fallback_name: &str,
+    version: &str,
+) -> (String, Vec<String>) {
+    let package = package(cargo_toml);
+    let name = package
+        .as_ref()
+        .and_then(|p| string_at(p, "name"))
+        .unwrap_or(fallback_name);
+    let version = package
+        .as_ref()
+        .and_then(|p| string_at(p, "version"))
+        .unwrap_or(version);
+
+    let tag = format!("{}-{}", name, version);
+    let names = TARGETS
+        .iter()
+        .map(|target| format!("{}-{}.tar.gz", tag, target))
+        .collect();
+    (tag, names)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let vars = [("name", "tool"), ("version", "1.2.0")];
+        assert_eq!(
+            render("{ name }-v{version}.tgz", &vars).as_deref(),
+            Some("tool-v1.2.0.tgz")
+        );
+        assert_eq!(render("{ bin }", &vars), None);
+        assert_eq!(render("{ name", &vars), None);
+    }
+
+    #[test]
+    fn test_asset_names() {
+        let manifest = r#"
+[package]
+name = "tool"
+version = "1.2.0"
+repository = "https://github.com/owner/tool"
+
+[package.metadata.binstall]
+pkg-url = "{ repo }/releases/download/v{ version }/{ name }-{ target }{ archive-suffix }"
+"#;
+        assert_eq!(
+            asset_names(manifest, "ignored"),
+            vec![
+                "tool-x86_64-unknown-linux-gnu.tgz",
+                "tool-x86_64-unknown-linux-gnu.tar.gz",
+                "tool-x86_64-unknown-linux-musl.tgz",
+                "tool-x86_64-unknown-linux-musl.tar.gz",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_asset_names_overrides_and_workspace_version() {
+        let manifest = r#"
+[package]
+name = "tool"
+version.workspace = true
+
+[package.metadata.binstall]
+pkg-url = "https://example.com/{ name }-{ version }-{ target }.{ archive-format }"
+pkg-fmt = "zip"
+
+[package.metadata.binstall.overrides.x86_64-unknown-linux-gnu]
+pkg-fmt = "bin"
+pkg-url = "https://example.com/{ name }-{ version }-linux{ binary-ext }"
+"#;
+        assert_eq!(
+            asset_names(manifest, "2.0.0"),
+            vec![
+                "tool-2.0.0-linux",
+                "tool-2.0.0-x86_64-unknown-linux-musl.zip",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_quickinstall_release() {
+        let manifest = "[package]\nname = \"tool-cli\"\nversion = \"1.2.0\"\n";
+        assert_eq!(
+            quickinstall_release(manifest, "tool", "ignored"),
+            (
+                "tool-cli-1.2.0".to_string(),
+                vec![
+                    "tool-cli-1.2.0-x86_64-unknown-linux-gnu.tar.gz".to_string(),
+                    "tool-cli-1.2.0-x86_64-unknown-linux-musl.tar.gz".to_string(),
+                ]
+            )
+        );
+        assert_eq!(quickinstall_release("", "tool", "2.0.0").0, "tool-2.0.0");
+    }
+
+    #[test]
+    fn test_asset_names_without_metadata() {
+        assert!(asset_names("[package]\nname = \"tool\"\n", "1.0.0").is_empty());
+        assert!(asset_names("[workspace]\nmembers = []\n", "1.0.0").is_empty());
+        assert!(asset_names("not toml", "1.0.0").is_empty());
+    }
+}
@@ -0,0 +1,112 @@
+//! On-disk cache for GitHub API responses.
+//!
+//! Entries are keyed by request URL and expire after a fixed TTL measured
+//! from when they were written. The cache is best effort: any failure to
+//! read or write an entry is treated as a miss.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+/// A cached response body along with the next page link it carried.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CachedResponse {
+    pub url: String,
+    pub body: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next: Option<String>,
+}
+
+pub struct ResponseCache {
+    dir: PathBuf,
+    ttl: Duration,
+}
+
+impl ResponseCache {
+    pub fn new(dir: impl Into<PathBuf>, ttl: Duration) -> Self {
+        Self {
+            dir: dir.into(),
+            ttl,
+        }
+    }
+
+    fn entry_path(&self, url: &str) -> PathBuf {
+        let digest = Sha256::digest(url.as_bytes());
+        let key: String = digest.iter().map(|b| format!("{:02x}", b)).collect();
+        self.dir.join(format!("{}.json", key))
+    }
+
+    /// Returns the cached response for `url` if one was stored within the TTL.
+    pub fn get(&self, url: &str) -> Option<CachedResponse> {
+        let path = self.entry_path(url);
+        let modified = fs::metadata(&path).and_then(|m| m.modified()).ok()?;
+        let age = SystemTime::now()
+            .duration_since(modified)
+            .unwrap_or_default();
+        if age > self.ttl {
+            return None;
+        }
+
+        let entry: CachedResponse = serde_json::from_slice(&fs::read(&path).ok()?).ok()?;
+        // Guard against hash collisions and hand-edited files
+        (entry.url == url).then_some(entry)
+    }
+
+    pub fn put(&self, entry: &CachedResponse) {
+        let Ok(data) = serde_json::to_vec(entry) else {
+            return;
+        };
+        if fs::create_dir_all(&self.dir).is_ok() {
+            let _ = fs::write(self.entry_path(&entry.url), data);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn entry(url: &str) -> CachedResponse {
+        CachedResponse {
+            url: url.to_string(),
+            body: "{}".to_string(),
+            next: Some(format!("{}&page=2", url)),
+        }
+    }
+
+    #[test]
+    fn test_cache_roundtrip() {
+        let temp_dir = TempDir::new().unwrap();
+        let cache = ResponseCache::new(temp_dir.path().join("http"), Duration::from_secs(60));
+
+        assert_eq!(cache.get("https://example.com/a"), None);
+        cache.put(&entry("https://example.com/a"));
+        assert_eq!(
+            cache.get("https://example.com/a"),
+            Some(entry("https://example.com/a"))
+        );
+        assert_eq!(cache.get("https://example.com/b"), None);
+    }
+
+    #[test]
+    fn test_cache_expired_entry() {
+        let temp_dir = TempDir::new().unwrap();
+        let cache = ResponseCache::new(temp_dir.path(), Duration::ZERO);
+
+        cache.put(&entry("https://example.com/a"));
+        std::thread::sleep(Duration::from_millis(10));
+        assert_eq!(cache.get("https://example.com/a"), None);
+    }
+
+    #[test]
+    fn test_cache_ignores_corrupt_entry() {
+        let temp_dir = TempDir::new().unwrap();
+        let cache = ResponseCache::new(temp_dir.path(), Duration::from_secs(60));
+
+        fs::write(cache.entry_path("https://example.com/a"), "not json").unwrap();
+        assert_eq!(cache.get("https://example.com/a"), None);
+    }
+}
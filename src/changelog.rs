@@ -0,0 +1,141 @@
+use crate::config::{Config, Tool};
+use crate::error::{OktofetchError, Result};
+use crate::github::Release;
+use crate::source;
+use crate::version;
+use std::cmp::Ordering;
+
+/// Releases newer than the installed version, oldest first. Without an
+/// installed version only the newest release is returned.
+pub fn releases_since<'a>(tool: &Tool, releases: &'a [Release]) -> Vec<&'a Release> {
+    let Some(installed) = &tool.version else {
+        return releases.iter().take(1).collect();
+    };
+
+    let mut newer: Vec<&Release> = releases
+        .iter()
+        .filter(|r| {
+            version::compare(&tool.version_from_tag(&r.tag_name), installed) == Ordering::Greater
+        })
+        .collect();
+    newer.sort_by(|a, b| {
+        version::compare(
+            &tool.version_from_tag(&a.tag_name),
+            &tool.version_from_tag(&b.tag_name),
+        )
+    });
+    newer
+}
+
+/// Renders release notes as consecutive sections headed by tag and title.
+pub fn render(releases: &[&Release]) -> String {
+    let mut out = String::new();
+    for release in releases {
+        out.push_str(&format!("## {}", release.tag_name));
+        if !release.name.is_empty() && release.name != release.tag_name {
+            out.push_str(&format!(" ({})", release.name));
+        }
+        out.push_str("\n\n");
+
+        match release.body.as_deref().map(str::trim) {
+            Some(body) if !body.is_empty() => out.push_str(body),
+            _ => out.push_str("No release notes."),
+        }
+        out.push_str("\n\n");
+    }
+    out
+}
+
+pub async fn show_changelog(config: &Config, name: &str) -> Result<()> {
+    let tool = config
+        .get_tool(name)
+        .ok_or_else(|| OktofetchError::ToolNotFound(name.to_string()))?;
+
+    let source = source::for_tool(tool, &config.settings, &config.http())?;
+    let channel = tool.channel.unwrap_or_default();
+    let releases = source
+        .releases(tool.release_repo(), &channel.list_options())
+        .await?;
+
+    let pending = releases_since(tool, &releases);
+    if pending.is_empty() {
+        println!(
+            "{} is up to date ({})",
+            tool.name,
+            tool.version.as_deref().unwrap_or("unknown")
+        );
+        return Ok(());
+    }
+
+    print!("{}", render(&pending));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn release(tag: &str, body: Option<&str>) -> Release {
+        Release {
+            id: 0,
+            tag_name: tag.to_string(),
+            name: tag.to_string(),
+            assets: vec![],
+            prerelease: false,
+            draft: false,
+            body: body.map(str::to_string),
+            published_at: None,
+        }
+    }
+
+    #[test]
+    fn test_releases_since_installed() {
+        let releases = vec![
+            release("v1.3.0", None),
+            release("v1.10.0", None),
+            release("v1.2.0", None),
+            release("v1.1.0", None),
+        ];
+        let mut tool = Tool {
+            name: "mytool".to_string(),
+            version: Some("v1.2.0".to_string()),
+            ..Default::default()
+        };
+
+        let tags: Vec<&str> = releases_since(&tool, &releases)
+            .iter()
+            .map(|r| r.tag_name.as_str())
+            .collect();
+        assert_eq!(tags, vec!["v1.3.0", "v1.10.0"]);
+
+        tool.version = Some("v1.10.0".to_string());
+        assert!(releases_since(&tool, &releases).is_empty());
+
+        tool.version = None;
+        assert_eq!(releases_since(&tool, &releases)[0].tag_name, "v1.3.0");
+    }
+
+    #[test]
+    fn test_releases_since_with_tag_format() {
+        let releases = vec![release("tool/v2.0.0", None), release("tool/v1.0.0", None)];
+        let tool = Tool {
+            name: "tool".to_string(),
+            version: Some("1.0.0".to_string()),
+            tag_format: Some("tool/v{version}".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(releases_since(&tool, &releases).len(), 1);
+    }
+
+    #[test]
+    fn test_render() {
+        let a = release("v1.1.0", Some("- Fixed a bug\n"));
+        let mut b = release("v1.2.0", Some("  "));
+        b.name = "Big release".to_string();
+
+        assert_eq!(
+            render(&[&a, &b]),
+            "## v1.1.0\n\n- Fixed a bug\n\n## v1.2.0 (Big release)\n\nNo release notes.\n\n"
+        );
+    }
+}
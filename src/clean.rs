@@ -0,0 +1,390 @@
+use crate::binary;
+use crate::config::{Config, Settings};
+use crate::error::{OktofetchError, Result};
+use crate::human;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// What `oktofetch clean` removes. All three when none is selected.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CleanTargets {
+    /// Leftover downloads from interrupted updates
+    pub downloads: bool,
+    /// Cached API responses
+    pub metadata: bool,
+    /// `.previous` backups and retained versions other than the installed one
+    pub backups: bool,
+}
+
+impl CleanTargets {
+    fn resolve(self) -> Self {
+        if self == Self::default() {
+            Self {
+                downloads: true,
+                metadata: true,
+                backups: true,
+            }
+        } else {
+            self
+        }
+    }
+}
+
+/// Total size in bytes of a file or directory tree.
+pub fn disk_usage(path: &Path) -> u64 {
+    let Ok(metadata) = fs::symlink_metadata(path) else {
+        return 0;
+    };
+    if !metadata.is_dir() {
+        return metadata.len();
+    }
+
+    fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .map(|e| disk_usage(&e.path()))
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+/// Removes a file or directory tree, returning the bytes freed.
+fn remove(path: &Path) -> Result<u64> {
+    let size = disk_usage(path);
+    if fs::symlink_metadata(path)?.is_dir() {
+        fs::remove_dir_all(path)?;
+    } else {
+        fs::remove_file(path)?;
+    }
+    Ok(size)
+}
+
+/// Removes everything inside `dir`, keeping the directory itself.
+fn empty_dir(dir: &Path) -> Result<u64> {
+    if !dir.is_dir() {
+        return Ok(0);
+    }
+
+    let mut freed = 0;
+    for entry in fs::read_dir(dir)? {
+        freed += remove(&entry?.path())?;
+    }
+    Ok(freed)
+}
+
+/// Bounds the download and API response caches are pruned to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheLimits {
+    /// Seconds a cached file may go unmodified
+    pub max_age: Option<u64>,
+    /// Total bytes across all cache directories
+    pub max_size: Option<u64>,
+}
+
+impl CacheLimits {
+    pub fn from_settings(settings: &Settings) -> Self {
+        Self {
+            max_age: settings.cache_max_age,
+            max_size: settings.cache_max_size,
+        }
+    }
+
+    fn is_unlimited(self) -> bool {
+        self == Self::default()
+    }
+}
+
+/// Collects every file below `dir` with its size and modification time.
+fn cache_files(dir: &Path, files: &mut Vec<(SystemTime, u64, PathBuf)>) -> Result<()> {
+    if !dir.is_dir() {
+        return Ok(());
+    }
+
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        if metadata.is_dir() {
+            cache_files(&entry.path(), files)?;
+        } else {
+            files.push((metadata.modified()?, metadata.len(), entry.path()));
+        }
+    }
+    Ok(())
+}
+
+/// Removes the empty directories below `dir`, keeping `dir` itself.
+fn remove_empty_dirs(dir: &Path) -> Result<()> {
+    if !dir.is_dir() {
+        return Ok(());
+    }
+
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            remove_empty_dirs(&path)?;
+            if fs::read_dir(&path)?.next().is_none() {
+                fs::remove_dir(&path)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Deletes cached files older than the age limit, then the oldest remaining
+/// ones until the caches fit the size limit. Returns the bytes freed.
+pub fn prune_cache(dirs: &[PathBuf], limits: CacheLimits, now: SystemTime) -> Result<u64> {
+    let mut files = Vec::new();
+    for dir in dirs {
+        cache_files(dir, &mut files)?;
+    }
+    // Oldest first
+    files.sort();
+
+    let mut total: u64 = files.iter().map(|(_, size, _)| size).sum();
+    let mut freed = 0;
+    for (modified, size, path) in files {
+        let age = now.duration_since(modified).unwrap_or_default();
+        let expired = limits
+            .max_age
+            .is_some_and(|max| age > Duration::from_secs(max));
+        let oversized = limits.max_size.is_some_and(|max| total > max);
+        if !expired && !oversized {
+            break;
+        }
+
+        fs::remove_file(&path)?;
+        total -= size;
+        freed += size;
+    }
+
+    for dir in dirs {
+        remove_empty_dirs(dir)?;
+    }
+    Ok(freed)
+}
+
+/// Prunes the caches to the configured limits. Returns `None` when no limit
+/// is set, so there was nothing to do.
+pub fn auto_prune(settings: &Settings) -> Result<Option<u64>> {
+    let limits = CacheLimits::from_settings(settings);
+    if limits.is_unlimited() {
+        return Ok(None);
+    }
+
+    let dirs = [Config::downloads_dir()?, Config::cache_dir()?.join("http")];
+    prune_cache(&dirs, limits, SystemTime::now()).map(Some)
+}
+
+/// `oktofetch clean --auto`: prunes the caches to the configured limits.
+pub fn clean_auto(config: &Config) -> Result<()> {
+    let freed = auto_prune(&config.settings)?.ok_or_else(|| {
+        OktofetchError::Other("Set cache_max_age or cache_max_size to use clean --auto".to_string())
+    })?;
+    println!("Pruned caches: {}", human::format_size(freed));
+    Ok(())
+}
+
+/// Backups that are safe to delete: the `.previous` copies oktofetch made of
+/// configured tools in the install directory, and retained versions other
+/// than each tool's installed and kept ones. Other files in the install
+/// directory are never touched, whatever their name.
+pub fn stale_backups(config: &Config, versions_dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut paths: Vec<PathBuf> = config
+        .tools
+        .iter()
+        .map(|tool| binary::backup_path(&config.install_dir_for(tool), tool.installed_name()))
+        .filter(|path| path.is_file())
+        .collect();
+
+    if versions_dir.is_dir() {
+        for tool_entry in fs::read_dir(versions_dir)? {
+            let tool_entry = tool_entry?;
+            let name = tool_entry.file_name().to_string_lossy().to_string();
+            let tool = config.get_tool(&name);
+            let retained: Vec<String> = tool
+                .into_iter()
+                .flat_map(|t| t.version.iter().chain(&t.kept_versions))
+                .map(|version| binary::version_dir_name(version))
+                .collect();
+
+            if !tool_entry.file_type()?.is_dir() {
+                continue;
+            }
+            for version_entry in fs::read_dir(tool_entry.path())? {
+                let version_entry = version_entry?;
+                let version = version_entry.file_name().to_string_lossy().to_string();
+                if !retained.contains(&version) {
+                    paths.push(version_entry.path());
+                }
+            }
+        }
+    }
+
+    paths.sort();
+    paths.dedup();
+    Ok(paths)
+}
+
+pub fn clean(config: &Config, targets: CleanTargets) -> Result<()> {
+    let targets = targets.resolve();
+    let mut total = 0;
+
+    if targets.downloads {
+        let freed = empty_dir(&Config::downloads_dir()?)?;
+        println!("Removed downloads: {}", human::format_size(freed));
+        total += freed;
+    }
+
+    if targets.metadata {
+        let freed = empty_dir(&Config::cache_dir()?.join("http"))?;
+        println!(
+            "Removed cached API responses: {}",
+            human::format_size(freed)
+        );
+        total += freed;
+    }
+
+    if targets.backups {
+        let mut freed = 0;
+        for path in stale_backups(config, &Config::versions_dir()?)? {
+            freed += remove(&path)?;
+        }
+        println!("Removed backups: {}", human::format_size(freed));
+        total += freed;
+    }
+
+    println!("Reclaimed {} in total", human::format_size(total));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Tool;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_clean_targets_default_to_all() {
+        let all = CleanTargets::default().resolve();
+        assert!(all.downloads && all.metadata && all.backups);
+
+        let only = CleanTargets {
+            metadata: true,
+            ..Default::default()
+        }
+        .resolve();
+        assert!(only.metadata && !only.downloads && !only.backups);
+    }
+
+    #[test]
+    fn test_disk_usage_and_empty_dir() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::create_dir_all(temp_dir.path().join("a/b")).unwrap();
+        fs::write(temp_dir.path().join("a/one"), "12345").unwrap();
+        fs::write(temp_dir.path().join("a/b/two"), "123").unwrap();
+
+        assert_eq!(disk_usage(temp_dir.path()), 8);
+        assert_eq!(empty_dir(temp_dir.path()).unwrap(), 8);
+        assert!(temp_dir.path().is_dir());
+        assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 0);
+        assert_eq!(empty_dir(&temp_dir.path().join("missing")).unwrap(), 0);
+    }
+
+    fn write_aged(path: &Path, content: &str, age_days: u64) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+        let modified = SystemTime::now() - Duration::from_secs(age_days * 86400);
+        fs::File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_prune_cache_by_age() {
+        let temp_dir = TempDir::new().unwrap();
+        let downloads = temp_dir.path().join("downloads");
+        let http = temp_dir.path().join("http");
+        write_aged(&downloads.join("k9s/v1.0.0/k9s.tar.gz"), "12345", 40);
+        write_aged(&downloads.join("k9s/v2.0.0/k9s.tar.gz"), "12345", 1);
+        write_aged(&http.join("old"), "123", 31);
+
+        let limits = CacheLimits {
+            max_age: Some(30 * 86400),
+            max_size: None,
+        };
+        let freed = prune_cache(
+            &[downloads.clone(), http.clone()],
+            limits,
+            SystemTime::now(),
+        );
+
+        assert_eq!(freed.unwrap(), 8);
+        assert!(!downloads.join("k9s/v1.0.0").exists());
+        assert!(downloads.join("k9s/v2.0.0/k9s.tar.gz").exists());
+        assert!(http.is_dir());
+    }
+
+    #[test]
+    fn test_prune_cache_by_size() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        write_aged(&dir.join("oldest"), "1234", 3);
+        write_aged(&dir.join("older"), "1234", 2);
+        write_aged(&dir.join("newest"), "1234", 1);
+
+        let limits = CacheLimits {
+            max_age: None,
+            max_size: Some(5),
+        };
+        assert_eq!(
+            prune_cache(&[dir.to_path_buf()], limits, SystemTime::now()).unwrap(),
+            8
+        );
+        assert!(dir.join("newest").exists());
+        assert!(!dir.join("older").exists());
+    }
+
+    #[test]
+    fn test_stale_backups() {
+        let temp_dir = TempDir::new().unwrap();
+        let install_dir = temp_dir.path().join("bin");
+        let versions_dir = temp_dir.path().join("versions");
+        fs::create_dir_all(&install_dir).unwrap();
+        fs::write(install_dir.join("k9s"), "new").unwrap();
+        fs::write(install_dir.join("k9s.previous"), "old").unwrap();
+        // Not oktofetch's to delete
+        fs::write(install_dir.join("notes.previous"), "mine").unwrap();
+        for version in ["v0.9.0", "v1.0.0", "v2.0.0"] {
+            fs::create_dir_all(versions_dir.join("k9s").join(version)).unwrap();
+        }
+        fs::create_dir_all(versions_dir.join("gone/v0.1.0")).unwrap();
+        fs::create_dir_all(versions_dir.join("k9s/cli%2Fv0.5.0")).unwrap();
+
+        let mut config = Config::default();
+        config.settings.install_dir = install_dir.clone();
+        config
+            .add_tool(Tool {
+                name: "k9s".to_string(),
+                repo: "owner/k9s".to_string(),
+                version: Some("v2.0.0".to_string()),
+                kept_versions: vec!["v0.9.0".to_string(), "cli/v0.5.0".to_string()],
+                ..Default::default()
+            })
+            .unwrap();
+
+        let stale = stale_backups(&config, &versions_dir).unwrap();
+        assert_eq!(
+            stale,
+            vec![
+                install_dir.join("k9s.previous"),
+                versions_dir.join("gone/v0.1.0"),
+                versions_dir.join("k9s/v1.0.0"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,209 @@
+//! Shell completion scripts shipped inside release archives.
+//!
+//! Completions found while extracting a tool are copied to one directory per
+//! shell under [`Config::completions_dir`](crate::config::Config::completions_dir),
+//! next to small loader scripts that `oktofetch env` sources.
+
+use crate::error::Result;
+use crate::shell::Shell;
+use std::path::{Path, PathBuf};
+
+const SHELLS: [Shell; 3] = [Shell::Bash, Shell::Zsh, Shell::Fish];
+
+/// Which shell an extracted file is a completion script for, judged by its
+/// name: `*.bash`, `*.zsh`, `*.fish`, `_<tool>`, or anything under a path
+/// mentioning "completion".
+pub fn classify(path: &str, tool: &str) -> Option<Shell> {
+    let path = Path::new(path);
+    let file_name = path.file_name()?.to_str()?;
+
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("bash") => return Some(Shell::Bash),
+        Some("zsh") => return Some(Shell::Zsh),
+        Some("fish") => return Some(Shell::Fish),
+        _ => {}
+    }
+    if file_name == format!("_{}", tool) {
+        return Some(Shell::Zsh);
+    }
+
+    // Docs and other files next to the scripts aren't completions
+    let lower = path.to_string_lossy().to_lowercase();
+    if !lower.contains("complet") || path.extension().is_some_and(|e| e != "sh") {
+        return None;
+    }
+    // e.g. completions/zsh/_tool or contrib/completion/tool-completion.bash
+    let in_dir = |name: &str| path.components().any(|c| c.as_os_str() == name);
+    if in_dir("zsh") || file_name.starts_with('_') {
+        Some(Shell::Zsh)
+    } else if in_dir("fish") {
+        Some(Shell::Fish)
+    } else {
+        Some(Shell::Bash)
+    }
+}
+
+/// File name a completion is installed under, following each shell's
+/// lookup convention for `command`.
+fn installed_name(shell: Shell, command: &str) -> String {
+    match shell {
+        Shell::Bash => command.to_string(),
+        Shell::Zsh => format!("_{}", command),
+        Shell::Fish => format!("{}.fish", command),
+    }
+}
+
+/// Copies the completion scripts among `extracted_files` into `dir`, at most
+/// one per shell, named for `command`. `tool` is the binary name used to
+/// recognise zsh `_<tool>` files. Returns the installed paths.
+pub fn install(
+    extracted_files: &[String],
+    extract_dir: &Path,
+    tool: &str,
+    command: &str,
+    dir: &Path,
+) -> Result<Vec<PathBuf>> {
+    let mut installed = Vec::new();
+
+    for shell in SHELLS {
+        let Some(file) = extracted_files
+            .iter()
+            .find(|f| extract_dir.join(f).is_file() && classify(f, tool) == Some(shell))
+        else {
+            continue;
+        };
+
+        let shell_dir = dir.join(shell.name());
+        std::fs::create_dir_all(&shell_dir)?;
+        let dest = shell_dir.join(installed_name(shell, command));
+        std::fs::copy(extract_dir.join(file), &dest)?;
+        installed.push(dest);
+    }
+
+    if !installed.is_empty() {
+        write_loaders(dir)?;
+    }
+    Ok(installed)
+}
+
+/// Deletes the completion scripts installed in `dir` for `command`, e.g.
+/// when its tool is removed. Returns the deleted paths.
+pub fn remove(command: &str, dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut removed = Vec::new();
+    for shell in SHELLS {
+        let path = dir.join(shell.name()).join(installed_name(shell, command));
+        if path.is_file() {
+            std::fs::remove_file(&path)?;
+            removed.push(path);
+        }
+    }
+    Ok(removed)
+}
+
+/// Writes `oktofetch.<shell>` scripts loading every installed completion.
+fn write_loaders(dir: &Path) -> Result<()> {
+    for shell in SHELLS {
+        let shell_dir = dir.join(shell.name());
+        let shell_dir = shell_dir.display();
+        let script = match shell {
+            Shell::Bash => {
+                format!("for f in \"{shell_dir}\"/*; do\n  [ -r \"$f\" ] && . \"$f\"\ndone\n")
+            }
+            Shell::Zsh => format!(
+                "fpath=(\"{shell_dir}\" $fpath)\n\
+                 if (( $+functions[compdef] )); then\n  \
+                 for f in \"{shell_dir}\"/_*(N); do\n    \
+                 autoload -Uz ${{f:t}} && compdef ${{f:t}} ${{${{f:t}}#_}}\n  \
+                 done\nfi\n"
+            ),
+            Shell::Fish => format!("for f in \"{shell_dir}\"/*.fish\n    source $f\nend\n"),
+        };
+        std::fs::write(dir.join(format!("oktofetch.{}", shell.name())), script)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(classify("tool.bash", "tool"), Some(Shell::Bash));
+        assert_eq!(classify("completions/tool.fish", "tool"), Some(Shell::Fish));
+        assert_eq!(classify("autocomplete/tool.zsh", "tool"), Some(Shell::Zsh));
+        assert_eq!(classify("tool-1.0/_tool", "tool"), Some(Shell::Zsh));
+        assert_eq!(classify("completions/zsh/tool", "tool"), Some(Shell::Zsh));
+        assert_eq!(classify("completion/fish/tool", "tool"), Some(Shell::Fish));
+        assert_eq!(
+            classify("contrib/completion/tool", "tool"),
+            Some(Shell::Bash)
+        );
+        assert_eq!(classify("tool", "tool"), None);
+        assert_eq!(classify("README.md", "tool"), None);
+        assert_eq!(classify("completions/README.md", "tool"), None);
+        assert_eq!(
+            classify("completion/tool-completion.sh", "tool"),
+            Some(Shell::Bash)
+        );
+        assert_eq!(classify("_other", "tool"), None);
+    }
+
+    #[test]
+    fn test_install() {
+        let temp_dir = TempDir::new().unwrap();
+        let extract_dir = temp_dir.path().join("extracted");
+        fs::create_dir_all(extract_dir.join("completions")).unwrap();
+        for name in ["tool", "completions/tool.bash", "completions/_tool"] {
+            fs::write(extract_dir.join(name), name).unwrap();
+        }
+        let files: Vec<String> = ["tool", "completions/tool.bash", "completions/_tool"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+
+        let dir = temp_dir.path().join("completions");
+        let installed = install(&files, &extract_dir, "tool", "t", &dir).unwrap();
+
+        assert_eq!(installed, vec![dir.join("bash/t"), dir.join("zsh/_t")]);
+        assert_eq!(
+            fs::read_to_string(dir.join("bash/t")).unwrap(),
+            "completions/tool.bash"
+        );
+        let loader = fs::read_to_string(dir.join("oktofetch.bash")).unwrap();
+        assert!(loader.contains(&format!("\"{}\"/*", dir.join("bash").display())));
+        assert!(dir.join("oktofetch.zsh").is_file());
+        assert!(dir.join("oktofetch.fish").is_file());
+    }
+
+    #[test]
+    fn test_remove() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        for path in ["bash/t", "fish/t.fish", "bash/other"] {
+            fs::create_dir_all(dir.join(path).parent().unwrap()).unwrap();
+            fs::write(dir.join(path), "complete").unwrap();
+        }
+
+        let removed = remove("t", dir).unwrap();
+        assert_eq!(removed, vec![dir.join("bash/t"), dir.join("fish/t.fish")]);
+        assert!(!dir.join("bash/t").exists());
+        assert!(dir.join("bash/other").exists());
+        assert!(remove("t", dir).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_install_without_completions() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("tool"), "bin").unwrap();
+
+        let dir = temp_dir.path().join("completions");
+        let installed =
+            install(&["tool".to_string()], temp_dir.path(), "tool", "tool", &dir).unwrap();
+
+        assert!(installed.is_empty());
+        assert!(!dir.exists());
+    }
+}
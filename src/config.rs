@@ -1,23 +1,280 @@
-use crate::error::{OktofetchError, Result};
+use crate::archive::ExtractLimits;
+use crate::error::{Context, OktofetchError, Result};
+use crate::github::ListOptions;
+use crate::http::{HttpCache, HttpContext, HttpPolicy};
+use crate::state::{self, State};
 use directories::ProjectDirs;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
 use std::env;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// Config file given with `--config`, used instead of the default location.
+static CONFIG_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+static UNTRUSTED_CONFIG: OnceLock<PathBuf> = OnceLock::new();
+
+/// Environment variable naming the config file when `--config` isn't given.
+pub const CONFIG_ENV: &str = "OKTOFETCH_CONFIG";
+
+/// Config file names looked for, in order of preference.
+const CONFIG_FILE_NAMES: &[&str] = &["config.toml", "config.yaml", "config.yml", "config.json"];
+
+/// Serialization formats a config file can use, chosen by its extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Toml,
+    Yaml,
+    Json,
+}
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl ConfigFormat {
+    /// Format for `path`: `.yaml`/`.yml` and `.json` are recognised, anything
+    /// else is TOML.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("yaml" | "yml") => Self::Yaml,
+            Some("json") => Self::Json,
+            _ => Self::Toml,
+        }
+    }
+
+    fn parse(self, content: &str) -> std::result::Result<Config, String> {
+        match self {
+            Self::Toml => toml::from_str(content).map_err(|e| e.to_string()),
+            Self::Yaml => serde_norway::from_str(content).map_err(|e| e.to_string()),
+            Self::Json => serde_json::from_str(content).map_err(|e| e.to_string()),
+        }
+    }
+
+    fn serialize(self, config: &Config) -> std::result::Result<String, String> {
+        match self {
+            Self::Toml => toml::to_string_pretty(config).map_err(|e| e.to_string()),
+            Self::Yaml => serde_norway::to_string(config).map_err(|e| e.to_string()),
+            Self::Json => serde_json::to_string_pretty(config)
+                .map(|json| json + "\n")
+                .map_err(|e| e.to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Config {
+    #[serde(default)]
     pub settings: Settings,
     #[serde(default)]
     pub tools: Vec<Tool>,
+    /// Settings as read from the file, before environment overrides
+    #[serde(skip)]
+    pub(crate) file_settings: Option<Settings>,
+    /// Setting keys currently taken from `OKTOFETCH_*` variables
+    #[serde(skip)]
+    pub(crate) env_overrides: Vec<String>,
+    /// `install_dir` as written in the file and as resolved on load, so an
+    /// unchanged value is saved the way the user wrote it
+    #[serde(skip)]
+    pub(crate) written_install_dir: Option<(PathBuf, PathBuf)>,
+    /// The file loaded and its declarative part as it would be saved, so a
+    /// save that only changes state leaves the file, comments and all, alone
+    #[serde(skip)]
+    pub(crate) loaded: Option<(PathBuf, String)>,
+    /// HTTP client for these settings, created on first use
+    #[serde(skip)]
+    pub(crate) http: HttpCache,
+    /// Latest release tags by tool name, looked up in one batch ahead of a
+    /// bulk update
+    #[serde(skip)]
+    pub(crate) latest_tags: HashMap<String, String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Settings {
     pub install_dir: PathBuf,
+    /// Maximum total bytes written when extracting a single archive
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_extract_size: Option<u64>,
+    /// Maximum size of any single file inside an archive
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_extract_file_size: Option<u64>,
+    /// Maximum number of entries extracted from a single archive
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_extract_files: Option<usize>,
+    /// Number of installed versions to retain per tool in the versions directory
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keep_versions: Option<usize>,
+    /// Seconds release metadata responses are served from the on-disk cache
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cache_ttl: Option<u64>,
+    /// Seconds after which cached downloads and API responses are pruned
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cache_max_age: Option<u64>,
+    /// Seconds within which `update --all` trusts the last check of a tool
+    /// instead of asking upstream again
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_check_interval: Option<u64>,
+    /// Total bytes the download and API response caches are pruned down to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cache_max_size: Option<u64>,
+    /// Asset size in bytes above which updates ask before downloading
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confirm_download_size: Option<u64>,
+    /// Ask instead of only warning when an update picks an asset shaped
+    /// differently from the last one, e.g. musl instead of gnu
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confirm_asset_change: Option<bool>,
+    /// Download speed cap in bytes per second
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_download_rate: Option<u64>,
+    /// Concurrent range requests used for large downloads (opt-in when above 1)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub download_connections: Option<usize>,
+    /// Extra hosts downloads may be redirected to, besides GitHub's own
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub redirect_hosts: Option<Vec<String>>,
+    /// User-Agent header sent with every request
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_agent: Option<String>,
+    /// Install shell completion scripts found in release archives
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub install_completions: Option<bool>,
+    /// Fall back to quickinstall's prebuilt binaries for Rust tools whose
+    /// releases have none for this platform
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quickinstall: Option<bool>,
+    /// Keywords favoured when picking among matching assets, strongest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub prefer: Vec<String>,
+    /// Least verification every download must pass before it is installed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verification: Option<Verification>,
+    /// Which IP version connections use
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ip_version: Option<IpVersion>,
+    /// Retries and timeouts of HTTP requests (`[settings.http]`)
+    #[serde(default, skip_serializing_if = "HttpSettings::is_empty")]
+    pub http: HttpSettings,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Which IP version connections are made over.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IpVersion {
+    /// Whichever works, trying both as the resolver returns them
+    #[default]
+    Auto,
+    /// IPv4 only, for networks with broken IPv6
+    V4,
+    /// IPv6 only
+    V6,
+}
+
+impl std::fmt::Display for IpVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Auto => "auto",
+            Self::V4 => "v4",
+            Self::V6 => "v6",
+        })
+    }
+}
+
+impl IpVersion {
+    /// Unspecified local address that restricts connections to this version.
+    pub fn local_address(self) -> Option<std::net::IpAddr> {
+        use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+        match self {
+            Self::Auto => None,
+            Self::V4 => Some(IpAddr::V4(Ipv4Addr::UNSPECIFIED)),
+            Self::V6 => Some(IpAddr::V6(Ipv6Addr::UNSPECIFIED)),
+        }
+    }
+}
+
+/// `[settings.http]`: how requests are retried and how long they may take.
+/// Unset fields use the defaults of [`HttpPolicy`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HttpSettings {
+    /// Extra attempts after a connection failure, timeout or server error
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retries: Option<u32>,
+    /// Milliseconds before the first retry, doubling for each further one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backoff_base: Option<u64>,
+    /// Seconds to wait for a connection to be established
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub connect_timeout: Option<u64>,
+    /// Seconds to wait for the next piece of a response
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub read_timeout: Option<u64>,
+}
+
+impl HttpSettings {
+    fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// How much proof of integrity a download needs before it is installed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Verification {
+    /// Install whatever was downloaded, checking digests only when known
+    #[default]
+    Off,
+    /// Require a SHA256, published by the release or pinned in the config
+    Checksum,
+    /// Require a signature or attestation from a configured key
+    Signature,
+}
+
+impl std::fmt::Display for Verification {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Off => "off",
+            Self::Checksum => "checksum",
+            Self::Signature => "signature",
+        })
+    }
+}
+
+/// Which releases a tool may be updated to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Channel {
+    /// Newest stable release (what GitHub marks as latest)
+    #[default]
+    Latest,
+    /// Newest published release, including pre-releases
+    Prerelease,
+    /// Newest release of any kind, including drafts visible to the token
+    Any,
+}
+
+impl std::fmt::Display for Channel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Latest => "latest",
+            Self::Prerelease => "prerelease",
+            Self::Any => "any",
+        })
+    }
+}
+
+impl Channel {
+    /// Release listing filters matching this channel.
+    pub fn list_options(self) -> ListOptions {
+        ListOptions {
+            include_prereleases: self != Self::Latest,
+            include_drafts: self == Self::Any,
+            limit: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Tool {
     pub name: String,
     pub repo: String,
@@ -25,11 +282,183 @@ pub struct Tool {
     pub binary_name: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub asset_pattern: Option<String>,
+    /// Keywords ruling an asset out (case-insensitive), e.g. `["musl", "static"]`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub asset_exclude: Vec<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub version: Option<String>,
+    /// SHA256 of the binary as written at install time
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub checksum: Option<String>,
+    /// Digest of the release asset the binary came from: the one GitHub
+    /// published, or else the SHA256 computed while downloading it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub asset_digest: Option<String>,
+    /// Unix time the binary was last installed or updated
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub installed_at: Option<u64>,
+    /// Unix time upstream was last successfully checked for a new release
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub checked_at: Option<u64>,
+    /// Size in bytes of the release asset the binary came from
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub asset_size: Option<u64>,
+    /// Name of the release asset the binary came from
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub asset_name: Option<String>,
+    /// Unix time the newest release seen upstream was published
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub released_at: Option<u64>,
+    /// The upstream repository was archived when last looked at
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub archived: bool,
+    /// Versions installed side by side with `add --keep`, each in its own
+    /// directory under the versions directory
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub kept_versions: Vec<String>,
+    /// Required SHA256 of the release asset; any other download is refused
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+    /// Release source: "github" (default), "oci://<image>" or "command:<path>" for an external plugin
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// Script that receives the release assets as JSON on stdin and prints the one to use
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub asset_selector: Option<String>,
+    /// Tag naming scheme with a `{version}` placeholder, e.g. `release-{version}`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tag_format: Option<String>,
+    /// Repository the release binaries are published in, when it differs from `repo`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub release_repo: Option<String>,
+    /// Which releases are eligible for updates (defaults to `latest`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub channel: Option<Channel>,
+    /// Shell command building the tool from its source tarball when a release
+    /// has no suitable binary, e.g. `cargo install --path .`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub build: Option<String>,
+    /// Picks the asset named by the `[package.metadata.binstall]` section of
+    /// the repository's Cargo.toml, for Rust tools that publish one
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub binstall: bool,
+    /// Overrides the `quickinstall` setting for this tool
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quickinstall: Option<bool>,
+    /// File name to install the binary as, when it should differ from `binary_name`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub install_as: Option<String>,
+    /// Keeps the whole extracted release in the versions directory and
+    /// installs a wrapper script that runs the binary from there, for tools
+    /// that need the files shipped alongside it
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub bundle: bool,
+    /// Held at its current version: skipped by bulk updates
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub pinned: bool,
+    /// Versions updates may move to, e.g. `^1.4` or `>=1.2, <2`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version_req: Option<String>,
+    /// Directory to install this tool in instead of the `install_dir` setting
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub install_dir: Option<PathBuf>,
 }
 
-fn expand_path(path: &str) -> String {
+impl Tool {
+    /// Name of the binary to look for inside the release asset.
+    pub fn binary_file_name(&self) -> &str {
+        self.binary_name.as_deref().unwrap_or(&self.name)
+    }
+
+    /// File name of the installed binary inside the install directory.
+    pub fn installed_name(&self) -> &str {
+        self.install_as
+            .as_deref()
+            .unwrap_or_else(|| self.binary_file_name())
+    }
+
+    /// Repository releases are fetched from.
+    pub fn release_repo(&self) -> &str {
+        self.release_repo.as_deref().unwrap_or(&self.repo)
+    }
+
+    /// Directory the installed version's bundle lives in, for a tool with
+    /// `bundle` set that has been installed.
+    pub fn bundle_dir(&self, versions_dir: &Path) -> Option<PathBuf> {
+        let version = self.version.as_deref().filter(|_| self.bundle)?;
+        Some(crate::binary::version_dir(
+            &versions_dir.join(&self.name),
+            version,
+        ))
+    }
+
+    /// Whether `version_req` allows `version`; any version does without one.
+    pub fn accepts_version(&self, version: &str) -> bool {
+        self.version_req
+            .as_deref()
+            .is_none_or(|requirement| crate::version::satisfies(version, requirement))
+    }
+
+    /// Whether releases come from GitHub rather than an image or plugin.
+    pub fn is_github(&self) -> bool {
+        matches!(self.source.as_deref(), None | Some("github"))
+    }
+
+    /// Builds the release tag for a version using `tag_format`, if set.
+    pub fn tag_for_version(&self, version: &str) -> String {
+        match &self.tag_format {
+            Some(format) => format.replace("{version}", version),
+            None => version.to_string(),
+        }
+    }
+
+    /// Extracts the version from a release tag using `tag_format`. Tags that
+    /// don't fit the format are returned unchanged.
+    pub fn version_from_tag(&self, tag: &str) -> String {
+        let Some((prefix, suffix)) = self
+            .tag_format
+            .as_deref()
+            .and_then(|format| format.split_once("{version}"))
+        else {
+            return tag.to_string();
+        };
+
+        tag.strip_prefix(prefix)
+            .and_then(|rest| rest.strip_suffix(suffix))
+            .filter(|version| !version.is_empty())
+            .unwrap_or(tag)
+            .to_string()
+    }
+}
+
+/// Replaces `path` with `content` without ever leaving a partly written file:
+/// the data goes to a temporary file in the same directory, which is synced
+/// and then renamed over `path`. An existing file's permissions are kept.
+/// When `path` is a symlink, e.g. into a dotfiles repository, the file it
+/// points to is replaced and the link stays.
+pub fn write_atomic(path: &Path, content: &[u8]) -> Result<()> {
+    use std::io::Write;
+
+    let resolved = fs::canonicalize(path).ok();
+    let path = resolved.as_deref().unwrap_or(path);
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let mut temp = tempfile::NamedTempFile::new_in(dir)?;
+    temp.write_all(content)?;
+    if let Ok(metadata) = fs::metadata(path) {
+        temp.as_file().set_permissions(metadata.permissions())?;
+    }
+    temp.as_file().sync_all()?;
+    temp.persist(path).map_err(|e| e.error)?;
+
+    // Make the rename itself durable
+    fs::File::open(dir)?.sync_all()?;
+    Ok(())
+}
+
+pub fn expand_path(path: &str) -> String {
     let mut expanded = path.to_string();
 
     // Handle tilde expansion
@@ -101,103 +530,1185 @@ fn expand_path(path: &str) -> String {
 }
 
 impl Config {
+    /// Loads the config file, then applies `OKTOFETCH_*` overrides. Command
+    /// line options in turn take precedence over both.
     pub fn load() -> Result<Self> {
-        let config_path = Self::config_path()?;
+        let mut config = Self::load_from(&Self::config_path()?)?;
+        State::load(&Self::state_path()?)?.apply(&mut config);
+        config.apply_env_overrides()?;
+        Ok(config)
+    }
+
+    /// HTTP client shared by every request made for this config, built from
+    /// the settings the first time it's needed and again after they change.
+    pub fn http(&self) -> HttpContext {
+        self.http.get(&self.settings)
+    }
+
+    /// Environment variable overriding setting `key`, e.g. `OKTOFETCH_CACHE_TTL`.
+    pub fn env_var(key: &str) -> String {
+        format!("OKTOFETCH_{}", key.to_uppercase().replace('.', "_"))
+    }
+
+    /// Replaces settings with any `OKTOFETCH_<KEY>` environment variables set.
+    /// The overridden values are kept out of the file on save.
+    pub fn apply_env_overrides(&mut self) -> Result<()> {
+        let file_settings = self.settings.clone();
+
+        for (key, _) in SETTING_KEYS {
+            let var = Self::env_var(key);
+            let Some(value) = env::var(&var).ok().filter(|v| !v.is_empty()) else {
+                continue;
+            };
+            self.settings
+                .set(key, &value)
+                .map_err(|e| OktofetchError::Other(format!("{}: {}", var, e)))?;
+            self.env_overrides.push(key.to_string());
+        }
+
+        if !self.env_overrides.is_empty() {
+            let expanded_path = expand_path(&self.settings.install_dir.to_string_lossy());
+            self.settings.install_dir = PathBuf::from(expanded_path);
+            self.file_settings = Some(file_settings);
+        }
+        Ok(())
+    }
+
+    /// Stops treating `key` as an environment override, so a value set
+    /// explicitly is saved.
+    pub fn forget_env_override(&mut self, key: &str) {
+        self.env_overrides.retain(|k| k != key);
+    }
+
+    /// Settings to write back: keys taken from the environment get the value
+    /// the file had (or are left out).
+    fn settings_to_save(&self) -> Result<Settings> {
+        let mut settings = self.settings_without_env()?;
+        if let Some((written, resolved)) = &self.written_install_dir
+            && settings.install_dir == *resolved
+        {
+            settings.install_dir = written.clone();
+        }
+        Ok(settings)
+    }
+
+    fn settings_without_env(&self) -> Result<Settings> {
+        let Some(file_settings) = &self.file_settings else {
+            return Ok(self.settings.clone());
+        };
+
+        let to_table = |settings: &Settings| match toml::Value::try_from(settings) {
+            Ok(toml::Value::Table(table)) => Ok(table),
+            Ok(_) => Err("settings are not a table".to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+        let config_error =
+            |e: String| OktofetchError::Other(format!("Cannot save settings: {}", e));
+
+        let mut settings = to_table(&self.settings).map_err(config_error)?;
+        let original = to_table(file_settings).map_err(config_error)?;
+        for key in &self.env_overrides {
+            // Keys like `http.retries` live in a nested table
+            let (section, name) = match key.split_once('.') {
+                Some((section, name)) => (Some(section), name),
+                None => (None, key.as_str()),
+            };
+            let original_table = match section {
+                Some(section) => original.get(section).and_then(|v| v.as_table()),
+                None => Some(&original),
+            };
+            let value = original_table.and_then(|table| table.get(name)).cloned();
+
+            let target = match section {
+                Some(section) => settings
+                    .entry(section)
+                    .or_insert_with(|| toml::Value::Table(toml::Table::new()))
+                    .as_table_mut(),
+                None => Some(&mut settings),
+            };
+            if let Some(target) = target {
+                match value {
+                    Some(value) => target.insert(name.to_string(), value),
+                    None => target.remove(name),
+                };
+            }
+        }
+        toml::Value::Table(settings)
+            .try_into()
+            .map_err(|e: toml::de::Error| config_error(e.to_string()))
+    }
 
+    /// Loads the config at `config_path`, in the format its extension names.
+    pub fn load_from(config_path: &Path) -> Result<Self> {
         if !config_path.exists() {
             return Ok(Self::default());
         }
 
-        let content = fs::read_to_string(&config_path)
-            .map_err(|e| OktofetchError::ConfigError(e.to_string(), config_path.clone()))?;
-
-        let mut config: Self = toml::from_str(&content)
-            .map_err(|e| OktofetchError::ConfigError(e.to_string(), config_path))?;
+        let content = fs::read_to_string(config_path)
+            .map_err(|e| OktofetchError::ConfigError(e.to_string(), config_path.to_path_buf()))?;
+
+        let mut config = ConfigFormat::from_path(config_path)
+            .parse(&content)
+            .map_err(|e| OktofetchError::ConfigError(e, config_path.to_path_buf()))?;
+        for tool in &config.tools {
+            let checks = [
+                tool.install_as
+                    .as_deref()
+                    .map(|name| check_file_name("install_as", name)),
+                tool.install_dir
+                    .as_deref()
+                    .map(|dir| check_install_dir("install_dir", &dir.to_string_lossy())),
+            ];
+            if let Some(e) = checks.into_iter().flatten().find_map(Result::err) {
+                return Err(OktofetchError::ConfigError(
+                    format!("tool {}: {}", tool.name, e),
+                    config_path.to_path_buf(),
+                ));
+            }
+        }
 
-        // Expand environment variables and tilde in install_dir
-        let expanded_path = expand_path(&config.settings.install_dir.to_string_lossy());
-        config.settings.install_dir = PathBuf::from(expanded_path);
+        // Expand environment variables and tilde in install_dir, and take
+        // relative paths from the config file's directory
+        let written = config.settings.install_dir.clone();
+        let mut install_dir = PathBuf::from(expand_path(&written.to_string_lossy()));
+        if install_dir.is_relative()
+            && let Some(dir) = std::path::absolute(config_path)?.parent()
+        {
+            install_dir = dir.join(install_dir);
+        }
+        config.settings.install_dir = install_dir.clone();
+        config.written_install_dir = Some((written, install_dir));
+        config.loaded = Some((config_path.to_path_buf(), config.declarative(config_path)?));
 
         Ok(config)
     }
 
+    /// Saves the declarative config and, separately, the installed state.
     pub fn save(&self) -> Result<()> {
         let config_path = Self::config_path()?;
+        self.save_to(&config_path)
+            .with_context(|| format!("while saving {}", config_path.display()))?;
+        let state_path = Self::state_path()?;
+        State::of(self)
+            .save(&state_path)
+            .with_context(|| format!("while saving {}", state_path.display()))
+    }
 
+    /// Writes the config to `config_path` in the format its extension names.
+    /// Installed versions and checksums are left to the state file.
+    pub fn save_to(&self, config_path: &Path) -> Result<()> {
         if let Some(parent) = config_path.parent() {
             fs::create_dir_all(parent)?;
         }
 
-        let content = toml::to_string_pretty(self)
-            .map_err(|e| OktofetchError::ConfigError(e.to_string(), config_path.clone()))?;
+        let content = self.declarative(config_path)?;
+        let unchanged = self
+            .loaded
+            .as_ref()
+            .is_some_and(|(path, loaded)| path == config_path && *loaded == content);
+        if unchanged && config_path.exists() {
+            return Ok(());
+        }
 
-        fs::write(&config_path, content)?;
+        if config_path.exists() {
+            let previous = fs::read(config_path)?;
+            if previous == content.as_bytes() {
+                return Ok(());
+            }
+            write_atomic(&Self::backup_path(config_path), &previous)?;
+        }
+        write_atomic(config_path, content.as_bytes())?;
         Ok(())
     }
 
+    /// The config as `save_to` writes it to `config_path`: without state or
+    /// environment overrides.
+    fn declarative(&self, config_path: &Path) -> Result<String> {
+        let mut config = Config {
+            settings: self.settings_to_save()?,
+            ..self.clone()
+        };
+        config.tools.iter_mut().for_each(state::strip);
+        ConfigFormat::from_path(config_path)
+            .serialize(&config)
+            .map_err(|e| OktofetchError::ConfigError(e, config_path.to_path_buf()))
+    }
+
+    /// Where `save_to` keeps the previous contents of `config_path`, e.g.
+    /// `config.toml.bak`.
+    pub fn backup_path(config_path: &Path) -> PathBuf {
+        let mut name = config_path.file_name().unwrap_or_default().to_os_string();
+        name.push(".bak");
+        config_path.with_file_name(name)
+    }
+
+    /// Makes `load` and `save` use `path` for the rest of the process. `~`
+    /// and environment variables are expanded, as in `$OKTOFETCH_CONFIG`.
+    pub fn use_path(path: PathBuf) {
+        let _ = CONFIG_OVERRIDE.set(Self::expand_config_path(&path));
+    }
+
+    /// Expands `~` and environment variables in a config path given on the
+    /// command line or in `$OKTOFETCH_CONFIG`.
+    fn expand_config_path(path: &Path) -> PathBuf {
+        PathBuf::from(expand_path(&path.to_string_lossy()))
+    }
+
+    /// Config file set with [`Config::use_path`], if any.
+    pub fn path_override() -> Option<&'static Path> {
+        CONFIG_OVERRIDE.get().map(PathBuf::as_path)
+    }
+
+    /// Whether the config file was chosen with `--config` or
+    /// `$OKTOFETCH_CONFIG` rather than found in the config directory.
+    pub fn is_explicit_path() -> bool {
+        Self::path_override().is_some() || env::var(CONFIG_ENV).is_ok_and(|path| !path.is_empty())
+    }
+
+    /// Marks the config in use as not the user's own when it was chosen
+    /// explicitly: it may be a shared toolfile or a project's, so the
+    /// commands it names (build commands, asset selectors, `command:`
+    /// sources) only run once the user agrees. Called once at startup.
+    pub fn check_trust() -> Result<()> {
+        if Self::is_explicit_path() {
+            let _ = UNTRUSTED_CONFIG.set(Self::config_path()?);
+        }
+        Ok(())
+    }
+
+    /// The config file in use if it isn't the user's own, see
+    /// [`Config::check_trust`].
+    pub fn untrusted_path() -> Option<&'static Path> {
+        UNTRUSTED_CONFIG.get().map(PathBuf::as_path)
+    }
+
+    /// The config file in use: `--config`, then `$OKTOFETCH_CONFIG`, then the
+    /// first existing file in the config directory.
     pub fn config_path() -> Result<PathBuf> {
+        if let Some(path) = Self::path_override() {
+            return Ok(path.to_path_buf());
+        }
+        if let Some(path) = env::var(CONFIG_ENV).ok().filter(|p| !p.is_empty()) {
+            return Ok(Self::expand_config_path(Path::new(&path)));
+        }
+
         let proj_dirs = ProjectDirs::from("com", "oktofetch", "oktofetch").ok_or_else(|| {
             OktofetchError::Other("Cannot determine config directory".to_string())
         })?;
 
-        Ok(proj_dirs.config_dir().join("config.toml"))
+        // Use whichever config file exists, in the order TOML, YAML, JSON
+        let dir = proj_dirs.config_dir();
+        Ok(CONFIG_FILE_NAMES
+            .iter()
+            .map(|name| dir.join(name))
+            .find(|path| path.is_file())
+            .unwrap_or_else(|| dir.join(CONFIG_FILE_NAMES[0])))
+    }
+
+    /// State file for the config in use: `state.json` in the data directory,
+    /// or one named after the config file when it was chosen explicitly, so
+    /// separate configs don't share installed versions.
+    pub fn state_path() -> Result<PathBuf> {
+        let dir = Self::data_dir()?;
+        if !Self::is_explicit_path() {
+            return Ok(dir.join("state.json"));
+        }
+
+        let config_path = Self::config_path()?;
+        let config_path = std::path::absolute(&config_path).unwrap_or(config_path);
+        let hash = format!(
+            "{:x}",
+            Sha256::digest(config_path.to_string_lossy().as_bytes())
+        );
+        Ok(dir.join(format!("state-{}.json", &hash[..16])))
+    }
+
+    /// Project files the shell hook may activate, see `oktofetch allow`.
+    pub fn allowlist_path() -> Result<PathBuf> {
+        Ok(Self::data_dir()?.join("allowed.json"))
+    }
+
+    /// Progress of a running `update --all`, kept for `--resume`.
+    pub fn journal_path() -> Result<PathBuf> {
+        Ok(Self::state_path()?.with_extension("journal"))
+    }
+
+    /// Lock file serialising changes to the config in use and its state.
+    pub fn lock_path() -> Result<PathBuf> {
+        Ok(Self::state_path()?.with_extension("lock"))
+    }
+
+    pub fn data_dir() -> Result<PathBuf> {
+        let proj_dirs = ProjectDirs::from("com", "oktofetch", "oktofetch")
+            .ok_or_else(|| OktofetchError::Other("Cannot determine data directory".to_string()))?;
+
+        Ok(proj_dirs.data_dir().to_path_buf())
+    }
+
+    pub fn cache_dir() -> Result<PathBuf> {
+        let proj_dirs = ProjectDirs::from("com", "oktofetch", "oktofetch")
+            .ok_or_else(|| OktofetchError::Other("Cannot determine cache directory".to_string()))?;
+
+        Ok(proj_dirs.cache_dir().to_path_buf())
+    }
+
+    /// Scratch space for in-progress downloads. Anything left here is from an
+    /// interrupted update and can be removed with `oktofetch clean`.
+    pub fn downloads_dir() -> Result<PathBuf> {
+        Ok(Self::cache_dir()?.join("downloads"))
+    }
+
+    /// Directory generated shell completion scripts are written to.
+    pub fn completions_dir() -> Result<PathBuf> {
+        Ok(Self::data_dir()?.join("completions"))
+    }
+
+    /// Directory holding retained copies of installed versions, one subdirectory per tool.
+    pub fn versions_dir() -> Result<PathBuf> {
+        Ok(Self::data_dir()?.join("versions"))
     }
 
     pub fn add_tool(&mut self, tool: Tool) -> Result<()> {
+        if let Some(install_as) = &tool.install_as {
+            check_file_name("install_as", install_as)?;
+        }
         if self.tools.iter().any(|t| t.name == tool.name) {
             return Err(OktofetchError::Other(format!(
                 "Tool '{}' already exists",
                 tool.name
             )));
         }
-        self.tools.push(tool);
+        self.tools.push(tool);
+        Ok(())
+    }
+
+    pub fn remove_tool(&mut self, name: &str) -> Result<()> {
+        let initial_len = self.tools.len();
+        self.tools.retain(|t| t.name != name);
+
+        if self.tools.len() == initial_len {
+            return Err(OktofetchError::ToolNotFound(name.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Directory `tool` is installed in: its own `install_dir`, with `~` and
+    /// environment variables expanded, or else the `install_dir` setting.
+    pub fn install_dir_for(&self, tool: &Tool) -> PathBuf {
+        match &tool.install_dir {
+            Some(dir) => PathBuf::from(expand_path(&dir.to_string_lossy())),
+            None => self.settings.install_dir.clone(),
+        }
+    }
+
+    pub fn get_tool(&self, name: &str) -> Option<&Tool> {
+        self.tools.iter().find(|t| t.name == name)
+    }
+
+    pub fn get_tool_mut(&mut self, name: &str) -> Option<&mut Tool> {
+        self.tools.iter_mut().find(|t| t.name == name)
+    }
+
+    /// Resolves a tool name that may contain `*` and `?` wildcards, for
+    /// commands acting on a single tool. A pattern must match exactly one
+    /// tool; plain names are returned unchanged.
+    pub fn resolve_name(&self, pattern: &str) -> Result<String> {
+        let mut names = self.resolve_names(std::slice::from_ref(&pattern.to_string()))?;
+        if names.len() > 1 {
+            return Err(OktofetchError::Other(format!(
+                "'{}' matches several tools: {}. Name one of them",
+                pattern,
+                names.join(", ")
+            )));
+        }
+        Ok(names.remove(0))
+    }
+
+    /// Expands wildcard patterns among `patterns` to the matching tool names,
+    /// in config order and without duplicates. A pattern matching nothing is
+    /// an error; plain names are passed through for the caller to check.
+    pub fn resolve_names(&self, patterns: &[String]) -> Result<Vec<String>> {
+        let mut names: Vec<String> = Vec::new();
+        let mut push = |name: &str| {
+            if !names.iter().any(|n| n == name) {
+                names.push(name.to_string());
+            }
+        };
+
+        for pattern in patterns {
+            if !is_glob(pattern) {
+                push(pattern);
+                continue;
+            }
+            let mut matched = false;
+            for tool in self.tools.iter().filter(|t| glob_match(pattern, &t.name)) {
+                push(&tool.name);
+                matched = true;
+            }
+            if !matched {
+                return Err(OktofetchError::ToolNotFound(pattern.clone()));
+            }
+        }
+        Ok(names)
+    }
+
+    pub fn update_tool_version(&mut self, name: &str, version: String) -> Result<()> {
+        let tool = self
+            .get_tool_mut(name)
+            .ok_or_else(|| OktofetchError::ToolNotFound(name.to_string()))?;
+        tool.version = Some(version);
+        Ok(())
+    }
+
+    pub fn update_tool_checksum(&mut self, name: &str, checksum: String) -> Result<()> {
+        let tool = self
+            .get_tool_mut(name)
+            .ok_or_else(|| OktofetchError::ToolNotFound(name.to_string()))?;
+        tool.checksum = Some(checksum);
+        Ok(())
+    }
+}
+
+/// The kind of value a setting or tool key takes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyValue {
+    /// Free-form, described for the user, e.g. "size, e.g. 1G"
+    Text(&'static str),
+    /// One of a fixed set of values, e.g. to offer in shell completion
+    OneOf(&'static [&'static str]),
+}
+
+const BOOL: KeyValue = KeyValue::OneOf(&["true", "false"]);
+
+impl std::fmt::Display for KeyValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Text(text) => f.write_str(text),
+            Self::OneOf([]) => Ok(()),
+            Self::OneOf([value]) => f.write_str(value),
+            Self::OneOf([init @ .., last]) => write!(f, "{} or {}", init.join(", "), last),
+        }
+    }
+}
+
+/// Settings `oktofetch config set` accepts, with the kind of value each takes.
+pub const SETTING_KEYS: &[(&str, KeyValue)] = &[
+    ("install_dir", KeyValue::Text("path")),
+    ("max_extract_size", KeyValue::Text("size, e.g. 1G")),
+    ("max_extract_file_size", KeyValue::Text("size, e.g. 512M")),
+    ("max_extract_files", KeyValue::Text("number")),
+    ("keep_versions", KeyValue::Text("number")),
+    ("cache_ttl", KeyValue::Text("seconds")),
+    ("cache_max_age", KeyValue::Text("duration, e.g. 30d")),
+    ("cache_max_size", KeyValue::Text("size, e.g. 1G")),
+    ("min_check_interval", KeyValue::Text("duration, e.g. 6h")),
+    ("confirm_download_size", KeyValue::Text("size, e.g. 200M")),
+    ("confirm_asset_change", BOOL),
+    (
+        "max_download_rate",
+        KeyValue::Text("size per second, e.g. 500K"),
+    ),
+    ("download_connections", KeyValue::Text("number")),
+    ("redirect_hosts", KeyValue::Text("comma-separated hosts")),
+    (
+        "user_agent",
+        KeyValue::Text("text, e.g. \"oktofetch/1.0 acme-build\""),
+    ),
+    ("install_completions", BOOL),
+    ("quickinstall", BOOL),
+    ("prefer", KeyValue::Text("comma-separated keywords")),
+    (
+        "verification",
+        KeyValue::OneOf(&["off", "checksum", "signature"]),
+    ),
+    ("ip_version", KeyValue::OneOf(&["auto", "v4", "v6"])),
+    ("http.retries", KeyValue::Text("number")),
+    ("http.backoff_base", KeyValue::Text("milliseconds")),
+    ("http.connect_timeout", KeyValue::Text("duration, e.g. 30s")),
+    ("http.read_timeout", KeyValue::Text("duration, e.g. 2m")),
+];
+
+fn is_glob(pattern: &str) -> bool {
+    pattern.contains(['*', '?'])
+}
+
+/// Matches `text` against a shell-style pattern where `*` stands for any run
+/// of characters and `?` for exactly one.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` and the text position it currently covers up to
+    let mut backtrack = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star, covered)) => {
+                    p = star + 1;
+                    t = covered + 1;
+                    backtrack = Some((star, covered + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+fn unknown_key(key: &str) -> OktofetchError {
+    OktofetchError::Other(format!(
+        "Unknown config key: {}. Run 'oktofetch config set --list' to see valid keys",
+        key
+    ))
+}
+
+fn invalid_value(key: &str, value: &str, reason: impl std::fmt::Display) -> OktofetchError {
+    OktofetchError::Other(format!("Invalid value '{}' for {}: {}", value, key, reason))
+}
+
+/// Rejects anything but a plain file name, so `value` can't point outside
+/// the directory it is joined to.
+fn check_file_name(key: &str, value: &str) -> Result<()> {
+    let mut components = Path::new(value).components();
+    let single = matches!(components.next(), Some(std::path::Component::Normal(_)))
+        && components.next().is_none();
+    if !single || value.contains('/') {
+        return Err(invalid_value(
+            key,
+            value,
+            "expected a file name without '/', other than . or ..",
+        ));
+    }
+    Ok(())
+}
+
+fn check_install_dir(key: &str, value: &str) -> Result<()> {
+    if !Path::new(&expand_path(value)).is_absolute() {
+        return Err(invalid_value(
+            key,
+            value,
+            "expected an absolute path (~ and environment variables are expanded)",
+        ));
+    }
+    Ok(())
+}
+
+fn parse_size_value(key: &str, value: &str) -> Result<u64> {
+    crate::human::parse_size(value).map_err(|e| invalid_value(key, value, e))
+}
+
+fn parse_duration_value(key: &str, value: &str) -> Result<u64> {
+    crate::human::parse_duration(value).map_err(|e| invalid_value(key, value, e))
+}
+
+fn parse_number<T: std::str::FromStr>(key: &str, value: &str) -> Result<T>
+where
+    T::Err: std::fmt::Display,
+{
+    value
+        .trim()
+        .parse()
+        .map_err(|e| invalid_value(key, value, e))
+}
+
+fn parse_bool(key: &str, value: &str) -> Result<bool> {
+    match value.trim().to_lowercase().as_str() {
+        "true" | "yes" | "on" | "1" => Ok(true),
+        "false" | "no" | "off" | "0" => Ok(false),
+        _ => Err(invalid_value(key, value, "expected true or false")),
+    }
+}
+
+fn parse_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// Tool fields `oktofetch set` accepts. The installed version and checksums
+/// are maintained by oktofetch and left out.
+pub const TOOL_KEYS: &[(&str, KeyValue)] = &[
+    ("repo", KeyValue::Text("owner/repo")),
+    (
+        "binary_name",
+        KeyValue::Text("file name inside the archive"),
+    ),
+    ("install_as", KeyValue::Text("installed file name")),
+    (
+        "asset_pattern",
+        KeyValue::Text("substring of the asset name"),
+    ),
+    ("asset_exclude", KeyValue::Text("comma-separated keywords")),
+    ("asset_selector", KeyValue::Text("path to a script")),
+    ("release_repo", KeyValue::Text("owner/repo")),
+    (
+        "tag_format",
+        KeyValue::Text("tag with a {version} placeholder"),
+    ),
+    ("channel", KeyValue::OneOf(&["latest", "prerelease", "any"])),
+    (
+        "source",
+        KeyValue::Text("github, oci://<image> or command:<path>"),
+    ),
+    ("pinned", BOOL),
+    (
+        "build",
+        KeyValue::Text("shell command, e.g. \"cargo install --path .\""),
+    ),
+    ("binstall", BOOL),
+    ("quickinstall", BOOL),
+    ("bundle", BOOL),
+    (
+        "sha256",
+        KeyValue::Text("SHA256 the release asset must have (64 hex digits)"),
+    ),
+    (
+        "version_req",
+        KeyValue::Text("version requirement, e.g. ^1.4"),
+    ),
+    (
+        "install_dir",
+        KeyValue::Text("absolute path, ~ and $VARS expanded"),
+    ),
+];
+
+/// The fixed values a setting or tool key takes, if it has any, e.g. to
+/// offer them in shell completion.
+pub fn key_values(key: &str) -> &'static [&'static str] {
+    SETTING_KEYS
+        .iter()
+        .chain(TOOL_KEYS)
+        .find(|(name, _)| *name == key)
+        .and_then(|(_, value)| match value {
+            KeyValue::OneOf(values) => Some(*values),
+            KeyValue::Text(_) => None,
+        })
+        .unwrap_or_default()
+}
+
+fn unknown_tool_key(key: &str) -> OktofetchError {
+    let keys: Vec<&str> = TOOL_KEYS.iter().map(|(key, _)| *key).collect();
+    OktofetchError::Other(format!(
+        "Unknown tool key: {}. Valid keys: {}",
+        key,
+        keys.join(", ")
+    ))
+}
+
+fn parse_ip_version(key: &str, value: &str) -> Result<IpVersion> {
+    match value.trim().to_lowercase().as_str() {
+        "auto" => Ok(IpVersion::Auto),
+        "v4" | "4" | "ipv4" => Ok(IpVersion::V4),
+        "v6" | "6" | "ipv6" => Ok(IpVersion::V6),
+        _ => Err(invalid_value(key, value, "expected auto, v4 or v6")),
+    }
+}
+
+fn parse_verification(key: &str, value: &str) -> Result<Verification> {
+    match value.trim() {
+        "off" => Ok(Verification::Off),
+        "checksum" => Ok(Verification::Checksum),
+        "signature" => Ok(Verification::Signature),
+        _ => Err(invalid_value(
+            key,
+            value,
+            "expected off, checksum or signature",
+        )),
+    }
+}
+
+fn parse_channel(key: &str, value: &str) -> Result<Channel> {
+    match value.trim() {
+        "latest" => Ok(Channel::Latest),
+        "prerelease" => Ok(Channel::Prerelease),
+        "any" => Ok(Channel::Any),
+        _ => Err(invalid_value(
+            key,
+            value,
+            "expected latest, prerelease or any",
+        )),
+    }
+}
+
+impl Tool {
+    /// Sets field `key` from its command-line form, rejecting invalid values.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<()> {
+        let text = Some(value.to_string());
+        match key {
+            "repo" => {
+                if value.split('/').count() != 2 || value.split('/').any(str::is_empty) {
+                    return Err(invalid_value(key, value, "expected owner/repo"));
+                }
+                self.repo = value.to_string();
+            }
+            "binary_name" => self.binary_name = text,
+            "install_as" => {
+                check_file_name(key, value)?;
+                self.install_as = text;
+            }
+            "asset_pattern" => self.asset_pattern = text,
+            "asset_exclude" => self.asset_exclude = parse_list(value),
+            "asset_selector" => self.asset_selector = text,
+            "release_repo" => self.release_repo = text,
+            "tag_format" => {
+                if !value.contains("{version}") {
+                    return Err(invalid_value(key, value, "missing {version} placeholder"));
+                }
+                self.tag_format = text;
+            }
+            "channel" => self.channel = Some(parse_channel(key, value)?),
+            "source" => self.source = text,
+            "build" => self.build = text,
+            "binstall" => self.binstall = parse_bool(key, value)?,
+            "quickinstall" => self.quickinstall = Some(parse_bool(key, value)?),
+            "pinned" => self.pinned = parse_bool(key, value)?,
+            "bundle" => self.bundle = parse_bool(key, value)?,
+            "sha256" => {
+                let hex = value.strip_prefix("sha256:").unwrap_or(value);
+                if hex.len() != 64 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+                    return Err(invalid_value(key, value, "expected 64 hex digits"));
+                }
+                self.sha256 = Some(hex.to_lowercase());
+            }
+            "version_req" => {
+                crate::version::check_requirement(value)
+                    .map_err(|reason| invalid_value(key, value, reason))?;
+                self.version_req = text;
+            }
+            "install_dir" => {
+                check_install_dir(key, value)?;
+                self.install_dir = Some(PathBuf::from(value));
+            }
+            _ => return Err(unknown_tool_key(key)),
+        }
+        Ok(())
+    }
+
+    /// Clears field `key` back to its default.
+    pub fn unset(&mut self, key: &str) -> Result<()> {
+        match key {
+            "repo" => {
+                return Err(OktofetchError::Other(
+                    "repo is required and can't be unset".to_string(),
+                ));
+            }
+            "binary_name" => self.binary_name = None,
+            "install_as" => self.install_as = None,
+            "asset_pattern" => self.asset_pattern = None,
+            "asset_exclude" => self.asset_exclude.clear(),
+            "asset_selector" => self.asset_selector = None,
+            "release_repo" => self.release_repo = None,
+            "tag_format" => self.tag_format = None,
+            "channel" => self.channel = None,
+            "source" => self.source = None,
+            "build" => self.build = None,
+            "binstall" => self.binstall = false,
+            "quickinstall" => self.quickinstall = None,
+            "pinned" => self.pinned = false,
+            "bundle" => self.bundle = false,
+            "sha256" => self.sha256 = None,
+            "version_req" => self.version_req = None,
+            "install_dir" => self.install_dir = None,
+            _ => return Err(unknown_tool_key(key)),
+        }
+        Ok(())
+    }
+}
+
+impl Settings {
+    /// Sets `key` from its command-line form, rejecting values of the wrong type.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<()> {
+        match key {
+            "install_dir" => self.install_dir = PathBuf::from(value),
+            "max_extract_size" => self.max_extract_size = Some(parse_size_value(key, value)?),
+            "max_extract_file_size" => {
+                self.max_extract_file_size = Some(parse_size_value(key, value)?)
+            }
+            "max_extract_files" => self.max_extract_files = Some(parse_number(key, value)?),
+            "keep_versions" => self.keep_versions = Some(parse_number(key, value)?),
+            "cache_ttl" => self.cache_ttl = Some(parse_number(key, value)?),
+            "cache_max_age" => self.cache_max_age = Some(parse_duration_value(key, value)?),
+            "cache_max_size" => self.cache_max_size = Some(parse_size_value(key, value)?),
+            "min_check_interval" => {
+                self.min_check_interval = Some(parse_duration_value(key, value)?)
+            }
+            "confirm_download_size" => {
+                self.confirm_download_size = Some(parse_size_value(key, value)?)
+            }
+            "confirm_asset_change" => self.confirm_asset_change = Some(parse_bool(key, value)?),
+            "max_download_rate" => self.max_download_rate = Some(parse_size_value(key, value)?),
+            "download_connections" => self.download_connections = Some(parse_number(key, value)?),
+            "redirect_hosts" => self.redirect_hosts = Some(parse_list(value)),
+            "user_agent" => {
+                if value.trim().is_empty() {
+                    return Err(invalid_value(key, value, "must not be empty"));
+                }
+                self.user_agent = Some(value.trim().to_string())
+            }
+            "install_completions" => self.install_completions = Some(parse_bool(key, value)?),
+            "quickinstall" => self.quickinstall = Some(parse_bool(key, value)?),
+            "prefer" => self.prefer = parse_list(value),
+            "verification" => self.verification = Some(parse_verification(key, value)?),
+            "ip_version" => self.ip_version = Some(parse_ip_version(key, value)?),
+            "http.retries" => self.http.retries = Some(parse_number(key, value)?),
+            "http.backoff_base" => self.http.backoff_base = Some(parse_number(key, value)?),
+            "http.connect_timeout" => {
+                self.http.connect_timeout = Some(parse_duration_value(key, value)?)
+            }
+            "http.read_timeout" => self.http.read_timeout = Some(parse_duration_value(key, value)?),
+            _ => return Err(unknown_key(key)),
+        }
         Ok(())
     }
 
-    pub fn remove_tool(&mut self, name: &str) -> Result<()> {
-        let initial_len = self.tools.len();
-        self.tools.retain(|t| t.name != name);
+    /// Effective value of `key` as `config set` would accept it, or `None`
+    /// when the setting is off. Defaults are filled in where one applies.
+    pub fn get(&self, key: &str) -> Result<Option<String>> {
+        let limits = self.extract_limits();
+        let policy = self.http_policy();
+        let value = match key {
+            "install_dir" => Some(self.install_dir.display().to_string()),
+            "max_extract_size" => Some(limits.max_total_size.to_string()),
+            "max_extract_file_size" => Some(limits.max_file_size.to_string()),
+            "max_extract_files" => Some(limits.max_files.to_string()),
+            "keep_versions" => self.keep_versions.map(|n| n.to_string()),
+            "cache_ttl" => self.cache_ttl.map(|n| n.to_string()),
+            "cache_max_age" => self.cache_max_age.map(|n| n.to_string()),
+            "cache_max_size" => self.cache_max_size.map(|n| n.to_string()),
+            "min_check_interval" => self.min_check_interval.map(|n| n.to_string()),
+            "confirm_download_size" => self.confirm_download_size.map(|n| n.to_string()),
+            "confirm_asset_change" => Some(self.confirm_asset_change.unwrap_or(false).to_string()),
+            "max_download_rate" => self.max_download_rate.map(|n| n.to_string()),
+            "download_connections" => Some(self.download_connections.unwrap_or(1).to_string()),
+            "redirect_hosts" => self.redirect_hosts.as_ref().map(|hosts| hosts.join(",")),
+            "user_agent" => Some(self.user_agent().to_string()),
+            "install_completions" => Some(self.install_completions.unwrap_or(false).to_string()),
+            "quickinstall" => Some(self.quickinstall.unwrap_or(false).to_string()),
+            "prefer" => (!self.prefer.is_empty()).then(|| self.prefer.join(",")),
+            "verification" => Some(self.verification.unwrap_or_default().to_string()),
+            "ip_version" => Some(self.ip_version.unwrap_or_default().to_string()),
+            "http.retries" => Some(policy.retries.to_string()),
+            "http.backoff_base" => Some(policy.backoff_base.as_millis().to_string()),
+            "http.connect_timeout" => Some(policy.connect_timeout.as_secs().to_string()),
+            "http.read_timeout" => Some(policy.read_timeout.as_secs().to_string()),
+            _ => return Err(unknown_key(key)),
+        };
+        Ok(value)
+    }
 
-        if self.tools.len() == initial_len {
-            return Err(OktofetchError::ToolNotFound(name.to_string()));
+    /// Reverts `key` to its default, which drops it from the config file.
+    pub fn unset(&mut self, key: &str) -> Result<()> {
+        match key {
+            "install_dir" => self.install_dir = Settings::default().install_dir,
+            "max_extract_size" => self.max_extract_size = None,
+            "max_extract_file_size" => self.max_extract_file_size = None,
+            "max_extract_files" => self.max_extract_files = None,
+            "keep_versions" => self.keep_versions = None,
+            "cache_ttl" => self.cache_ttl = None,
+            "cache_max_age" => self.cache_max_age = None,
+            "cache_max_size" => self.cache_max_size = None,
+            "min_check_interval" => self.min_check_interval = None,
+            "confirm_download_size" => self.confirm_download_size = None,
+            "confirm_asset_change" => self.confirm_asset_change = None,
+            "max_download_rate" => self.max_download_rate = None,
+            "download_connections" => self.download_connections = None,
+            "redirect_hosts" => self.redirect_hosts = None,
+            "user_agent" => self.user_agent = None,
+            "install_completions" => self.install_completions = None,
+            "quickinstall" => self.quickinstall = None,
+            "prefer" => self.prefer.clear(),
+            "verification" => self.verification = None,
+            "ip_version" => self.ip_version = None,
+            "http.retries" => self.http.retries = None,
+            "http.backoff_base" => self.http.backoff_base = None,
+            "http.connect_timeout" => self.http.connect_timeout = None,
+            "http.read_timeout" => self.http.read_timeout = None,
+            _ => return Err(unknown_key(key)),
         }
         Ok(())
     }
 
-    pub fn get_tool(&self, name: &str) -> Option<&Tool> {
-        self.tools.iter().find(|t| t.name == name)
+    pub fn extract_limits(&self) -> ExtractLimits {
+        let defaults = ExtractLimits::default();
+        ExtractLimits {
+            max_total_size: self.max_extract_size.unwrap_or(defaults.max_total_size),
+            max_file_size: self.max_extract_file_size.unwrap_or(defaults.max_file_size),
+            max_files: self.max_extract_files.unwrap_or(defaults.max_files),
+        }
     }
 
-    pub fn get_tool_mut(&mut self, name: &str) -> Option<&mut Tool> {
-        self.tools.iter_mut().find(|t| t.name == name)
+    /// Retry and timeout behaviour from `[settings.http]`, with defaults for
+    /// anything unset.
+    pub fn http_policy(&self) -> HttpPolicy {
+        let defaults = HttpPolicy::default();
+        let seconds = |value: Option<u64>, default| value.map_or(default, Duration::from_secs);
+        HttpPolicy {
+            retries: self.http.retries.unwrap_or(defaults.retries),
+            backoff_base: self
+                .http
+                .backoff_base
+                .map_or(defaults.backoff_base, Duration::from_millis),
+            connect_timeout: seconds(self.http.connect_timeout, defaults.connect_timeout),
+            read_timeout: seconds(self.http.read_timeout, defaults.read_timeout),
+            local_address: self.ip_version.unwrap_or_default().local_address(),
+        }
     }
 
-    pub fn update_tool_version(&mut self, name: &str, version: String) -> Result<()> {
-        let tool = self
-            .get_tool_mut(name)
-            .ok_or_else(|| OktofetchError::ToolNotFound(name.to_string()))?;
-        tool.version = Some(version);
-        Ok(())
+    /// User-Agent to send: the configured one or `oktofetch/<version>`.
+    pub fn user_agent(&self) -> &str {
+        self.user_agent
+            .as_deref()
+            .unwrap_or(crate::http::DEFAULT_USER_AGENT)
+    }
+
+    /// How long cached API responses stay fresh, or `None` when caching is off.
+    pub fn cache_ttl(&self) -> Option<std::time::Duration> {
+        self.cache_ttl
+            .filter(|&secs| secs > 0)
+            .map(std::time::Duration::from_secs)
     }
 }
 
-impl Default for Config {
+impl Default for Settings {
     fn default() -> Self {
         let home = std::env::var("HOME").unwrap_or_else(|_| "/tmp".to_string());
         let install_dir = PathBuf::from(home).join(".local/bin");
 
         Self {
-            settings: Settings { install_dir },
-            tools: Vec::new(),
+            install_dir,
+            max_extract_size: None,
+            max_extract_file_size: None,
+            max_extract_files: None,
+            keep_versions: None,
+            cache_ttl: None,
+            cache_max_age: None,
+            cache_max_size: None,
+            min_check_interval: None,
+            confirm_download_size: None,
+            confirm_asset_change: None,
+            max_download_rate: None,
+            download_connections: None,
+            redirect_hosts: None,
+            user_agent: None,
+            install_completions: None,
+            quickinstall: None,
+            prefer: Vec::new(),
+            verification: None,
+            ip_version: None,
+            http: HttpSettings::default(),
         }
     }
 }
 
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use tempfile::TempDir;
 
+    #[test]
+    fn test_settings_set() {
+        let mut settings = Settings::default();
+
+        settings.set("install_dir", "/opt/bin").unwrap();
+        settings.set("max_extract_size", "2G").unwrap();
+        settings.set("keep_versions", "3").unwrap();
+        settings.set("cache_max_age", "2w").unwrap();
+        settings.set("install_completions", "yes").unwrap();
+        settings.set("prefer", "musl, static,").unwrap();
+        settings.set("verification", "checksum").unwrap();
+
+        assert_eq!(settings.install_dir, PathBuf::from("/opt/bin"));
+        assert_eq!(settings.max_extract_size, Some(2 * 1024 * 1024 * 1024));
+        assert_eq!(settings.keep_versions, Some(3));
+        assert_eq!(settings.cache_max_age, Some(14 * 86400));
+        assert_eq!(settings.install_completions, Some(true));
+        assert_eq!(settings.prefer, vec!["musl", "static"]);
+        assert_eq!(settings.verification, Some(Verification::Checksum));
+    }
+
+    #[test]
+    fn test_settings_set_validates() {
+        let mut settings = Settings::default();
+
+        let err = settings.set("keep_versions", "many").unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("Invalid value 'many' for keep_versions")
+        );
+        assert!(settings.set("max_download_rate", "fast").is_err());
+        assert!(settings.set("install_completions", "maybe").is_err());
+        assert!(settings.set("verification", "strict").is_err());
+        assert!(settings.set("colors", "true").is_err());
+        assert_eq!(settings.keep_versions, None);
+    }
+
+    #[test]
+    fn test_setting_keys_are_settable() {
+        for (key, _) in SETTING_KEYS {
+            let err = Settings::default().set(key, "x").err();
+            assert!(
+                !err.is_some_and(|e| e.to_string().contains("Unknown config key")),
+                "{}",
+                key
+            );
+            assert!(Settings::default().unset(key).is_ok(), "{}", key);
+        }
+    }
+
+    #[test]
+    fn test_key_values_are_accepted() {
+        for (key, _) in SETTING_KEYS {
+            for value in key_values(key) {
+                assert!(
+                    Settings::default().set(key, value).is_ok(),
+                    "{} = {}",
+                    key,
+                    value
+                );
+            }
+        }
+        for (key, _) in TOOL_KEYS {
+            for value in key_values(key) {
+                assert!(
+                    Tool::default().set(key, value).is_ok(),
+                    "{} = {}",
+                    key,
+                    value
+                );
+            }
+        }
+        assert!(key_values("install_dir").is_empty());
+        assert_eq!(key_values("channel"), ["latest", "prerelease", "any"]);
+        assert!(key_values("nonsense").is_empty());
+    }
+
+    #[test]
+    fn test_key_value_display() {
+        assert_eq!(BOOL.to_string(), "true or false");
+        assert_eq!(
+            KeyValue::OneOf(&["auto", "v4", "v6"]).to_string(),
+            "auto, v4 or v6"
+        );
+        assert_eq!(KeyValue::Text("number").to_string(), "number");
+    }
+
+    #[test]
+    fn test_tool_set_and_unset() {
+        let mut tool = Tool {
+            name: "k9s".to_string(),
+            repo: "derailed/k9s".to_string(),
+            ..Default::default()
+        };
+
+        tool.set("asset_pattern", "Linux_amd64").unwrap();
+        tool.set("asset_exclude", "musl,static").unwrap();
+        tool.set("channel", "prerelease").unwrap();
+        tool.set("pinned", "true").unwrap();
+        tool.set("bundle", "yes").unwrap();
+        assert_eq!(tool.asset_pattern.as_deref(), Some("Linux_amd64"));
+        assert_eq!(tool.asset_exclude, vec!["musl", "static"]);
+        assert_eq!(tool.channel, Some(Channel::Prerelease));
+        assert!(tool.pinned);
+        assert!(tool.bundle);
+
+        assert!(tool.set("channel", "nightly").is_err());
+        assert!(tool.set("tag_format", "release").is_err());
+        assert!(tool.set("repo", "nope").is_err());
+        assert!(tool.set("version", "v1").is_err());
+        assert!(tool.set("sha256", "abc").is_err());
+        for bad in [
+            "",
+            ".",
+            "..",
+            "../bin/ls",
+            "/usr/bin/ls",
+            "sub/dir",
+            "kube9/",
+        ] {
+            assert!(tool.set("install_as", bad).is_err(), "{:?}", bad);
+        }
+        tool.set("install_as", "kube9").unwrap();
+        assert_eq!(tool.install_as.as_deref(), Some("kube9"));
+        let mut config = Config::default();
+        let escaping = Tool {
+            install_as: Some("../escape".to_string()),
+            ..tool.clone()
+        };
+        assert!(config.add_tool(escaping).is_err());
+        tool.set("sha256", &format!("sha256:{}", "AB".repeat(32)))
+            .unwrap();
+        assert_eq!(tool.sha256, Some("ab".repeat(32)));
+        tool.set("version_req", ">=1.2, <2").unwrap();
+        assert_eq!(tool.version_req.as_deref(), Some(">=1.2, <2"));
+        assert!(tool.accepts_version("1.9.0") && !tool.accepts_version("2.0.0"));
+        assert!(tool.set("version_req", "newest").is_err());
+        assert!(tool.set("install_dir", "bin").is_err());
+        tool.set("install_dir", "/opt/k9s/bin").unwrap();
+        assert_eq!(config.install_dir_for(&tool), PathBuf::from("/opt/k9s/bin"));
+
+        for (key, _) in TOOL_KEYS.iter().filter(|(key, _)| *key != "repo") {
+            tool.unset(key).unwrap();
+        }
+        assert!(tool.unset("repo").is_err());
+        let serialized = toml::to_string(&tool).unwrap();
+        assert_eq!(serialized, "name = \"k9s\"\nrepo = \"derailed/k9s\"\n");
+    }
+
+    #[test]
+    fn test_settings_get() {
+        let mut settings = Settings::default();
+        settings.set("install_dir", "/opt/bin").unwrap();
+        settings.set("prefer", "musl,static").unwrap();
+
+        assert_eq!(settings.get("install_dir").unwrap().unwrap(), "/opt/bin");
+        assert_eq!(settings.get("prefer").unwrap().unwrap(), "musl,static");
+        assert_eq!(
+            settings.get("max_extract_files").unwrap().unwrap(),
+            crate::archive::DEFAULT_MAX_EXTRACT_FILES.to_string()
+        );
+        assert_eq!(
+            settings.get("install_completions").unwrap().unwrap(),
+            "false"
+        );
+        assert_eq!(settings.get("cache_ttl").unwrap(), None);
+        assert!(settings.get("colors").is_err());
+
+        for (key, _) in SETTING_KEYS {
+            assert!(settings.get(key).is_ok(), "{}", key);
+        }
+    }
+
+    #[test]
+    fn test_settings_unset() {
+        let mut settings = Settings::default();
+        settings.set("install_dir", "/opt/bin").unwrap();
+        settings.set("cache_ttl", "600").unwrap();
+        settings.set("prefer", "musl").unwrap();
+
+        settings.unset("install_dir").unwrap();
+        settings.unset("cache_ttl").unwrap();
+        settings.unset("prefer").unwrap();
+
+        assert_eq!(settings.install_dir, Settings::default().install_dir);
+        let serialized = toml::to_string(&settings).unwrap();
+        assert!(!serialized.contains("cache_ttl"));
+        assert!(!serialized.contains("prefer"));
+        assert!(settings.unset("colors").is_err());
+    }
+
     #[test]
     fn test_config_default() {
         let config = Config::default();
@@ -220,6 +1731,7 @@ mod tests {
             binary_name: None,
             asset_pattern: None,
             version: None,
+            ..Default::default()
         };
 
         assert!(config.add_tool(tool).is_ok());
@@ -236,6 +1748,7 @@ mod tests {
             binary_name: None,
             asset_pattern: None,
             version: None,
+            ..Default::default()
         };
         let tool2 = tool1.clone();
 
@@ -253,6 +1766,7 @@ mod tests {
             binary_name: None,
             asset_pattern: None,
             version: None,
+            ..Default::default()
         };
 
         config.add_tool(tool).unwrap();
@@ -277,6 +1791,7 @@ mod tests {
             binary_name: Some("custom-name".to_string()),
             asset_pattern: None,
             version: None,
+            ..Default::default()
         };
 
         config.add_tool(tool).unwrap();
@@ -303,6 +1818,7 @@ mod tests {
             binary_name: None,
             asset_pattern: Some("linux-x64".to_string()),
             version: Some("v0.32.5".to_string()),
+            ..Default::default()
         };
         config.add_tool(tool).unwrap();
 
@@ -324,6 +1840,70 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_config_formats_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = Config::default();
+        config.settings.install_dir = PathBuf::from("/custom/path");
+        config.settings.prefer = vec!["musl".to_string()];
+        config
+            .add_tool(Tool {
+                name: "k9s".to_string(),
+                repo: "derailed/k9s".to_string(),
+                channel: Some(Channel::Prerelease),
+                pinned: true,
+                ..Default::default()
+            })
+            .unwrap();
+
+        for name in ["config.toml", "config.yaml", "config.yml", "config.json"] {
+            let path = temp_dir.path().join(name);
+            config.save_to(&path).unwrap();
+            let loaded = Config::load_from(&path).unwrap();
+
+            assert_eq!(loaded.settings.install_dir, PathBuf::from("/custom/path"));
+            assert_eq!(loaded.settings.prefer, vec!["musl"]);
+            assert_eq!(loaded.tools[0].channel, Some(Channel::Prerelease));
+            assert!(loaded.tools[0].pinned);
+        }
+
+        let yaml = fs::read_to_string(temp_dir.path().join("config.yaml")).unwrap();
+        assert!(yaml.contains("install_dir: /custom/path"));
+        let json = fs::read_to_string(temp_dir.path().join("config.json")).unwrap();
+        assert!(json.contains("\"install_dir\": \"/custom/path\""));
+    }
+
+    #[test]
+    fn test_load_yaml_config() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("config.yaml");
+        fs::write(
+            &path,
+            "settings:\n  install_dir: /opt/bin\ntools:\n  - name: k9s\n    repo: derailed/k9s\n",
+        )
+        .unwrap();
+
+        let config = Config::load_from(&path).unwrap();
+        assert_eq!(config.settings.install_dir, PathBuf::from("/opt/bin"));
+        assert_eq!(config.tools[0].repo, "derailed/k9s");
+
+        fs::write(&path, "settings: [").unwrap();
+        assert!(matches!(
+            Config::load_from(&path),
+            Err(OktofetchError::ConfigError(_, _))
+        ));
+
+        fs::write(
+            &path,
+            "tools:\n  - name: k9s\n    repo: derailed/k9s\n    install_as: ../../.bashrc\n",
+        )
+        .unwrap();
+        assert!(matches!(
+            Config::load_from(&path),
+            Err(OktofetchError::ConfigError(_, _))
+        ));
+    }
+
     #[test]
     fn test_expand_path_tilde() {
         unsafe {
@@ -421,6 +2001,7 @@ mod tests {
             binary_name: None,
             asset_pattern: None,
             version: Some("v1.0.0".to_string()),
+            ..Default::default()
         };
         config.add_tool(tool).unwrap();
 
@@ -438,6 +2019,133 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_update_tool_checksum() {
+        let mut config = Config::default();
+        config
+            .add_tool(Tool {
+                name: "mytool".to_string(),
+                repo: "owner/repo".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+
+        config
+            .update_tool_checksum("mytool", "abc123".to_string())
+            .unwrap();
+        assert_eq!(
+            config.get_tool("mytool").unwrap().checksum,
+            Some("abc123".to_string())
+        );
+        assert!(
+            config
+                .update_tool_checksum("nonexistent", "abc123".to_string())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_channel_list_options() {
+        assert_eq!(Channel::Latest.list_options(), ListOptions::default());
+
+        let opts = Channel::Prerelease.list_options();
+        assert!(opts.include_prereleases);
+        assert!(!opts.include_drafts);
+
+        let opts = Channel::Any.list_options();
+        assert!(opts.include_prereleases);
+        assert!(opts.include_drafts);
+    }
+
+    #[test]
+    fn test_channel_serialization() {
+        let tool: Tool = toml::from_str(
+            r#"
+            name = "mytool"
+            repo = "owner/repo"
+            channel = "prerelease"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(tool.channel, Some(Channel::Prerelease));
+        assert_eq!(Channel::Prerelease.to_string(), "prerelease");
+        assert!(
+            toml::to_string(&tool)
+                .unwrap()
+                .contains("channel = \"prerelease\"")
+        );
+
+        let result: std::result::Result<Tool, _> = toml::from_str(
+            r#"
+            name = "mytool"
+            repo = "owner/repo"
+            channel = "nightly"
+            "#,
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_release_repo_fallback() {
+        let mut tool = Tool {
+            name: "mytool".to_string(),
+            repo: "org/tool".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(tool.release_repo(), "org/tool");
+
+        tool.release_repo = Some("org/tool-releases".to_string());
+        assert_eq!(tool.release_repo(), "org/tool-releases");
+        assert_eq!(tool.repo, "org/tool");
+    }
+
+    #[test]
+    fn test_tag_format_round_trip() {
+        let mut tool = Tool {
+            name: "mytool".to_string(),
+            repo: "owner/repo".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(tool.tag_for_version("v1.2.3"), "v1.2.3");
+        assert_eq!(tool.version_from_tag("v1.2.3"), "v1.2.3");
+
+        tool.tag_format = Some("mytool/v{version}".to_string());
+        assert_eq!(tool.tag_for_version("1.2.3"), "mytool/v1.2.3");
+        assert_eq!(tool.version_from_tag("mytool/v1.2.3"), "1.2.3");
+
+        tool.tag_format = Some("release-{version}-final".to_string());
+        assert_eq!(tool.version_from_tag("release-2.0-final"), "2.0");
+    }
+
+    #[test]
+    fn test_version_from_tag_non_matching() {
+        let tool = Tool {
+            name: "mytool".to_string(),
+            tag_format: Some("release-{version}".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(tool.version_from_tag("v1.0.0"), "v1.0.0");
+        assert_eq!(tool.version_from_tag("release-"), "release-");
+    }
+
+    #[test]
+    fn test_binary_file_name() {
+        let mut tool = Tool {
+            name: "mytool".to_string(),
+            repo: "owner/repo".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(tool.binary_file_name(), "mytool");
+
+        tool.binary_name = Some("mybin".to_string());
+        assert_eq!(tool.binary_file_name(), "mybin");
+        assert_eq!(tool.installed_name(), "mybin");
+
+        tool.install_as = Some("kube9".to_string());
+        assert_eq!(tool.binary_file_name(), "mybin");
+        assert_eq!(tool.installed_name(), "kube9");
+    }
+
     #[test]
     fn test_get_tool_mut() {
         let mut config = Config::default();
@@ -447,6 +2155,7 @@ mod tests {
             binary_name: None,
             asset_pattern: None,
             version: None,
+            ..Default::default()
         };
         config.add_tool(tool).unwrap();
 
@@ -463,11 +2172,348 @@ mod tests {
 
     #[test]
     fn test_config_path() {
-        let result = Config::config_path();
-        assert!(result.is_ok());
-        let path = result.unwrap();
+        temp_env::with_var_unset(CONFIG_ENV, || {
+            let result = Config::config_path();
+            assert!(result.is_ok());
+            let path = result.unwrap();
+            assert!(path.to_string_lossy().contains("oktofetch"));
+            assert!(path.to_string_lossy().contains("config.toml"));
+        });
+    }
+
+    #[test]
+    fn test_env_overrides_are_not_saved() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("config.toml");
+        fs::write(
+            &path,
+            "[settings]\ninstall_dir = \"/file/bin\"\nkeep_versions = 2\n\n\
+             [settings.http]\nretries = 5\n",
+        )
+        .unwrap();
+
+        temp_env::with_vars(
+            [
+                ("OKTOFETCH_INSTALL_DIR", Some("/env/bin")),
+                ("OKTOFETCH_CACHE_TTL", Some("60")),
+                ("OKTOFETCH_KEEP_VERSIONS", None),
+                ("OKTOFETCH_HTTP_RETRIES", Some("0")),
+                ("OKTOFETCH_HTTP_READ_TIMEOUT", Some("2m")),
+            ],
+            || {
+                let mut config = Config::load_from(&path).unwrap();
+                config.apply_env_overrides().unwrap();
+
+                assert_eq!(config.settings.install_dir, PathBuf::from("/env/bin"));
+                assert_eq!(config.settings.cache_ttl, Some(60));
+                assert_eq!(config.settings.keep_versions, Some(2));
+                assert_eq!(config.settings.http.retries, Some(0));
+                assert_eq!(config.settings.http.read_timeout, Some(120));
+                assert_eq!(
+                    config.env_overrides,
+                    [
+                        "install_dir",
+                        "cache_ttl",
+                        "http.retries",
+                        "http.read_timeout"
+                    ]
+                );
+
+                config.settings.set("prefer", "musl").unwrap();
+                config.save_to(&path).unwrap();
+            },
+        );
+
+        let saved = Config::load_from(&path).unwrap();
+        assert_eq!(saved.settings.install_dir, PathBuf::from("/file/bin"));
+        assert_eq!(saved.settings.cache_ttl, None);
+        assert_eq!(saved.settings.keep_versions, Some(2));
+        assert_eq!(saved.settings.prefer, vec!["musl"]);
+        assert_eq!(saved.settings.http.retries, Some(5));
+        assert_eq!(saved.settings.http.read_timeout, None);
+    }
+
+    #[test]
+    fn test_http_policy() {
+        let mut settings = Settings::default();
+        assert_eq!(settings.http_policy(), HttpPolicy::default());
+        assert!(!toml::to_string(&settings).unwrap().contains("http"));
+
+        settings.set("http.retries", "4").unwrap();
+        settings.set("http.backoff_base", "250").unwrap();
+        settings.set("http.connect_timeout", "10s").unwrap();
+        assert!(settings.set("http.read_timeout", "soon").is_err());
+
+        let policy = settings.http_policy();
+        assert_eq!(policy.retries, 4);
+        assert_eq!(policy.backoff_base, Duration::from_millis(250));
+        assert_eq!(policy.connect_timeout, Duration::from_secs(10));
+        assert_eq!(policy.read_timeout, HttpPolicy::default().read_timeout);
+        assert_eq!(settings.get("http.read_timeout").unwrap().unwrap(), "60");
+        assert_eq!(policy.local_address, None);
+
+        settings.set("ip_version", "v4").unwrap();
+        assert_eq!(
+            settings.http_policy().local_address,
+            Some(std::net::Ipv4Addr::UNSPECIFIED.into())
+        );
+        assert_eq!(settings.get("ip_version").unwrap().unwrap(), "v4");
+        assert!(settings.set("ip_version", "v5").is_err());
+
+        let serialized = toml::to_string(&settings).unwrap();
+        assert!(serialized.contains("[http]\nretries = 4\n"));
+        settings.unset("http.retries").unwrap();
+        assert_eq!(
+            settings.http_policy().retries,
+            HttpPolicy::default().retries
+        );
+    }
+
+    #[test]
+    fn test_env_override_invalid_value() {
+        temp_env::with_var("OKTOFETCH_KEEP_VERSIONS", Some("lots"), || {
+            let err = Config::default().apply_env_overrides().unwrap_err();
+            assert!(err.to_string().contains("OKTOFETCH_KEEP_VERSIONS"));
+        });
+    }
+
+    #[test]
+    fn test_save_to_leaves_out_state() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("config.toml");
+        let mut config = Config::default();
+        config
+            .add_tool(Tool {
+                name: "k9s".to_string(),
+                repo: "derailed/k9s".to_string(),
+                version: Some("v0.32.5".to_string()),
+                checksum: Some("abc".to_string()),
+                ..Default::default()
+            })
+            .unwrap();
+
+        config.save_to(&path).unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(!content.contains("version"));
+        assert!(!content.contains("checksum"));
+        assert_eq!(config.tools[0].version.as_deref(), Some("v0.32.5"));
+    }
+
+    #[test]
+    fn test_write_atomic() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("config.toml");
+
+        write_atomic(&path, b"first").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "first");
+
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o640)).unwrap();
+        write_atomic(&path, b"second").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "second");
+        assert_eq!(
+            fs::metadata(&path).unwrap().permissions().mode() & 0o777,
+            0o640
+        );
+
+        // Only the target is left behind, no temporary files
+        assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 1);
+
+        // A symlinked config stays a symlink
+        let dotfiles = temp_dir.path().join("dotfiles");
+        fs::create_dir(&dotfiles).unwrap();
+        fs::write(dotfiles.join("oktofetch.toml"), "linked").unwrap();
+        let link = temp_dir.path().join("link.toml");
+        std::os::unix::fs::symlink(dotfiles.join("oktofetch.toml"), &link).unwrap();
+        write_atomic(&link, b"third").unwrap();
+        assert!(fs::symlink_metadata(&link).unwrap().is_symlink());
+        assert_eq!(
+            fs::read_to_string(dotfiles.join("oktofetch.toml")).unwrap(),
+            "third"
+        );
+        assert_eq!(fs::read_dir(&dotfiles).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("kube*", "kubectl"));
+        assert!(glob_match("kube*", "kube"));
+        assert!(glob_match("*fmt", "dprint-fmt"));
+        assert!(glob_match("k?s", "k9s"));
+        assert!(glob_match("*-*-*", "a-b-c"));
+        assert!(!glob_match("kube*", "minikube"));
+        assert!(!glob_match("k?s", "k99s"));
+        assert!(!glob_match("a*b", "acbd"));
+    }
+
+    #[test]
+    fn test_resolve_names() {
+        let mut config = Config::default();
+        for name in ["kubectl", "kubens", "k9s", "terraform"] {
+            config
+                .add_tool(Tool {
+                    name: name.to_string(),
+                    repo: format!("owner/{}", name),
+                    ..Default::default()
+                })
+                .unwrap();
+        }
+        let names = |patterns: &[&str]| {
+            let patterns: Vec<String> = patterns.iter().map(|p| p.to_string()).collect();
+            config.resolve_names(&patterns)
+        };
+
+        assert_eq!(
+            names(&["kube*", "k9s", "kubectl"]).unwrap(),
+            vec!["kubectl", "kubens", "k9s"]
+        );
+        assert_eq!(names(&["unknown"]).unwrap(), vec!["unknown"]);
+        assert!(matches!(
+            names(&["vault*"]),
+            Err(OktofetchError::ToolNotFound(p)) if p == "vault*"
+        ));
+
+        assert_eq!(config.resolve_name("terra*").unwrap(), "terraform");
+        assert_eq!(config.resolve_name("nope").unwrap(), "nope");
+        let err = config.resolve_name("kube*").unwrap_err();
+        assert!(err.to_string().contains("kubectl, kubens"));
+    }
+
+    #[test]
+    fn test_relative_install_dir() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("oktofetch.toml");
+        fs::write(&path, "[settings]\ninstall_dir = \".oktofetch/bin\"\n").unwrap();
+
+        let mut config = Config::load_from(&path).unwrap();
+        assert_eq!(
+            config.settings.install_dir,
+            temp_dir.path().join(".oktofetch/bin")
+        );
+
+        config.save_to(&path).unwrap();
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(content.contains("install_dir = \".oktofetch/bin\""));
+
+        config.settings.install_dir = PathBuf::from("/opt/bin");
+        config.save_to(&path).unwrap();
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(content.contains("install_dir = \"/opt/bin\""));
+    }
+
+    #[test]
+    fn test_save_to_leaves_file_alone_for_state_changes() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("config.toml");
+        let written = "# my hand-written comment\n\
+                       [settings]\n\
+                       install_dir = \"/opt/bin\"\n\n\
+                       [[tools]]\n\
+                       name = \"k9s\"\n\
+                       repo = \"derailed/k9s\"\n";
+        fs::write(&path, written).unwrap();
+
+        let mut config = Config::load_from(&path).unwrap();
+        let tool = config.get_tool_mut("k9s").unwrap();
+        tool.checked_at = Some(1_700_000_000);
+        tool.version = Some("v0.32.5".to_string());
+        config.save_to(&path).unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), written);
+        assert!(!Config::backup_path(&path).exists());
+
+        // A declarative change is written
+        config.get_tool_mut("k9s").unwrap().pinned = true;
+        config.save_to(&path).unwrap();
+        assert!(fs::read_to_string(&path).unwrap().contains("pinned = true"));
+    }
+
+    #[test]
+    fn test_save_to_keeps_backup() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("config.toml");
+        let backup = Config::backup_path(&path);
+        assert!(backup.ends_with("config.toml.bak"));
+
+        let mut config = Config::default();
+        config.save_to(&path).unwrap();
+        assert!(!backup.exists());
+
+        let first = fs::read_to_string(&path).unwrap();
+        config.save_to(&path).unwrap();
+        assert!(!backup.exists());
+
+        config
+            .add_tool(Tool {
+                name: "k9s".to_string(),
+                repo: "derailed/k9s".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        config.save_to(&path).unwrap();
+
+        assert_eq!(fs::read_to_string(&backup).unwrap(), first);
+        assert!(fs::read_to_string(&path).unwrap().contains("k9s"));
+    }
+
+    #[test]
+    fn test_state_path() {
+        temp_env::with_var_unset(CONFIG_ENV, || {
+            assert!(Config::state_path().unwrap().ends_with("state.json"));
+        });
+        temp_env::with_var(CONFIG_ENV, Some("/srv/a.toml"), || {
+            let a = Config::state_path().unwrap();
+            temp_env::with_var(CONFIG_ENV, Some("/srv/b.toml"), || {
+                let b = Config::state_path().unwrap();
+                assert_ne!(a, b);
+                assert!(
+                    b.file_name()
+                        .unwrap()
+                        .to_string_lossy()
+                        .starts_with("state-")
+                );
+            });
+        });
+    }
+
+    #[test]
+    fn test_config_path_env_override() {
+        temp_env::with_var(CONFIG_ENV, Some("/srv/team/oktofetch.yaml"), || {
+            assert_eq!(
+                Config::config_path().unwrap(),
+                PathBuf::from("/srv/team/oktofetch.yaml")
+            );
+        });
+        temp_env::with_var(CONFIG_ENV, Some(""), || {
+            assert!(Config::config_path().unwrap().ends_with("config.toml"));
+        });
+    }
+
+    #[test]
+    fn test_expand_config_path() {
+        temp_env::with_vars(
+            [("HOME", Some("/home/testuser")), ("TEAM", Some("ops"))],
+            || {
+                assert_eq!(
+                    Config::expand_config_path(Path::new("~/$TEAM/oktofetch.toml")),
+                    PathBuf::from("/home/testuser/ops/oktofetch.toml")
+                );
+                assert_eq!(
+                    Config::expand_config_path(Path::new("/srv/c.yaml")),
+                    PathBuf::from("/srv/c.yaml")
+                );
+            },
+        );
+    }
+
+    #[test]
+    fn test_versions_dir() {
+        let path = Config::versions_dir().unwrap();
         assert!(path.to_string_lossy().contains("oktofetch"));
-        assert!(path.to_string_lossy().contains("config.toml"));
+        assert!(path.ends_with("versions"));
+        assert!(path.starts_with(Config::data_dir().unwrap()));
     }
 
     #[test]
@@ -478,6 +2524,7 @@ mod tests {
             binary_name: Some("testbin".to_string()),
             asset_pattern: Some("linux-x64".to_string()),
             version: Some("v1.0.0".to_string()),
+            ..Default::default()
         };
 
         let serialized = toml::to_string(&tool).unwrap();
@@ -504,6 +2551,7 @@ mod tests {
             binary_name: None,
             asset_pattern: None,
             version: None,
+            ..Default::default()
         };
 
         let serialized = toml::to_string(&tool).unwrap();
@@ -573,6 +2621,7 @@ mod tests {
             binary_name: Some("bin".to_string()),
             asset_pattern: None,
             version: Some("v1.0.0".to_string()),
+            ..Default::default()
         };
 
         let tool2 = tool1.clone();
@@ -586,6 +2635,7 @@ mod tests {
     fn test_settings_serialization() {
         let settings = Settings {
             install_dir: PathBuf::from("/custom/path"),
+            ..Settings::default()
         };
 
         let serialized = toml::to_string(&settings).unwrap();
@@ -609,6 +2659,7 @@ mod tests {
                     binary_name: None,
                     asset_pattern: None,
                     version: None,
+                    ..Default::default()
                 })
                 .unwrap();
         }
@@ -666,4 +2717,42 @@ mod tests {
         assert_eq!(super::expand_path("$TEST1-$TEST2"), "value1-value2");
         assert_eq!(super::expand_path("${TEST1}-${TEST2}"), "value1-value2");
     }
+
+    #[test]
+    fn test_cache_ttl_setting() {
+        let mut settings = Settings::default();
+        assert_eq!(settings.cache_ttl(), None);
+
+        settings.cache_ttl = Some(0);
+        assert_eq!(settings.cache_ttl(), None);
+
+        settings.cache_ttl = Some(300);
+        assert_eq!(
+            settings.cache_ttl(),
+            Some(std::time::Duration::from_secs(300))
+        );
+    }
+
+    #[test]
+    fn test_extract_limits_defaults() {
+        let settings = Settings::default();
+        assert_eq!(settings.extract_limits(), ExtractLimits::default());
+    }
+
+    #[test]
+    fn test_extract_limits_from_settings() {
+        let settings: Settings = toml::from_str(
+            r#"
+            install_dir = "/custom/path"
+            max_extract_size = 2048
+            max_extract_files = 5
+            "#,
+        )
+        .unwrap();
+
+        let limits = settings.extract_limits();
+        assert_eq!(limits.max_total_size, 2048);
+        assert_eq!(limits.max_file_size, ExtractLimits::default().max_file_size);
+        assert_eq!(limits.max_files, 5);
+    }
 }
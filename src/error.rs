@@ -1,4 +1,7 @@
+use crate::human;
+use crate::redact;
 use std::io;
+use std::panic::Location;
 use std::path::PathBuf;
 use thiserror::Error;
 
@@ -15,9 +18,20 @@ pub enum OktofetchError {
     #[error("Repository not found: {0}")]
     RepoNotFound(String),
 
+    #[error("Release {tag} not found in {repo}")]
+    ReleaseNotFound { repo: String, tag: String },
+
     #[error("No suitable release for {platform} {arch}")]
     NoSuitableRelease { platform: String, arch: String },
 
+    /// The GitHub API quota is used up. `reset_at` is the unix time it
+    /// refills, when the response said so.
+    #[error("GitHub API rate limit exceeded{}", rate_limit_hint(*reset_at, *authenticated))]
+    RateLimited {
+        reset_at: Option<u64>,
+        authenticated: bool,
+    },
+
     #[error("Config error: {0} at {1}")]
     ConfigError(String, PathBuf),
 
@@ -33,29 +47,122 @@ pub enum OktofetchError {
     #[error("IO error: {0}")]
     Io(#[from] io::Error),
 
-    #[error("HTTP error: {0}")]
+    #[error("HTTP error: {}", redact::text(&.0.to_string()))]
     Reqwest(#[from] reqwest::Error),
 
+    /// Something had to be asked, but prompts are off.
+    #[error("{0} (prompts are off: --non-interactive or $CI is set)")]
+    InputRequired(String),
+
     #[error("{0}")]
     Other(String),
+
+    /// `source` annotated with the operation that failed, added with [`Context`].
+    #[error("{context}: {source}")]
+    Context {
+        context: String,
+        location: &'static Location<'static>,
+        source: Box<OktofetchError>,
+    },
 }
 
 impl OktofetchError {
     pub fn exit_code(&self) -> i32 {
         match self {
+            Self::Context { .. } => self.root().exit_code(),
             Self::ToolNotFound(_) => 1,
             Self::GithubApi(_) => 2,
             Self::RepoNotFound(_) => 1,
             Self::NoSuitableRelease { .. } => 3,
+            Self::ReleaseNotFound { .. } => 12,
+            Self::RateLimited { .. } => 13,
             Self::ConfigError(_, _) => 4,
             Self::DownloadFailed(_) => 7,
             Self::ExtractionFailed(_) => 8,
             Self::BinaryNotFound(_) => 9,
             Self::Io(_) => 10,
             Self::Reqwest(_) => 11,
+            Self::InputRequired(_) => 14,
             Self::Other(_) => 1,
         }
     }
+
+    /// The innermost error, below any added context.
+    pub fn root(&self) -> &Self {
+        match self {
+            Self::Context { source, .. } => source.root(),
+            other => other,
+        }
+    }
+
+    /// Text shown to the user. With `verbose`, each context goes on its own
+    /// line with the place it was added, followed by every underlying cause.
+    /// Credentials in URLs and headers are masked either way.
+    pub fn report(&self, verbose: bool) -> String {
+        if !verbose {
+            return redact::text(&self.to_string());
+        }
+
+        let mut lines = Vec::new();
+        let mut error = self;
+        while let Self::Context {
+            context,
+            location,
+            source,
+        } = error
+        {
+            lines.push(format!("{}\n    at {}", context, location));
+            error = source;
+        }
+        lines.push(error.to_string());
+
+        let mut cause = std::error::Error::source(error);
+        while let Some(err) = cause {
+            lines.push(err.to_string());
+            cause = err.source();
+        }
+
+        let mut report = lines.remove(0);
+        for line in lines {
+            report.push_str("\nCaused by: ");
+            report.push_str(&line);
+        }
+        redact::text(&report)
+    }
+}
+
+/// Adds operation context to errors, e.g. which asset was being downloaded
+/// when an I/O error occurred.
+pub trait Context<T> {
+    fn with_context(self, context: impl FnOnce() -> String) -> Result<T>;
+}
+
+impl<T, E: Into<OktofetchError>> Context<T> for std::result::Result<T, E> {
+    #[track_caller]
+    fn with_context(self, context: impl FnOnce() -> String) -> Result<T> {
+        let location = Location::caller();
+        self.map_err(|e| OktofetchError::Context {
+            context: context(),
+            location,
+            source: Box::new(e.into()),
+        })
+    }
+}
+
+fn rate_limit_hint(reset_at: Option<u64>, authenticated: bool) -> String {
+    let mut hint = String::new();
+    if let Some(reset_at) = reset_at {
+        let wait = reset_at.saturating_sub(human::unix_now());
+        hint.push_str(&format!(
+            "; resets in {} (at unix time {})",
+            human::format_duration(wait),
+            reset_at
+        ));
+    }
+    if !authenticated {
+        hint.push_str("; set GITHUB_TOKEN or run `oktofetch auth login` for a higher limit");
+    }
+    hint
 }
 
 #[cfg(test)]
@@ -96,6 +203,79 @@ mod tests {
             OktofetchError::BinaryNotFound("error".to_string()).exit_code(),
             9
         );
+        assert_eq!(
+            OktofetchError::ReleaseNotFound {
+                repo: "owner/repo".to_string(),
+                tag: "v1.0.0".to_string()
+            }
+            .exit_code(),
+            12
+        );
+        assert_eq!(
+            OktofetchError::RateLimited {
+                reset_at: None,
+                authenticated: true
+            }
+            .exit_code(),
+            13
+        );
+    }
+
+    #[test]
+    fn test_rate_limited_display() {
+        let err = OktofetchError::RateLimited {
+            reset_at: Some(human::unix_now() + 90),
+            authenticated: false,
+        };
+        let display = err.to_string();
+        assert!(display.starts_with("GitHub API rate limit exceeded; resets in"));
+        assert!(display.contains("GITHUB_TOKEN"));
+
+        let err = OktofetchError::RateLimited {
+            reset_at: None,
+            authenticated: true,
+        };
+        assert_eq!(err.to_string(), "GitHub API rate limit exceeded");
+    }
+
+    #[test]
+    fn test_context_chain() {
+        let io_err = std::io::Error::new(std::io::ErrorKind::PermissionDenied, "permission denied");
+        let err = Err::<(), _>(io_err)
+            .with_context(|| "while installing k9s".to_string())
+            .with_context(|| "while updating k9s".to_string())
+            .unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "while updating k9s: while installing k9s: IO error: permission denied"
+        );
+        assert_eq!(err.exit_code(), 10);
+        assert!(matches!(err.root(), OktofetchError::Io(_)));
+        assert_eq!(err.report(false), err.to_string());
+
+        let report = err.report(true);
+        let lines: Vec<_> = report.lines().collect();
+        assert_eq!(lines[0], "while updating k9s");
+        assert!(lines[1].trim_start().starts_with("at src/error.rs:"));
+        assert_eq!(lines[2], "Caused by: while installing k9s");
+        assert_eq!(lines[4], "Caused by: IO error: permission denied");
+    }
+
+    #[test]
+    fn test_report_redacts_credentials() {
+        let err: Result<()> = Err(OktofetchError::DownloadFailed(
+            "https://cdn.example/a.tgz?sig=s3cret: 403".to_string(),
+        ));
+        let err = err
+            .with_context(|| "Failed to download a.tgz".to_string())
+            .unwrap_err();
+
+        for verbose in [false, true] {
+            let report = err.report(verbose);
+            assert!(!report.contains("s3cret"), "{}", report);
+            assert!(report.contains("https://cdn.example/a.tgz?sig=***"));
+        }
     }
 
     #[test]
@@ -131,6 +311,10 @@ mod tests {
             OktofetchError::DownloadFailed("download error".to_string()),
             OktofetchError::ExtractionFailed("extract error".to_string()),
             OktofetchError::BinaryNotFound("binary not found".to_string()),
+            OktofetchError::ReleaseNotFound {
+                repo: "owner/repo".to_string(),
+                tag: "v1.0.0".to_string(),
+            },
             OktofetchError::Other("other error".to_string()),
         ];
 
@@ -0,0 +1,149 @@
+//! Machine-readable progress for `--porcelain`: one JSON object per line on
+//! stdout, with the human-oriented output moved to stderr.
+//!
+//! Every event has an `event` field naming it and a `tool` field. Fields are
+//! only ever added, never renamed or removed.
+
+use serde::Serialize;
+use std::fs::File;
+use std::io::Write;
+use std::os::fd::FromRawFd;
+use std::sync::{Mutex, OnceLock};
+
+/// Where events go; unset unless `--porcelain` was given.
+static SINK: OnceLock<Mutex<File>> = OnceLock::new();
+
+/// Tool the running download belongs to.
+static CURRENT_TOOL: Mutex<String> = Mutex::new(String::new());
+
+/// Download progress is reported at most once per percent, or per MiB when
+/// the size isn't known.
+const UNKNOWN_SIZE_STEP: u64 = 1024 * 1024;
+
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event<'a> {
+    /// Work on a tool began; `version` is the one installed before
+    Started {
+        tool: &'a str,
+        version: Option<&'a str>,
+    },
+    Download {
+        tool: &'a str,
+        bytes: u64,
+        total: Option<u64>,
+        percent: Option<u64>,
+    },
+    Installed {
+        tool: &'a str,
+        version: Option<&'a str>,
+    },
+    UpToDate {
+        tool: &'a str,
+        version: Option<&'a str>,
+    },
+    Skipped {
+        tool: &'a str,
+    },
+    Failed {
+        tool: &'a str,
+        error: String,
+    },
+}
+
+/// Sends events to stdout from now on, and points stdout itself at stderr
+/// so that nothing else gets mixed in.
+pub fn enable() -> std::io::Result<()> {
+    // SAFETY: plain descriptor duplication; the duplicate is owned by the File
+    let events = unsafe {
+        let fd = libc::dup(libc::STDOUT_FILENO);
+        if fd < 0 || libc::dup2(libc::STDERR_FILENO, libc::STDOUT_FILENO) < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        File::from_raw_fd(fd)
+    };
+    let _ = SINK.set(Mutex::new(events));
+    Ok(())
+}
+
+pub fn enabled() -> bool {
+    SINK.get().is_some()
+}
+
+pub fn emit(event: &Event) {
+    if let Event::Started { tool, .. } = event {
+        *CURRENT_TOOL.lock().unwrap_or_else(|e| e.into_inner()) = tool.to_string();
+    }
+    let Some(sink) = SINK.get() else {
+        return;
+    };
+    if let Ok(line) = serde_json::to_string(event) {
+        let mut sink = sink.lock().unwrap_or_else(|e| e.into_inner());
+        let _ = writeln!(sink, "{}", line);
+        let _ = sink.flush();
+    }
+}
+
+/// Whether going from `before` to `after` bytes crosses a reporting step.
+fn crosses_step(before: u64, after: u64, total: Option<u64>) -> bool {
+    match total {
+        Some(total) if total > 0 => before * 100 / total != after * 100 / total,
+        _ => before / UNKNOWN_SIZE_STEP != after / UNKNOWN_SIZE_STEP,
+    }
+}
+
+/// Reports that the current download grew from `before` to `after` bytes.
+pub fn download_progress(before: u64, after: u64, total: Option<u64>) {
+    if !enabled() || (before > 0 && !crosses_step(before, after, total)) {
+        return;
+    }
+    let tool = CURRENT_TOOL
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone();
+    emit(&Event::Download {
+        tool: &tool,
+        bytes: after,
+        total,
+        percent: total.filter(|t| *t > 0).map(|t| after * 100 / t),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_format() {
+        let line = serde_json::to_string(&Event::Download {
+            tool: "k9s",
+            bytes: 512,
+            total: Some(1024),
+            percent: Some(50),
+        })
+        .unwrap();
+        assert_eq!(
+            line,
+            r#"{"event":"download","tool":"k9s","bytes":512,"total":1024,"percent":50}"#
+        );
+
+        let line = serde_json::to_string(&Event::UpToDate {
+            tool: "k9s",
+            version: None,
+        })
+        .unwrap();
+        assert_eq!(
+            line,
+            r#"{"event":"up_to_date","tool":"k9s","version":null}"#
+        );
+    }
+
+    #[test]
+    fn test_crosses_step() {
+        assert!(!crosses_step(100, 105, Some(1000)));
+        assert!(crosses_step(105, 110, Some(1000)));
+        assert!(crosses_step(999, 1000, Some(1000)));
+        assert!(!crosses_step(10, 20, None));
+        assert!(crosses_step(10, UNKNOWN_SIZE_STEP, None));
+    }
+}
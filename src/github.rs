@@ -1,12 +1,33 @@
+use crate::auth;
+use crate::cache::{CachedResponse, ResponseCache};
 use crate::error::{OktofetchError, Result};
-use reqwest::Client;
+use crate::events;
+use crate::http::{HttpContext, redirect_error};
+use crate::human;
+use crate::redact;
+use crate::throttle::Throttle;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Release {
+    #[serde(default)]
+    pub id: u64,
     pub tag_name: String,
     pub name: String,
     pub assets: Vec<Asset>,
+    #[serde(default)]
+    pub prerelease: bool,
+    #[serde(default)]
+    pub draft: bool,
+    /// Release notes, in Markdown
+    #[serde(default)]
+    pub body: Option<String>,
+    /// When the release was published, e.g. `2024-03-01T12:30:00Z`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub published_at: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -14,28 +35,221 @@ pub struct Asset {
     pub name: String,
     pub browser_download_url: String,
     pub size: u64,
+    /// Content digest published by GitHub, e.g. `sha256:<hex>`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub digest: Option<String>,
 }
 
+impl Asset {
+    /// Hex SHA256 from `digest`, if GitHub published one.
+    pub fn sha256(&self) -> Option<&str> {
+        self.digest.as_deref()?.strip_prefix("sha256:")
+    }
+}
+
+/// Quota for one API resource, as reported by `/rate_limit`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RateLimit {
+    pub limit: u64,
+    pub remaining: u64,
+    /// Unix timestamp when the quota resets
+    pub reset: u64,
+    #[serde(default)]
+    pub used: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct RateLimitResources {
+    core: RateLimit,
+}
+
+#[derive(Debug, Deserialize)]
+struct RateLimitResponse {
+    resources: RateLimitResources,
+}
+
+/// A repository returned by the search API.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RepoSearchResult {
+    pub full_name: String,
+    #[serde(default)]
+    pub stargazers_count: u64,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// A repository as the repos API describes it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Repository {
+    pub full_name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub homepage: Option<String>,
+    #[serde(default)]
+    pub license: Option<License>,
+    #[serde(default)]
+    pub stargazers_count: u64,
+    /// Read-only: no further releases are coming
+    #[serde(default)]
+    pub archived: bool,
+}
+
+/// License GitHub detected in a repository.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct License {
+    pub name: String,
+    /// SPDX identifier, `NOASSERTION` when GitHub couldn't tell
+    #[serde(default)]
+    pub spdx_id: Option<String>,
+}
+
+impl License {
+    /// The SPDX identifier if there is a real one, else the license's name.
+    pub fn label(&self) -> &str {
+        match self.spdx_id.as_deref() {
+            Some(id) if id != "NOASSERTION" => id,
+            _ => &self.name,
+        }
+    }
+}
+
+/// What the GraphQL lookup found about one repository.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RepoSnapshot {
+    /// Tag of the latest release, if there is one
+    pub latest_tag: Option<String>,
+    /// Unix time the latest release was published
+    pub released_at: Option<u64>,
+    pub archived: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct RepoSearchResponse {
+    items: Vec<RepoSearchResult>,
+}
+
+/// Filters applied when listing releases.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ListOptions {
+    pub include_prereleases: bool,
+    pub include_drafts: bool,
+    /// Stop after this many matching releases
+    pub limit: Option<usize>,
+}
+
+impl ListOptions {
+    pub fn matches(&self, release: &Release) -> bool {
+        (self.include_prereleases || !release.prerelease) && (self.include_drafts || !release.draft)
+    }
+
+    /// Filters and truncates an already-fetched release list.
+    pub fn apply(&self, releases: Vec<Release>) -> Vec<Release> {
+        releases
+            .into_iter()
+            .filter(|r| self.matches(r))
+            .take(self.limit.unwrap_or(usize::MAX))
+            .collect()
+    }
+}
+
+const GITHUB_API_URL: &str = "https://api.github.com";
+
+/// Web root serving the unauthenticated `releases.atom` feeds.
+const GITHUB_WEB_URL: &str = "https://github.com";
+
+/// Number of assets the releases endpoints embed before the list is cut off.
+const EMBEDDED_ASSET_LIMIT: usize = 30;
+
+/// Assets smaller than this are always downloaded over a single connection.
+const PARALLEL_DOWNLOAD_MIN_SIZE: u64 = 32 * 1024 * 1024;
+
+/// Largest page size the GitHub REST API accepts.
+const MAX_PER_PAGE: usize = 100;
+
+/// Repositories looked up per GraphQL query, well within the API's limits
+/// on query cost.
+const GRAPHQL_BATCH_SIZE: usize = 50;
+
 pub struct GithubClient {
-    client: Client,
+    http: HttpContext,
     token: Option<String>,
+    api_base: String,
+    web_base: String,
+    cache: Option<ResponseCache>,
+    /// Download speed cap in bytes per second
+    download_rate: Option<u64>,
+    /// Concurrent range requests per large download
+    connections: usize,
+    /// Smallest asset split across connections
+    parallel_min_size: u64,
+    /// Print the URL each download was finally served from
+    verbose: bool,
 }
 
 impl GithubClient {
-    pub fn new() -> Self {
-        let token = std::env::var("GITHUB_TOKEN").ok();
+    pub fn new(http: &HttpContext) -> Self {
+        let token = auth::token().map(|token| token.value);
 
         Self {
-            client: Client::new(),
+            http: http.clone(),
             token,
+            api_base: GITHUB_API_URL.to_string(),
+            web_base: GITHUB_WEB_URL.to_string(),
+            cache: None,
+            download_rate: None,
+            connections: 1,
+            parallel_min_size: PARALLEL_DOWNLOAD_MIN_SIZE,
+            verbose: false,
         }
     }
 
-    pub async fn get_latest_release(&self, repo: &str) -> Result<Release> {
-        let url = format!("https://api.github.com/repos/{}/releases/latest", repo);
+    /// Points the client at a different API root, e.g. a mock server in tests.
+    #[cfg(test)]
+    pub fn with_api_base(mut self, api_base: &str) -> Self {
+        self.api_base = api_base.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Points release feed requests at a different web root.
+    #[cfg(test)]
+    pub fn with_web_base(mut self, web_base: &str) -> Self {
+        self.web_base = web_base.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Serves release metadata from `cache` while its entries are fresh.
+    pub fn with_cache(mut self, cache: ResponseCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Caps asset downloads at `bytes_per_sec`.
+    pub fn with_download_rate(mut self, bytes_per_sec: u64) -> Self {
+        self.download_rate = Some(bytes_per_sec);
+        self
+    }
+
+    /// Downloads large assets over `connections` concurrent range requests.
+    pub fn with_connections(mut self, connections: usize) -> Self {
+        self.connections = connections.max(1);
+        self
+    }
+
+    /// Prints the final URL of each download.
+    pub fn set_verbose(&mut self, verbose: bool) {
+        self.verbose = verbose;
+    }
+
+    pub fn has_token(&self) -> bool {
+        self.token.is_some()
+    }
 
-        let mut request = self.client.get(&url).header("User-Agent", "oktofetch");
+    fn api_request(&self, url: &str) -> reqwest::RequestBuilder {
+        self.authorized(self.http.client().get(url))
+    }
 
+    fn authorized(&self, mut request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
         if let Some(token) = &self.token {
             // Use "Bearer" for fine-grained tokens (github_pat_*), "token" for classic tokens
             let auth_prefix = if token.starts_with("github_pat_") {
@@ -46,10 +260,24 @@ impl GithubClient {
             request = request.header("Authorization", format!("{} {}", auth_prefix, token));
         }
 
-        let response = request.send().await?;
+        request
+    }
+
+    /// Sends a GET to the API, mapping error statuses. A 404 becomes
+    /// `not_found`, since its meaning depends on the endpoint.
+    async fn api_get(
+        &self,
+        url: &str,
+        not_found: impl FnOnce() -> OktofetchError,
+    ) -> Result<reqwest::Response> {
+        let response = self.http.send(|_| self.api_request(url)).await?;
 
         if response.status() == 404 {
-            return Err(OktofetchError::RepoNotFound(repo.to_string()));
+            return Err(not_found());
+        }
+
+        if let Some(error) = rate_limit_error(&response, self.has_token()) {
+            return Err(error);
         }
 
         if !response.status().is_success() {
@@ -59,14 +287,300 @@ impl GithubClient {
             )));
         }
 
-        let release: Release = response.json().await?;
+        Ok(response)
+    }
+
+    /// Fetches a JSON document, going through the response cache when one is
+    /// configured. Returns the parsed body and the next page link, if any.
+    async fn get_json<T: DeserializeOwned>(
+        &self,
+        url: &str,
+        not_found: impl FnOnce() -> OktofetchError,
+    ) -> Result<(T, Option<String>)> {
+        if let Some(entry) = self.cache.as_ref().and_then(|c| c.get(url))
+            && let Ok(body) = serde_json::from_str(&entry.body)
+        {
+            return Ok((body, entry.next));
+        }
+
+        let response = self.api_get(url, not_found).await?;
+        let next = next_page_url(response.headers());
+        let text = response.text().await?;
+        let body = serde_json::from_str(&text).map_err(|e| {
+            OktofetchError::GithubApi(format!("Invalid response from {}: {}", url, e))
+        })?;
+
+        if let Some(cache) = &self.cache {
+            cache.put(&CachedResponse {
+                url: url.to_string(),
+                body: text,
+                next: next.clone(),
+            });
+        }
+
+        Ok((body, next))
+    }
+
+    /// Returns the core REST API quota. Querying it doesn't count against the quota.
+    pub async fn get_rate_limit(&self) -> Result<RateLimit> {
+        let url = format!("{}/rate_limit", self.api_base);
+
+        let response = self
+            .api_get(&url, || {
+                OktofetchError::GithubApi("Rate limit endpoint not found".to_string())
+            })
+            .await?;
+
+        let body: RateLimitResponse = response.json().await?;
+        Ok(body.resources.core)
+    }
+
+    /// Searches repositories by name, most starred first.
+    pub async fn search_repositories(
+        &self,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<RepoSearchResult>> {
+        let url = reqwest::Url::parse_with_params(
+            &format!("{}/search/repositories", self.api_base),
+            &[
+                ("q", format!("{} in:name", query)),
+                ("sort", "stars".to_string()),
+                ("per_page", limit.min(MAX_PER_PAGE).to_string()),
+            ],
+        )
+        .map_err(|e| OktofetchError::Other(format!("Invalid search query: {}", e)))?;
+
+        let (body, _): (RepoSearchResponse, _) = self
+            .get_json(url.as_str(), || {
+                OktofetchError::GithubApi("Search endpoint not found".to_string())
+            })
+            .await?;
+
+        Ok(body.items)
+    }
+
+    /// Fetches every page of a list endpoint by following `Link: rel="next"`.
+    async fn get_paginated<T: DeserializeOwned>(&self, url: &str, repo: &str) -> Result<Vec<T>> {
+        let mut items = Vec::new();
+        let mut next = Some(url.to_string());
+
+        while let Some(url) = next {
+            let (page, next_url): (Vec<T>, _) = self
+                .get_json(&url, || OktofetchError::RepoNotFound(repo.to_string()))
+                .await?;
+            next = next_url;
+            items.extend(page);
+        }
+
+        Ok(items)
+    }
+
+    /// Replaces a release's embedded asset list with the full paginated one
+    /// when the embedded list may have been cut off.
+    pub async fn complete_assets(&self, repo: &str, mut release: Release) -> Result<Release> {
+        if release.id == 0 || release.assets.len() < EMBEDDED_ASSET_LIMIT {
+            return Ok(release);
+        }
+
+        let url = format!(
+            "{}/repos/{}/releases/{}/assets?per_page={}",
+            self.api_base, repo, release.id, MAX_PER_PAGE
+        );
+        release.assets = self.get_paginated(&url, repo).await?;
         Ok(release)
     }
 
-    pub async fn download_asset(&self, url: &str, dest: &std::path::Path) -> Result<()> {
+    /// The release GitHub marks as latest. Repositories that only publish
+    /// pre-releases have none, so the newest non-draft release stands in.
+    pub async fn get_latest_release(&self, repo: &str) -> Result<Release> {
+        let url = format!("{}/repos/{}/releases/latest", self.api_base, repo);
+
+        let release = match self
+            .get_json(&url, || OktofetchError::RepoNotFound(repo.to_string()))
+            .await
+        {
+            Ok((release, _)) => release,
+            // Also a 404 when the repository exists but has no stable release;
+            // listing tells the two apart
+            Err(OktofetchError::RepoNotFound(_)) => {
+                let opts = ListOptions {
+                    include_prereleases: true,
+                    include_drafts: false,
+                    limit: Some(1),
+                };
+                self.list_releases(repo, &opts)
+                    .await?
+                    .into_iter()
+                    .next()
+                    .ok_or_else(|| {
+                        OktofetchError::GithubApi(format!("No releases found in {}", repo))
+                    })?
+            }
+            Err(e) => return Err(e),
+        };
+        self.complete_assets(repo, release).await
+    }
+
+    pub async fn get_release_by_tag(&self, repo: &str, tag: &str) -> Result<Release> {
+        let url = format!("{}/repos/{}/releases/tags/{}", self.api_base, repo, tag);
+
+        let (release, _): (Release, _) = self
+            .get_json(&url, || OktofetchError::ReleaseNotFound {
+                repo: repo.to_string(),
+                tag: tag.to_string(),
+            })
+            .await?;
+        self.complete_assets(repo, release).await
+    }
+
+    /// Lists releases newest first, following pagination until `opts.limit`
+    /// matching releases have been collected or the list is exhausted.
+    pub async fn list_releases(&self, repo: &str, opts: &ListOptions) -> Result<Vec<Release>> {
+        let mut releases = Vec::new();
+        let mut next = Some(format!(
+            "{}/repos/{}/releases?per_page={}",
+            self.api_base, repo, MAX_PER_PAGE
+        ));
+
+        while let Some(url) = next {
+            let (page, next_url): (Vec<Release>, _) = self
+                .get_json(&url, || OktofetchError::RepoNotFound(repo.to_string()))
+                .await?;
+            next = next_url;
+
+            for release in page.into_iter().filter(|r| opts.matches(r)) {
+                releases.push(release);
+                if opts.limit.is_some_and(|limit| releases.len() >= limit) {
+                    return Ok(releases);
+                }
+            }
+        }
+
+        Ok(releases)
+    }
+
+    /// Tag of the newest entry in the repository's `releases.atom` feed. The
+    /// feed doesn't count against the API rate limit, but lists pre-releases
+    /// too, so it's only good for telling that nothing new was published.
+    pub async fn feed_latest_tag(&self, repo: &str) -> Result<Option<String>> {
+        let url = format!("{}/{}/releases.atom", self.web_base, repo);
+        let response = self.http.send(|client| client.get(&url)).await?;
+
+        if !response.status().is_success() {
+            return Err(OktofetchError::GithubApi(format!(
+                "Release feed returned status: {}",
+                response.status()
+            )));
+        }
+
+        Ok(first_feed_tag(&response.text().await?))
+    }
+
+    /// Latest releases and archive status of `repos` (`owner/name`), looked
+    /// up with one GraphQL query per batch of repositories instead of a REST
+    /// call each. Repositories that can't be found are left out. The GraphQL
+    /// API only accepts authenticated requests.
+    pub async fn repo_snapshots(&self, repos: &[&str]) -> Result<HashMap<String, RepoSnapshot>> {
+        let url = format!("{}/graphql", self.api_base);
+        let mut snapshots = HashMap::new();
+
+        for batch in repos.chunks(GRAPHQL_BATCH_SIZE) {
+            let body = serde_json::json!({ "query": repo_snapshots_query(batch) });
+            let response = self
+                .http
+                .send(|client| self.authorized(client.post(&url).json(&body)))
+                .await?;
+            if let Some(error) = rate_limit_error(&response, self.has_token()) {
+                return Err(error);
+            }
+            if !response.status().is_success() {
+                return Err(OktofetchError::GithubApi(format!(
+                    "GraphQL API returned status: {}",
+                    response.status()
+                )));
+            }
+
+            // Missing repositories come back as null next to an error entry
+            let body: serde_json::Value = response.json().await?;
+            for (i, repo) in batch.iter().enumerate() {
+                let found = &body["data"][format!("r{}", i)];
+                if found.is_null() {
+                    continue;
+                }
+                let release = &found["latestRelease"];
+                snapshots.insert(
+                    repo.to_string(),
+                    RepoSnapshot {
+                        latest_tag: release["tagName"].as_str().map(str::to_string),
+                        released_at: release["publishedAt"]
+                            .as_str()
+                            .and_then(human::parse_timestamp),
+                        archived: found["isArchived"].as_bool().unwrap_or(false),
+                    },
+                );
+            }
+        }
+
+        Ok(snapshots)
+    }
+
+    /// Metadata of `repo`: description, license, stars, homepage and whether
+    /// it is archived.
+    pub async fn get_repository(&self, repo: &str) -> Result<Repository> {
+        let url = format!("{}/repos/{}", self.api_base, repo);
+        let (repository, _) = self
+            .get_json(&url, || OktofetchError::RepoNotFound(repo.to_string()))
+            .await?;
+        Ok(repository)
+    }
+
+    /// Contents of `path` in the repository at `reference`, or `None` if
+    /// there is no such file.
+    pub async fn get_repo_file(
+        &self,
+        repo: &str,
+        reference: &str,
+        path: &str,
+    ) -> Result<Option<String>> {
+        let url = format!(
+            "{}/repos/{}/contents/{}?ref={}",
+            self.api_base, repo, path, reference
+        );
+        let response = self
+            .http
+            .send(|_| {
+                self.api_request(&url)
+                    .header("Accept", "application/vnd.github.raw")
+            })
+            .await?;
+
+        if response.status() == 404 {
+            return Ok(None);
+        }
+        if let Some(error) = rate_limit_error(&response, self.has_token()) {
+            return Err(error);
+        }
+        if !response.status().is_success() {
+            return Err(OktofetchError::GithubApi(format!(
+                "API returned status: {}",
+                response.status()
+            )));
+        }
+
+        Ok(Some(response.text().await?))
+    }
+
+    /// Downloads `url` to `dest`, returning the SHA256 of the bytes written.
+    pub async fn download_asset(&self, url: &str, dest: &std::path::Path) -> Result<String> {
+        use sha2::{Digest, Sha256};
         use tokio::io::AsyncWriteExt;
 
-        let response = self.client.get(url).send().await?;
+        let mut response = self
+            .http
+            .send(|client| client.get(url))
+            .await
+            .map_err(redirect_error)?;
 
         if !response.status().is_success() {
             return Err(OktofetchError::DownloadFailed(format!(
@@ -74,25 +588,262 @@ impl GithubClient {
                 response.status()
             )));
         }
+        if self.verbose {
+            println!("Downloading from {}", redact::url(response.url()));
+        }
 
+        let expected = response.content_length();
+        let mut written = 0;
         let mut file = tokio::fs::File::create(dest).await?;
-        let content = response.bytes().await?;
-        file.write_all(&content).await?;
+        let mut hasher = Sha256::new();
+        let mut throttle = self.download_rate.map(Throttle::new);
+        while let Some(chunk) = response.chunk().await? {
+            file.write_all(&chunk).await?;
+            hasher.update(&chunk);
+            events::download_progress(written, written + chunk.len() as u64, expected);
+            written += chunk.len() as u64;
+            if let Some(throttle) = &mut throttle {
+                throttle.consume(chunk.len() as u64).await;
+            }
+        }
         file.flush().await?;
         file.sync_all().await?;
 
-        Ok(())
+        check_length(expected, written)?;
+        Ok(format!("{:x}", hasher.finalize()))
+    }
+
+    /// Downloads a release asset, splitting it into concurrent range requests
+    /// when parallel downloads are enabled and the asset is large enough.
+    /// Returns the asset's SHA256; ranges arrive out of order, so a parallel
+    /// download hashes the assembled file afterwards.
+    pub async fn download_release_asset(
+        &self,
+        asset: &Asset,
+        dest: &Path,
+    ) -> Result<Option<String>> {
+        // Parallel connections would defeat a configured rate limit
+        if self.connections > 1
+            && self.download_rate.is_none()
+            && asset.size >= self.parallel_min_size
+            && self
+                .download_ranges(&asset.browser_download_url, asset.size, dest)
+                .await?
+        {
+            return crate::binary::sha256_file(dest).map(Some);
+        }
+
+        self.download_asset(&asset.browser_download_url, dest)
+            .await
+            .map(Some)
+    }
+
+    /// Fetches `size` bytes from `url` as `connections` concurrent byte ranges
+    /// written straight into place. Returns `false`, having written nothing,
+    /// if the server doesn't honour `Range`.
+    async fn download_ranges(&self, url: &str, size: u64, dest: &Path) -> Result<bool> {
+        let chunk_size = size.div_ceil(self.connections as u64);
+        let ranges: Vec<(u64, u64)> = (0..size)
+            .step_by(chunk_size as usize)
+            .map(|start| (start, (start + chunk_size).min(size) - 1))
+            .collect();
+
+        // Probe with the first range before committing to the others
+        let (first_start, first_end) = ranges[0];
+        let first = self.range_request(url, first_start, first_end).await?;
+        if first.status() != reqwest::StatusCode::PARTIAL_CONTENT {
+            return Ok(false);
+        }
+        if self.verbose {
+            println!(
+                "Downloading from {} over {} connections",
+                first.url(),
+                ranges.len()
+            );
+        }
+
+        let file = std::fs::File::create(dest)?;
+        file.set_len(size)?;
+
+        let mut tasks = tokio::task::JoinSet::new();
+        tasks.spawn(write_range(
+            first,
+            file.try_clone()?,
+            first_start,
+            first_end,
+        ));
+        for &(start, end) in &ranges[1..] {
+            let response = self.range_request(url, start, end).await?;
+            if response.status() != reqwest::StatusCode::PARTIAL_CONTENT {
+                return Err(OktofetchError::DownloadFailed(format!(
+                    "Range request returned status: {}",
+                    response.status()
+                )));
+            }
+            tasks.spawn(write_range(response, file.try_clone()?, start, end));
+        }
+
+        let mut written = 0;
+        while let Some(result) = tasks.join_next().await {
+            let range_size =
+                result.map_err(|e| OktofetchError::DownloadFailed(e.to_string()))??;
+            events::download_progress(written, written + range_size, Some(size));
+            written += range_size;
+        }
+        file.sync_all()?;
+
+        Ok(true)
+    }
+
+    async fn range_request(&self, url: &str, start: u64, end: u64) -> Result<reqwest::Response> {
+        self.http
+            .send(|client| {
+                client
+                    .get(url)
+                    .header(reqwest::header::RANGE, format!("bytes={}-{}", start, end))
+            })
+            .await
+            .map_err(redirect_error)
+    }
+}
+
+/// GraphQL query for the latest release of each repo, aliased `r0`, `r1`, ...
+fn repo_snapshots_query(repos: &[&str]) -> String {
+    let fields: Vec<String> = repos
+        .iter()
+        .enumerate()
+        .filter_map(|(i, repo)| {
+            let (owner, name) = repo.split_once('/')?;
+            Some(format!(
+                "r{}: repository(owner: {}, name: {}) {{ isArchived latestRelease {{ tagName publishedAt }} }}",
+                i,
+                serde_json::Value::from(owner),
+                serde_json::Value::from(name)
+            ))
+        })
+        .collect();
+    format!("query {{ {} }}", fields.join(" "))
+}
+
+/// Fails a download whose size differs from the announced Content-Length, so
+/// a truncated file never reaches the extractor.
+/// Recognises an exhausted quota: a 403 or 429 with no requests remaining,
+/// or one asking the client to back off with `Retry-After`.
+fn rate_limit_error(response: &reqwest::Response, authenticated: bool) -> Option<OktofetchError> {
+    let status = response.status();
+    if status != reqwest::StatusCode::FORBIDDEN && status != reqwest::StatusCode::TOO_MANY_REQUESTS
+    {
+        return None;
+    }
+
+    let header = |name: &str| -> Option<u64> {
+        response
+            .headers()
+            .get(name)?
+            .to_str()
+            .ok()?
+            .trim()
+            .parse()
+            .ok()
+    };
+    let retry_after = header("retry-after");
+    if header("x-ratelimit-remaining") != Some(0) && retry_after.is_none() {
+        return None;
+    }
+
+    let reset_at = retry_after
+        .map(|secs| crate::human::unix_now() + secs)
+        .or_else(|| header("x-ratelimit-reset"));
+    Some(OktofetchError::RateLimited {
+        reset_at,
+        authenticated,
+    })
+}
+
+fn check_length(expected: Option<u64>, written: u64) -> Result<()> {
+    match expected {
+        Some(expected) if expected != written => Err(OktofetchError::DownloadFailed(format!(
+            "received {} of {} bytes (connection closed early?)",
+            written, expected
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// Streams one range response into `file` at `start`, checking it delivered
+/// exactly the requested bytes. Returns how many that was.
+async fn write_range(
+    mut response: reqwest::Response,
+    file: std::fs::File,
+    start: u64,
+    end: u64,
+) -> Result<u64> {
+    use std::os::unix::fs::FileExt;
+
+    let mut offset = start;
+    while let Some(chunk) = response.chunk().await? {
+        if offset + chunk.len() as u64 > end + 1 {
+            return Err(OktofetchError::DownloadFailed(format!(
+                "Range {}-{} returned too much data",
+                start, end
+            )));
+        }
+        file.write_all_at(&chunk, offset)?;
+        offset += chunk.len() as u64;
+    }
+
+    if offset != end + 1 {
+        return Err(OktofetchError::DownloadFailed(format!(
+            "Range {}-{} ended after {} bytes",
+            start,
+            end,
+            offset - start
+        )));
     }
+    Ok(offset - start)
+}
+
+/// Extracts the tag of the first `<entry>` in a releases Atom feed from its
+/// `.../releases/tag/<tag>` link.
+fn first_feed_tag(feed: &str) -> Option<String> {
+    const MARKER: &str = "/releases/tag/";
+
+    let entry = &feed[feed.find("<entry>")?..];
+    let entry = &entry[..entry.find("</entry>").unwrap_or(entry.len())];
+    let start = entry.find(MARKER)? + MARKER.len();
+    let tag = &entry[start..];
+    let tag = &tag[..tag.find('"')?];
+
+    (!tag.is_empty()).then(|| tag.replace("%2F", "/"))
+}
+
+/// Extracts the `rel="next"` URL from a `Link` header, if present.
+fn next_page_url(headers: &reqwest::header::HeaderMap) -> Option<String> {
+    let link = headers.get(reqwest::header::LINK)?.to_str().ok()?;
+
+    link.split(',').find_map(|part| {
+        let (url, params) = part.split_once(';')?;
+        params
+            .split(';')
+            .any(|p| p.trim() == r#"rel="next""#)
+            .then(|| {
+                url.trim()
+                    .trim_start_matches('<')
+                    .trim_end_matches('>')
+                    .to_string()
+            })
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::http::{DEFAULT_USER_AGENT, HttpPolicy};
 
     #[test]
     fn test_github_client_new_without_token() {
         temp_env::with_var_unset("GITHUB_TOKEN", || {
-            let client = GithubClient::new();
+            let client = GithubClient::new(&HttpContext::default());
             assert!(client.token.is_none());
         });
     }
@@ -100,7 +851,7 @@ mod tests {
     #[test]
     fn test_github_client_new_with_token() {
         temp_env::with_var("GITHUB_TOKEN", Some("test_token_123"), || {
-            let client = GithubClient::new();
+            let client = GithubClient::new(&HttpContext::default());
             assert_eq!(client.token, Some("test_token_123".to_string()));
         });
     }
@@ -130,16 +881,10 @@ mod tests {
             .mount(&mock_server)
             .await;
 
-        let client = GithubClient::new();
+        let client = GithubClient::new(&HttpContext::default());
         let url = format!("{}/repos/owner/repo/releases/latest", mock_server.uri());
 
-        let response = client
-            .client
-            .get(&url)
-            .header("User-Agent", "oktofetch")
-            .send()
-            .await
-            .unwrap();
+        let response = client.http.client().get(&url).send().await.unwrap();
 
         assert!(response.status().is_success());
         let release: Release = response.json().await.unwrap();
@@ -160,19 +905,13 @@ mod tests {
             .mount(&mock_server)
             .await;
 
-        let client = GithubClient::new();
+        let client = GithubClient::new(&HttpContext::default());
         let url = format!(
             "{}/repos/owner/nonexistent/releases/latest",
             mock_server.uri()
         );
 
-        let response = client
-            .client
-            .get(&url)
-            .header("User-Agent", "oktofetch")
-            .send()
-            .await
-            .unwrap();
+        let response = client.http.client().get(&url).send().await.unwrap();
 
         assert_eq!(response.status(), 404);
     }
@@ -195,7 +934,7 @@ mod tests {
         let temp_dir = TempDir::new().unwrap();
         let dest_path = temp_dir.path().join("downloaded-file");
 
-        let client = GithubClient::new();
+        let client = GithubClient::new(&HttpContext::default());
         let url = format!("{}/download/asset", mock_server.uri());
 
         let result = client.download_asset(&url, &dest_path).await;
@@ -207,67 +946,884 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_download_asset_failure() {
-        use tempfile::TempDir;
+    async fn test_download_asset_redirect_hosts() {
         use wiremock::matchers::{method, path};
         use wiremock::{Mock, MockServer, ResponseTemplate};
 
         let mock_server = MockServer::start().await;
-
         Mock::given(method("GET"))
-            .and(path("/download/notfound"))
-            .respond_with(ResponseTemplate::new(404))
+            .and(path("/download/asset"))
+            .respond_with(
+                ResponseTemplate::new(302)
+                    .insert_header("Location", format!("{}/cdn/asset", mock_server.uri())),
+            )
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/cdn/asset"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(b"payload".to_vec()))
             .mount(&mock_server)
             .await;
 
-        let temp_dir = TempDir::new().unwrap();
-        let dest_path = temp_dir.path().join("downloaded-file");
-
-        let client = GithubClient::new();
-        let url = format!("{}/download/notfound", mock_server.uri());
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let dest = temp_dir.path().join("asset");
+        let url = format!("{}/download/asset", mock_server.uri());
 
-        let result = client.download_asset(&url, &dest_path).await;
+        let err = GithubClient::new(&HttpContext::default())
+            .download_asset(&url, &dest)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, OktofetchError::DownloadFailed(_)));
+        assert!(err.to_string().contains("untrusted host 127.0.0.1"));
 
-        assert!(result.is_err());
-        assert!(!dest_path.exists());
+        let http = HttpContext::new(
+            DEFAULT_USER_AGENT,
+            HttpPolicy::default(),
+            &["127.0.0.1".to_string()],
+        );
+        let client = GithubClient::new(&http);
+        let sha256 = client.download_asset(&url, &dest).await.unwrap();
+        assert_eq!(std::fs::read(&dest).unwrap(), b"payload");
+        assert_eq!(sha256, crate::binary::sha256_file(&dest).unwrap());
     }
 
     #[test]
-    fn test_release_serialization() {
-        let json = r#"{
-            "tag_name": "v1.0.0",
-            "name": "Release 1.0.0",
-            "assets": [
-                {
-                    "name": "app-linux-x64.tar.gz",
-                    "browser_download_url": "https://example.com/download",
-                    "size": 1024
-                }
-            ]
-        }"#;
+    fn test_check_length() {
+        assert!(check_length(Some(10), 10).is_ok());
+        assert!(check_length(None, 10).is_ok());
 
-        let release: Release = serde_json::from_str(json).unwrap();
-        assert_eq!(release.tag_name, "v1.0.0");
-        assert_eq!(release.name, "Release 1.0.0");
-        assert_eq!(release.assets.len(), 1);
-        assert_eq!(release.assets[0].name, "app-linux-x64.tar.gz");
-        assert_eq!(release.assets[0].size, 1024);
+        let err = check_length(Some(10), 4).unwrap_err();
+        assert!(matches!(err, OktofetchError::DownloadFailed(_)));
+        assert!(err.to_string().contains("received 4 of 10 bytes"));
     }
 
-    #[test]
-    fn test_asset_serialization() {
-        let json = r#"{
-            "name": "myapp.tar.gz",
-            "browser_download_url": "https://github.com/releases/download/myapp.tar.gz",
-            "size": 2048
-        }"#;
+    /// Serves `body`, honouring single `Range: bytes=a-b` headers.
+    struct RangeResponder(Vec<u8>);
 
-        let asset: Asset = serde_json::from_str(json).unwrap();
-        assert_eq!(asset.name, "myapp.tar.gz");
-        assert_eq!(
-            asset.browser_download_url,
-            "https://github.com/releases/download/myapp.tar.gz"
-        );
-        assert_eq!(asset.size, 2048);
+    impl wiremock::Respond for RangeResponder {
+        fn respond(&self, request: &wiremock::Request) -> wiremock::ResponseTemplate {
+            let range = request
+                .headers
+                .get("range")
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.strip_prefix("bytes="))
+                .and_then(|v| v.split_once('-'))
+                .and_then(|(a, b)| Some((a.parse::<usize>().ok()?, b.parse::<usize>().ok()?)));
+
+            match range {
+                Some((start, end)) => wiremock::ResponseTemplate::new(206)
+                    .set_body_bytes(self.0[start..=end].to_vec()),
+                None => wiremock::ResponseTemplate::new(200).set_body_bytes(self.0.clone()),
+            }
+        }
+    }
+
+    fn parallel_asset(server: &str, size: usize) -> Asset {
+        Asset {
+            name: "big.tar.gz".to_string(),
+            browser_download_url: format!("{}/download/big", server),
+            size: size as u64,
+            digest: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_download_release_asset_in_ranges() {
+        use wiremock::matchers::{header_exists, method, path};
+        use wiremock::{Mock, MockServer};
+
+        let mock_server = MockServer::start().await;
+        let body: Vec<u8> = (0..10_000u32).map(|i| (i % 251) as u8).collect();
+
+        Mock::given(method("GET"))
+            .and(path("/download/big"))
+            .and(header_exists("range"))
+            .respond_with(RangeResponder(body.clone()))
+            .expect(3)
+            .mount(&mock_server)
+            .await;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let dest = temp_dir.path().join("big.tar.gz");
+        let mut client = GithubClient::new(&HttpContext::default()).with_connections(3);
+        client.parallel_min_size = 1;
+
+        let sha256 = client
+            .download_release_asset(&parallel_asset(&mock_server.uri(), body.len()), &dest)
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read(&dest).unwrap(), body);
+        assert_eq!(sha256, Some(crate::binary::sha256_file(&dest).unwrap()));
+    }
+
+    #[tokio::test]
+    async fn test_download_release_asset_without_range_support() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let mock_server = MockServer::start().await;
+        let body = vec![1u8; 5000];
+
+        Mock::given(method("GET"))
+            .and(path("/download/big"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(body.clone()))
+            .mount(&mock_server)
+            .await;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let dest = temp_dir.path().join("big.tar.gz");
+        let mut client = GithubClient::new(&HttpContext::default()).with_connections(4);
+        client.parallel_min_size = 1;
+
+        // Falling back to a single connection hashes the download as it goes
+        let sha256 = client
+            .download_release_asset(&parallel_asset(&mock_server.uri(), body.len()), &dest)
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read(&dest).unwrap(), body);
+        assert_eq!(sha256, Some(crate::binary::sha256_file(&dest).unwrap()));
+    }
+
+    #[tokio::test]
+    async fn test_download_asset_rate_limited() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/download/asset"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(vec![7u8; 4000]))
+            .mount(&mock_server)
+            .await;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let dest_path = temp_dir.path().join("downloaded-file");
+        let client = GithubClient::new(&HttpContext::default()).with_download_rate(20_000);
+
+        let start = std::time::Instant::now();
+        client
+            .download_asset(&format!("{}/download/asset", mock_server.uri()), &dest_path)
+            .await
+            .unwrap();
+        assert!(start.elapsed() >= std::time::Duration::from_millis(190));
+        assert_eq!(std::fs::read(&dest_path).unwrap(), vec![7u8; 4000]);
+    }
+
+    #[tokio::test]
+    async fn test_download_asset_failure() {
+        use tempfile::TempDir;
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/download/notfound"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&mock_server)
+            .await;
+
+        let temp_dir = TempDir::new().unwrap();
+        let dest_path = temp_dir.path().join("downloaded-file");
+
+        let client = GithubClient::new(&HttpContext::default());
+        let url = format!("{}/download/notfound", mock_server.uri());
+
+        let result = client.download_asset(&url, &dest_path).await;
+
+        assert!(result.is_err());
+        assert!(!dest_path.exists());
+    }
+
+    #[test]
+    fn test_release_serialization() {
+        let json = r#"{
+            "tag_name": "v1.0.0",
+            "name": "Release 1.0.0",
+            "assets": [
+                {
+                    "name": "app-linux-x64.tar.gz",
+                    "browser_download_url": "https://example.com/download",
+                    "size": 1024
+                }
+            ]
+        }"#;
+
+        let release: Release = serde_json::from_str(json).unwrap();
+        assert_eq!(release.tag_name, "v1.0.0");
+        assert!(!release.prerelease);
+        assert!(!release.draft);
+        assert_eq!(release.name, "Release 1.0.0");
+        assert_eq!(release.assets.len(), 1);
+        assert_eq!(release.assets[0].name, "app-linux-x64.tar.gz");
+        assert_eq!(release.assets[0].size, 1024);
+    }
+
+    #[test]
+    fn test_asset_serialization() {
+        let json = r#"{
+            "name": "myapp.tar.gz",
+            "browser_download_url": "https://github.com/releases/download/myapp.tar.gz",
+            "size": 2048
+        }"#;
+
+        let asset: Asset = serde_json::from_str(json).unwrap();
+        assert_eq!(asset.name, "myapp.tar.gz");
+        assert_eq!(
+            asset.browser_download_url,
+            "https://github.com/releases/download/myapp.tar.gz"
+        );
+        assert_eq!(asset.size, 2048);
+        assert_eq!(asset.sha256(), None);
+    }
+
+    #[test]
+    fn test_asset_digest() {
+        let json = r#"{
+            "name": "myapp.tar.gz",
+            "browser_download_url": "https://github.com/releases/download/myapp.tar.gz",
+            "size": 2048,
+            "digest": "sha256:ab12"
+        }"#;
+
+        let mut asset: Asset = serde_json::from_str(json).unwrap();
+        assert_eq!(asset.sha256(), Some("ab12"));
+
+        asset.digest = Some("sha512:cd34".to_string());
+        assert_eq!(asset.sha256(), None);
+    }
+
+    #[tokio::test]
+    async fn test_list_releases() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let mock_server = MockServer::start().await;
+
+        let releases_json = r#"[
+            {"tag_name": "v2.0.0", "name": "Two", "assets": []},
+            {"tag_name": "v1.0.0", "name": "One", "assets": []}
+        ]"#;
+
+        Mock::given(method("GET"))
+            .and(path("/repos/owner/repo/releases"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(releases_json))
+            .mount(&mock_server)
+            .await;
+
+        let client = GithubClient::new(&HttpContext::default()).with_api_base(&mock_server.uri());
+        let releases = client
+            .list_releases("owner/repo", &ListOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(releases.len(), 2);
+        assert_eq!(releases[0].tag_name, "v2.0.0");
+
+        let result = client
+            .list_releases("owner/missing", &ListOptions::default())
+            .await;
+        assert!(matches!(result, Err(OktofetchError::RepoNotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_user_agent() {
+        use wiremock::matchers::{header, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        assert!(DEFAULT_USER_AGENT.starts_with("oktofetch/"));
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/repos/owner/repo/releases/tags/v1.0.0"))
+            .and(header("user-agent", "oktofetch/1.0 acme-build"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string(r#"{"tag_name": "v1.0.0", "name": "One", "assets": []}"#),
+            )
+            .mount(&mock_server)
+            .await;
+
+        let http = HttpContext::new(
+            "oktofetch/1.0 acme-build",
+            HttpPolicy::default(),
+            &["mirror.example.com".to_string()],
+        );
+        let client = GithubClient::new(&http).with_api_base(&mock_server.uri());
+        let release = client.get_release_by_tag("owner/repo", "v1.0.0").await;
+        assert_eq!(release.unwrap().tag_name, "v1.0.0");
+    }
+
+    #[test]
+    fn test_repo_snapshots_query() {
+        assert_eq!(
+            repo_snapshots_query(&["derailed/k9s", "invalid", "sharkdp/fd"]),
+            "query { r0: repository(owner: \"derailed\", name: \"k9s\") { isArchived latestRelease { tagName publishedAt } } \
+             r2: repository(owner: \"sharkdp\", name: \"fd\") { isArchived latestRelease { tagName publishedAt } } }"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_repo_snapshots() {
+        use wiremock::matchers::{body_string_contains, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/graphql"))
+            .and(body_string_contains("latestRelease"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                r#"{
+                    "data": {
+                        "r0": {
+                            "isArchived": false,
+                            "latestRelease": {"tagName": "v0.32.5", "publishedAt": "2024-03-01T12:30:05Z"}
+                        },
+                        "r1": {"isArchived": true, "latestRelease": null},
+                        "r2": null
+                    },
+                    "errors": [{"type": "NOT_FOUND", "path": ["r2"]}]
+                }"#,
+            ))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let client = GithubClient::new(&HttpContext::default()).with_api_base(&mock_server.uri());
+        let snapshots = client
+            .repo_snapshots(&["derailed/k9s", "owner/unreleased", "owner/missing"])
+            .await
+            .unwrap();
+        assert_eq!(snapshots.len(), 2);
+        assert_eq!(
+            snapshots["derailed/k9s"],
+            RepoSnapshot {
+                latest_tag: Some("v0.32.5".to_string()),
+                released_at: Some(1_709_296_205),
+                archived: false,
+            }
+        );
+        assert_eq!(snapshots["owner/unreleased"].latest_tag, None);
+        assert!(snapshots["owner/unreleased"].archived);
+    }
+
+    #[tokio::test]
+    async fn test_get_repository() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/repos/owner/old"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string(r#"{"full_name": "owner/old", "archived": true}"#),
+            )
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/repos/derailed/k9s"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                r#"{
+                    "full_name": "derailed/k9s",
+                    "description": "Kubernetes CLI To Manage Your Clusters In Style!",
+                    "homepage": "https://k9scli.io",
+                    "stargazers_count": 27000,
+                    "license": {"key": "apache-2.0", "name": "Apache License 2.0", "spdx_id": "Apache-2.0"}
+                }"#,
+            ))
+            .mount(&mock_server)
+            .await;
+
+        let client = GithubClient::new(&HttpContext::default()).with_api_base(&mock_server.uri());
+        assert!(client.get_repository("owner/old").await.unwrap().archived);
+
+        let k9s = client.get_repository("derailed/k9s").await.unwrap();
+        assert!(!k9s.archived);
+        assert_eq!(k9s.stargazers_count, 27000);
+        assert_eq!(k9s.homepage.as_deref(), Some("https://k9scli.io"));
+        assert_eq!(k9s.license.unwrap().label(), "Apache-2.0");
+
+        let unknown = License {
+            name: "Other".to_string(),
+            spdx_id: Some("NOASSERTION".to_string()),
+        };
+        assert_eq!(unknown.label(), "Other");
+        assert!(matches!(
+            client.get_repository("owner/gone").await,
+            Err(OktofetchError::RepoNotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_get_latest_release_falls_back_to_prereleases() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/repos/owner/repo/releases"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                r#"[
+                    {"tag_name": "v3.0.0-draft", "name": "", "assets": [], "draft": true},
+                    {"tag_name": "v2.0.0-rc.1", "name": "", "assets": [], "prerelease": true},
+                    {"tag_name": "v1.0.0-rc.1", "name": "", "assets": [], "prerelease": true}
+                ]"#,
+            ))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/repos/owner/empty/releases"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("[]"))
+            .mount(&mock_server)
+            .await;
+
+        let client = GithubClient::new(&HttpContext::default()).with_api_base(&mock_server.uri());
+        let release = client.get_latest_release("owner/repo").await.unwrap();
+        assert_eq!(release.tag_name, "v2.0.0-rc.1");
+
+        let err = client.get_latest_release("owner/empty").await.unwrap_err();
+        assert!(err.to_string().contains("No releases found in owner/empty"));
+
+        // The repository itself is missing
+        let err = client
+            .get_latest_release("owner/missing")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, OktofetchError::RepoNotFound(_)));
+    }
+
+    #[tokio::test]
+    async fn test_get_repo_file() {
+        use wiremock::matchers::{header, method, path, query_param};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/repos/owner/tool/contents/Cargo.toml"))
+            .and(query_param("ref", "v1.0.0"))
+            .and(header("Accept", "application/vnd.github.raw"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("[package]\n"))
+            .mount(&mock_server)
+            .await;
+
+        let client = GithubClient::new(&HttpContext::default()).with_api_base(&mock_server.uri());
+        assert_eq!(
+            client
+                .get_repo_file("owner/tool", "v1.0.0", "Cargo.toml")
+                .await
+                .unwrap()
+                .as_deref(),
+            Some("[package]\n")
+        );
+        assert_eq!(
+            client
+                .get_repo_file("owner/tool", "v1.0.0", "go.mod")
+                .await
+                .unwrap(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_release_by_tag() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/repos/owner/repo/releases/tags/tool/v1.0.0"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string(r#"{"tag_name": "tool/v1.0.0", "name": "One", "assets": []}"#),
+            )
+            .mount(&mock_server)
+            .await;
+
+        let client = GithubClient::new(&HttpContext::default()).with_api_base(&mock_server.uri());
+        let release = client
+            .get_release_by_tag("owner/repo", "tool/v1.0.0")
+            .await
+            .unwrap();
+        assert_eq!(release.tag_name, "tool/v1.0.0");
+
+        let err = client
+            .get_release_by_tag("owner/repo", "v9.9.9")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, OktofetchError::ReleaseNotFound { ref tag, .. } if tag == "v9.9.9"));
+    }
+
+    #[test]
+    fn test_next_page_url() {
+        let mut headers = reqwest::header::HeaderMap::new();
+        assert_eq!(next_page_url(&headers), None);
+
+        headers.insert(
+            reqwest::header::LINK,
+            r#"<https://api.github.com/repositories/1/releases/2/assets?page=2>; rel="next", <https://api.github.com/repositories/1/releases/2/assets?page=3>; rel="last""#
+                .parse()
+                .unwrap(),
+        );
+        assert_eq!(
+            next_page_url(&headers),
+            Some("https://api.github.com/repositories/1/releases/2/assets?page=2".to_string())
+        );
+
+        headers.insert(
+            reqwest::header::LINK,
+            r#"<https://api.github.com/x?page=1>; rel="prev""#.parse().unwrap(),
+        );
+        assert_eq!(next_page_url(&headers), None);
+    }
+
+    #[tokio::test]
+    async fn test_latest_release_paginates_assets() {
+        use wiremock::matchers::{method, path, query_param};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let mock_server = MockServer::start().await;
+
+        let asset = |i: usize| {
+            serde_json::json!({
+                "name": format!("asset-{}", i),
+                "browser_download_url": format!("https://example.com/{}", i),
+                "size": i
+            })
+        };
+        let embedded: Vec<_> = (0..30).map(asset).collect();
+        let page1: Vec<_> = (0..100).map(asset).collect();
+        let page2: Vec<_> = (100..120).map(asset).collect();
+
+        Mock::given(method("GET"))
+            .and(path("/repos/owner/repo/releases/latest"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "id": 42,
+                "tag_name": "v1.0.0",
+                "name": "One",
+                "assets": embedded
+            })))
+            .mount(&mock_server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path("/repos/owner/repo/releases/42/assets"))
+            .and(query_param("page", "2"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(page2))
+            .mount(&mock_server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path("/repos/owner/repo/releases/42/assets"))
+            .and(query_param("per_page", "100"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header(
+                        "Link",
+                        format!(
+                            r#"<{}/repos/owner/repo/releases/42/assets?page=2>; rel="next""#,
+                            mock_server.uri()
+                        )
+                        .as_str(),
+                    )
+                    .set_body_json(page1),
+            )
+            .mount(&mock_server)
+            .await;
+
+        let client = GithubClient::new(&HttpContext::default()).with_api_base(&mock_server.uri());
+        let release = client.get_latest_release("owner/repo").await.unwrap();
+        assert_eq!(release.assets.len(), 120);
+        assert_eq!(release.assets[119].name, "asset-119");
+    }
+
+    #[tokio::test]
+    async fn test_complete_assets_skips_short_lists() {
+        let client = GithubClient::new(&HttpContext::default()).with_api_base("http://127.0.0.1:1");
+        let release = Release {
+            id: 7,
+            tag_name: "v1.0.0".to_string(),
+            name: "One".to_string(),
+            assets: Vec::new(),
+            prerelease: false,
+            draft: false,
+            body: None,
+            published_at: None,
+        };
+        // No request is made, so the unreachable base URL doesn't matter
+        let release = client.complete_assets("owner/repo", release).await.unwrap();
+        assert!(release.assets.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_list_releases_filters_and_paginates() {
+        use wiremock::matchers::{method, path, query_param};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let mock_server = MockServer::start().await;
+
+        let release = |tag: &str, prerelease: bool, draft: bool| {
+            serde_json::json!({
+                "tag_name": tag,
+                "name": tag,
+                "assets": [],
+                "prerelease": prerelease,
+                "draft": draft
+            })
+        };
+
+        Mock::given(method("GET"))
+            .and(path("/repos/owner/repo/releases"))
+            .and(query_param("page", "2"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(vec![
+                release("v1.0.0", false, false),
+                release("v0.9.0", false, false),
+            ]))
+            .mount(&mock_server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path("/repos/owner/repo/releases"))
+            .and(query_param("per_page", "100"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header(
+                        "Link",
+                        format!(
+                            r#"<{}/repos/owner/repo/releases?page=2>; rel="next""#,
+                            mock_server.uri()
+                        )
+                        .as_str(),
+                    )
+                    .set_body_json(vec![
+                        release("v2.0.0-draft", false, true),
+                        release("v2.0.0-rc1", true, false),
+                        release("v1.1.0", false, false),
+                    ]),
+            )
+            .mount(&mock_server)
+            .await;
+
+        let client = GithubClient::new(&HttpContext::default()).with_api_base(&mock_server.uri());
+        let tags =
+            |releases: Vec<Release>| releases.into_iter().map(|r| r.tag_name).collect::<Vec<_>>();
+
+        let stable = client
+            .list_releases("owner/repo", &ListOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(tags(stable), vec!["v1.1.0", "v1.0.0", "v0.9.0"]);
+
+        let opts = ListOptions {
+            include_prereleases: true,
+            include_drafts: true,
+            limit: Some(2),
+        };
+        let newest = client.list_releases("owner/repo", &opts).await.unwrap();
+        assert_eq!(tags(newest), vec!["v2.0.0-draft", "v2.0.0-rc1"]);
+    }
+
+    #[test]
+    fn test_list_options_apply() {
+        let release = |tag: &str, prerelease: bool| Release {
+            id: 0,
+            tag_name: tag.to_string(),
+            name: tag.to_string(),
+            assets: Vec::new(),
+            prerelease,
+            draft: false,
+            body: None,
+            published_at: None,
+        };
+        let releases = vec![release("v2-rc", true), release("v1", false)];
+
+        let opts = ListOptions {
+            limit: Some(1),
+            ..ListOptions::default()
+        };
+        let filtered = opts.apply(releases);
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].tag_name, "v1");
+    }
+
+    #[tokio::test]
+    async fn test_get_rate_limit() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/rate_limit"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                r#"{
+                    "resources": {
+                        "core": {"limit": 60, "remaining": 12, "reset": 1700000000, "used": 48},
+                        "search": {"limit": 10, "remaining": 10, "reset": 1700000000, "used": 0}
+                    },
+                    "rate": {"limit": 60, "remaining": 12, "reset": 1700000000, "used": 48}
+                }"#,
+            ))
+            .mount(&mock_server)
+            .await;
+
+        let client = GithubClient::new(&HttpContext::default()).with_api_base(&mock_server.uri());
+        let rate = client.get_rate_limit().await.unwrap();
+        assert_eq!(rate.limit, 60);
+        assert_eq!(rate.remaining, 12);
+        assert_eq!(rate.reset, 1700000000);
+        assert_eq!(rate.used, 48);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_error() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/repos/owner/limited/releases/latest"))
+            .respond_with(
+                ResponseTemplate::new(403)
+                    .insert_header("x-ratelimit-remaining", "0")
+                    .insert_header("x-ratelimit-reset", "1700000000"),
+            )
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/repos/owner/private/releases/latest"))
+            .respond_with(ResponseTemplate::new(403).insert_header("x-ratelimit-remaining", "42"))
+            .mount(&mock_server)
+            .await;
+
+        let client = temp_env::with_var_unset("GITHUB_TOKEN", || {
+            GithubClient::new(&HttpContext::default()).with_api_base(&mock_server.uri())
+        });
+
+        let err = client
+            .get_latest_release("owner/limited")
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            OktofetchError::RateLimited {
+                reset_at: Some(1700000000),
+                authenticated: false
+            }
+        ));
+
+        // A 403 with quota left is a plain API error
+        let err = client
+            .get_latest_release("owner/private")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, OktofetchError::GithubApi(_)));
+    }
+
+    #[tokio::test]
+    async fn test_cached_responses_skip_api() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let mock_server = MockServer::start().await;
+        let temp_dir = tempfile::TempDir::new().unwrap();
+
+        Mock::given(method("GET"))
+            .and(path("/repos/owner/repo/releases/latest"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                r#"{"tag_name": "v1.0.0", "name": "Release 1.0.0", "assets": []}"#,
+            ))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let cache = || ResponseCache::new(temp_dir.path(), std::time::Duration::from_secs(60));
+
+        for _ in 0..2 {
+            let client = GithubClient::new(&HttpContext::default())
+                .with_api_base(&mock_server.uri())
+                .with_cache(cache());
+            let release = client.get_latest_release("owner/repo").await.unwrap();
+            assert_eq!(release.tag_name, "v1.0.0");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_search_repositories() {
+        use wiremock::matchers::{method, path, query_param};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/search/repositories"))
+            .and(query_param("q", "k9s in:name"))
+            .and(query_param("sort", "stars"))
+            .and(query_param("per_page", "5"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                r#"{
+                    "total_count": 2,
+                    "items": [
+                        {"full_name": "derailed/k9s", "stargazers_count": 27000, "description": "Kubernetes CLI"},
+                        {"full_name": "someone/k9s-plugins", "stargazers_count": 12, "description": null}
+                    ]
+                }"#,
+            ))
+            .mount(&mock_server)
+            .await;
+
+        let client = GithubClient::new(&HttpContext::default()).with_api_base(&mock_server.uri());
+        let results = client.search_repositories("k9s", 5).await.unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].full_name, "derailed/k9s");
+        assert_eq!(results[0].stargazers_count, 27000);
+        assert_eq!(results[1].description, None);
+    }
+
+    const FEED: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+  <link type="text/html" rel="alternate" href="https://github.com/owner/repo/releases"/>
+  <title>Release notes from repo</title>
+  <entry>
+    <id>tag:github.com,2008:Repository/1/v1.2.0</id>
+    <link rel="alternate" type="text/html" href="https://github.com/owner/repo/releases/tag/v1.2.0"/>
+    <title>v1.2.0</title>
+  </entry>
+  <entry>
+    <link rel="alternate" type="text/html" href="https://github.com/owner/repo/releases/tag/v1.1.0"/>
+  </entry>
+</feed>"#;
+
+    #[test]
+    fn test_first_feed_tag() {
+        assert_eq!(first_feed_tag(FEED), Some("v1.2.0".to_string()));
+        assert_eq!(first_feed_tag("<feed></feed>"), None);
+        assert_eq!(
+            first_feed_tag(
+                r#"<entry><link href="https://github.com/o/r/releases/tag/tool%2Fv2"/></entry>"#
+            ),
+            Some("tool/v2".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_feed_latest_tag() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/owner/repo/releases.atom"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(FEED))
+            .mount(&mock_server)
+            .await;
+
+        let client = GithubClient::new(&HttpContext::default()).with_web_base(&mock_server.uri());
+        assert_eq!(
+            client.feed_latest_tag("owner/repo").await.unwrap(),
+            Some("v1.2.0".to_string())
+        );
+        assert!(client.feed_latest_tag("owner/missing").await.is_err());
     }
 }
@@ -0,0 +1,580 @@
+//! HTTP plumbing shared by all release sources: the client, its retry and
+//! timeout policy, and which hosts redirects may lead to.
+
+use crate::config::Settings;
+use crate::error::OktofetchError;
+use crate::human;
+use crate::redact;
+use reqwest::redirect::Policy;
+use reqwest::{Client, Response, ResponseBuilderExt};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Hosts (and their subdomains) requests may be redirected to, e.g. release
+/// assets bouncing from github.com to objects.githubusercontent.com.
+const REDIRECT_HOSTS: &[&str] = &["github.com", "githubusercontent.com"];
+
+/// Redirect hops followed before a request is abandoned.
+const MAX_REDIRECTS: usize = 10;
+
+/// User-Agent sent with every request unless `user_agent` is configured.
+pub const DEFAULT_USER_AGENT: &str = concat!("oktofetch/", env!("CARGO_PKG_VERSION"));
+
+/// Largest response body `--debug-http-dir` saves.
+const MAX_DUMP_SIZE: u64 = 10 * 1024 * 1024;
+
+/// Set by `--debug-http`: whether requests are traced and where bodies are
+/// saved, if anywhere.
+static DEBUG_HTTP: OnceLock<Option<PathBuf>> = OnceLock::new();
+
+/// Bodies saved so far, numbering the dump files in request order.
+static DUMPED: AtomicUsize = AtomicUsize::new(0);
+
+/// How requests are retried and how long they may take.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HttpPolicy {
+    /// Extra attempts after a connection failure, timeout or server error
+    pub retries: u32,
+    /// Wait before the first retry; it doubles for each further one
+    pub backoff_base: Duration,
+    pub connect_timeout: Duration,
+    /// Longest wait for the next piece of a response
+    pub read_timeout: Duration,
+    /// Address to connect from, which pins connections to its IP version
+    pub local_address: Option<std::net::IpAddr>,
+}
+
+impl Default for HttpPolicy {
+    fn default() -> Self {
+        Self {
+            retries: 2,
+            backoff_base: Duration::from_millis(500),
+            connect_timeout: Duration::from_secs(30),
+            read_timeout: Duration::from_secs(60),
+            local_address: None,
+        }
+    }
+}
+
+impl HttpPolicy {
+    /// Wait before retry number `attempt`, counting from 0.
+    fn backoff(&self, attempt: u32) -> Duration {
+        self.backoff_base.saturating_mul(1 << attempt.min(16))
+    }
+}
+
+/// Sends the request `build` creates, retrying connection failures, timeouts
+/// and 5xx responses as `policy` allows. The last outcome is returned as is.
+pub async fn send_with_retry(
+    policy: &HttpPolicy,
+    build: impl Fn() -> reqwest::RequestBuilder,
+) -> reqwest::Result<reqwest::Response> {
+    let mut attempt = 0;
+    loop {
+        let result = match DEBUG_HTTP.get() {
+            Some(dump_dir) => send_traced(build(), dump_dir.as_deref()).await,
+            None => build().send().await,
+        };
+        let retryable = match &result {
+            Ok(response) => response.status().is_server_error(),
+            Err(e) => e.is_connect() || e.is_timeout(),
+        };
+        if !retryable || attempt >= policy.retries {
+            return result;
+        }
+
+        tokio::time::sleep(policy.backoff(attempt)).await;
+        attempt += 1;
+    }
+}
+
+/// Traces every request on stderr for the rest of the process, and saves
+/// textual request and response bodies to `dump_dir` if given.
+pub fn enable_debug(dump_dir: Option<PathBuf>) -> std::io::Result<()> {
+    if let Some(dir) = &dump_dir {
+        std::fs::create_dir_all(dir)?;
+    }
+    let _ = DEBUG_HTTP.set(dump_dir);
+    Ok(())
+}
+
+/// Sends `builder`'s request and prints a line with its outcome, timing and
+/// the rate limit it reports. Credentials are redacted throughout.
+async fn send_traced(
+    builder: reqwest::RequestBuilder,
+    dump_dir: Option<&Path>,
+) -> reqwest::Result<Response> {
+    let (client, request) = builder.build_split();
+    let request = request?;
+    let requested = request.url().clone();
+    let head = format!("{} {}", request.method(), redact::url(&requested));
+    let dump = dump_dir.map(|dir| (dir, request_dump(&request)));
+
+    let started = Instant::now();
+    let result = client.execute(request).await;
+    let elapsed = format!("{}ms", started.elapsed().as_millis());
+
+    let response = match result {
+        Ok(response) => response,
+        Err(e) => {
+            eprintln!(
+                "HTTP {} -> failed after {}: {}",
+                head,
+                elapsed,
+                redact::text(&e.to_string())
+            );
+            return Err(e);
+        }
+    };
+    eprintln!(
+        "HTTP {} -> {} in {}",
+        head,
+        describe_response(&response, &requested),
+        elapsed
+    );
+
+    match dump {
+        Some((dir, request_dump)) if is_textual(&response) => {
+            dump_response(dir, &head, request_dump, response).await
+        }
+        _ => Ok(response),
+    }
+}
+
+/// Status of `response`, where it was served from when redirected away from
+/// `requested`, and the rate limit its headers report.
+fn describe_response(response: &Response, requested: &reqwest::Url) -> String {
+    let mut line = response.status().to_string();
+    if response.url() != requested {
+        line.push_str(&format!(" from {}", redact::url(response.url())));
+    }
+
+    let header = |name: &str| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+    };
+    if let (Some(remaining), Some(limit)) =
+        (header("x-ratelimit-remaining"), header("x-ratelimit-limit"))
+    {
+        line.push_str(&format!(" [rate limit {}/{}", remaining, limit));
+        if let Some(resource) = header("x-ratelimit-resource") {
+            line.push_str(&format!(" {}", resource));
+        }
+        if let Some(reset) = header("x-ratelimit-reset").and_then(|r| r.parse::<u64>().ok()) {
+            let wait = reset.saturating_sub(human::unix_now());
+            line.push_str(&format!(", resets in {}", human::format_duration(wait)));
+        }
+        line.push(']');
+    }
+    line
+}
+
+/// Whether `response` is an API answer worth saving rather than a download.
+fn is_textual(response: &Response) -> bool {
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+    let textual = ["json", "xml", "text/"]
+        .iter()
+        .any(|kind| content_type.contains(kind));
+    textual && response.content_length().unwrap_or(0) <= MAX_DUMP_SIZE
+}
+
+/// Request headers and body as saved next to the response.
+fn request_dump(request: &reqwest::Request) -> String {
+    let mut dump = String::new();
+    for (name, value) in request.headers() {
+        let value = value.to_str().unwrap_or("<binary>");
+        dump.push_str(&format!(
+            "{}: {}\n",
+            name,
+            redact::header(name.as_str(), value)
+        ));
+    }
+    if let Some(body) = request.body().and_then(|body| body.as_bytes()) {
+        dump.push('\n');
+        dump.push_str(&redact::text(&String::from_utf8_lossy(body)));
+        dump.push('\n');
+    }
+    dump
+}
+
+/// Saves the exchange to a numbered file in `dir` and hands back an
+/// equivalent response, since reading the body consumes it.
+async fn dump_response(
+    dir: &Path,
+    head: &str,
+    request_dump: String,
+    response: Response,
+) -> reqwest::Result<Response> {
+    let status = response.status();
+    let version = response.version();
+    let url = response.url().clone();
+    let headers = response.headers().clone();
+    let body = response.bytes().await?;
+
+    let mut text = format!("{}\n{}\n{:?} {}\n", head, request_dump, version, status);
+    for (name, value) in &headers {
+        let value = value.to_str().unwrap_or("<binary>");
+        text.push_str(&format!(
+            "{}: {}\n",
+            name,
+            redact::header(name.as_str(), value)
+        ));
+    }
+    text.push('\n');
+    text.push_str(&redact::body(&String::from_utf8_lossy(&body)));
+
+    let number = DUMPED.fetch_add(1, Ordering::Relaxed) + 1;
+    let path = dir.join(format!("{:04}.txt", number));
+    match std::fs::write(&path, text) {
+        Ok(()) => eprintln!("HTTP   saved to {}", path.display()),
+        Err(e) => eprintln!("HTTP   could not save to {}: {}", path.display(), e),
+    }
+
+    let mut rebuilt = ::http::Response::builder()
+        .status(status)
+        .version(version)
+        .url(url)
+        .body(body)
+        .expect("parts of a received response are valid");
+    *rebuilt.headers_mut() = headers;
+    Ok(Response::from(rebuilt))
+}
+
+/// The HTTP client every request goes through: one connection pool with the
+/// user agent, timeouts and redirect rules applied once. Clones share the
+/// pool; the redirect allowlist is fixed once the context is built.
+#[derive(Debug, Clone)]
+pub struct HttpContext {
+    client: Client,
+    policy: HttpPolicy,
+    user_agent: String,
+    /// Hosts redirects may lead to besides GitHub's own, lowercased
+    redirect_hosts: Arc<Vec<String>>,
+}
+
+impl HttpContext {
+    pub fn new(user_agent: &str, policy: HttpPolicy, redirect_hosts: &[String]) -> Self {
+        let redirect_hosts: Arc<Vec<String>> = Arc::new(
+            redirect_hosts
+                .iter()
+                .map(|h| h.to_ascii_lowercase())
+                .collect(),
+        );
+        Self {
+            client: build_client(redirect_hosts.clone(), user_agent, &policy),
+            policy,
+            user_agent: user_agent.to_string(),
+            redirect_hosts,
+        }
+    }
+
+    /// Context for the configured user agent, HTTP policy and redirect hosts.
+    pub fn from_settings(settings: &Settings) -> Self {
+        Self::new(
+            settings.user_agent(),
+            settings.http_policy(),
+            settings.redirect_hosts.as_deref().unwrap_or_default(),
+        )
+    }
+
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// Context for one source that may also be redirected to `host` and its
+    /// subdomains, e.g. a container registry handing blobs off to its CDN.
+    /// `self` and every other source keep their own allowlist.
+    pub fn allowing_redirects_to(&self, host: &str) -> Self {
+        let host = host.to_ascii_lowercase();
+        if self.redirect_hosts.contains(&host) {
+            return self.clone();
+        }
+        let mut hosts = self.redirect_hosts.as_ref().clone();
+        hosts.push(host);
+        Self::new(&self.user_agent, self.policy, &hosts)
+    }
+
+    /// Sends the request `build` creates with [`send_with_retry`].
+    pub async fn send(
+        &self,
+        build: impl Fn(&Client) -> reqwest::RequestBuilder,
+    ) -> reqwest::Result<reqwest::Response> {
+        send_with_retry(&self.policy, || build(&self.client)).await
+    }
+}
+
+impl Default for HttpContext {
+    fn default() -> Self {
+        Self::new(DEFAULT_USER_AGENT, HttpPolicy::default(), &[])
+    }
+}
+
+/// The [`HttpContext`] for a config's settings, built on first use and
+/// rebuilt whenever the settings it was built from have changed.
+#[derive(Debug, Default)]
+pub struct HttpCache(Mutex<Option<(HttpKey, HttpContext)>>);
+
+/// Settings an [`HttpContext`] is built from.
+type HttpKey = (String, HttpPolicy, Vec<String>);
+
+impl HttpCache {
+    pub fn get(&self, settings: &Settings) -> HttpContext {
+        let key = (
+            settings.user_agent().to_string(),
+            settings.http_policy(),
+            settings.redirect_hosts.clone().unwrap_or_default(),
+        );
+        let mut cached = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        match cached.as_ref() {
+            Some((built_for, http)) if *built_for == key => http.clone(),
+            _ => {
+                let http = HttpContext::from_settings(settings);
+                *cached = Some((key, http.clone()));
+                http
+            }
+        }
+    }
+}
+
+impl Clone for HttpCache {
+    fn clone(&self) -> Self {
+        Self(Mutex::new(
+            self.0.lock().unwrap_or_else(|e| e.into_inner()).clone(),
+        ))
+    }
+}
+
+/// HTTP client that only follows redirects to GitHub's hosts and those in
+/// `extra_hosts`, identifies itself as `user_agent` and applies the timeouts
+/// of `policy`. Proxies from the environment and the system's TLS roots are
+/// reqwest's defaults.
+fn build_client(extra_hosts: Arc<Vec<String>>, user_agent: &str, policy: &HttpPolicy) -> Client {
+    let redirects = Policy::custom(move |attempt| {
+        if attempt.previous().len() >= MAX_REDIRECTS {
+            return attempt.error(format!("stopped after {} redirects", MAX_REDIRECTS));
+        }
+        let allowed = |host: &str| {
+            REDIRECT_HOSTS
+                .iter()
+                .any(|h| host_allowed(host, &[h.to_string()]))
+                || host_allowed(host, &extra_hosts)
+        };
+        match attempt.url().host_str() {
+            Some(host) if allowed(host) => attempt.follow(),
+            host => {
+                let message = format!(
+                    "refusing redirect to untrusted host {}",
+                    host.unwrap_or("(none)")
+                );
+                attempt.error(message)
+            }
+        }
+    });
+
+    Client::builder()
+        .redirect(redirects)
+        .user_agent(user_agent)
+        .connect_timeout(policy.connect_timeout)
+        .read_timeout(policy.read_timeout)
+        .local_address(policy.local_address)
+        .build()
+        .expect("HTTP client configuration is valid")
+}
+
+/// Whether `host` is one of `allowed` or a subdomain of one.
+fn host_allowed(host: &str, allowed: &[String]) -> bool {
+    let host = host.to_ascii_lowercase();
+    allowed.iter().any(|entry| {
+        host == *entry
+            || host
+                .strip_suffix(entry.as_str())
+                .is_some_and(|prefix| prefix.ends_with('.'))
+    })
+}
+
+/// Reports a rejected redirect as a failed download with the policy's reason.
+pub fn redirect_error(error: reqwest::Error) -> OktofetchError {
+    if !error.is_redirect() {
+        return error.into();
+    }
+
+    let reason = std::error::Error::source(&error)
+        .map(|source| source.to_string())
+        .unwrap_or_else(|| error.to_string());
+    let url = error
+        .url()
+        .map(redact::url)
+        .unwrap_or_else(|| "download".to_string());
+    OktofetchError::DownloadFailed(format!("{}: {}", url, redact::text(&reason)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_host_allowed() {
+        let allowed = vec!["github.com".to_string(), "mirror.example".to_string()];
+
+        assert!(host_allowed("github.com", &allowed));
+        assert!(host_allowed("objects.GitHub.com", &allowed));
+        assert!(host_allowed("mirror.example", &allowed));
+        assert!(!host_allowed("evilgithub.com", &allowed));
+        assert!(!host_allowed("github.com.evil.net", &allowed));
+    }
+
+    #[tokio::test]
+    async fn test_send_with_retry() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/flaky"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(2)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/flaky"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&mock_server)
+            .await;
+
+        let client = reqwest::Client::new();
+        let url = format!("{}/flaky", mock_server.uri());
+        let policy = HttpPolicy {
+            retries: 1,
+            backoff_base: Duration::from_millis(1),
+            ..Default::default()
+        };
+        let response = send_with_retry(&policy, || client.get(&url)).await;
+        assert_eq!(response.unwrap().status(), 503);
+
+        let response = send_with_retry(&policy, || client.get(&url)).await;
+        assert_eq!(response.unwrap().status(), 200);
+
+        // Client errors aren't retried
+        let missing = format!("{}/missing", mock_server.uri());
+        let response = send_with_retry(&policy, || client.get(&missing)).await;
+        assert_eq!(response.unwrap().status(), 404);
+        assert_eq!(mock_server.received_requests().await.unwrap().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_send_traced_dumps_api_responses() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/graphql"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("x-ratelimit-remaining", "4999")
+                    .insert_header("x-ratelimit-limit", "5000")
+                    .set_body_json(serde_json::json!({"data": {"ok": true}})),
+            )
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/asset"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(vec![0u8; 16]))
+            .mount(&mock_server)
+            .await;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let client = Client::new();
+        let url = format!("{}/graphql", mock_server.uri());
+        let request = client
+            .post(&url)
+            .header("Authorization", "bearer ghp_secret")
+            .body(r#"{"query":"{ viewer { login } }"}"#);
+
+        let response = send_traced(request, Some(temp_dir.path())).await.unwrap();
+        let described = describe_response(&response, &reqwest::Url::parse(&url).unwrap());
+        assert!(described.starts_with("200 OK [rate limit 4999/5000"));
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(body["data"]["ok"], true);
+
+        let files: Vec<_> = std::fs::read_dir(temp_dir.path()).unwrap().collect();
+        assert_eq!(files.len(), 1);
+        let dump = std::fs::read_to_string(files[0].as_ref().unwrap().path()).unwrap();
+        assert!(dump.starts_with(&format!("POST {}\n", url)));
+        assert!(dump.contains("authorization: bearer ***"));
+        assert!(!dump.contains("ghp_secret"));
+        assert!(dump.contains("viewer"));
+        assert!(dump.contains(r#"{"data":{"ok":true}}"#));
+
+        // Downloads pass through untouched and aren't saved
+        let asset = format!("{}/asset", mock_server.uri());
+        let response = send_traced(client.get(&asset), Some(temp_dir.path()))
+            .await
+            .unwrap();
+        assert_eq!(response.bytes().await.unwrap().len(), 16);
+        assert_eq!(std::fs::read_dir(temp_dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_http_policy_backoff() {
+        let policy = HttpPolicy::default();
+        assert_eq!(policy.backoff(0), Duration::from_millis(500));
+        assert_eq!(policy.backoff(2), Duration::from_secs(2));
+    }
+
+    #[tokio::test]
+    async fn test_context_redirect_hosts_per_source() {
+        use wiremock::matchers::{header, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/moved"))
+            .respond_with(
+                ResponseTemplate::new(302)
+                    .insert_header("Location", format!("{}/target", mock_server.uri())),
+            )
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/target"))
+            .and(header("user-agent", "acme/1.0"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&mock_server)
+            .await;
+
+        let http = HttpContext::new("acme/1.0", HttpPolicy::default(), &[]);
+        let url = format!("{}/moved", mock_server.uri());
+        let err = http.send(|client| client.get(&url)).await.unwrap_err();
+        assert!(redirect_error(err).to_string().contains("untrusted host"));
+
+        let source_http = http.allowing_redirects_to("127.0.0.1");
+        let response = source_http.send(|client| client.get(&url)).await.unwrap();
+        assert_eq!(response.status(), 200);
+
+        // The host was only allowed for that source
+        let err = http.send(|client| client.get(&url)).await.unwrap_err();
+        assert!(redirect_error(err).to_string().contains("untrusted host"));
+    }
+
+    #[test]
+    fn test_http_cache_follows_settings() {
+        let mut settings = Settings::default();
+        let cache = HttpCache::default();
+        assert_eq!(cache.get(&settings).policy, settings.http_policy());
+
+        settings.http.retries = Some(7);
+        settings.redirect_hosts = Some(vec!["Mirror.example".to_string()]);
+        let http = cache.get(&settings);
+        assert_eq!(http.policy.retries, 7);
+        assert_eq!(*http.redirect_hosts, ["mirror.example"]);
+    }
+}
@@ -0,0 +1,212 @@
+//! Formatting helpers for human-readable output.
+
+/// Formats a number of seconds as a compact duration, e.g. `1h 5m` or `42s`.
+pub fn format_duration(secs: u64) -> String {
+    let (hours, minutes, seconds) = (secs / 3600, (secs % 3600) / 60, secs % 60);
+
+    if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else if minutes > 0 {
+        format!("{}m {}s", minutes, seconds)
+    } else {
+        format!("{}s", seconds)
+    }
+}
+
+/// Describes how long ago Unix time `then` was, in the largest whole unit,
+/// e.g. `3 weeks ago` or `just now`.
+pub fn format_age(then: u64, now: u64) -> String {
+    const UNITS: [(&str, u64); 6] = [
+        ("year", 365 * 86400),
+        ("month", 30 * 86400),
+        ("week", 7 * 86400),
+        ("day", 86400),
+        ("hour", 3600),
+        ("minute", 60),
+    ];
+
+    let elapsed = now.saturating_sub(then);
+    UNITS
+        .iter()
+        .find(|(_, secs)| elapsed >= *secs)
+        .map(|(unit, secs)| {
+            let count = elapsed / secs;
+            let plural = if count == 1 { "" } else { "s" };
+            format!("{} {}{} ago", count, unit, plural)
+        })
+        .unwrap_or_else(|| "just now".to_string())
+}
+
+/// Parses a UTC timestamp as the GitHub API writes them, e.g.
+/// `2024-03-01T12:30:00Z`, into Unix time.
+pub fn parse_timestamp(input: &str) -> Option<u64> {
+    let (date, time) = input.trim().strip_suffix('Z')?.split_once('T')?;
+    let mut date = date.splitn(3, '-').map(|part| part.parse::<i64>().ok());
+    let (year, month, day) = (date.next()??, date.next()??, date.next()??);
+    let mut time = time.splitn(3, ':').map(|part| part.parse::<i64>().ok());
+    let (hour, minute, second) = (time.next()??, time.next()??, time.next()??);
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) || hour > 23 || minute > 59 {
+        return None;
+    }
+
+    // Days since the epoch of the civil date, after Howard Hinnant's algorithm
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146_097 + day_of_era - 719_468;
+
+    u64::try_from(days * 86400 + hour * 3600 + minute * 60 + second).ok()
+}
+
+/// Formats a byte count using binary units, e.g. `1.5 MiB`.
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+/// Parses a byte count with an optional binary suffix, e.g. `500K`, `2M` or `1G`.
+pub fn parse_size(input: &str) -> Result<u64, String> {
+    let input = input.trim();
+    let (digits, multiplier) = match input.char_indices().last() {
+        Some((i, c)) if c.is_ascii_alphabetic() => {
+            let multiplier = match c.to_ascii_uppercase() {
+                'K' => 1024,
+                'M' => 1024 * 1024,
+                'G' => 1024 * 1024 * 1024,
+                _ => return Err(format!("Unknown size suffix in '{}'", input)),
+            };
+            (&input[..i], multiplier)
+        }
+        _ => (input, 1),
+    };
+
+    let n = digits
+        .parse::<u64>()
+        .map_err(|_| format!("Invalid size '{}'", input))?;
+    n.checked_mul(multiplier)
+        .ok_or_else(|| format!("Size '{}' is too large", input))
+}
+
+/// Parses a number of seconds with an optional unit suffix: `s`, `m`, `h`,
+/// `d` or `w`, e.g. `90`, `12h` or `30d`.
+pub fn parse_duration(input: &str) -> Result<u64, String> {
+    let input = input.trim();
+    let (digits, multiplier) = match input.char_indices().last() {
+        Some((i, c)) if c.is_ascii_alphabetic() => {
+            let multiplier = match c.to_ascii_lowercase() {
+                's' => 1,
+                'm' => 60,
+                'h' => 3600,
+                'd' => 86400,
+                'w' => 7 * 86400,
+                _ => return Err(format!("Unknown duration unit in '{}'", input)),
+            };
+            (&input[..i], multiplier)
+        }
+        _ => (input, 1),
+    };
+
+    let n = digits
+        .parse::<u64>()
+        .map_err(|_| format!("Invalid duration '{}'", input))?;
+    n.checked_mul(multiplier)
+        .ok_or_else(|| format!("Duration '{}' is too long", input))
+}
+
+/// Seconds since the Unix epoch.
+pub fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(0), "0s");
+        assert_eq!(format_duration(42), "42s");
+        assert_eq!(format_duration(125), "2m 5s");
+        assert_eq!(format_duration(3900), "1h 5m");
+    }
+
+    #[test]
+    fn test_parse_timestamp() {
+        assert_eq!(parse_timestamp("1970-01-01T00:00:00Z"), Some(0));
+        assert_eq!(parse_timestamp("2024-03-01T12:30:05Z"), Some(1_709_296_205));
+        assert_eq!(parse_timestamp("2000-02-29T00:00:00Z"), Some(951_782_400));
+        assert_eq!(parse_timestamp("2024-03-01T12:30:05+01:00"), None);
+        assert_eq!(parse_timestamp("2024-13-01T00:00:00Z"), None);
+        assert_eq!(parse_timestamp("yesterday"), None);
+    }
+
+    #[test]
+    fn test_format_age() {
+        let now = 1_700_000_000;
+        assert_eq!(format_age(now, now), "just now");
+        assert_eq!(format_age(now + 60, now), "just now");
+        assert_eq!(format_age(now - 90, now), "1 minute ago");
+        assert_eq!(format_age(now - 5 * 3600, now), "5 hours ago");
+        assert_eq!(format_age(now - 22 * 86400, now), "3 weeks ago");
+        assert_eq!(format_age(now - 400 * 86400, now), "1 year ago");
+    }
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(0), "0 B");
+        assert_eq!(format_size(1023), "1023 B");
+        assert_eq!(format_size(1536), "1.5 KiB");
+        assert_eq!(format_size(5 * 1024 * 1024), "5.0 MiB");
+        assert_eq!(format_size(3 * 1024 * 1024 * 1024), "3.0 GiB");
+    }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("1000"), Ok(1000));
+        assert_eq!(parse_size("500K"), Ok(500 * 1024));
+        assert_eq!(parse_size("2m"), Ok(2 * 1024 * 1024));
+        assert_eq!(parse_size("1G"), Ok(1024 * 1024 * 1024));
+        assert!(parse_size("5X").is_err());
+        assert!(parse_size("fast").is_err());
+        assert!(parse_size("").is_err());
+        assert_eq!(
+            parse_size("18014398509481984K"),
+            Err("Size '18014398509481984K' is too large".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("90"), Ok(90));
+        assert_eq!(parse_duration("12h"), Ok(12 * 3600));
+        assert_eq!(parse_duration("30d"), Ok(30 * 86400));
+        assert_eq!(parse_duration("1W"), Ok(7 * 86400));
+        assert!(parse_duration("3y").is_err());
+        assert!(parse_duration("soon").is_err());
+        assert_eq!(
+            parse_duration("99999999999999999w"),
+            Err("Duration '99999999999999999w' is too long".to_string())
+        );
+    }
+
+    #[test]
+    fn test_unix_now() {
+        assert!(unix_now() > 1_600_000_000);
+    }
+}
@@ -0,0 +1,283 @@
+//! Converts the tool lists of other version managers into oktofetch tools.
+
+use crate::config::{Config, Tool, expand_path};
+use crate::error::{OktofetchError, Result};
+use crate::source;
+use crate::tool;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Manifest formats `oktofetch import` reads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Manifest {
+    /// mise `config.toml` (`[tools]` table)
+    Mise,
+    /// asdf `.tool-versions`
+    Asdf,
+    /// Homebrew `Brewfile`
+    Brew,
+}
+
+impl Manifest {
+    /// Where the manifest usually lives when no path is given.
+    pub fn default_path(self) -> PathBuf {
+        PathBuf::from(match self {
+            Self::Mise => expand_path("~/.config/mise/config.toml"),
+            Self::Asdf => expand_path("~/.tool-versions"),
+            Self::Brew => "Brewfile".to_string(),
+        })
+    }
+
+    fn parse(self, content: &str) -> std::result::Result<Vec<Entry>, String> {
+        match self {
+            Self::Mise => parse_mise(content),
+            Self::Asdf => Ok(parse_tool_versions(content)),
+            Self::Brew => Ok(parse_brewfile(content)),
+        }
+    }
+}
+
+/// A tool named in a manifest, with its GitHub repository when the manifest
+/// says which one it is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub name: String,
+    pub repo: Option<String>,
+}
+
+impl Entry {
+    fn named(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            repo: None,
+        }
+    }
+}
+
+/// mise backends whose argument is a GitHub `owner/repo`.
+const GITHUB_BACKENDS: &[&str] = &["ubi", "github", "aqua"];
+
+/// Reads the `[tools]` table of a mise config. Keys are either plain names
+/// (`k9s = "latest"`) or `backend:argument` (`"ubi:derailed/k9s" = "latest"`).
+fn parse_mise(content: &str) -> std::result::Result<Vec<Entry>, String> {
+    let value: toml::Table = toml::from_str(content).map_err(|e| e.to_string())?;
+    let Some(tools) = value.get("tools").and_then(|t| t.as_table()) else {
+        return Ok(Vec::new());
+    };
+
+    Ok(tools
+        .keys()
+        .map(|key| match key.split_once(':') {
+            Some((backend, argument)) => {
+                // Backend options come in brackets, e.g. `ubi:owner/repo[exe=tool]`
+                let argument = argument.split('[').next().unwrap_or(argument);
+                let name = argument.rsplit('/').next().unwrap_or(argument);
+                let repo = (GITHUB_BACKENDS.contains(&backend) && argument.contains('/'))
+                    .then(|| argument.to_string());
+                Entry {
+                    name: name.to_string(),
+                    repo,
+                }
+            }
+            None => Entry::named(key),
+        })
+        .collect())
+}
+
+/// Reads an asdf `.tool-versions` file: one `<plugin> <version>...` per line.
+fn parse_tool_versions(content: &str) -> Vec<Entry> {
+    content
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or_default())
+        .filter_map(|line| line.split_whitespace().next())
+        .map(Entry::named)
+        .collect()
+}
+
+/// Reads the `brew "<formula>"` lines of a Brewfile. Casks, taps and other
+/// entry types aren't command-line tools oktofetch could install.
+fn parse_brewfile(content: &str) -> Vec<Entry> {
+    content
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("brew "))
+        .filter_map(|rest| {
+            let quote = rest.trim_start().chars().next()?;
+            let rest = rest.trim_start().strip_prefix(['"', '\''])?;
+            let formula = &rest[..rest.find(quote)?];
+            // Formulae from taps are written `owner/tap/formula`
+            Some(Entry::named(formula.rsplit('/').next()?))
+        })
+        .collect()
+}
+
+/// Repository a plain tool name most likely comes from: the most starred
+/// search result whose repository has exactly that name.
+async fn find_repo(config: &Config, name: &str) -> Result<Option<String>> {
+    let client = source::github_client(&config.settings, &config.http())?;
+    let results = client.search_repositories(name, 10).await?;
+    Ok(results
+        .into_iter()
+        .map(|repo| repo.full_name)
+        .find(|full_name| {
+            full_name
+                .rsplit('/')
+                .next()
+                .is_some_and(|repo_name| repo_name.eq_ignore_ascii_case(name))
+        }))
+}
+
+/// Checks one manifest entry, returning the tool to add and a description of
+/// the release found, or why it was skipped.
+async fn resolve(
+    config: &Config,
+    entry: &Entry,
+) -> Result<std::result::Result<(Tool, String), String>> {
+    if config.get_tool(&entry.name).is_some() {
+        return Ok(Err("already managed".to_string()));
+    }
+
+    let repo = match &entry.repo {
+        Some(repo) => repo.clone(),
+        None => match find_repo(config, &entry.name).await? {
+            Some(repo) => repo,
+            None => return Ok(Err("no GitHub repository by that name".to_string())),
+        },
+    };
+    let tool = Tool {
+        name: entry.name.clone(),
+        repo,
+        ..Default::default()
+    };
+
+    let source = source::for_tool(&tool, &config.settings, &config.http())?;
+    match tool::check_installable(source.as_ref(), &tool, &config.settings).await {
+        Ok((release, asset)) => {
+            let found = format!("{} ({}, {})", tool.repo, release.tag_name, asset.name);
+            Ok(Ok((tool, found)))
+        }
+        Err(e @ OktofetchError::RateLimited { .. }) => Err(e),
+        Err(e) => Ok(Err(format!("{}: {}", tool.repo, e))),
+    }
+}
+
+/// Adds a tool for every entry of the manifest at `path` that has a GitHub
+/// release installable on this platform. Versions pinned in the manifest are
+/// ignored; `update` installs the newest release.
+pub async fn import(config: &mut Config, kind: Manifest, path: &Path, dry_run: bool) -> Result<()> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| OktofetchError::ConfigError(e.to_string(), path.to_path_buf()))?;
+    let entries = kind
+        .parse(&content)
+        .map_err(|e| OktofetchError::ConfigError(e, path.to_path_buf()))?;
+
+    println!("Found {} tools in {}", entries.len(), path.display());
+    let mut added = 0;
+    let mut skipped = 0;
+    let mut result = Ok(());
+
+    for entry in &entries {
+        match resolve(config, entry).await {
+            Ok(Ok((tool, found))) => {
+                println!("  {:<20} {}", entry.name, found);
+                if !dry_run {
+                    config.add_tool(tool)?;
+                }
+                added += 1;
+            }
+            Ok(Err(reason)) => {
+                println!("  {:<20} skipped: {}", entry.name, reason);
+                skipped += 1;
+            }
+            // The remaining lookups would fail the same way
+            Err(e) => {
+                result = Err(e);
+                break;
+            }
+        }
+    }
+
+    if dry_run {
+        println!("\nSummary: {} would be added, {} skipped", added, skipped);
+    } else {
+        if added > 0 {
+            config.save()?;
+        }
+        println!("\nSummary: {} added, {} skipped", added, skipped);
+        if added > 0 {
+            println!("Run 'oktofetch update --all --only-missing' to install them");
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_mise() {
+        let content = r#"
+[env]
+FOO = "bar"
+
+[tools]
+k9s = "0.32.5"
+"ubi:BurntSushi/ripgrep" = { version = "latest", exe = "rg" }
+"aqua:junegunn/fzf[bin=fzf]" = "latest"
+"cargo:cargo-edit" = "latest"
+"#;
+        let mut entries = parse_mise(content).unwrap();
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+        assert_eq!(
+            entries,
+            vec![
+                Entry::named("cargo-edit"),
+                Entry {
+                    name: "fzf".to_string(),
+                    repo: Some("junegunn/fzf".to_string()),
+                },
+                Entry::named("k9s"),
+                Entry {
+                    name: "ripgrep".to_string(),
+                    repo: Some("BurntSushi/ripgrep".to_string()),
+                },
+            ]
+        );
+
+        assert!(
+            parse_mise("[settings]\nexperimental = true\n")
+                .unwrap()
+                .is_empty()
+        );
+        assert!(parse_mise("[tools").is_err());
+    }
+
+    #[test]
+    fn test_parse_tool_versions() {
+        let content =
+            "# managed by asdf\nnodejs 20.11.0\nterraform 1.7.0 1.6.0  # two versions\n\n";
+        assert_eq!(
+            parse_tool_versions(content),
+            vec![Entry::named("nodejs"), Entry::named("terraform")]
+        );
+    }
+
+    #[test]
+    fn test_parse_brewfile() {
+        let content = r#"
+tap "homebrew/bundle"
+brew "jq"
+brew 'k9s', restart_service: false
+brew "hashicorp/tap/terraform"
+cask "firefox"
+"#;
+        assert_eq!(
+            parse_brewfile(content),
+            vec![
+                Entry::named("jq"),
+                Entry::named("k9s"),
+                Entry::named("terraform")
+            ]
+        );
+    }
+}
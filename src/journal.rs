@@ -0,0 +1,123 @@
+//! Progress of a running `update --all`, so an interrupted run can pick up
+//! where it stopped with `--resume`.
+//!
+//! The journal names the tools the run has finished. It is rewritten after
+//! every tool and removed once the run gets to the end of its list, so one
+//! only exists while a run is going or after it was cut short.
+
+use crate::config;
+use crate::error::{OktofetchError, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Journal {
+    /// Tools that were updated, found up to date or skipped
+    #[serde(default)]
+    pub done: Vec<String>,
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl Journal {
+    /// Starts a new run at `path`, discarding what an earlier one left.
+    pub fn start(path: &Path) -> Result<Self> {
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+        Ok(Self {
+            done: Vec::new(),
+            path: path.to_path_buf(),
+        })
+    }
+
+    /// Continues the run recorded at `path`, or starts one if there is none.
+    pub fn resume(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Self::start(path);
+        }
+
+        let content = fs::read_to_string(path)?;
+        let journal: Self = serde_json::from_str(&content)
+            .map_err(|e| OktofetchError::ConfigError(e.to_string(), path.to_path_buf()))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            ..journal
+        })
+    }
+
+    pub fn is_done(&self, name: &str) -> bool {
+        self.done.iter().any(|done| done == name)
+    }
+
+    /// Records that `name` is finished.
+    pub fn finish(&mut self, name: &str) -> Result<()> {
+        if !self.is_done(name) {
+            self.done.push(name.to_string());
+        }
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let content = serde_json::to_string_pretty(self).map_err(std::io::Error::from)?;
+        config::write_atomic(&self.path, (content + "\n").as_bytes())
+    }
+
+    /// Removes the journal of a run that got through its whole list.
+    pub fn complete(self) -> Result<()> {
+        if self.path.exists() {
+            fs::remove_file(&self.path)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_resume_interrupted_run() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("data/state.journal");
+
+        let mut journal = Journal::start(&path).unwrap();
+        assert!(!path.exists());
+        journal.finish("k9s").unwrap();
+        journal.finish("fd").unwrap();
+        journal.finish("k9s").unwrap();
+
+        // The process dies here; the next run picks the journal up
+        let resumed = Journal::resume(&path).unwrap();
+        assert_eq!(resumed.done, vec!["k9s", "fd"]);
+        assert!(resumed.is_done("fd"));
+        assert!(!resumed.is_done("rg"));
+
+        resumed.complete().unwrap();
+        assert!(!path.exists());
+        assert!(Journal::resume(&path).unwrap().done.is_empty());
+    }
+
+    #[test]
+    fn test_start_discards_earlier_run() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("state.journal");
+        Journal::start(&path).unwrap().finish("k9s").unwrap();
+
+        let journal = Journal::start(&path).unwrap();
+        assert!(journal.done.is_empty());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_resume_corrupt_journal() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("state.journal");
+        fs::write(&path, "not json").unwrap();
+
+        let err = Journal::resume(&path).unwrap_err();
+        assert!(matches!(err, OktofetchError::ConfigError(_, _)));
+    }
+}
@@ -0,0 +1,95 @@
+//! Advisory lock serialising commands that modify the config, the state or
+//! installed binaries, e.g. a cron update overlapping with a manual one.
+
+use crate::error::{OktofetchError, Result};
+use std::fs::{self, File};
+use std::os::unix::io::AsRawFd;
+use std::path::Path;
+
+/// Held lock; released when dropped (or when the process exits).
+#[derive(Debug)]
+pub struct Lock {
+    _file: File,
+}
+
+/// Takes an exclusive lock on `path`. When another process holds it, waits
+/// for it to finish, or fails right away if `wait` is false.
+pub fn acquire(path: &Path, wait: bool) -> Result<Lock> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let file = File::options()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(path)?;
+
+    if flock(&file, libc::LOCK_EX | libc::LOCK_NB)? {
+        return Ok(Lock { _file: file });
+    }
+    if !wait {
+        return Err(OktofetchError::Other(format!(
+            "Another oktofetch process is running (lock held on {}). \
+             Try again once it finishes, or drop --no-wait to wait for it",
+            path.display()
+        )));
+    }
+
+    eprintln!("Waiting for another oktofetch process to finish...");
+    flock(&file, libc::LOCK_EX)?;
+    Ok(Lock { _file: file })
+}
+
+/// Calls `flock(2)`, returning false if a non-blocking request found the lock taken.
+fn flock(file: &File, operation: libc::c_int) -> Result<bool> {
+    loop {
+        // SAFETY: the descriptor stays valid for the duration of the call
+        if unsafe { libc::flock(file.as_raw_fd(), operation) } == 0 {
+            return Ok(true);
+        }
+        let error = std::io::Error::last_os_error();
+        match error.raw_os_error() {
+            Some(libc::EINTR) => continue,
+            Some(libc::EWOULDBLOCK) => return Ok(false),
+            _ => return Err(error.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_lock_is_exclusive() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("locks/oktofetch.lock");
+
+        let lock = acquire(&path, false).unwrap();
+        let err = acquire(&path, false).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("Another oktofetch process is running")
+        );
+
+        drop(lock);
+        assert!(acquire(&path, false).is_ok());
+    }
+
+    #[test]
+    fn test_lock_waits_for_release() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("oktofetch.lock");
+
+        let lock = acquire(&path, false).unwrap();
+        let waiter = {
+            let path = path.clone();
+            std::thread::spawn(move || acquire(&path, true).map(|_| ()))
+        };
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        drop(lock);
+
+        assert!(waiter.join().unwrap().is_ok());
+    }
+}
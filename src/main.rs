@@ -1,17 +1,41 @@
-use clap::{Parser, Subcommand};
-use std::path::PathBuf;
+use clap::{CommandFactory, Parser, Subcommand};
+use clap_complete::engine::{ArgValueCandidates, CompletionCandidate};
 use std::process;
 
 mod archive;
+mod auth;
 mod binary;
+mod binstall;
+mod cache;
+mod changelog;
+mod clean;
+mod completions;
 mod config;
 mod error;
+mod events;
 mod github;
+mod http;
+mod human;
+mod import;
+mod journal;
+mod lock;
+mod oci;
 mod platform;
+mod prompt;
+mod redact;
+mod shell;
+mod source;
+mod source_build;
+mod state;
+mod status;
+mod throttle;
 mod tool;
+mod ui;
+mod usage;
+mod version;
 
 use config::Config;
-use error::Result;
+use error::{Context, Result};
 
 #[derive(Parser)]
 #[command(name = "oktofetch")]
@@ -23,6 +47,39 @@ struct Cli {
     /// Verbose output
     #[arg(short, long, global = true)]
     verbose: bool,
+
+    /// Config file to use instead of the default (also $OKTOFETCH_CONFIG)
+    #[arg(long, global = true, value_name = "PATH")]
+    config: Option<std::path::PathBuf>,
+
+    /// Fail instead of waiting when another oktofetch run is making changes
+    #[arg(long, global = true)]
+    no_wait: bool,
+
+    /// Print progress events as JSON lines on stdout, other output on stderr
+    #[arg(long, global = true)]
+    porcelain: bool,
+
+    /// Trace every HTTP request on stderr: method, URL, status, time and rate limit
+    #[arg(long, global = true)]
+    debug_http: bool,
+
+    /// Also save API request and response bodies to numbered files in DIR
+    #[arg(long, global = true, value_name = "DIR")]
+    debug_http_dir: Option<std::path::PathBuf>,
+
+    /// GitHub token for this run, ahead of $GITHUB_TOKEN and the OS keyring
+    #[arg(long, global = true, value_name = "TOKEN")]
+    token: Option<String>,
+
+    /// Answer yes to every confirmation, e.g. of large downloads
+    #[arg(short, long, global = true)]
+    yes: bool,
+
+    /// Never prompt: decline confirmations and fail where a choice is needed
+    /// (also when $CI is set)
+    #[arg(long, global = true)]
+    non_interactive: bool,
 }
 
 #[derive(Subcommand)]
@@ -39,18 +96,83 @@ enum Commands {
         /// Binary name to extract and install
         #[arg(short, long)]
         binary: Option<String>,
+
+        /// Repository the release binaries are published in, if not the main repo
+        #[arg(long)]
+        release_repo: Option<String>,
+
+        /// File name to install the binary as
+        #[arg(long)]
+        install_as: Option<String>,
+
+        /// Add without checking the repository has a release for this platform
+        #[arg(long, alias = "offline", conflicts_with = "install")]
+        no_verify: bool,
+
+        /// Install the tool right after adding it
+        #[arg(short, long)]
+        install: bool,
+
+        /// Install this version instead of the latest release
+        #[arg(long, conflicts_with = "no_verify")]
+        version: Option<String>,
+
+        /// Keep --version in its own directory, side by side with the installed one
+        #[arg(long, requires = "version")]
+        keep: bool,
     },
 
+    /// Manage a binary that is already in the install directory
+    Adopt {
+        /// File name of the binary in the install directory, used as the tool name
+        name: String,
+
+        /// GitHub repository it comes from (owner/repo or full URL)
+        repo: String,
+    },
+
+    /// Suggest repositories for unmanaged executables in the install directory
+    Scan {
+        /// Repositories to suggest per executable
+        #[arg(long, default_value_t = 3)]
+        candidates: usize,
+    },
+
+    /// Add the tools listed by another version manager
+    Import {
+        /// Manifest format to read
+        #[arg(long, value_enum)]
+        from: import::Manifest,
+
+        /// Manifest file (defaults to the format's usual location)
+        path: Option<std::path::PathBuf>,
+
+        /// Show what would be added without changing the config
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Print the tool list with installed versions as a shareable toolfile
+    Freeze,
+
     /// Remove a tool from management
     Remove {
         /// Tool name to remove
         name: String,
+
+        /// Don't ask for confirmation
+        #[arg(short, long)]
+        force: bool,
+
+        /// Delete this version kept side by side instead of the tool
+        #[arg(long)]
+        version: Option<String>,
     },
 
     /// Update one or all tools
     Update {
-        /// Tool name to update (omit for all)
-        name: Option<String>,
+        /// Tools to update (omit for all)
+        names: Vec<String>,
 
         /// Update all tools
         #[arg(short, long)]
@@ -59,15 +181,167 @@ enum Commands {
         /// Force reinstallation even if version matches
         #[arg(short, long)]
         force: bool,
+
+        /// Install a specific version instead of the latest release
+        #[arg(long, conflicts_with = "all")]
+        version: Option<String>,
+
+        /// Only install tools whose binary is missing or was modified
+        #[arg(long, conflicts_with_all = ["force", "version"])]
+        only_missing: bool,
+
+        /// Comma-separated tools to skip when updating all
+        #[arg(long, value_delimiter = ',', conflicts_with = "names")]
+        except: Vec<String>,
+
+        /// Review each pending update and confirm it before downloading
+        #[arg(short, long, conflicts_with = "names")]
+        interactive: bool,
+
+        /// Cap download speed, in bytes per second with optional K/M/G suffix
+        #[arg(long, value_parser = human::parse_size)]
+        limit_rate: Option<u64>,
+
+        /// Stop at the first tool that fails to update and exit with an error
+        #[arg(long)]
+        fail_fast: bool,
+
+        /// Skip the tools an interrupted update of all tools already finished
+        #[arg(long, conflicts_with_all = ["names", "version"])]
+        resume: bool,
+
+        /// Skip tools checked for updates more recently than this, e.g. 6h
+        /// (overrides min_check_interval)
+        #[arg(long, value_name = "DURATION", value_parser = human::parse_duration)]
+        min_check_interval: Option<u64>,
     },
 
     /// List all managed tools
-    List,
+    List {
+        /// Print the full tool records as JSON
+        #[arg(long)]
+        json: bool,
+
+        /// Order to list tools in (default: config order)
+        #[arg(long, value_enum)]
+        sort: Option<tool::ListSort>,
+
+        /// Only list tools that are installed, missing, or match tag=<text>
+        /// or repo=<text>; repeat to combine
+        #[arg(long, value_name = "FILTER", value_parser = tool::parse_list_filter)]
+        filter: Vec<tool::ListFilter>,
+    },
+
+    /// Compare configured tools against the install directory
+    Status {
+        /// Also check that installed releases still exist upstream
+        #[arg(long)]
+        remote: bool,
+    },
+
+    /// Remove cached downloads, API responses and old backups
+    Clean {
+        /// Remove leftover downloads from interrupted updates
+        #[arg(long)]
+        downloads: bool,
+
+        /// Remove cached API responses
+        #[arg(long)]
+        metadata: bool,
+
+        /// Remove .previous backups and retained versions that aren't installed
+        #[arg(long)]
+        backups: bool,
+
+        /// Prune the caches to cache_max_age and cache_max_size instead
+        #[arg(long, conflicts_with_all = ["downloads", "metadata", "backups"])]
+        auto: bool,
+    },
+
+    /// Show disk space used by each tool and by the caches
+    Du,
+
+    /// Show release notes between the installed and the newest version
+    Changelog {
+        /// Tool name
+        name: String,
+    },
 
     /// Show information about a tool
     Info {
         /// Tool name
         name: String,
+
+        /// Print the full tool record as JSON
+        #[arg(long)]
+        json: bool,
+
+        /// Also show the repository's description, license, stars and homepage
+        #[arg(long)]
+        remote: bool,
+
+        /// List every asset of the newest release and which ones match
+        #[arg(long)]
+        assets: bool,
+    },
+
+    /// Print shell setup for PATH and completions, e.g. eval "$(oktofetch env)"
+    Env {
+        /// Shell to emit code for (detected from $SHELL by default)
+        #[arg(long, value_enum)]
+        shell: Option<shell::Shell>,
+    },
+
+    /// Print shell code that switches to a project's oktofetch.toml and install
+    /// directory when entering it
+    Hook {
+        /// Shell to emit code for, or direnv for a .envrc snippet
+        #[arg(value_enum)]
+        target: shell::HookTarget,
+    },
+
+    /// Let the shell hook use a project's oktofetch.toml as it is now
+    Allow {
+        /// Project file (the nearest oktofetch.toml by default)
+        path: Option<std::path::PathBuf>,
+
+        /// Stop the hook from using the file
+        #[arg(long)]
+        revoke: bool,
+
+        /// Exit with an error unless the file is allowed (used by the hook)
+        #[arg(long, hide = true, conflicts_with = "revoke")]
+        check: bool,
+    },
+
+    /// Open the interactive dashboard
+    Ui,
+
+    /// Show the remaining GitHub API quota
+    Ratelimit,
+
+    /// Set a field of one tool's entry, e.g. asset_pattern
+    Set {
+        /// Tool name
+        name: String,
+
+        /// Field name (e.g., asset_pattern)
+        #[arg(add = ArgValueCandidates::new(tool_key_candidates))]
+        key: String,
+
+        /// Field value
+        #[arg(add = ArgValueCandidates::new(key_value_candidates))]
+        value: String,
+    },
+
+    /// Clear a field of one tool's entry
+    Unset {
+        /// Tool name
+        name: String,
+
+        /// Field name (e.g., asset_pattern)
+        #[arg(add = ArgValueCandidates::new(tool_key_candidates))]
+        key: String,
     },
 
     /// Show or set configuration
@@ -75,6 +349,24 @@ enum Commands {
         #[command(subcommand)]
         command: Option<ConfigCommands>,
     },
+
+    /// Manage the GitHub token kept in the OS keyring
+    Auth {
+        #[command(subcommand)]
+        command: AuthCommands,
+    },
+}
+
+#[derive(Subcommand)]
+enum AuthCommands {
+    /// Check a GitHub token and store it in the OS keyring (from --token or stdin)
+    Login,
+
+    /// Remove the stored token from the OS keyring
+    Logout,
+
+    /// Show which token requests would use
+    Status,
 }
 
 #[derive(Subcommand)]
@@ -82,61 +374,357 @@ enum ConfigCommands {
     /// Show current configuration
     Show,
 
-    /// Set installation directory
+    /// Set a configuration value
     Set {
         /// Configuration key (e.g., install_dir)
-        key: String,
+        #[arg(
+            required_unless_present = "list",
+            add = ArgValueCandidates::new(setting_key_candidates)
+        )]
+        key: Option<String>,
 
         /// Configuration value
-        value: String,
+        #[arg(
+            required_unless_present = "list",
+            add = ArgValueCandidates::new(key_value_candidates)
+        )]
+        value: Option<String>,
+
+        /// List the valid keys and the values they take
+        #[arg(long, conflicts_with_all = ["key", "value"])]
+        list: bool,
+    },
+
+    /// Print one configuration value (empty when the setting is off)
+    Get {
+        /// Configuration key (e.g., install_dir)
+        #[arg(add = ArgValueCandidates::new(setting_key_candidates))]
+        key: String,
+    },
+
+    /// Revert a configuration value to its default
+    Unset {
+        /// Configuration key (e.g., cache_ttl)
+        #[arg(add = ArgValueCandidates::new(setting_key_candidates))]
+        key: String,
     },
 }
 
 #[tokio::main]
 async fn main() {
+    // Answers shell completion requests, e.g. from `source <(COMPLETE=bash oktofetch)`
+    clap_complete::CompleteEnv::with_factory(Cli::command).complete();
+
     let cli = Cli::parse();
+    let verbose = cli.verbose;
+
+    if cli.porcelain
+        && let Err(e) = events::enable()
+    {
+        eprintln!("Error: cannot set up --porcelain output: {}", e);
+        process::exit(1);
+    }
+    if (cli.debug_http || cli.debug_http_dir.is_some())
+        && let Err(e) = http::enable_debug(cli.debug_http_dir.clone())
+    {
+        eprintln!("Error: cannot set up --debug-http-dir: {}", e);
+        process::exit(1);
+    }
 
     if let Err(e) = run(cli).await {
-        eprintln!("Error: {}", e);
+        eprintln!("Error: {}", e.report(verbose));
         let exit_code = e.exit_code();
         process::exit(exit_code);
     }
 }
 
+/// Whether `command` changes the config, the state or installed binaries,
+/// and so must not run alongside another such command.
+fn needs_lock(command: &Commands) -> bool {
+    match command {
+        Commands::Add { .. }
+        | Commands::Adopt { .. }
+        | Commands::Import { .. }
+        | Commands::Remove { .. }
+        | Commands::Update { .. }
+        | Commands::Set { .. }
+        | Commands::Unset { .. }
+        | Commands::Clean { .. } => true,
+        Commands::Config { command } => matches!(
+            command,
+            Some(ConfigCommands::Set { list: false, .. } | ConfigCommands::Unset { .. })
+        ),
+        _ => false,
+    }
+}
+
 async fn run(cli: Cli) -> Result<()> {
+    if let Some(path) = cli.config {
+        Config::use_path(path);
+    }
+    Config::check_trust()?;
+    if let Some(token) = cli.token {
+        auth::use_token(token);
+    }
+    prompt::configure(cli.yes, cli.non_interactive);
+    let _lock = if needs_lock(&cli.command) {
+        Some(lock::acquire(&Config::lock_path()?, !cli.no_wait)?)
+    } else {
+        None
+    };
+
     match cli.command {
-        Commands::Add { repo, name, binary } => {
+        Commands::Add {
+            repo,
+            name,
+            binary,
+            release_repo,
+            install_as,
+            no_verify,
+            install,
+            version,
+            keep,
+        } => {
+            let mut config = Config::load()?;
+            let opts = tool::AddOptions {
+                name,
+                binary_name: binary,
+                release_repo,
+                install_as,
+                no_verify,
+                install,
+                version,
+                keep,
+                verbose: cli.verbose,
+            };
+            tool::add_tool(&mut config, repo, opts).await
+        }
+
+        Commands::Adopt { name, repo } => {
+            let mut config = Config::load()?;
+            tool::adopt_tool(&mut config, &name, &repo, cli.verbose).await
+        }
+
+        Commands::Scan { candidates } => {
+            let config = Config::load()?;
+            status::scan(&config, candidates).await
+        }
+
+        Commands::Import {
+            from,
+            path,
+            dry_run,
+        } => {
             let mut config = Config::load()?;
-            tool::add_tool(&mut config, repo, name, binary).await
+            let path = path.unwrap_or_else(|| from.default_path());
+            import::import(&mut config, from, &path, dry_run).await
         }
 
-        Commands::Remove { name } => {
+        Commands::Freeze => {
+            let config = Config::load()?;
+            print!("{}", tool::freeze(&config)?);
+            Ok(())
+        }
+
+        Commands::Remove {
+            name,
+            force,
+            version,
+        } => {
             let mut config = Config::load()?;
-            tool::remove_tool(&mut config, &name)
+            let name = config.resolve_name(&name)?;
+            match version {
+                Some(version) => tool::remove_kept_version(&mut config, &name, &version, force),
+                None => tool::remove_tool(&mut config, &name, force),
+            }
         }
 
-        Commands::Update { name, all, force } => {
+        Commands::Update {
+            names,
+            all,
+            force,
+            version,
+            only_missing,
+            except,
+            interactive,
+            limit_rate,
+            fail_fast,
+            resume,
+            min_check_interval,
+        } => {
             let mut config = Config::load()?;
+            let names = config.resolve_names(&names)?;
+            let opts = tool::UpdateOptions {
+                verbose: cli.verbose,
+                force,
+                version,
+                only_missing,
+                except,
+                interactive,
+                yes: cli.yes,
+                limit_rate,
+                fail_fast,
+                resume,
+                min_check_interval,
+                keep: false,
+            };
 
-            if all || name.is_none() {
-                tool::update_all_tools(&mut config, cli.verbose, force).await
-            } else if let Some(tool_name) = name {
-                tool::update_tool(&mut config, &tool_name, cli.verbose, force).await
+            let result = match names.as_slice() {
+                _ if opts.version.is_some() && names.len() != 1 => {
+                    return Err(error::OktofetchError::Other(
+                        "--version requires a single tool name".to_string(),
+                    ));
+                }
+                [] => tool::update_all_tools(&mut config, &opts).await,
+                _ if all => tool::update_all_tools(&mut config, &opts).await,
+                [tool_name] => tool::update_tool(&mut config, tool_name, &opts).await,
+                _ => tool::update_tools(&mut config, &names, &opts).await,
+            };
+
+            // Updates fill the caches, so trim them while we hold the lock
+            match clean::auto_prune(&config.settings) {
+                Ok(Some(freed)) if freed > 0 && cli.verbose => {
+                    println!("Pruned caches: {}", human::format_size(freed))
+                }
+                Err(e) if cli.verbose => eprintln!("Warning: failed to prune caches: {}", e),
+                _ => {}
+            }
+            result
+        }
+
+        Commands::List { json, sort, filter } => {
+            let config = Config::load()?;
+            let query = tool::ListQuery {
+                filters: filter,
+                sort,
+            };
+            if json {
+                tool::list_tools_json(&config, &query)
             } else {
-                Err(error::OktofetchError::Other(
-                    "Specify a tool name or use --all".to_string(),
-                ))
+                tool::list_tools(&config, &query)
+            }
+        }
+
+        Commands::Status { remote } => {
+            let config = Config::load()?;
+            status::show_status(&config, remote).await
+        }
+
+        Commands::Clean { auto: true, .. } => {
+            let config = Config::load()?;
+            clean::clean_auto(&config)
+        }
+
+        Commands::Clean {
+            downloads,
+            metadata,
+            backups,
+            auto: false,
+        } => {
+            let config = Config::load()?;
+            clean::clean(
+                &config,
+                clean::CleanTargets {
+                    downloads,
+                    metadata,
+                    backups,
+                },
+            )
+        }
+
+        Commands::Du => {
+            let config = Config::load()?;
+            usage::show_usage(&config)
+        }
+
+        Commands::Changelog { name } => {
+            let config = Config::load()?;
+            let name = config.resolve_name(&name)?;
+            changelog::show_changelog(&config, &name).await
+        }
+
+        Commands::Info {
+            name,
+            json,
+            remote,
+            assets,
+        } => {
+            let config = Config::load()?;
+            let name = config.resolve_name(&name)?;
+            let repository = match remote {
+                true => remote_repository(&config, &name).await?,
+                false => None,
+            };
+            let release = match assets {
+                true => Some(tool::assess_release(&config, tool_by_name(&config, &name)?).await?),
+                false => None,
+            };
+            if json {
+                show_tool_info_json(&config, &name, repository, release)
+            } else {
+                show_tool_info(&config, &name, repository.as_ref())?;
+                if let Some((release, assets)) = &release {
+                    show_release_assets(release, assets);
+                }
+                Ok(())
             }
         }
 
-        Commands::List => {
+        Commands::Hook { target } => print_hook(target),
+
+        Commands::Allow {
+            path,
+            revoke,
+            check,
+        } => allow_project(path, revoke, check),
+
+        Commands::Env { shell } => {
             let config = Config::load()?;
-            tool::list_tools(&config)
+            let shell = shell.unwrap_or_else(shell::Shell::detect);
+            let completions =
+                Config::completions_dir()?.join(format!("oktofetch.{}", shell.name()));
+            print!(
+                "{}",
+                shell::setup_script(
+                    shell,
+                    &config.settings.install_dir,
+                    completions.is_file().then_some(completions.as_path()),
+                )
+            );
+            Ok(())
+        }
+
+        Commands::Ui => {
+            if prompt::mode() == prompt::Mode::NonInteractive {
+                return Err(error::OktofetchError::InputRequired(
+                    "ui is interactive".to_string(),
+                ));
+            }
+            ui::run(Config::load()?).await
         }
 
-        Commands::Info { name } => {
+        Commands::Ratelimit => {
             let config = Config::load()?;
-            show_tool_info(&config, &name)
+            let client = github::GithubClient::new(&config.http());
+            show_rate_limit(&client).await
+        }
+
+        Commands::Set { name, key, value } => {
+            let mut config = Config::load()?;
+            let name = config.resolve_name(&name)?;
+            tool_entry(&mut config, &name)?.set(&key, &value)?;
+            config.save()?;
+            println!("Set {} of {} to {}", key, name, value);
+            Ok(())
+        }
+
+        Commands::Unset { name, key } => {
+            let mut config = Config::load()?;
+            let name = config.resolve_name(&name)?;
+            tool_entry(&mut config, &name)?.unset(&key)?;
+            config.save()?;
+            println!("Cleared {} of {}", key, name);
+            Ok(())
         }
 
         Commands::Config { command } => match command {
@@ -144,34 +732,384 @@ async fn run(cli: Cli) -> Result<()> {
                 let config = Config::load()?;
                 show_config(&config)
             }
-            Some(ConfigCommands::Set { key, value }) => {
+            Some(ConfigCommands::Set { list: true, .. }) => {
+                list_config_keys();
+                Ok(())
+            }
+            Some(ConfigCommands::Set { key, value, .. }) => {
                 let mut config = Config::load()?;
-                set_config(&mut config, &key, &value)
+                set_config(
+                    &mut config,
+                    &key.unwrap_or_default(),
+                    &value.unwrap_or_default(),
+                )
+            }
+            Some(ConfigCommands::Get { key }) => {
+                let config = Config::load()?;
+                println!("{}", config.settings.get(&key)?.unwrap_or_default());
+                Ok(())
+            }
+            Some(ConfigCommands::Unset { key }) => {
+                let mut config = Config::load()?;
+                config.settings.unset(&key)?;
+                config.forget_env_override(&key);
+                config.save()?;
+                println!("Reset {} to its default", key);
+                warn_env_override(&key);
+                Ok(())
+            }
+        },
+
+        Commands::Auth { command } => match command {
+            AuthCommands::Login => auth_login(&Config::load()?).await,
+            AuthCommands::Logout => {
+                if auth::logout()? {
+                    println!("Removed the GitHub token from the OS keyring");
+                } else {
+                    println!("No GitHub token stored in the OS keyring");
+                }
+                Ok(())
+            }
+            AuthCommands::Status => {
+                match auth::token() {
+                    Some(token) => println!("Using a GitHub token from {}", token.source),
+                    None => println!("No GitHub token; run `oktofetch auth login` to store one"),
+                }
+                Ok(())
             }
         },
     }
 }
 
-fn show_tool_info(config: &Config, name: &str) -> Result<()> {
+fn tool_by_name<'a>(config: &'a Config, name: &str) -> Result<&'a config::Tool> {
+    config
+        .get_tool(name)
+        .ok_or_else(|| error::OktofetchError::ToolNotFound(name.to_string()))
+}
+
+fn tool_entry<'a>(config: &'a mut Config, name: &str) -> Result<&'a mut config::Tool> {
+    config
+        .get_tool_mut(name)
+        .ok_or_else(|| error::OktofetchError::ToolNotFound(name.to_string()))
+}
+
+/// Metadata of a tool's repository, for `info --remote`. Tools not
+/// installed from GitHub have none.
+async fn remote_repository(config: &Config, name: &str) -> Result<Option<github::Repository>> {
+    let tool = config
+        .get_tool(name)
+        .ok_or_else(|| error::OktofetchError::ToolNotFound(name.to_string()))?;
+    if !tool.is_github() {
+        eprintln!(
+            "{} isn't installed from GitHub, no repository to look up",
+            name
+        );
+        return Ok(None);
+    }
+
+    let client = source::github_client(&config.settings, &config.http())?;
+    client.get_repository(&tool.repo).await.map(Some)
+}
+
+fn show_tool_info(
+    config: &Config,
+    name: &str,
+    repository: Option<&github::Repository>,
+) -> Result<()> {
     let tool = config
         .get_tool(name)
         .ok_or_else(|| error::OktofetchError::ToolNotFound(name.to_string()))?;
 
     println!("Tool: {}", tool.name);
     println!("Repository: {}", tool.repo);
+    if let Some(repository) = repository {
+        if let Some(description) = &repository.description {
+            println!("Description: {}", description);
+        }
+        if let Some(license) = &repository.license {
+            println!("License: {}", license.label());
+        }
+        println!("Stars: {}", repository.stargazers_count);
+        if let Some(homepage) = repository.homepage.as_deref().filter(|h| !h.is_empty()) {
+            println!("Homepage: {}", homepage);
+        }
+        if repository.archived {
+            println!("Archived: yes");
+        }
+    }
+    if let Some(release_repo) = &tool.release_repo {
+        println!("Release repository: {}", release_repo);
+    }
     if let Some(version) = &tool.version {
         println!("Version: {}", version);
     }
+    if let Some(installed_at) = tool.installed_at {
+        println!(
+            "Updated: {}",
+            human::format_age(installed_at, human::unix_now())
+        );
+    }
+    if let Some(checked_at) = tool.checked_at {
+        println!(
+            "Checked: {}",
+            human::format_age(checked_at, human::unix_now())
+        );
+    }
+    if let Some(reason) = tool::unmaintained_reason(tool, human::unix_now()) {
+        println!("Possibly unmaintained: {}", reason);
+    }
+    if let Some(asset) = &tool.asset_name {
+        println!("Asset: {}", asset);
+    }
+    if let Some(size) = tool.asset_size {
+        println!("Download size: {}", human::format_size(size));
+    }
+    if let Some(size) = tool::installed_size(config, tool) {
+        println!("Installed size: {}", human::format_size(size));
+    }
     if let Some(binary) = &tool.binary_name {
         println!("Binary name: {}", binary);
     }
+    if let Some(install_as) = &tool.install_as {
+        println!("Installed as: {}", install_as);
+    }
     if let Some(pattern) = &tool.asset_pattern {
         println!("Asset pattern: {}", pattern);
     }
+    if let Some(channel) = &tool.channel {
+        println!("Channel: {}", channel);
+    }
+    if let Some(tag_format) = &tool.tag_format {
+        println!("Tag format: {}", tag_format);
+    }
+    if let Some(selector) = &tool.asset_selector {
+        println!("Asset selector: {}", selector);
+    }
+    if let Some(source) = &tool.source {
+        println!("Source: {}", source);
+    }
+    if let Some(build) = &tool.build {
+        println!("Build command: {}", build);
+    }
+    if tool.binstall {
+        println!("Asset from: cargo-binstall metadata");
+    }
+    if let Some(quickinstall) = tool.quickinstall {
+        println!("Quickinstall fallback: {}", quickinstall);
+    }
+    if let Some(checksum) = &tool.checksum {
+        println!("SHA256: {}", checksum);
+    }
+    if let Some(digest) = &tool.asset_digest {
+        println!("Asset digest: {}", digest);
+    }
+    if let Some(sha256) = &tool.sha256 {
+        println!("Required asset SHA256: {}", sha256);
+    }
+    if tool.pinned {
+        println!("Pinned: yes (skipped by update --all)");
+    }
+    if !tool.kept_versions.is_empty() {
+        println!(
+            "Kept side by side: {} (in {})",
+            tool.kept_versions.join(", "),
+            Config::versions_dir()?.join(&tool.name).display()
+        );
+    }
+    if tool.bundle {
+        match tool.bundle_dir(&Config::versions_dir()?) {
+            Some(dir) => println!("Bundle: {}", dir.display()),
+            None => println!("Bundle: yes (not installed yet)"),
+        }
+    }
 
     Ok(())
 }
 
+/// Lists a release's assets, marking the one an update would install and
+/// saying which rules each matches, to help with writing an `asset_pattern`.
+fn show_release_assets(release: &github::Release, assets: &[tool::AssetReport]) {
+    println!();
+    println!(
+        "Assets of {} (* = the one update installs):",
+        release.tag_name
+    );
+    let width = assets.iter().map(|a| a.name.len()).max().unwrap_or(0);
+    for asset in assets {
+        let mut matches = Vec::new();
+        if asset.platform {
+            matches.push("platform");
+        }
+        if asset.pattern == Some(true) {
+            matches.push("pattern");
+        }
+        if asset.excluded {
+            matches.push("excluded");
+        }
+        println!(
+            "  {} {:<width$}  {:>10}  {}",
+            if asset.selected { "*" } else { " " },
+            asset.name,
+            human::format_size(asset.size),
+            matches.join(", "),
+        );
+    }
+    if !assets.iter().any(|a| a.selected) {
+        println!("No asset is picked; set asset_pattern to choose one");
+    }
+}
+
+/// A tool record with the repository metadata `--remote` fetched and the
+/// release assets `--assets` assessed.
+#[derive(serde::Serialize)]
+struct InfoRecord<'a> {
+    #[serde(flatten)]
+    record: tool::ToolRecord<'a>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    repository: Option<github::Repository>,
+    /// Tag of the newest release, whose assets are listed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    release: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    assets: Option<Vec<tool::AssetReport>>,
+}
+
+fn show_tool_info_json(
+    config: &Config,
+    name: &str,
+    repository: Option<github::Repository>,
+    release: Option<(github::Release, Vec<tool::AssetReport>)>,
+) -> Result<()> {
+    let tool = tool_by_name(config, name)?;
+    let (release, assets) = match release {
+        Some((release, assets)) => (Some(release.tag_name), Some(assets)),
+        None => (None, None),
+    };
+
+    tool::print_json(&InfoRecord {
+        record: tool::tool_record(config, tool),
+        repository,
+        release,
+        assets,
+    })
+}
+
+fn allow_project(path: Option<std::path::PathBuf>, revoke: bool, check: bool) -> Result<()> {
+    let file = match path {
+        Some(path) => path,
+        None => shell::find_project_file(&std::env::current_dir()?).ok_or_else(|| {
+            error::OktofetchError::Other(format!(
+                "No {} in this directory or its parents",
+                shell::PROJECT_FILE
+            ))
+        })?,
+    };
+    let list_path = Config::allowlist_path()?;
+    let mut list = shell::Allowlist::load(&list_path)?;
+
+    if check {
+        if !list.is_allowed(&file) {
+            return Err(error::OktofetchError::Other(format!(
+                "{} is not allowed",
+                file.display()
+            )));
+        }
+    } else if revoke {
+        if list.revoke(&file) {
+            list.save(&list_path)?;
+            println!("Revoked {}", file.display());
+        } else {
+            println!("{} was not allowed", file.display());
+        }
+    } else {
+        let file = list.allow(&file)?;
+        list.save(&list_path)?;
+        println!("Allowed {}", file.display());
+    }
+    Ok(())
+}
+
+fn print_hook(target: shell::HookTarget) -> Result<()> {
+    let shell = match target {
+        shell::HookTarget::Bash => shell::Shell::Bash,
+        shell::HookTarget::Zsh => shell::Shell::Zsh,
+        shell::HookTarget::Fish => shell::Shell::Fish,
+        shell::HookTarget::Direnv => {
+            let file = shell::find_project_file(&std::env::current_dir()?).ok_or_else(|| {
+                error::OktofetchError::Other(format!(
+                    "No {} in this directory or its parents",
+                    shell::PROJECT_FILE
+                ))
+            })?;
+            let config = Config::load_from(&file)?;
+            print!(
+                "{}",
+                shell::direnv_script(&file, &config.settings.install_dir)
+            );
+            return Ok(());
+        }
+    };
+    print!("{}", shell::hook_script(shell));
+    Ok(())
+}
+
+async fn show_rate_limit(client: &github::GithubClient) -> Result<()> {
+    let rate = client.get_rate_limit().await?;
+    let reset_in = rate.reset.saturating_sub(human::unix_now());
+
+    println!("GitHub API rate limit (core):");
+    println!("  Remaining: {}/{}", rate.remaining, rate.limit);
+    println!(
+        "  Resets in: {} (at unix time {})",
+        human::format_duration(reset_in),
+        rate.reset
+    );
+    println!(
+        "  Authenticated: {}",
+        match auth::token() {
+            Some(token) if client.has_token() => format!("yes ({})", token.source),
+            _ =>
+                "no (set GITHUB_TOKEN or run `oktofetch auth login` for a higher limit)".to_string(),
+        }
+    );
+    Ok(())
+}
+
+/// Stores the `--token` value, or one read from stdin, once GitHub has
+/// accepted it.
+async fn auth_login(config: &Config) -> Result<()> {
+    use std::io::IsTerminal;
+
+    let token = match auth::token() {
+        Some(token) if token.source == auth::TokenSource::Flag => token.value,
+        _ => {
+            let stdin = std::io::stdin();
+            let token = if stdin.is_terminal() {
+                if !prompt::can_ask() {
+                    return Err(error::OktofetchError::InputRequired(
+                        "auth login needs a token from --token or stdin".to_string(),
+                    ));
+                }
+                // Not echoed, so it doesn't stay on screen or in scrollback
+                let typed = rpassword::prompt_password("Paste a GitHub token: ")?;
+                auth::read_token(&mut typed.as_bytes())?
+            } else {
+                auth::read_token(&mut stdin.lock())?
+            };
+            auth::use_token(token.clone());
+            token
+        }
+    };
+
+    github::GithubClient::new(&config.http())
+        .get_rate_limit()
+        .await
+        .with_context(|| "GitHub did not accept the token".to_string())?;
+    auth::login(&token)?;
+    println!("Stored the GitHub token in the OS keyring");
+    Ok(())
+}
+
 fn show_config(config: &Config) -> Result<()> {
     println!("Configuration:");
     println!(
@@ -182,29 +1120,133 @@ fn show_config(config: &Config) -> Result<()> {
     Ok(())
 }
 
-fn set_config(config: &mut Config, key: &str, value: &str) -> Result<()> {
-    match key {
-        "install_dir" => {
-            config.settings.install_dir = PathBuf::from(value);
-            config.save()?;
-            println!("Set install_dir to {}", value);
-            Ok(())
-        }
-        _ => Err(error::OktofetchError::Other(format!(
-            "Unknown config key: {}. Valid keys: install_dir",
-            key
-        ))),
-    }
+fn set_config(config: &mut Config, key: &str, value: &str) -> Result<()> {
+    config.settings.set(key, value)?;
+    config.forget_env_override(key);
+    config.save()?;
+    println!("Set {} to {}", key, value);
+    warn_env_override(key);
+    Ok(())
+}
+
+/// Points out that the environment will keep overriding a value just saved.
+fn warn_env_override(key: &str) {
+    let var = Config::env_var(key);
+    if std::env::var(&var).is_ok_and(|v| !v.is_empty()) {
+        println!("Note: {} is set and overrides this setting", var);
+    }
+}
+
+fn list_config_keys() {
+    for (key, kind) in config::SETTING_KEYS {
+        println!("{:<24}{}", key, kind);
+    }
+}
+
+fn key_candidates(keys: &[(&str, config::KeyValue)]) -> Vec<CompletionCandidate> {
+    keys.iter()
+        .map(|(key, kind)| CompletionCandidate::new(key).help(Some(kind.to_string().into())))
+        .collect()
+}
+
+fn setting_key_candidates() -> Vec<CompletionCandidate> {
+    key_candidates(config::SETTING_KEYS)
+}
+
+fn tool_key_candidates() -> Vec<CompletionCandidate> {
+    key_candidates(config::TOOL_KEYS)
+}
+
+/// Values for the key typed just before the word being completed. The
+/// completion request carries the command line after `--`.
+fn key_value_candidates() -> Vec<CompletionCandidate> {
+    let args: Vec<String> = std::env::args().collect();
+    let key = previous_word(&args, std::env::var("_CLAP_COMPLETE_INDEX").ok());
+    config::key_values(key.unwrap_or_default())
+        .iter()
+        .map(CompletionCandidate::new)
+        .collect()
+}
+
+/// The word before the one at `index` (the last word when the shell doesn't
+/// say) among the words after `--` in `args`.
+fn previous_word(args: &[String], index: Option<String>) -> Option<&str> {
+    let words = &args[args.iter().position(|a| a == "--")? + 1..];
+    let index = index
+        .and_then(|i| i.parse::<usize>().ok())
+        .unwrap_or(words.len().saturating_sub(1));
+    words.get(index.checked_sub(1)?).map(String::as_str)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_previous_word() {
+        let args: Vec<String> = [
+            "oktofetch",
+            "--",
+            "oktofetch",
+            "config",
+            "set",
+            "channel",
+            "",
+        ]
+        .iter()
+        .map(|a| a.to_string())
+        .collect();
+        assert_eq!(previous_word(&args, None), Some("channel"));
+        assert_eq!(previous_word(&args, Some("3".to_string())), Some("set"));
+        assert_eq!(previous_word(&args[..2], None), None);
+        assert_eq!(previous_word(&args[..1], None), None);
+    }
+
+    #[test]
+    fn test_key_candidates() {
+        let candidates = setting_key_candidates();
+        assert_eq!(candidates.len(), config::SETTING_KEYS.len());
+        assert_eq!(candidates[0].get_value(), "install_dir");
+        assert_eq!(tool_key_candidates()[0].get_value(), "repo");
+    }
+
+    #[test]
+    fn test_show_tool_info_json() {
+        let mut config = Config::default();
+        config
+            .add_tool(config::Tool {
+                name: "test".to_string(),
+                repo: "owner/test".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+
+        assert!(show_tool_info_json(&config, "test", None, None).is_ok());
+        assert!(show_tool_info_json(&config, "missing", None, None).is_err());
+
+        let repository: github::Repository = serde_json::from_str(
+            r#"{"full_name": "owner/test", "description": "A test tool", "stargazers_count": 42}"#,
+        )
+        .unwrap();
+        assert!(show_tool_info(&config, "test", Some(&repository)).is_ok());
+
+        let record = InfoRecord {
+            record: tool::tool_record(&config, config.get_tool("test").unwrap()),
+            repository: Some(repository),
+            release: None,
+            assets: None,
+        };
+        let json = serde_json::to_value(&record).unwrap();
+        assert_eq!(json["name"], "test");
+        assert_eq!(json["repository"]["stargazers_count"], 42);
+        assert_eq!(json["repository"]["description"], "A test tool");
+    }
 
     #[test]
     fn test_show_tool_info_not_found() {
         let config = Config::default();
-        let result = show_tool_info(&config, "nonexistent");
+        let result = show_tool_info(&config, "nonexistent", None);
         assert!(result.is_err());
     }
 
@@ -217,10 +1259,11 @@ mod tests {
             binary_name: Some("test-bin".to_string()),
             asset_pattern: Some("linux-x64".to_string()),
             version: Some("v1.0.0".to_string()),
+            ..Default::default()
         };
         config.add_tool(tool).unwrap();
 
-        let result = show_tool_info(&config, "test");
+        let result = show_tool_info(&config, "test", None);
         assert!(result.is_ok());
     }
 
@@ -261,10 +1304,11 @@ mod tests {
             binary_name: Some("binary".to_string()),
             asset_pattern: Some("pattern".to_string()),
             version: Some("v1.2.3".to_string()),
+            ..Default::default()
         };
         config.add_tool(tool).unwrap();
 
-        let result = show_tool_info(&config, "fulltool");
+        let result = show_tool_info(&config, "fulltool", None);
         assert!(result.is_ok());
     }
 
@@ -277,10 +1321,11 @@ mod tests {
             binary_name: None,
             asset_pattern: None,
             version: None,
+            ..Default::default()
         };
         config.add_tool(tool).unwrap();
 
-        let result = show_tool_info(&config, "minimal");
+        let result = show_tool_info(&config, "minimal", None);
         assert!(result.is_ok());
     }
 
@@ -298,7 +1343,9 @@ mod tests {
     fn test_cli_parsing_add_command() {
         let cli = Cli::parse_from(["oktofetch", "add", "owner/repo"]);
         match cli.command {
-            Commands::Add { repo, name, binary } => {
+            Commands::Add {
+                repo, name, binary, ..
+            } => {
                 assert_eq!(repo, "owner/repo");
                 assert!(name.is_none());
                 assert!(binary.is_none());
@@ -319,7 +1366,9 @@ mod tests {
             "mybin",
         ]);
         match cli.command {
-            Commands::Add { repo, name, binary } => {
+            Commands::Add {
+                repo, name, binary, ..
+            } => {
                 assert_eq!(repo, "owner/repo");
                 assert_eq!(name, Some("mytool".to_string()));
                 assert_eq!(binary, Some("mybin".to_string()));
@@ -328,23 +1377,155 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_cli_parsing_add_install_as() {
+        let cli = Cli::parse_from(["oktofetch", "add", "derailed/k9s", "--install-as", "kube9"]);
+        match cli.command {
+            Commands::Add { install_as, .. } => {
+                assert_eq!(install_as, Some("kube9".to_string()));
+            }
+            _ => panic!("Expected Add command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_parsing_add_release_repo() {
+        let cli = Cli::parse_from([
+            "oktofetch",
+            "add",
+            "org/tool",
+            "--release-repo",
+            "org/tool-releases",
+        ]);
+        match cli.command {
+            Commands::Add { release_repo, .. } => {
+                assert_eq!(release_repo, Some("org/tool-releases".to_string()));
+            }
+            _ => panic!("Expected Add command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_parsing_add_no_verify() {
+        for flag in ["--no-verify", "--offline"] {
+            let cli = Cli::parse_from(["oktofetch", "add", "derailed/k9s", flag]);
+            match cli.command {
+                Commands::Add { no_verify, .. } => assert!(no_verify),
+                _ => panic!("Expected Add command"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_cli_parsing_add_install() {
+        let cli = Cli::parse_from(["oktofetch", "add", "derailed/k9s", "--install"]);
+        match cli.command {
+            Commands::Add { install, .. } => assert!(install),
+            _ => panic!("Expected Add command"),
+        }
+
+        assert!(
+            Cli::try_parse_from(["oktofetch", "add", "derailed/k9s", "-i", "--offline"]).is_err()
+        );
+    }
+
+    #[test]
+    fn test_cli_parsing_add_keep() {
+        let cli = Cli::parse_from([
+            "oktofetch",
+            "add",
+            "hashicorp/terraform",
+            "--version",
+            "v1.4.6",
+            "--keep",
+        ]);
+        match cli.command {
+            Commands::Add { version, keep, .. } => {
+                assert_eq!(version.as_deref(), Some("v1.4.6"));
+                assert!(keep);
+            }
+            _ => panic!("Expected Add command"),
+        }
+
+        assert!(
+            Cli::try_parse_from(["oktofetch", "add", "hashicorp/terraform", "--keep"]).is_err()
+        );
+
+        let cli = Cli::parse_from(["oktofetch", "remove", "terraform", "--version", "v1.4.6"]);
+        assert!(matches!(
+            cli.command,
+            Commands::Remove { version: Some(v), .. } if v == "v1.4.6"
+        ));
+    }
+
+    #[test]
+    fn test_cli_parsing_adopt() {
+        let cli = Cli::parse_from(["oktofetch", "adopt", "k9s", "derailed/k9s"]);
+        match cli.command {
+            Commands::Adopt { name, repo } => {
+                assert_eq!(name, "k9s");
+                assert_eq!(repo, "derailed/k9s");
+            }
+            _ => panic!("Expected Adopt command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_parsing_scan() {
+        let cli = Cli::parse_from(["oktofetch", "scan"]);
+        assert!(matches!(cli.command, Commands::Scan { candidates: 3 }));
+
+        let cli = Cli::parse_from(["oktofetch", "scan", "--candidates", "5"]);
+        assert!(matches!(cli.command, Commands::Scan { candidates: 5 }));
+    }
+
+    #[test]
+    fn test_cli_parsing_import() {
+        let cli = Cli::parse_from(["oktofetch", "import", "--from", "asdf", "--dry-run"]);
+        match cli.command {
+            Commands::Import {
+                from,
+                path,
+                dry_run,
+            } => {
+                assert_eq!(from, import::Manifest::Asdf);
+                assert!(path.is_none());
+                assert!(dry_run);
+            }
+            _ => panic!("Expected Import command"),
+        }
+
+        assert!(Cli::try_parse_from(["oktofetch", "import"]).is_err());
+    }
+
     #[test]
     fn test_cli_parsing_remove() {
         let cli = Cli::parse_from(["oktofetch", "remove", "mytool"]);
         match cli.command {
-            Commands::Remove { name } => {
+            Commands::Remove {
+                name,
+                force,
+                version,
+            } => {
                 assert_eq!(name, "mytool");
+                assert!(!force);
+                assert_eq!(version, None);
             }
             _ => panic!("Expected Remove command"),
         }
+
+        let cli = Cli::parse_from(["oktofetch", "remove", "mytool", "-f"]);
+        assert!(matches!(cli.command, Commands::Remove { force: true, .. }));
     }
 
     #[test]
     fn test_cli_parsing_update() {
         let cli = Cli::parse_from(["oktofetch", "update", "mytool"]);
         match cli.command {
-            Commands::Update { name, all, force } => {
-                assert_eq!(name, Some("mytool".to_string()));
+            Commands::Update {
+                names, all, force, ..
+            } => {
+                assert_eq!(names, vec!["mytool".to_string()]);
                 assert!(!all);
                 assert!(!force);
             }
@@ -352,12 +1533,23 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_cli_parsing_update_several() {
+        let cli = Cli::parse_from(["oktofetch", "update", "k9s", "fzf", "bat"]);
+        match cli.command {
+            Commands::Update { names, .. } => assert_eq!(names, vec!["k9s", "fzf", "bat"]),
+            _ => panic!("Expected Update command"),
+        }
+    }
+
     #[test]
     fn test_cli_parsing_update_all() {
         let cli = Cli::parse_from(["oktofetch", "update", "--all"]);
         match cli.command {
-            Commands::Update { name, all, force } => {
-                assert!(name.is_none());
+            Commands::Update {
+                names, all, force, ..
+            } => {
+                assert!(names.is_empty());
                 assert!(all);
                 assert!(!force);
             }
@@ -365,12 +1557,59 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_cli_parsing_update_fail_fast() {
+        let cli = Cli::parse_from(["oktofetch", "update", "--all", "--fail-fast"]);
+        assert!(matches!(
+            cli.command,
+            Commands::Update {
+                fail_fast: true,
+                ..
+            }
+        ));
+
+        let cli = Cli::parse_from(["oktofetch", "update", "--all"]);
+        assert!(matches!(
+            cli.command,
+            Commands::Update {
+                fail_fast: false,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_cli_parsing_update_resume() {
+        let cli = Cli::parse_from(["oktofetch", "update", "--all", "--resume"]);
+        assert!(matches!(cli.command, Commands::Update { resume: true, .. }));
+
+        let result = Cli::try_parse_from(["oktofetch", "update", "k9s", "--resume"]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_cli_parsing_update_min_check_interval() {
+        let cli = Cli::parse_from(["oktofetch", "update", "--all", "--min-check-interval", "6h"]);
+        assert!(matches!(
+            cli.command,
+            Commands::Update {
+                min_check_interval: Some(21600),
+                ..
+            }
+        ));
+
+        let result = Cli::try_parse_from(["oktofetch", "update", "--min-check-interval", "soon"]);
+        assert!(result.is_err());
+    }
+
     #[test]
     fn test_cli_parsing_update_force() {
         let cli = Cli::parse_from(["oktofetch", "update", "mytool", "--force"]);
         match cli.command {
-            Commands::Update { name, all, force } => {
-                assert_eq!(name, Some("mytool".to_string()));
+            Commands::Update {
+                names, all, force, ..
+            } => {
+                assert_eq!(names, vec!["mytool".to_string()]);
                 assert!(!all);
                 assert!(force);
             }
@@ -378,23 +1617,263 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_cli_parsing_update_only_missing() {
+        let cli = Cli::parse_from(["oktofetch", "update", "--all", "--only-missing"]);
+        assert!(matches!(
+            cli.command,
+            Commands::Update {
+                all: true,
+                only_missing: true,
+                ..
+            }
+        ));
+
+        assert!(Cli::try_parse_from(["oktofetch", "update", "--only-missing", "--force"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parsing_update_except() {
+        let cli = Cli::parse_from([
+            "oktofetch",
+            "update",
+            "--all",
+            "--except",
+            "terraform,vault",
+        ]);
+        match cli.command {
+            Commands::Update { all, except, .. } => {
+                assert!(all);
+                assert_eq!(except, vec!["terraform".to_string(), "vault".to_string()]);
+            }
+            _ => panic!("Expected Update command"),
+        }
+
+        assert!(Cli::try_parse_from(["oktofetch", "update", "k9s", "--except", "vault"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parsing_update_interactive() {
+        let cli = Cli::parse_from(["oktofetch", "update", "--all", "--interactive"]);
+        assert!(matches!(
+            cli.command,
+            Commands::Update {
+                interactive: true,
+                ..
+            }
+        ));
+
+        assert!(Cli::try_parse_from(["oktofetch", "update", "k9s", "-i"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parsing_update_yes() {
+        let cli = Cli::parse_from(["oktofetch", "update", "k9s", "--yes"]);
+        assert!(cli.yes);
+
+        let cli = Cli::parse_from(["oktofetch", "update", "k9s"]);
+        assert!(!cli.yes);
+        assert!(!cli.non_interactive);
+
+        let cli = Cli::parse_from(["oktofetch", "-y", "--non-interactive", "remove", "k9s"]);
+        assert!(cli.yes);
+        assert!(cli.non_interactive);
+    }
+
+    #[test]
+    fn test_cli_parsing_update_limit_rate() {
+        let cli = Cli::parse_from(["oktofetch", "update", "--all", "--limit-rate", "500K"]);
+        match cli.command {
+            Commands::Update { limit_rate, .. } => assert_eq!(limit_rate, Some(500 * 1024)),
+            _ => panic!("Expected Update command"),
+        }
+
+        assert!(Cli::try_parse_from(["oktofetch", "update", "--limit-rate", "fast"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parsing_update_version() {
+        let cli = Cli::parse_from(["oktofetch", "update", "mytool", "--version", "1.2.3"]);
+        match cli.command {
+            Commands::Update { names, version, .. } => {
+                assert_eq!(names, vec!["mytool".to_string()]);
+                assert_eq!(version, Some("1.2.3".to_string()));
+            }
+            _ => panic!("Expected Update command"),
+        }
+
+        assert!(
+            Cli::try_parse_from(["oktofetch", "update", "--all", "--version", "1.2.3"]).is_err()
+        );
+    }
+
     #[test]
     fn test_cli_parsing_list() {
         let cli = Cli::parse_from(["oktofetch", "list"]);
-        matches!(cli.command, Commands::List);
+        assert!(matches!(cli.command, Commands::List { json: false, .. }));
+
+        let cli = Cli::parse_from(["oktofetch", "list", "--json"]);
+        assert!(matches!(cli.command, Commands::List { json: true, .. }));
+
+        let cli = Cli::parse_from([
+            "oktofetch",
+            "list",
+            "--sort",
+            "updated",
+            "--filter",
+            "installed",
+            "--filter",
+            "repo=sharkdp",
+        ]);
+        match cli.command {
+            Commands::List { sort, filter, .. } => {
+                assert_eq!(sort, Some(tool::ListSort::Updated));
+                assert_eq!(
+                    filter,
+                    vec![
+                        tool::ListFilter::Installed,
+                        tool::ListFilter::Repo("sharkdp".to_string())
+                    ]
+                );
+            }
+            _ => panic!("Expected List command"),
+        }
+        assert!(Cli::try_parse_from(["oktofetch", "list", "--filter", "pinned"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parsing_status() {
+        let cli = Cli::parse_from(["oktofetch", "status"]);
+        assert!(matches!(cli.command, Commands::Status { remote: false }));
+
+        let cli = Cli::parse_from(["oktofetch", "status", "--remote"]);
+        assert!(matches!(cli.command, Commands::Status { remote: true }));
+    }
+
+    #[test]
+    fn test_cli_parsing_clean() {
+        let cli = Cli::parse_from(["oktofetch", "clean"]);
+        assert!(matches!(
+            cli.command,
+            Commands::Clean {
+                downloads: false,
+                metadata: false,
+                backups: false,
+                auto: false
+            }
+        ));
+
+        let cli = Cli::parse_from(["oktofetch", "clean", "--downloads", "--backups"]);
+        assert!(matches!(
+            cli.command,
+            Commands::Clean {
+                downloads: true,
+                metadata: false,
+                backups: true,
+                auto: false
+            }
+        ));
+
+        let cli = Cli::parse_from(["oktofetch", "clean", "--auto"]);
+        assert!(matches!(cli.command, Commands::Clean { auto: true, .. }));
+        assert!(Cli::try_parse_from(["oktofetch", "clean", "--auto", "--metadata"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parsing_changelog() {
+        let cli = Cli::parse_from(["oktofetch", "changelog", "k9s"]);
+        assert!(matches!(cli.command, Commands::Changelog { name } if name == "k9s"));
     }
 
     #[test]
     fn test_cli_parsing_info() {
         let cli = Cli::parse_from(["oktofetch", "info", "mytool"]);
         match cli.command {
-            Commands::Info { name } => {
+            Commands::Info {
+                name,
+                json,
+                remote,
+                assets,
+            } => {
                 assert_eq!(name, "mytool");
+                assert!(!json);
+                assert!(!remote);
+                assert!(!assets);
+            }
+            _ => panic!("Expected Info command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_parsing_info_assets() {
+        let cli = Cli::parse_from(["oktofetch", "info", "k9s", "--assets", "--json"]);
+        match cli.command {
+            Commands::Info {
+                name, json, assets, ..
+            } => {
+                assert_eq!(name, "k9s");
+                assert!(json);
+                assert!(assets);
             }
             _ => panic!("Expected Info command"),
         }
     }
 
+    #[test]
+    fn test_cli_parsing_env() {
+        let cli = Cli::parse_from(["oktofetch", "env"]);
+        assert!(matches!(cli.command, Commands::Env { shell: None }));
+
+        let cli = Cli::parse_from(["oktofetch", "env", "--shell", "fish"]);
+        assert!(matches!(
+            cli.command,
+            Commands::Env {
+                shell: Some(shell::Shell::Fish)
+            }
+        ));
+    }
+
+    #[test]
+    fn test_cli_parsing_allow() {
+        let cli = Cli::parse_from(["oktofetch", "allow"]);
+        assert!(matches!(
+            cli.command,
+            Commands::Allow {
+                path: None,
+                revoke: false,
+                check: false
+            }
+        ));
+
+        let cli = Cli::parse_from(["oktofetch", "allow", "p/oktofetch.toml", "--revoke"]);
+        assert!(matches!(cli.command, Commands::Allow { revoke: true, .. }));
+        assert!(Cli::try_parse_from(["oktofetch", "allow", "--check", "--revoke"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parsing_hook() {
+        let cli = Cli::parse_from(["oktofetch", "hook", "direnv"]);
+        assert!(matches!(
+            cli.command,
+            Commands::Hook {
+                target: shell::HookTarget::Direnv
+            }
+        ));
+        assert!(Cli::try_parse_from(["oktofetch", "hook"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parsing_ui() {
+        let cli = Cli::parse_from(["oktofetch", "ui"]);
+        assert!(matches!(cli.command, Commands::Ui));
+    }
+
+    #[test]
+    fn test_cli_parsing_ratelimit() {
+        let cli = Cli::parse_from(["oktofetch", "ratelimit"]);
+        assert!(matches!(cli.command, Commands::Ratelimit));
+    }
+
     #[test]
     fn test_cli_parsing_config_show() {
         let cli = Cli::parse_from(["oktofetch", "config", "show"]);
@@ -411,9 +1890,10 @@ mod tests {
         let cli = Cli::parse_from(["oktofetch", "config", "set", "install_dir", "/custom/path"]);
         match cli.command {
             Commands::Config { command } => match command {
-                Some(ConfigCommands::Set { key, value }) => {
-                    assert_eq!(key, "install_dir");
-                    assert_eq!(value, "/custom/path");
+                Some(ConfigCommands::Set { key, value, list }) => {
+                    assert_eq!(key.as_deref(), Some("install_dir"));
+                    assert_eq!(value.as_deref(), Some("/custom/path"));
+                    assert!(!list);
                 }
                 _ => panic!("Expected Set subcommand"),
             },
@@ -421,6 +1901,135 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_cli_parsing_set_and_unset() {
+        let cli = Cli::parse_from(["oktofetch", "set", "k9s", "asset_pattern", "Linux_amd64"]);
+        match cli.command {
+            Commands::Set { name, key, value } => {
+                assert_eq!(name, "k9s");
+                assert_eq!(key, "asset_pattern");
+                assert_eq!(value, "Linux_amd64");
+            }
+            _ => panic!("Expected Set command"),
+        }
+
+        let cli = Cli::parse_from(["oktofetch", "unset", "k9s", "asset_pattern"]);
+        assert!(matches!(cli.command, Commands::Unset { .. }));
+    }
+
+    #[test]
+    fn test_needs_lock() {
+        let command = |args: &[&str]| {
+            Cli::parse_from(std::iter::once("oktofetch").chain(args.iter().copied())).command
+        };
+
+        assert!(needs_lock(&command(&["update", "--all"])));
+        assert!(needs_lock(&command(&["remove", "k9s"])));
+        assert!(needs_lock(&command(&["config", "set", "cache_ttl", "60"])));
+        assert!(!needs_lock(&command(&["config", "set", "--list"])));
+        assert!(!needs_lock(&command(&["config", "get", "install_dir"])));
+        assert!(!needs_lock(&command(&["list"])));
+        assert!(!needs_lock(&command(&["ui"])));
+    }
+
+    #[test]
+    fn test_cli_no_wait_flag() {
+        let cli = Cli::parse_from(["oktofetch", "update", "--all", "--no-wait"]);
+        assert!(cli.no_wait);
+        assert!(!Cli::parse_from(["oktofetch", "list"]).no_wait);
+    }
+
+    #[test]
+    fn test_cli_config_flag() {
+        let cli = Cli::parse_from(["oktofetch", "--config", "/tmp/team.toml", "list"]);
+        assert_eq!(cli.config, Some(PathBuf::from("/tmp/team.toml")));
+
+        let cli = Cli::parse_from(["oktofetch", "config", "show", "--config", "/tmp/c.yaml"]);
+        assert_eq!(cli.config, Some(PathBuf::from("/tmp/c.yaml")));
+        assert!(matches!(cli.command, Commands::Config { .. }));
+
+        let cli = Cli::parse_from(["oktofetch", "list"]);
+        assert!(cli.config.is_none());
+    }
+
+    #[test]
+    fn test_cli_debug_http_flags() {
+        let cli = Cli::parse_from(["oktofetch", "update", "k9s", "--debug-http"]);
+        assert!(cli.debug_http);
+        assert!(cli.debug_http_dir.is_none());
+
+        let cli = Cli::parse_from(["oktofetch", "--debug-http-dir", "/tmp/trace", "ratelimit"]);
+        assert_eq!(cli.debug_http_dir, Some(PathBuf::from("/tmp/trace")));
+        assert!(!Cli::parse_from(["oktofetch", "list"]).debug_http);
+    }
+
+    #[test]
+    fn test_cli_token_flag() {
+        let cli = Cli::parse_from(["oktofetch", "update", "--all", "--token", "ghp_x"]);
+        assert_eq!(cli.token.as_deref(), Some("ghp_x"));
+        assert!(Cli::parse_from(["oktofetch", "list"]).token.is_none());
+    }
+
+    #[test]
+    fn test_cli_parsing_auth() {
+        let cli = Cli::parse_from(["oktofetch", "auth", "login", "--token", "ghp_x"]);
+        assert!(matches!(
+            cli.command,
+            Commands::Auth {
+                command: AuthCommands::Login
+            }
+        ));
+        assert_eq!(cli.token.as_deref(), Some("ghp_x"));
+
+        let cli = Cli::parse_from(["oktofetch", "auth", "logout"]);
+        assert!(matches!(
+            cli.command,
+            Commands::Auth {
+                command: AuthCommands::Logout
+            }
+        ));
+        assert!(Cli::try_parse_from(["oktofetch", "auth"]).is_err());
+        assert!(!needs_lock(
+            &Cli::parse_from(["oktofetch", "auth", "status"]).command
+        ));
+    }
+
+    #[test]
+    fn test_cli_parsing_config_get() {
+        let cli = Cli::parse_from(["oktofetch", "config", "get", "install_dir"]);
+        match cli.command {
+            Commands::Config {
+                command: Some(ConfigCommands::Get { key }),
+            } => assert_eq!(key, "install_dir"),
+            _ => panic!("Expected Get subcommand"),
+        }
+    }
+
+    #[test]
+    fn test_cli_parsing_config_unset() {
+        let cli = Cli::parse_from(["oktofetch", "config", "unset", "cache_ttl"]);
+        match cli.command {
+            Commands::Config {
+                command: Some(ConfigCommands::Unset { key }),
+            } => assert_eq!(key, "cache_ttl"),
+            _ => panic!("Expected Unset subcommand"),
+        }
+    }
+
+    #[test]
+    fn test_cli_parsing_config_set_list() {
+        let cli = Cli::parse_from(["oktofetch", "config", "set", "--list"]);
+        assert!(matches!(
+            cli.command,
+            Commands::Config {
+                command: Some(ConfigCommands::Set { list: true, .. })
+            }
+        ));
+
+        assert!(Cli::try_parse_from(["oktofetch", "config", "set", "install_dir"]).is_err());
+        assert!(Cli::try_parse_from(["oktofetch", "config", "set", "--list", "a", "b"]).is_err());
+    }
+
     #[test]
     fn test_cli_verbose_flag() {
         let cli = Cli::parse_from(["oktofetch", "-v", "list"]);
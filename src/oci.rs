@@ -0,0 +1,604 @@
+//! Release source that pulls binaries out of OCI container images.
+//!
+//! Talks to the registry HTTP API directly (no docker daemon): the image
+//! manifest is resolved for linux/amd64, and the tool's binary is copied
+//! out of the topmost layer that contains it.
+
+use crate::error::{OktofetchError, Result};
+use crate::github::{Asset, ListOptions, Release};
+use crate::http::HttpContext;
+use crate::source::ReleaseSource;
+use async_trait::async_trait;
+use reqwest::StatusCode;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+/// Prefix of an image source, e.g. `oci://ghcr.io/org/tool:latest`.
+pub const OCI_PREFIX: &str = "oci://";
+
+/// Manifest types the source understands, single-platform and multi-platform.
+const MANIFEST_ACCEPT: &str = "application/vnd.oci.image.index.v1+json, \
+    application/vnd.oci.image.manifest.v1+json, \
+    application/vnd.docker.distribution.manifest.list.v2+json, \
+    application/vnd.docker.distribution.manifest.v2+json";
+
+/// Image label carrying the version of the packaged software.
+const VERSION_LABEL: &str = "org.opencontainers.image.version";
+
+/// An image reference split into registry, repository and tag or digest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageRef {
+    pub registry: String,
+    pub name: String,
+    pub reference: String,
+}
+
+impl ImageRef {
+    /// Parses `registry/name[:tag|@digest]`; the tag defaults to `latest`.
+    pub fn parse(spec: &str) -> Result<Self> {
+        let invalid = || {
+            OktofetchError::Other(format!(
+                "Invalid image reference '{}'. Expected registry/name[:tag]",
+                spec
+            ))
+        };
+
+        let (registry, rest) = spec.split_once('/').ok_or_else(invalid)?;
+        let (name, reference) = match rest.split_once('@') {
+            Some((name, digest)) => (name, digest),
+            None => match rest.rsplit_once(':') {
+                Some((name, tag)) if !tag.contains('/') => (name, tag),
+                _ => (rest, "latest"),
+            },
+        };
+        if registry.is_empty() || name.is_empty() || reference.is_empty() {
+            return Err(invalid());
+        }
+
+        Ok(Self {
+            registry: registry.to_string(),
+            name: name.to_string(),
+            reference: reference.to_string(),
+        })
+    }
+
+    fn with_reference(&self, reference: &str) -> Self {
+        Self {
+            reference: reference.to_string(),
+            ..self.clone()
+        }
+    }
+}
+
+impl std::fmt::Display for ImageRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let separator = if self.reference.contains(':') {
+            '@'
+        } else {
+            ':'
+        };
+        write!(
+            f,
+            "{}/{}{}{}",
+            self.registry, self.name, separator, self.reference
+        )
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Manifest {
+    /// Per-platform manifests, when this is an index
+    #[serde(default)]
+    manifests: Vec<Descriptor>,
+    config: Option<Descriptor>,
+    #[serde(default)]
+    layers: Vec<Descriptor>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Descriptor {
+    #[serde(default)]
+    media_type: String,
+    digest: String,
+    platform: Option<Platform>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Platform {
+    os: String,
+    architecture: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ImageConfig {
+    #[serde(default)]
+    config: ImageConfigDetails,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ImageConfigDetails {
+    #[serde(rename = "Labels", default)]
+    labels: Option<HashMap<String, String>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    #[serde(alias = "access_token")]
+    token: String,
+}
+
+/// Release source reading binaries from a container image.
+pub struct OciSource {
+    image: ImageRef,
+    binary: String,
+    http: HttpContext,
+    scheme: &'static str,
+    /// Bearer token from the registry's auth service, once obtained
+    token: Mutex<Option<String>>,
+}
+
+impl OciSource {
+    /// Source for `spec` (without the `oci://` prefix) that extracts `binary`.
+    /// Its requests may also be redirected to the image's registry.
+    pub fn new(spec: &str, binary: &str, http: &HttpContext) -> Result<Self> {
+        let image = ImageRef::parse(spec)?;
+
+        Ok(Self {
+            http: http.allowing_redirects_to(&image.registry),
+            image,
+            binary: binary.to_string(),
+            scheme: "https",
+            token: Mutex::new(None),
+        })
+    }
+
+    /// Talks to the registry over plain HTTP, e.g. a mock server in tests.
+    #[cfg(test)]
+    fn with_plain_http(mut self) -> Self {
+        self.scheme = "http";
+        self
+    }
+
+    /// GETs a registry API path, authenticating when the registry asks to.
+    async fn get(&self, image: &ImageRef, path: &str, accept: &str) -> Result<reqwest::Response> {
+        let url = format!(
+            "{}://{}/v2/{}/{}",
+            self.scheme, image.registry, image.name, path
+        );
+
+        let mut response = self.send(&url, accept).await?;
+        if response.status() == StatusCode::UNAUTHORIZED {
+            let challenge = response
+                .headers()
+                .get(reqwest::header::WWW_AUTHENTICATE)
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or_default()
+                .to_string();
+            let token = self.fetch_token(&challenge).await?;
+            *self.token.lock().unwrap() = Some(token);
+            response = self.send(&url, accept).await?;
+        }
+
+        match response.status() {
+            status if status.is_success() => Ok(response),
+            StatusCode::NOT_FOUND => Err(OktofetchError::ReleaseNotFound {
+                repo: format!("{}/{}", image.registry, image.name),
+                tag: image.reference.clone(),
+            }),
+            status => Err(OktofetchError::Other(format!(
+                "Registry {} returned {} for {}",
+                image.registry, status, url
+            ))),
+        }
+    }
+
+    async fn send(&self, url: &str, accept: &str) -> Result<reqwest::Response> {
+        let token = self.token.lock().unwrap().clone();
+        let response = self
+            .http
+            .send(|client| {
+                let request = client.get(url).header(reqwest::header::ACCEPT, accept);
+                match &token {
+                    Some(token) => request.bearer_auth(token),
+                    None => request,
+                }
+            })
+            .await?;
+        Ok(response)
+    }
+
+    /// Obtains an anonymous pull token from the service named in a
+    /// `WWW-Authenticate: Bearer ...` challenge.
+    async fn fetch_token(&self, challenge: &str) -> Result<String> {
+        let params = parse_challenge(challenge).ok_or_else(|| {
+            OktofetchError::Other(format!(
+                "Registry {} requires unsupported authentication: {}",
+                self.image.registry, challenge
+            ))
+        })?;
+        let realm = params.get("realm").cloned().unwrap_or_default();
+        let query: Vec<(&str, &str)> = ["service", "scope"]
+            .iter()
+            .filter_map(|key| params.get(*key).map(|value| (*key, value.as_str())))
+            .collect();
+
+        let response = self
+            .http
+            .send(|client| client.get(&realm).query(&query))
+            .await?;
+        if !response.status().is_success() {
+            return Err(OktofetchError::Other(format!(
+                "Registry token request to {} failed with status: {}",
+                realm,
+                response.status()
+            )));
+        }
+
+        let body: TokenResponse = response.json().await?;
+        Ok(body.token)
+    }
+
+    /// Fetches the linux/amd64 manifest for `image`, returning it with its digest.
+    async fn resolve(&self, image: &ImageRef) -> Result<(String, Manifest)> {
+        let (digest, manifest) = self.manifest(image).await?;
+        if manifest.manifests.is_empty() {
+            return Ok((digest, manifest));
+        }
+
+        let entry = manifest
+            .manifests
+            .iter()
+            .find(|m| {
+                m.platform
+                    .as_ref()
+                    .is_some_and(|p| p.os == "linux" && p.architecture == "amd64")
+            })
+            .ok_or_else(|| OktofetchError::NoSuitableRelease {
+                platform: "Linux".to_string(),
+                arch: "x86_64".to_string(),
+            })?;
+        self.manifest(&image.with_reference(&entry.digest)).await
+    }
+
+    async fn manifest(&self, image: &ImageRef) -> Result<(String, Manifest)> {
+        let path = format!("manifests/{}", image.reference);
+        let body = self
+            .get(image, &path, MANIFEST_ACCEPT)
+            .await?
+            .bytes()
+            .await?;
+        let digest = format!("sha256:{:x}", Sha256::digest(&body));
+        let manifest = serde_json::from_slice(&body).map_err(|e| {
+            OktofetchError::Other(format!("Invalid image manifest for {}: {}", image, e))
+        })?;
+        Ok((digest, manifest))
+    }
+
+    /// Builds a release for `image`: the version comes from the image's
+    /// version label, or is the manifest digest when the image has none.
+    async fn release(&self, image: &ImageRef) -> Result<Release> {
+        let (digest, manifest) = self.resolve(image).await?;
+
+        let mut version = None;
+        if let Some(config) = &manifest.config {
+            let path = format!("blobs/{}", config.digest);
+            let config: ImageConfig = self.get(image, &path, "*/*").await?.json().await?;
+            version = config
+                .config
+                .labels
+                .and_then(|mut labels| labels.remove(VERSION_LABEL));
+        }
+
+        Ok(Release {
+            id: 0,
+            tag_name: version.unwrap_or_else(|| digest.clone()),
+            name: image.to_string(),
+            assets: vec![Asset {
+                name: format!("{}-linux-amd64.tar", self.binary),
+                browser_download_url: format!("{}{}", OCI_PREFIX, image.with_reference(&digest)),
+                size: 0,
+                digest: None,
+            }],
+            prerelease: false,
+            draft: false,
+            body: None,
+            published_at: None,
+        })
+    }
+
+    /// Downloads a layer blob to a temp file next to `dest`, checking its digest.
+    async fn fetch_layer(
+        &self,
+        image: &ImageRef,
+        layer: &Descriptor,
+        dest: &Path,
+    ) -> Result<tempfile::NamedTempFile> {
+        let dir = dest.parent().unwrap_or(Path::new("."));
+        let mut file = tempfile::NamedTempFile::new_in(dir)?;
+        let mut hasher = Sha256::new();
+
+        let path = format!("blobs/{}", layer.digest);
+        let mut response = self.get(image, &path, "*/*").await?;
+        while let Some(chunk) = response.chunk().await? {
+            hasher.update(&chunk);
+            file.write_all(&chunk)?;
+        }
+        file.flush()?;
+
+        let actual = format!("sha256:{:x}", hasher.finalize());
+        if actual != layer.digest {
+            return Err(OktofetchError::DownloadFailed(format!(
+                "Layer {} of {} has digest {}",
+                layer.digest, image, actual
+            )));
+        }
+        Ok(file)
+    }
+}
+
+/// Parses the parameters of a `Bearer` authentication challenge.
+fn parse_challenge(header: &str) -> Option<HashMap<String, String>> {
+    let params = header.strip_prefix("Bearer ")?;
+    let params: HashMap<String, String> = params
+        .split(',')
+        .filter_map(|pair| {
+            let (key, value) = pair.trim().split_once('=')?;
+            Some((key.to_string(), value.trim_matches('"').to_string()))
+        })
+        .collect();
+    params.contains_key("realm").then_some(params)
+}
+
+/// Opens a layer tarball according to its media type.
+fn layer_reader(media_type: &str, file: std::fs::File) -> Result<Box<dyn Read>> {
+    if media_type.ends_with("gzip") {
+        Ok(Box::new(flate2::read::GzDecoder::new(file)))
+    } else if media_type.ends_with("zstd") {
+        Ok(Box::new(zstd::Decoder::new(file)?))
+    } else {
+        Ok(Box::new(file))
+    }
+}
+
+/// Copies the regular file named `binary` out of a layer into a single-entry
+/// tarball at `dest`. Returns whether the layer contained it.
+fn copy_binary(layer: impl Read, binary: &str, dest: &Path) -> Result<bool> {
+    let mut archive = tar::Archive::new(layer);
+    for entry in archive.entries()? {
+        let entry = entry?;
+        let path = entry.path()?;
+        if !entry.header().entry_type().is_file()
+            || path.file_name().and_then(|n| n.to_str()) != Some(binary)
+        {
+            continue;
+        }
+
+        let mut header = entry.header().clone();
+        let mut builder = tar::Builder::new(std::fs::File::create(dest)?);
+        builder.append_data(&mut header, binary, entry)?;
+        builder.into_inner()?.sync_all()?;
+        return Ok(true);
+    }
+    Ok(false)
+}
+
+#[async_trait]
+impl ReleaseSource for OciSource {
+    fn name(&self) -> &str {
+        "oci"
+    }
+
+    async fn latest_release(&self, _repo: &str) -> Result<Release> {
+        self.release(&self.image).await
+    }
+
+    async fn release_by_tag(&self, _repo: &str, tag: &str) -> Result<Release> {
+        self.release(&self.image.with_reference(tag)).await
+    }
+
+    async fn releases(&self, _repo: &str, _opts: &ListOptions) -> Result<Vec<Release>> {
+        Err(OktofetchError::Other(format!(
+            "Image {} has no release list; only its current tag can be installed",
+            self.image
+        )))
+    }
+
+    async fn download(&self, asset: &Asset, dest: &Path) -> Result<Option<String>> {
+        let spec = asset
+            .browser_download_url
+            .strip_prefix(OCI_PREFIX)
+            .unwrap_or(&asset.browser_download_url);
+        let image = ImageRef::parse(spec)?;
+        let (_, manifest) = self.resolve(&image).await?;
+
+        // Later layers override earlier ones, so search from the top
+        for layer in manifest.layers.iter().rev() {
+            let blob = self.fetch_layer(&image, layer, dest).await?;
+            let reader = layer_reader(&layer.media_type, blob.reopen()?)?;
+            if copy_binary(reader, &self.binary, dest)? {
+                return Ok(None);
+            }
+        }
+
+        Err(OktofetchError::BinaryNotFound(format!(
+            "{} not found in image {}",
+            self.binary, image
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[test]
+    fn test_image_ref_parse() {
+        let image = ImageRef::parse("ghcr.io/org/tool:v1.2").unwrap();
+        assert_eq!(image.registry, "ghcr.io");
+        assert_eq!(image.name, "org/tool");
+        assert_eq!(image.reference, "v1.2");
+        assert_eq!(image.to_string(), "ghcr.io/org/tool:v1.2");
+
+        let image = ImageRef::parse("localhost:5000/tool").unwrap();
+        assert_eq!(image.registry, "localhost:5000");
+        assert_eq!(image.reference, "latest");
+
+        let image = ImageRef::parse("ghcr.io/org/tool@sha256:abc").unwrap();
+        assert_eq!(image.reference, "sha256:abc");
+        assert_eq!(image.to_string(), "ghcr.io/org/tool@sha256:abc");
+
+        assert!(ImageRef::parse("tool").is_err());
+        assert!(ImageRef::parse("ghcr.io/").is_err());
+    }
+
+    #[test]
+    fn test_parse_challenge() {
+        let params = parse_challenge(
+            r#"Bearer realm="https://ghcr.io/token",service="ghcr.io",scope="repository:org/tool:pull""#,
+        )
+        .unwrap();
+        assert_eq!(params["realm"], "https://ghcr.io/token");
+        assert_eq!(params["service"], "ghcr.io");
+        assert_eq!(params["scope"], "repository:org/tool:pull");
+
+        assert!(parse_challenge(r#"Basic realm="registry""#).is_none());
+        assert!(parse_challenge("Bearer service=\"x\"").is_none());
+    }
+
+    fn layer(entries: &[(&str, &[u8])]) -> Vec<u8> {
+        use flate2::Compression;
+        use flate2::write::GzEncoder;
+
+        let mut tar = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+        for (name, content) in entries {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o755);
+            header.set_cksum();
+            tar.append_data(&mut header, name, *content).unwrap();
+        }
+        tar.into_inner().unwrap().finish().unwrap()
+    }
+
+    fn digest(bytes: &[u8]) -> String {
+        format!("sha256:{:x}", Sha256::digest(bytes))
+    }
+
+    async fn mount_blob(server: &MockServer, bytes: Vec<u8>) -> String {
+        let digest = digest(&bytes);
+        Mock::given(method("GET"))
+            .and(path(format!("/v2/org/tool/blobs/{}", digest)))
+            .and(header("Authorization", "Bearer secret"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(bytes))
+            .mount(server)
+            .await;
+        digest
+    }
+
+    #[tokio::test]
+    async fn test_oci_release_and_download() {
+        let server = MockServer::start().await;
+        let registry = server.address().to_string();
+
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(401).insert_header(
+                "WWW-Authenticate",
+                format!(
+                    r#"Bearer realm="{}/token",service="test",scope="repository:org/tool:pull""#,
+                    server.uri()
+                ),
+            ))
+            .with_priority(10)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(r#"{"token":"secret"}"#))
+            .mount(&server)
+            .await;
+
+        let base = mount_blob(&server, layer(&[("bin/tool", b"old"), ("bin/sh", b"sh")])).await;
+        let top = mount_blob(&server, layer(&[("usr/local/bin/tool", b"new")])).await;
+        let config = mount_blob(
+            &server,
+            br#"{"config":{"Labels":{"org.opencontainers.image.version":"1.2.3"}}}"#.to_vec(),
+        )
+        .await;
+
+        let manifest = serde_json::json!({
+            "mediaType": "application/vnd.oci.image.manifest.v1+json",
+            "config": { "mediaType": "application/vnd.oci.image.config.v1+json", "digest": config },
+            "layers": [
+                { "mediaType": "application/vnd.oci.image.layer.v1.tar+gzip", "digest": base },
+                { "mediaType": "application/vnd.oci.image.layer.v1.tar+gzip", "digest": top },
+            ],
+        })
+        .to_string();
+        let manifest_digest = digest(manifest.as_bytes());
+        let index = serde_json::json!({
+            "mediaType": "application/vnd.oci.image.index.v1+json",
+            "manifests": [
+                { "digest": "sha256:arm", "platform": { "os": "linux", "architecture": "arm64" } },
+                { "digest": manifest_digest, "platform": { "os": "linux", "architecture": "amd64" } },
+            ],
+        })
+        .to_string();
+        for (reference, body) in [("latest", index), (manifest_digest.as_str(), manifest)] {
+            Mock::given(method("GET"))
+                .and(path(format!("/v2/org/tool/manifests/{}", reference)))
+                .and(header("Authorization", "Bearer secret"))
+                .respond_with(ResponseTemplate::new(200).set_body_string(body))
+                .mount(&server)
+                .await;
+        }
+
+        let source = OciSource::new(
+            &format!("{}/org/tool", registry),
+            "tool",
+            &HttpContext::default(),
+        )
+        .unwrap()
+        .with_plain_http();
+        let release = source.latest_release("org/tool").await.unwrap();
+        assert_eq!(release.tag_name, "1.2.3");
+        assert_eq!(release.assets[0].name, "tool-linux-amd64.tar");
+        assert_eq!(
+            release.assets[0].browser_download_url,
+            format!("oci://{}/org/tool@{}", registry, manifest_digest)
+        );
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let dest = temp_dir.path().join("tool.tar");
+        source.download(&release.assets[0], &dest).await.unwrap();
+
+        let mut archive = tar::Archive::new(std::fs::File::open(&dest).unwrap());
+        let mut entries = archive.entries().unwrap();
+        let mut entry = entries.next().unwrap().unwrap();
+        assert_eq!(entry.path().unwrap().to_str(), Some("tool"));
+        let mut content = String::new();
+        entry.read_to_string(&mut content).unwrap();
+        assert_eq!(content, "new");
+        assert!(entries.next().is_none());
+
+        let missing = OciSource::new(
+            &format!("{}/org/tool", registry),
+            "absent",
+            &HttpContext::default(),
+        )
+        .unwrap()
+        .with_plain_http();
+        let err = missing
+            .download(&release.assets[0], &dest)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, OktofetchError::BinaryNotFound(_)));
+    }
+}
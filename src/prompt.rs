@@ -0,0 +1,266 @@
+//! Whether oktofetch may stop and ask the user something. Scripts pass
+//! `--yes` or `--non-interactive`, and CI runs are recognized by `$CI`, so
+//! no prompt waits for input that never comes.
+
+use crate::config::Config;
+use crate::error::{OktofetchError, Result};
+use std::collections::HashMap;
+use std::io::{BufRead, IsTerminal, Write};
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// Prompts go to the terminal
+    Ask,
+    /// `--yes`: every confirmation is answered yes
+    AssumeYes,
+    /// `--non-interactive` or `$CI`: confirmations are declined, and choices
+    /// that have no safe default fail
+    NonInteractive,
+}
+
+static MODE: OnceLock<Mode> = OnceLock::new();
+
+/// Programs named by an untrusted config, and whether the user agreed to
+/// run them.
+static TRUSTED: Mutex<Option<HashMap<String, bool>>> = Mutex::new(None);
+
+/// Fixes the mode for the rest of the process from the global flags and
+/// `$CI`. `--yes` wins over the other two.
+pub fn configure(yes: bool, non_interactive: bool) {
+    let _ = MODE.set(mode_for(
+        yes,
+        non_interactive,
+        std::env::var("CI").ok().as_deref(),
+    ));
+}
+
+fn mode_for(yes: bool, non_interactive: bool, ci: Option<&str>) -> Mode {
+    if yes {
+        Mode::AssumeYes
+    } else if non_interactive || ci.is_some_and(is_set) {
+        Mode::NonInteractive
+    } else {
+        Mode::Ask
+    }
+}
+
+/// Whether `$CI` holds something other than an empty or false value.
+fn is_set(value: &str) -> bool {
+    !matches!(
+        value.trim().to_lowercase().as_str(),
+        "" | "0" | "false" | "no" | "off"
+    )
+}
+
+pub fn mode() -> Mode {
+    MODE.get().copied().unwrap_or(Mode::Ask)
+}
+
+/// Whether confirmations are answered yes without asking.
+pub fn assume_yes() -> bool {
+    mode() == Mode::AssumeYes
+}
+
+/// Whether a prompt may be shown and its answer waited for.
+pub fn can_ask() -> bool {
+    mode() == Mode::Ask
+}
+
+/// Asks `question` before something that can't be undone, unless `force`
+/// or `--yes` was given. With prompts off it fails rather than guess.
+/// Otherwise, without a terminal on stdin it doesn't ask, as scripts have
+/// always run such commands unattended.
+pub fn confirm_destructive(question: &str, force: bool) -> Result<bool> {
+    let stdin = std::io::stdin();
+    let terminal = stdin.is_terminal();
+    confirm_destructive_in(mode(), terminal, &mut stdin.lock(), question, force)
+}
+
+fn confirm_destructive_in(
+    mode: Mode,
+    terminal: bool,
+    input: &mut impl BufRead,
+    question: &str,
+    force: bool,
+) -> Result<bool> {
+    match mode {
+        _ if force => Ok(true),
+        Mode::AssumeYes => Ok(true),
+        Mode::NonInteractive => Err(OktofetchError::InputRequired(format!(
+            "'{}' needs confirmation; pass --force",
+            question
+        ))),
+        Mode::Ask if !terminal => Ok(true),
+        Mode::Ask => confirm(input, question),
+    }
+}
+
+/// Makes sure the user wants to run `program`, the config's `what` (e.g. its
+/// asset selector), when the config isn't their own, see
+/// [`Config::check_trust`]. Each program is asked about once per run;
+/// without a terminal only `--yes` allows it.
+pub fn trust_command(what: &str, program: &str) -> Result<()> {
+    let mut trusted = TRUSTED.lock().unwrap_or_else(|e| e.into_inner());
+    let trusted = trusted.get_or_insert_with(HashMap::new);
+    let allowed = match (trusted.get(program), Config::untrusted_path()) {
+        (Some(&allowed), _) => allowed,
+        (None, None) => return Ok(()),
+        (None, Some(config_path)) => {
+            let stdin = std::io::stdin();
+            let terminal = stdin.is_terminal();
+            let question = trust_question(config_path, what, program);
+            let allowed = confirm_trust_in(mode(), terminal, &mut stdin.lock(), &question)?;
+            trusted.insert(program.to_string(), allowed);
+            allowed
+        }
+    };
+    if !allowed {
+        return Err(OktofetchError::Other(format!(
+            "Not running {} {}, which you declined",
+            what, program
+        )));
+    }
+    Ok(())
+}
+
+/// Records an answer for [`trust_command`] without asking.
+#[cfg(test)]
+pub fn decide_trust(program: &str, allowed: bool) {
+    let mut trusted = TRUSTED.lock().unwrap_or_else(|e| e.into_inner());
+    trusted
+        .get_or_insert_with(HashMap::new)
+        .insert(program.to_string(), allowed);
+}
+
+fn trust_question(config_path: &Path, what: &str, program: &str) -> String {
+    format!(
+        "{} sets {} `{}`. Run it?",
+        config_path.display(),
+        what,
+        program
+    )
+}
+
+fn confirm_trust_in(
+    mode: Mode,
+    terminal: bool,
+    input: &mut impl BufRead,
+    question: &str,
+) -> Result<bool> {
+    match mode {
+        Mode::AssumeYes => Ok(true),
+        Mode::Ask if terminal => confirm(input, question),
+        Mode::Ask => Err(OktofetchError::Other(format!(
+            "'{}' needs confirmation, but there is no terminal to ask on; pass --yes",
+            question
+        ))),
+        Mode::NonInteractive => Err(OktofetchError::InputRequired(format!(
+            "'{}' needs confirmation; pass --yes",
+            question
+        ))),
+    }
+}
+
+/// Asks a yes/no question. Anything but an explicit yes, including end of
+/// input, declines.
+pub fn confirm(input: &mut impl BufRead, question: &str) -> Result<bool> {
+    print!("{} [y/N]: ", question);
+    std::io::stdout().flush()?;
+
+    let mut line = String::new();
+    input.read_line(&mut line)?;
+    Ok(matches!(line.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mode_for() {
+        assert_eq!(mode_for(false, false, None), Mode::Ask);
+        assert_eq!(mode_for(false, true, None), Mode::NonInteractive);
+        assert_eq!(mode_for(true, true, Some("true")), Mode::AssumeYes);
+        assert_eq!(mode_for(false, false, Some("true")), Mode::NonInteractive);
+        assert_eq!(mode_for(false, false, Some("1")), Mode::NonInteractive);
+        assert_eq!(mode_for(false, false, Some("false")), Mode::Ask);
+        assert_eq!(mode_for(false, false, Some("")), Mode::Ask);
+    }
+
+    #[test]
+    fn test_confirm() {
+        use std::io::Cursor;
+
+        assert!(confirm(&mut Cursor::new("y\n"), "Remove k9s?").unwrap());
+        assert!(confirm(&mut Cursor::new(" YES \n"), "Remove k9s?").unwrap());
+        assert!(!confirm(&mut Cursor::new("\n"), "Remove k9s?").unwrap());
+        assert!(!confirm(&mut Cursor::new("nope\n"), "Remove k9s?").unwrap());
+        assert!(!confirm(&mut Cursor::new(""), "Remove k9s?").unwrap());
+    }
+
+    #[test]
+    fn test_confirm_trust_in() {
+        use std::io::Cursor;
+
+        let question = trust_question(
+            Path::new("/srv/project/oktofetch.toml"),
+            "asset selector",
+            "./pick.sh",
+        );
+        assert_eq!(
+            question,
+            "/srv/project/oktofetch.toml sets asset selector `./pick.sh`. Run it?"
+        );
+        let ask = |mode, terminal, answer| {
+            confirm_trust_in(mode, terminal, &mut Cursor::new(answer), &question)
+        };
+        assert!(ask(Mode::AssumeYes, false, "").unwrap());
+        assert!(ask(Mode::Ask, true, "y\n").unwrap());
+        assert!(!ask(Mode::Ask, true, "\n").unwrap());
+        // Without a terminal nobody can agree, and declining would hide why
+        assert!(ask(Mode::Ask, false, "y\n").is_err());
+        assert!(matches!(
+            ask(Mode::NonInteractive, true, "y\n"),
+            Err(OktofetchError::InputRequired(_))
+        ));
+    }
+
+    #[test]
+    fn test_trust_command_remembers_answers() {
+        decide_trust("/tmp/oktofetch-test/declined", false);
+        decide_trust("/tmp/oktofetch-test/allowed", true);
+        assert!(trust_command("asset selector", "/tmp/oktofetch-test/declined").is_err());
+        assert!(trust_command("asset selector", "/tmp/oktofetch-test/allowed").is_ok());
+    }
+
+    #[test]
+    fn test_confirm_destructive_in() {
+        use std::io::Cursor;
+
+        let ask = |mode, terminal, answer, force| {
+            confirm_destructive_in(
+                mode,
+                terminal,
+                &mut Cursor::new(answer),
+                "Remove k9s?",
+                force,
+            )
+        };
+        assert!(ask(Mode::Ask, true, "y\n", false).unwrap());
+        assert!(!ask(Mode::Ask, true, "n\n", false).unwrap());
+        // Scripts without a terminal keep working
+        assert!(ask(Mode::Ask, false, "", false).unwrap());
+        assert!(ask(Mode::AssumeYes, true, "", false).unwrap());
+
+        // Prompts off: only --force goes ahead, terminal or not
+        for terminal in [true, false] {
+            assert!(matches!(
+                ask(Mode::NonInteractive, terminal, "y\n", false),
+                Err(OktofetchError::InputRequired(_))
+            ));
+            assert!(ask(Mode::NonInteractive, terminal, "", true).unwrap());
+        }
+    }
+}
@@ -0,0 +1,346 @@
+use crate::binary;
+use crate::config;
+use crate::error::{OktofetchError, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// File marking a project with its own tool list.
+pub const PROJECT_FILE: &str = "oktofetch.toml";
+
+/// Shells `oktofetch env` can emit setup code for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl Shell {
+    /// Guesses the shell from `$SHELL`, falling back to bash.
+    pub fn detect() -> Self {
+        let shell = std::env::var("SHELL").unwrap_or_default();
+        match Path::new(&shell).file_name().and_then(|n| n.to_str()) {
+            Some("zsh") => Self::Zsh,
+            Some("fish") => Self::Fish,
+            _ => Self::Bash,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Bash => "bash",
+            Self::Zsh => "zsh",
+            Self::Fish => "fish",
+        }
+    }
+}
+
+/// Shell code that puts `install_dir` on PATH (once, even if evaluated
+/// repeatedly) and sources `completions` when given.
+pub fn setup_script(shell: Shell, install_dir: &Path, completions: Option<&Path>) -> String {
+    let dir = install_dir.display();
+    let mut script = match shell {
+        Shell::Bash | Shell::Zsh => format!(
+            "case \":$PATH:\" in\n  *\":{dir}:\"*) ;;\n  *) export PATH=\"{dir}:$PATH\" ;;\nesac\n"
+        ),
+        Shell::Fish => {
+            format!("if not contains -- \"{dir}\" $PATH\n    set -gx PATH \"{dir}\" $PATH\nend\n")
+        }
+    };
+
+    if let Some(path) = completions {
+        script.push_str(&format!("source \"{}\"\n", path.display()));
+    }
+
+    script
+}
+
+/// What `oktofetch hook` emits code for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum HookTarget {
+    Bash,
+    Zsh,
+    Fish,
+    /// A `.envrc` snippet, evaluated by direnv on entering the directory
+    Direnv,
+}
+
+/// Nearest `oktofetch.toml` in `dir` or one of its parents.
+pub fn find_project_file(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .map(|dir| dir.join(PROJECT_FILE))
+        .find(|path| path.is_file())
+}
+
+/// POSIX function looking for the nearest project file whenever the prompt is
+/// drawn. A project file is only used once `oktofetch allow` has recorded it
+/// as it is now. Then it points `OKTOFETCH_CONFIG` at the file and puts the
+/// project's install directory on PATH, saving both beforehand; on leaving
+/// it puts back what was there. Nothing runs while the project stays the same.
+const POSIX_HOOK: &str = r#"_oktofetch_hook() {
+  local dir="$PWD" file="" bin
+  while [ -n "$dir" ]; do
+    if [ -f "$dir/oktofetch.toml" ]; then file="$dir/oktofetch.toml"; break; fi
+    dir="${dir%/*}"
+  done
+  [ "$file" = "${_OKTOFETCH_PROJECT_FILE-}" ] && return
+  if [ -n "${_OKTOFETCH_PROJECT_FILE-}" ]; then
+    if [ -n "${_OKTOFETCH_SAVED_CONFIG+x}" ]; then
+      export OKTOFETCH_CONFIG="$_OKTOFETCH_SAVED_CONFIG"
+    else
+      unset OKTOFETCH_CONFIG
+    fi
+    PATH="$_OKTOFETCH_SAVED_PATH"
+    unset _OKTOFETCH_SAVED_CONFIG _OKTOFETCH_SAVED_PATH
+    _OKTOFETCH_PROJECT_FILE=""
+  fi
+  [ -z "$file" ] && return
+  if ! command oktofetch allow --check "$file" >/dev/null 2>&1; then
+    [ "$file" != "${_OKTOFETCH_NOTED_FILE-}" ] &&
+      echo "oktofetch: $file is not allowed (or changed since); run 'oktofetch allow' to use it" >&2
+    _OKTOFETCH_NOTED_FILE="$file"
+    return
+  fi
+  _OKTOFETCH_PROJECT_FILE="$file"
+  _OKTOFETCH_SAVED_PATH="$PATH"
+  [ -n "${OKTOFETCH_CONFIG+x}" ] && _OKTOFETCH_SAVED_CONFIG="$OKTOFETCH_CONFIG"
+  export OKTOFETCH_CONFIG="$file"
+  bin="$(command oktofetch config get install_dir 2>/dev/null)"
+  case ":$PATH:" in
+    *":$bin:"*) ;;
+    *) [ -n "$bin" ] && PATH="$bin:$PATH" ;;
+  esac
+}
+"#;
+
+const FISH_HOOK: &str = r#"function _oktofetch_hook --on-event fish_prompt
+    set -l dir $PWD
+    set -l file ""
+    while test -n "$dir"
+        if test -f "$dir/oktofetch.toml"
+            set file "$dir/oktofetch.toml"
+            break
+        end
+        set dir (string replace -r '/[^/]*$' '' -- $dir)
+    end
+    test "$file" = "$_oktofetch_project_file"; and return
+    if test -n "$_oktofetch_project_file"
+        if set -q _oktofetch_saved_config
+            set -gx OKTOFETCH_CONFIG $_oktofetch_saved_config
+            set -e _oktofetch_saved_config
+        else
+            set -e OKTOFETCH_CONFIG
+        end
+        set -gx PATH $_oktofetch_saved_path
+        set -g _oktofetch_project_file ""
+    end
+    test -z "$file"; and return
+    if not command oktofetch allow --check $file >/dev/null 2>&1
+        if test "$file" != "$_oktofetch_noted_file"
+            echo "oktofetch: $file is not allowed (or changed since); run 'oktofetch allow' to use it" >&2
+        end
+        set -g _oktofetch_noted_file $file
+        return
+    end
+    set -g _oktofetch_project_file $file
+    set -g _oktofetch_saved_path $PATH
+    if set -q OKTOFETCH_CONFIG
+        set -g _oktofetch_saved_config $OKTOFETCH_CONFIG
+    end
+    set -gx OKTOFETCH_CONFIG $file
+    set -l bin (command oktofetch config get install_dir 2>/dev/null)
+    if test -n "$bin"; and not contains -- $bin $PATH
+        set -gx PATH $bin $PATH
+    end
+end
+_oktofetch_hook
+"#;
+
+/// Shell code switching to a project's tool list and install directory when
+/// entering a directory with an `oktofetch.toml`, and back when leaving it.
+pub fn hook_script(shell: Shell) -> String {
+    match shell {
+        Shell::Bash => format!(
+            "{POSIX_HOOK}case \";${{PROMPT_COMMAND-}};\" in\n  *\";_oktofetch_hook;\"*) ;;\n  \
+             *) PROMPT_COMMAND=\"_oktofetch_hook${{PROMPT_COMMAND:+;$PROMPT_COMMAND}}\" ;;\nesac\n"
+        ),
+        Shell::Zsh => {
+            format!("{POSIX_HOOK}autoload -Uz add-zsh-hook\nadd-zsh-hook precmd _oktofetch_hook\n")
+        }
+        Shell::Fish => FISH_HOOK.to_string(),
+    }
+}
+
+/// Project files the user allowed the hook to activate, each with the
+/// SHA256 it had then, so a file that changed has to be allowed again.
+/// Kept in the data directory, out of reach of any project.
+#[derive(Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Allowlist {
+    #[serde(default)]
+    files: BTreeMap<PathBuf, String>,
+}
+
+impl Allowlist {
+    /// Reads the allowlist, treating a missing file as empty.
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(path)?;
+        serde_json::from_str(&content)
+            .map_err(|e| OktofetchError::ConfigError(e.to_string(), path.to_path_buf()))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let content = serde_json::to_string_pretty(self).map_err(std::io::Error::from)?;
+        config::write_atomic(path, (content + "\n").as_bytes())?;
+        Ok(())
+    }
+
+    /// Records `file` as it is now, returning its canonical path.
+    pub fn allow(&mut self, file: &Path) -> Result<PathBuf> {
+        let file = fs::canonicalize(file)?;
+        let sha256 = binary::sha256_file(&file)?;
+        self.files.insert(file.clone(), sha256);
+        Ok(file)
+    }
+
+    /// Forgets `file`. Returns false when it wasn't allowed.
+    pub fn revoke(&mut self, file: &Path) -> bool {
+        let file = fs::canonicalize(file).unwrap_or_else(|_| file.to_path_buf());
+        self.files.remove(&file).is_some()
+    }
+
+    /// Whether `file` was allowed and hasn't changed since.
+    pub fn is_allowed(&self, file: &Path) -> bool {
+        let Ok(file) = fs::canonicalize(file) else {
+            return false;
+        };
+        self.files
+            .get(&file)
+            .is_some_and(|sha256| binary::sha256_file(&file).is_ok_and(|actual| actual == *sha256))
+    }
+}
+
+/// `.envrc` lines using the project file at `config` and its install
+/// directory `install_dir`; direnv restores the environment on leaving.
+pub fn direnv_script(config: &Path, install_dir: &Path) -> String {
+    format!(
+        "export OKTOFETCH_CONFIG=\"{}\"\nPATH_add \"{}\"\n",
+        config.display(),
+        install_dir.display()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_setup_script_posix() {
+        let script = setup_script(Shell::Bash, Path::new("/home/me/.local/bin"), None);
+        assert!(script.contains("*\":/home/me/.local/bin:\"*) ;;"));
+        assert!(script.contains("export PATH=\"/home/me/.local/bin:$PATH\""));
+        assert!(!script.contains("source"));
+    }
+
+    #[test]
+    fn test_setup_script_fish_with_completions() {
+        let script = setup_script(
+            Shell::Fish,
+            Path::new("/opt/bin"),
+            Some(Path::new("/data/completions/oktofetch.fish")),
+        );
+        assert!(script.contains("set -gx PATH \"/opt/bin\" $PATH"));
+        assert!(script.ends_with("source \"/data/completions/oktofetch.fish\"\n"));
+    }
+
+    #[test]
+    fn test_find_project_file() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let nested = temp_dir.path().join("src/deep");
+        std::fs::create_dir_all(&nested).unwrap();
+        assert_eq!(find_project_file(&nested), None);
+
+        let file = temp_dir.path().join(PROJECT_FILE);
+        std::fs::write(&file, "").unwrap();
+        assert_eq!(find_project_file(&nested), Some(file));
+    }
+
+    #[test]
+    fn test_hook_scripts() {
+        let bash = hook_script(Shell::Bash);
+        assert!(bash.starts_with("_oktofetch_hook() {"));
+        assert!(
+            bash.contains("PROMPT_COMMAND=\"_oktofetch_hook${PROMPT_COMMAND:+;$PROMPT_COMMAND}\"")
+        );
+
+        let zsh = hook_script(Shell::Zsh);
+        assert!(zsh.ends_with("add-zsh-hook precmd _oktofetch_hook\n"));
+
+        let fish = hook_script(Shell::Fish);
+        assert!(fish.contains("--on-event fish_prompt"));
+
+        // Only allowed files are used, and the user's own values come back
+        for script in [&bash, &fish] {
+            assert!(script.contains("oktofetch allow --check"));
+        }
+        assert!(bash.contains("export OKTOFETCH_CONFIG=\"$_OKTOFETCH_SAVED_CONFIG\""));
+        assert!(bash.contains("PATH=\"$_OKTOFETCH_SAVED_PATH\""));
+        assert!(fish.contains("set -gx OKTOFETCH_CONFIG $_oktofetch_saved_config"));
+
+        assert_eq!(
+            direnv_script(
+                Path::new("/p/oktofetch.toml"),
+                Path::new("/p/.oktofetch/bin")
+            ),
+            "export OKTOFETCH_CONFIG=\"/p/oktofetch.toml\"\nPATH_add \"/p/.oktofetch/bin\"\n"
+        );
+    }
+
+    #[test]
+    fn test_allowlist() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let list_path = temp_dir.path().join("data/allowed.json");
+        let file = temp_dir.path().join(PROJECT_FILE);
+        fs::write(&file, "[settings]\ninstall_dir = \".oktofetch/bin\"\n").unwrap();
+
+        let mut list = Allowlist::load(&list_path).unwrap();
+        assert!(!list.is_allowed(&file));
+        list.allow(&file).unwrap();
+        list.save(&list_path).unwrap();
+
+        let mut list = Allowlist::load(&list_path).unwrap();
+        assert!(list.is_allowed(&file));
+        assert!(list.is_allowed(&temp_dir.path().join(".").join(PROJECT_FILE)));
+
+        // Any change has to be allowed again
+        fs::write(&file, "[settings]\ninstall_dir = \"/usr/bin\"\n").unwrap();
+        assert!(!list.is_allowed(&file));
+        list.allow(&file).unwrap();
+        assert!(list.is_allowed(&file));
+
+        assert!(list.revoke(&file));
+        assert!(!list.revoke(&file));
+        assert!(!list.is_allowed(&file));
+        assert!(!list.is_allowed(&temp_dir.path().join("missing.toml")));
+    }
+
+    #[test]
+    fn test_detect_shell() {
+        temp_env::with_var("SHELL", Some("/usr/bin/zsh"), || {
+            assert_eq!(Shell::detect(), Shell::Zsh);
+        });
+        temp_env::with_var("SHELL", Some("/usr/local/bin/fish"), || {
+            assert_eq!(Shell::detect(), Shell::Fish);
+        });
+        temp_env::with_var("SHELL", None::<&str>, || {
+            assert_eq!(Shell::detect(), Shell::Bash);
+        });
+    }
+}
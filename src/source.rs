@@ -0,0 +1,511 @@
+use crate::cache::ResponseCache;
+use crate::config::{Channel, Config, Settings, Tool};
+use crate::error::{OktofetchError, Result};
+use crate::github::{Asset, GithubClient, ListOptions, Release};
+use crate::http::HttpContext;
+use crate::oci::{OCI_PREFIX, OciSource};
+use crate::{prompt, version};
+use async_trait::async_trait;
+use std::path::{Path, PathBuf};
+
+/// Exit status a plugin uses to report that a requested release doesn't exist.
+const NOT_FOUND_STATUS: i32 = 2;
+
+/// Prefix selecting an external release source program, e.g. `command:/usr/local/bin/my-forge`.
+pub const COMMAND_PREFIX: &str = "command:";
+
+/// A place releases can be fetched from.
+///
+/// Built-in sources are registered in [`for_tool`]. Anything else can be
+/// plugged in as an external program via `source = "command:<path>"`, see
+/// [`CommandSource`].
+#[async_trait]
+pub trait ReleaseSource: Send + Sync {
+    /// Short identifier used in messages
+    fn name(&self) -> &str;
+
+    async fn latest_release(&self, repo: &str) -> Result<Release>;
+
+    async fn release_by_tag(&self, repo: &str, tag: &str) -> Result<Release>;
+
+    /// Releases matching `opts`, newest first
+    async fn releases(&self, repo: &str, opts: &ListOptions) -> Result<Vec<Release>>;
+
+    /// Fills in assets a release listing may have truncated.
+    async fn complete_assets(&self, _repo: &str, release: Release) -> Result<Release> {
+        Ok(release)
+    }
+
+    /// Writes `asset` to `dest`. Returns its SHA256 if the source hashed the
+    /// bytes as they were written, which saves reading the file again to
+    /// verify it.
+    async fn download(&self, asset: &Asset, dest: &Path) -> Result<Option<String>>;
+
+    /// Newest tag according to a cheap, unmetered channel, if the source has
+    /// one. Used to skip the full lookup when nothing new was published.
+    async fn latest_tag_hint(&self, _repo: &str) -> Option<String> {
+        None
+    }
+
+    /// Whether the repository is archived, for sources that can tell. `None`
+    /// if unknown or the lookup failed.
+    async fn archived(&self, _repo: &str) -> Option<bool> {
+        None
+    }
+
+    /// Contents of a file in the repository at `reference`, for sources
+    /// that can read repository files. `None` if there is no such file.
+    async fn repo_file(
+        &self,
+        _repo: &str,
+        _reference: &str,
+        _path: &str,
+    ) -> Result<Option<String>> {
+        Ok(None)
+    }
+
+    /// Enables extra diagnostics, such as where downloads were served from.
+    fn set_verbose(&mut self, _verbose: bool) {}
+}
+
+#[async_trait]
+impl ReleaseSource for GithubClient {
+    fn name(&self) -> &str {
+        "github"
+    }
+
+    async fn latest_release(&self, repo: &str) -> Result<Release> {
+        self.get_latest_release(repo).await
+    }
+
+    async fn release_by_tag(&self, repo: &str, tag: &str) -> Result<Release> {
+        self.get_release_by_tag(repo, tag).await
+    }
+
+    async fn releases(&self, repo: &str, opts: &ListOptions) -> Result<Vec<Release>> {
+        self.list_releases(repo, opts).await
+    }
+
+    async fn complete_assets(&self, repo: &str, release: Release) -> Result<Release> {
+        GithubClient::complete_assets(self, repo, release).await
+    }
+
+    async fn download(&self, asset: &Asset, dest: &Path) -> Result<Option<String>> {
+        self.download_release_asset(asset, dest).await
+    }
+
+    /// Unauthenticated clients read the releases feed to save rate limit.
+    async fn latest_tag_hint(&self, repo: &str) -> Option<String> {
+        if self.has_token() {
+            return None;
+        }
+        self.feed_latest_tag(repo).await.ok().flatten()
+    }
+
+    async fn archived(&self, repo: &str) -> Option<bool> {
+        let repository = self.get_repository(repo).await.ok()?;
+        Some(repository.archived)
+    }
+
+    async fn repo_file(&self, repo: &str, reference: &str, path: &str) -> Result<Option<String>> {
+        self.get_repo_file(repo, reference, path).await
+    }
+
+    fn set_verbose(&mut self, verbose: bool) {
+        GithubClient::set_verbose(self, verbose);
+    }
+}
+
+/// Release source backed by an external program.
+///
+/// The program is invoked as:
+/// - `<program> latest <repo>`: print the latest release as JSON
+/// - `<program> tag <repo> <tag>`: print the release with that tag as JSON,
+///   or exit with status 2 if there is no such release
+/// - `<program> list <repo>`: print a JSON array of releases, newest first
+/// - `<program> download <url> <dest>`: write the asset at `url` to `dest`
+///
+/// Releases use the same JSON shape as the GitHub releases API
+/// (`tag_name`, `name`, `assets[].name/browser_download_url/size`).
+pub struct CommandSource {
+    program: PathBuf,
+}
+
+impl CommandSource {
+    pub fn new(program: impl Into<PathBuf>) -> Self {
+        Self {
+            program: program.into(),
+        }
+    }
+
+    async fn run(&self, args: &[&str]) -> Result<Vec<u8>> {
+        let output = self.output(args).await?;
+        self.stdout(args[0], output)
+    }
+
+    fn stdout(&self, command: &str, output: std::process::Output) -> Result<Vec<u8>> {
+        if !output.status.success() {
+            return Err(OktofetchError::Other(format!(
+                "Source plugin {} {} failed: {}",
+                self.program.display(),
+                command,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+
+        Ok(output.stdout)
+    }
+
+    async fn output(&self, args: &[&str]) -> Result<std::process::Output> {
+        tokio::process::Command::new(&self.program)
+            .args(args)
+            .output()
+            .await
+            .map_err(|e| {
+                OktofetchError::Other(format!(
+                    "Failed to run source plugin {}: {}",
+                    self.program.display(),
+                    e
+                ))
+            })
+    }
+
+    fn parse<T: serde::de::DeserializeOwned>(&self, stdout: &[u8]) -> Result<T> {
+        serde_json::from_slice(stdout).map_err(|e| {
+            OktofetchError::Other(format!(
+                "Invalid output from source plugin {}: {}",
+                self.program.display(),
+                e
+            ))
+        })
+    }
+}
+
+#[async_trait]
+impl ReleaseSource for CommandSource {
+    fn name(&self) -> &str {
+        "command"
+    }
+
+    async fn latest_release(&self, repo: &str) -> Result<Release> {
+        let stdout = self.run(&["latest", repo]).await?;
+        self.parse(&stdout)
+    }
+
+    async fn release_by_tag(&self, repo: &str, tag: &str) -> Result<Release> {
+        let output = self.output(&["tag", repo, tag]).await?;
+        if output.status.code() == Some(NOT_FOUND_STATUS) {
+            return Err(OktofetchError::ReleaseNotFound {
+                repo: repo.to_string(),
+                tag: tag.to_string(),
+            });
+        }
+
+        let stdout = self.stdout("tag", output)?;
+        self.parse(&stdout)
+    }
+
+    async fn releases(&self, repo: &str, opts: &ListOptions) -> Result<Vec<Release>> {
+        let stdout = self.run(&["list", repo]).await?;
+        let releases: Vec<Release> = self.parse(&stdout)?;
+        Ok(opts.apply(releases))
+    }
+
+    async fn download(&self, asset: &Asset, dest: &Path) -> Result<Option<String>> {
+        let dest = dest.to_string_lossy();
+        self.run(&["download", &asset.browser_download_url, &dest])
+            .await?;
+        Ok(None)
+    }
+}
+
+/// Finds the newest release allowed by the channel. `Latest` uses the
+/// dedicated endpoint; the others scan the release list.
+pub async fn newest_release(
+    source: &dyn ReleaseSource,
+    repo: &str,
+    channel: Channel,
+) -> Result<Release> {
+    if channel == Channel::Latest {
+        return source.latest_release(repo).await;
+    }
+
+    let opts = ListOptions {
+        limit: Some(1),
+        ..channel.list_options()
+    };
+    let release = source
+        .releases(repo, &opts)
+        .await?
+        .into_iter()
+        .next()
+        .ok_or_else(|| {
+            OktofetchError::GithubApi(format!("No eligible releases found in {}", repo))
+        })?;
+
+    source.complete_assets(repo, release).await
+}
+
+/// Finds the newest release `tool` may update to: the newest on its channel
+/// or, with a `version_req`, the highest version on the channel meeting it.
+pub async fn newest_release_for(source: &dyn ReleaseSource, tool: &Tool) -> Result<Release> {
+    let repo = tool.release_repo();
+    let channel = tool.channel.unwrap_or_default();
+    let Some(requirement) = &tool.version_req else {
+        return newest_release(source, repo, channel).await;
+    };
+
+    let release = source
+        .releases(repo, &channel.list_options())
+        .await?
+        .into_iter()
+        .filter(|r| tool.accepts_version(&tool.version_from_tag(&r.tag_name)))
+        .max_by(|a, b| version::compare(&a.tag_name, &b.tag_name))
+        .ok_or_else(|| {
+            OktofetchError::GithubApi(format!(
+                "No eligible release in {} matches {}",
+                repo, requirement
+            ))
+        })?;
+
+    source.complete_assets(repo, release).await
+}
+
+/// Builds a GitHub client on `http` from the download and caching settings.
+pub fn github_client(settings: &Settings, http: &HttpContext) -> Result<GithubClient> {
+    let mut client = GithubClient::new(http);
+    if let Some(rate) = settings.max_download_rate.filter(|&r| r > 0) {
+        client = client.with_download_rate(rate);
+    }
+    if let Some(connections) = settings.download_connections {
+        client = client.with_connections(connections);
+    }
+
+    match settings.cache_ttl() {
+        Some(ttl) => {
+            let dir = Config::cache_dir()?.join("http");
+            Ok(client.with_cache(ResponseCache::new(dir, ttl)))
+        }
+        None => Ok(client),
+    }
+}
+
+/// Whether the source's tag hint shows the tool's installed version is still
+/// the newest, so no release lookup is needed.
+pub async fn unchanged_per_hint(source: &dyn ReleaseSource, tool: &Tool) -> bool {
+    let Some(installed) = &tool.version else {
+        return false;
+    };
+
+    match source.latest_tag_hint(tool.release_repo()).await {
+        Some(tag) => &tool.version_from_tag(&tag) == installed && tool.accepts_version(installed),
+        None => false,
+    }
+}
+
+/// Resolves the release source configured for a tool, making its requests
+/// on `http`.
+pub fn for_tool(
+    tool: &Tool,
+    settings: &Settings,
+    http: &HttpContext,
+) -> Result<Box<dyn ReleaseSource>> {
+    match tool.source.as_deref() {
+        None | Some("github") => Ok(Box::new(github_client(settings, http)?)),
+        Some(spec) if spec.starts_with(COMMAND_PREFIX) => {
+            let program = &spec[COMMAND_PREFIX.len()..];
+            prompt::trust_command("release source", program)?;
+            Ok(Box::new(CommandSource::new(program)))
+        }
+        Some(spec) if spec.starts_with(OCI_PREFIX) => Ok(Box::new(OciSource::new(
+            &spec[OCI_PREFIX.len()..],
+            tool.binary_file_name(),
+            http,
+        )?)),
+        Some(other) => Err(OktofetchError::Other(format!(
+            "Unknown release source '{}' for {}. Use 'github', 'command:<path>' or 'oci://<image>'",
+            other, tool.name
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+    use tempfile::TempDir;
+
+    fn tool_with_source(source: Option<&str>) -> Tool {
+        Tool {
+            name: "mytool".to_string(),
+            repo: "owner/repo".to_string(),
+            source: source.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    fn write_plugin(dir: &Path) -> PathBuf {
+        let path = dir.join("plugin");
+        fs::write(
+            &path,
+            r#"#!/bin/sh
+case "$1" in
+  latest) echo '{"tag_name":"v1.0.0","name":"One","assets":[{"name":"a.tar.gz","browser_download_url":"mem://a","size":3}]}' ;;
+  tag) echo "{\"tag_name\":\"$3\",\"name\":\"$3\",\"assets\":[]}" ;;
+  list) echo '[{"tag_name":"v1.1.0-rc1","name":"RC","assets":[],"prerelease":true},{"tag_name":"v1.0.0","name":"One","assets":[]}]' ;;
+  download) printf abc > "$3" ;;
+  *) echo "bad command" >&2; exit 1 ;;
+esac
+"#,
+        )
+        .unwrap();
+        let mut perms = fs::metadata(&path).unwrap().permissions();
+        perms.set_mode(0o755);
+        fs::set_permissions(&path, perms).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_for_tool_builtin_sources() {
+        assert_eq!(
+            for_tool(
+                &tool_with_source(None),
+                &Settings::default(),
+                &HttpContext::default()
+            )
+            .unwrap()
+            .name(),
+            "github"
+        );
+        assert_eq!(
+            for_tool(
+                &tool_with_source(Some("github")),
+                &Settings::default(),
+                &HttpContext::default()
+            )
+            .unwrap()
+            .name(),
+            "github"
+        );
+        assert_eq!(
+            for_tool(
+                &tool_with_source(Some("command:/bin/true")),
+                &Settings::default(),
+                &HttpContext::default(),
+            )
+            .unwrap()
+            .name(),
+            "command"
+        );
+        assert_eq!(
+            for_tool(
+                &tool_with_source(Some("oci://ghcr.io/org/tool:latest")),
+                &Settings::default(),
+                &HttpContext::default(),
+            )
+            .unwrap()
+            .name(),
+            "oci"
+        );
+    }
+
+    #[test]
+    fn test_for_tool_unknown_source() {
+        let result = for_tool(
+            &tool_with_source(Some("gitlab")),
+            &Settings::default(),
+            &HttpContext::default(),
+        );
+        let err = result.err().unwrap();
+        assert!(format!("{}", err).contains("Unknown release source 'gitlab'"));
+    }
+
+    #[tokio::test]
+    async fn test_command_source_protocol() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = CommandSource::new(write_plugin(temp_dir.path()));
+
+        let release = source.latest_release("owner/repo").await.unwrap();
+        assert_eq!(release.tag_name, "v1.0.0");
+        assert_eq!(release.assets.len(), 1);
+
+        let tagged = source.release_by_tag("owner/repo", "v0.9.0").await.unwrap();
+        assert_eq!(tagged.tag_name, "v0.9.0");
+
+        let releases = source
+            .releases("owner/repo", &Channel::Any.list_options())
+            .await
+            .unwrap();
+        assert_eq!(releases.len(), 2);
+
+        let stable = source
+            .releases("owner/repo", &ListOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(stable.len(), 1);
+
+        let dest = temp_dir.path().join("asset");
+        source.download(&release.assets[0], &dest).await.unwrap();
+        assert_eq!(fs::read_to_string(&dest).unwrap(), "abc");
+    }
+
+    #[test]
+    fn test_for_tool_refuses_declined_command() {
+        let temp_dir = TempDir::new().unwrap();
+        let plugin = write_plugin(temp_dir.path());
+        let program = plugin.to_string_lossy().to_string();
+        let tool = tool_with_source(Some(&format!("command:{}", program)));
+        let http = HttpContext::default();
+        assert!(for_tool(&tool, &Settings::default(), &http).is_ok());
+
+        // As when the user said no to a command from someone else's config
+        prompt::decide_trust(&program, false);
+        let result = for_tool(&tool, &Settings::default(), &http);
+        assert!(format!("{}", result.err().unwrap()).contains("Not running release source"));
+    }
+
+    #[tokio::test]
+    async fn test_command_source_missing_program() {
+        let source = CommandSource::new("/nonexistent/plugin");
+        let result = source.latest_release("owner/repo").await;
+        assert!(format!("{}", result.unwrap_err()).contains("Failed to run source plugin"));
+    }
+
+    #[tokio::test]
+    async fn test_newest_release_channels() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = CommandSource::new(write_plugin(temp_dir.path()));
+
+        let latest = newest_release(&source, "owner/repo", Channel::Latest)
+            .await
+            .unwrap();
+        assert_eq!(latest.tag_name, "v1.0.0");
+
+        let pre = newest_release(&source, "owner/repo", Channel::Prerelease)
+            .await
+            .unwrap();
+        assert_eq!(pre.tag_name, "v1.1.0-rc1");
+
+        let any = newest_release(&source, "owner/repo", Channel::Any)
+            .await
+            .unwrap();
+        assert_eq!(any.tag_name, "v1.1.0-rc1");
+    }
+
+    #[tokio::test]
+    async fn test_newest_release_for_version_req() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = CommandSource::new(write_plugin(temp_dir.path()));
+        let mut tool = tool_with_source(None);
+        tool.channel = Some(Channel::Prerelease);
+
+        tool.version_req = Some("<1.1".to_string());
+        let release = newest_release_for(&source, &tool).await.unwrap();
+        assert_eq!(release.tag_name, "v1.0.0");
+
+        tool.version_req = Some("^2".to_string());
+        let err = newest_release_for(&source, &tool).await.unwrap_err();
+        assert!(err.to_string().contains("matches ^2"));
+    }
+}
@@ -0,0 +1,159 @@
+//! Builds tools from their release's source tarball, for repositories that
+//! publish no binaries. Only tools with a `build` command take this path.
+
+use crate::error::{OktofetchError, Result};
+use crate::github::Asset;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Asset standing in for the source tarball GitHub generates for `tag`.
+pub fn source_asset(repo: &str, tag: &str) -> Asset {
+    let repo_name = repo.rsplit('/').next().unwrap_or(repo);
+    Asset {
+        name: format!("{}-{}-source.tar.gz", repo_name, tag.replace('/', "_")),
+        browser_download_url: format!(
+            "https://github.com/{}/archive/refs/tags/{}.tar.gz",
+            repo, tag
+        ),
+        size: 0,
+        digest: None,
+    }
+}
+
+/// Directory the sources were unpacked to: GitHub tarballs hold a single
+/// `<owner>-<repo>-<sha>` directory.
+pub fn source_root(extract_dir: &Path) -> Result<PathBuf> {
+    let entries: Vec<PathBuf> = fs::read_dir(extract_dir)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<std::io::Result<_>>()?;
+    match entries.as_slice() {
+        [only] if only.is_dir() => Ok(only.clone()),
+        _ => Ok(extract_dir.to_path_buf()),
+    }
+}
+
+/// Runs `command` with `sh -c` in `source_dir` and returns the built binary.
+/// Common install locations point into `prefix` (`CARGO_INSTALL_ROOT`,
+/// `GOBIN`, `PREFIX` and `OKTOFETCH_PREFIX`), so `cargo install --path .`
+/// and similar commands don't install anywhere else. The binary is looked
+/// for in `prefix` first, then in the source tree.
+pub fn build(
+    command: &str,
+    source_dir: &Path,
+    prefix: &Path,
+    binary_name: &str,
+) -> Result<PathBuf> {
+    println!("Building from source: {}", command);
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .current_dir(source_dir)
+        .env("OKTOFETCH_PREFIX", prefix)
+        .env("CARGO_INSTALL_ROOT", prefix)
+        .env("GOBIN", prefix.join("bin"))
+        .env("PREFIX", prefix)
+        .status()
+        .map_err(|e| OktofetchError::Other(format!("Cannot run build command: {}", e)))?;
+
+    if !status.success() {
+        return Err(OktofetchError::Other(format!(
+            "Build command '{}' failed ({})",
+            command, status
+        )));
+    }
+
+    find_built_binary(&[prefix, source_dir], binary_name)
+}
+
+/// Executable named exactly `name`: the shallowest one in the first of
+/// `dirs` that has any.
+fn find_built_binary(dirs: &[&Path], name: &str) -> Result<PathBuf> {
+    for dir in dirs {
+        let mut found = Vec::new();
+        collect_executables(dir, name, &mut found)?;
+        if let Some(path) = found
+            .into_iter()
+            .min_by_key(|p| (p.components().count(), p.clone()))
+        {
+            return Ok(path);
+        }
+    }
+
+    Err(OktofetchError::BinaryNotFound(format!(
+        "The build produced no executable named {}",
+        name
+    )))
+}
+
+fn collect_executables(dir: &Path, name: &str, found: &mut Vec<PathBuf>) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    if !dir.is_dir() {
+        return Ok(());
+    }
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            collect_executables(&entry.path(), name, found)?;
+        } else if file_type.is_file()
+            && entry.file_name() == name
+            && entry.metadata()?.permissions().mode() & 0o111 != 0
+        {
+            found.push(entry.path());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_source_asset() {
+        let asset = source_asset("owner/tool", "tool/v1.0.0");
+        assert_eq!(asset.name, "tool-tool_v1.0.0-source.tar.gz");
+        assert_eq!(
+            asset.browser_download_url,
+            "https://github.com/owner/tool/archive/refs/tags/tool/v1.0.0.tar.gz"
+        );
+    }
+
+    #[test]
+    fn test_source_root() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::create_dir(temp_dir.path().join("owner-tool-abc123")).unwrap();
+        assert_eq!(
+            source_root(temp_dir.path()).unwrap(),
+            temp_dir.path().join("owner-tool-abc123")
+        );
+
+        fs::write(temp_dir.path().join("README"), "").unwrap();
+        assert_eq!(source_root(temp_dir.path()).unwrap(), temp_dir.path());
+    }
+
+    #[test]
+    fn test_build_installs_into_prefix() {
+        let temp_dir = TempDir::new().unwrap();
+        let source_dir = temp_dir.path().join("src");
+        let prefix = temp_dir.path().join("prefix");
+        fs::create_dir_all(&source_dir).unwrap();
+
+        // Leaves one copy in the tree and installs another into the prefix
+        let command = "printf '#!/bin/sh\\n' > tool && chmod +x tool && \
+                       mkdir -p \"$PREFIX/bin\" && cp tool \"$CARGO_INSTALL_ROOT/bin/tool\"";
+        let binary = build(command, &source_dir, &prefix, "tool").unwrap();
+        assert_eq!(binary, prefix.join("bin/tool"));
+
+        let binary = build("true", &source_dir, &temp_dir.path().join("none"), "tool").unwrap();
+        assert_eq!(binary, source_dir.join("tool"));
+
+        let err = build("exit 3", &source_dir, &prefix, "tool").unwrap_err();
+        assert!(err.to_string().contains("failed"));
+        let err = build("true", &source_dir, &prefix, "other").unwrap_err();
+        assert!(matches!(err, OktofetchError::BinaryNotFound(_)));
+    }
+}
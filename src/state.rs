@@ -0,0 +1,264 @@
+//! Machine-written state kept apart from the declarative config.
+//!
+//! Installed versions and checksums change on every update. Storing them in
+//! `state.json` means routine updates never rewrite the hand-edited config.
+//! In memory they stay on [`Tool`](crate::config::Tool); [`Config::load`] and
+//! [`Config::save`] move them between the two files.
+//!
+//! [`Config::load`]: crate::config::Config::load
+//! [`Config::save`]: crate::config::Config::save
+
+use crate::config::{self, Config, Tool};
+use crate::error::{OktofetchError, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// What oktofetch recorded about one installed tool.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ToolState {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// SHA256 of the binary as written at install time
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub checksum: Option<String>,
+    /// Digest of the release asset the binary came from, published by GitHub
+    /// or computed while downloading
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub asset_digest: Option<String>,
+    /// Unix time the binary was last installed or updated
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub installed_at: Option<u64>,
+    /// Unix time upstream was last successfully checked for a new release
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub checked_at: Option<u64>,
+    /// Size in bytes of the release asset the binary came from
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub asset_size: Option<u64>,
+    /// Name of the release asset the binary came from
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub asset_name: Option<String>,
+    /// Unix time the newest release seen upstream was published
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub released_at: Option<u64>,
+    /// The upstream repository was archived when last looked at
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub archived: bool,
+    /// Versions installed side by side with the current one
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub kept_versions: Vec<String>,
+}
+
+impl ToolState {
+    fn of(tool: &Tool) -> Self {
+        Self {
+            version: tool.version.clone(),
+            checksum: tool.checksum.clone(),
+            asset_digest: tool.asset_digest.clone(),
+            installed_at: tool.installed_at,
+            checked_at: tool.checked_at,
+            asset_size: tool.asset_size,
+            asset_name: tool.asset_name.clone(),
+            released_at: tool.released_at,
+            archived: tool.archived,
+            kept_versions: tool.kept_versions.clone(),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Contents of the state file: per-tool records keyed by tool name.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct State {
+    #[serde(default)]
+    pub tools: BTreeMap<String, ToolState>,
+}
+
+impl State {
+    /// Reads the state file, treating a missing file as empty.
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = fs::read_to_string(path)?;
+        serde_json::from_str(&content)
+            .map_err(|e| OktofetchError::ConfigError(e.to_string(), path.to_path_buf()))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let content = serde_json::to_string_pretty(self).map_err(std::io::Error::from)?;
+        config::write_atomic(path, (content + "\n").as_bytes())?;
+        Ok(())
+    }
+
+    /// Collects the state of every tool in `config`.
+    pub fn of(config: &Config) -> Self {
+        let tools = config
+            .tools
+            .iter()
+            .map(|tool| (tool.name.clone(), ToolState::of(tool)))
+            .filter(|(_, state)| !state.is_empty())
+            .collect();
+        Self { tools }
+    }
+
+    /// Fills in the recorded state of the tools in `config`. Tools without a
+    /// record keep what the config file had, so configs written before the
+    /// state file existed carry over.
+    pub fn apply(&self, config: &mut Config) {
+        for tool in &mut config.tools {
+            if let Some(state) = self.tools.get(&tool.name) {
+                tool.version = state.version.clone();
+                tool.checksum = state.checksum.clone();
+                tool.asset_digest = state.asset_digest.clone();
+                tool.installed_at = state.installed_at;
+                tool.checked_at = state.checked_at;
+                tool.asset_size = state.asset_size;
+                tool.asset_name = state.asset_name.clone();
+                tool.released_at = state.released_at;
+                tool.archived = state.archived;
+                tool.kept_versions = state.kept_versions.clone();
+            }
+        }
+    }
+}
+
+/// Clears the fields kept in the state file, leaving the declarative part.
+/// A pinned tool's version is part of its declaration, so it stays.
+pub fn strip(tool: &mut Tool) {
+    if !tool.pinned {
+        tool.version = None;
+    }
+    tool.checksum = None;
+    tool.asset_digest = None;
+    tool.installed_at = None;
+    tool.checked_at = None;
+    tool.asset_size = None;
+    tool.asset_name = None;
+    tool.released_at = None;
+    tool.archived = false;
+    tool.kept_versions.clear();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn config_with(tools: Vec<Tool>) -> Config {
+        let mut config = Config::default();
+        for tool in tools {
+            config.add_tool(tool).unwrap();
+        }
+        config
+    }
+
+    #[test]
+    fn test_state_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("state.json");
+        let config = config_with(vec![
+            Tool {
+                name: "k9s".to_string(),
+                version: Some("v0.32.5".to_string()),
+                checksum: Some("abc".to_string()),
+                installed_at: Some(1_700_000_000),
+                checked_at: Some(1_700_003_600),
+                asset_name: Some("k9s_Linux_amd64.tar.gz".to_string()),
+                released_at: Some(1_699_000_000),
+                archived: true,
+                kept_versions: vec!["v0.31.0".to_string()],
+                ..Default::default()
+            },
+            Tool {
+                name: "fresh".to_string(),
+                ..Default::default()
+            },
+        ]);
+
+        State::of(&config).save(&path).unwrap();
+        let state = State::load(&path).unwrap();
+
+        assert_eq!(state.tools.len(), 1);
+        assert_eq!(state.tools["k9s"].version.as_deref(), Some("v0.32.5"));
+        assert_eq!(state.tools["k9s"].checksum.as_deref(), Some("abc"));
+        assert_eq!(state.tools["k9s"].installed_at, Some(1_700_000_000));
+        assert_eq!(state.tools["k9s"].checked_at, Some(1_700_003_600));
+        assert_eq!(
+            state.tools["k9s"].asset_name.as_deref(),
+            Some("k9s_Linux_amd64.tar.gz")
+        );
+        assert_eq!(state.tools["k9s"].released_at, Some(1_699_000_000));
+        assert!(state.tools["k9s"].archived);
+        assert_eq!(state.tools["k9s"].kept_versions, vec!["v0.31.0"]);
+    }
+
+    #[test]
+    fn test_state_apply_keeps_legacy_values() {
+        let mut config = config_with(vec![
+            Tool {
+                name: "k9s".to_string(),
+                version: Some("v0.1.0".to_string()),
+                ..Default::default()
+            },
+            Tool {
+                name: "legacy".to_string(),
+                version: Some("v2.0.0".to_string()),
+                ..Default::default()
+            },
+        ]);
+        let mut state = State::default();
+        state.tools.insert(
+            "k9s".to_string(),
+            ToolState {
+                version: Some("v0.2.0".to_string()),
+                ..Default::default()
+            },
+        );
+
+        state.apply(&mut config);
+
+        assert_eq!(config.tools[0].version.as_deref(), Some("v0.2.0"));
+        assert_eq!(config.tools[1].version.as_deref(), Some("v2.0.0"));
+    }
+
+    #[test]
+    fn test_strip_keeps_pinned_version() {
+        let mut tool = Tool {
+            name: "k9s".to_string(),
+            version: Some("v0.32.5".to_string()),
+            checksum: Some("abc".to_string()),
+            pinned: true,
+            ..Default::default()
+        };
+        strip(&mut tool);
+        assert_eq!(tool.version.as_deref(), Some("v0.32.5"));
+        assert!(tool.checksum.is_none());
+
+        tool.pinned = false;
+        strip(&mut tool);
+        assert!(tool.version.is_none());
+    }
+
+    #[test]
+    fn test_state_load_missing_and_invalid() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("state.json");
+        assert_eq!(State::load(&path).unwrap(), State::default());
+
+        fs::write(&path, "{").unwrap();
+        assert!(matches!(
+            State::load(&path),
+            Err(OktofetchError::ConfigError(_, _))
+        ));
+    }
+}
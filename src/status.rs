@@ -0,0 +1,541 @@
+use crate::binary;
+use crate::config::{Config, Settings, Tool};
+use crate::error::{OktofetchError, Result};
+use crate::github::RepoSearchResult;
+use crate::http::HttpContext;
+use crate::human;
+use crate::source;
+use crate::tool::{self, ChecksumStatus};
+use crate::version;
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Differences between the configured tool set and what is on disk.
+#[derive(Debug, Default)]
+pub struct StatusReport {
+    /// Managed tools whose binary is absent: (tool, expected path)
+    pub missing: Vec<(String, PathBuf)>,
+    /// Executables in the install directory that no tool manages
+    pub untracked: Vec<String>,
+    /// Tools whose binary reports a different version: (tool, configured, reported)
+    pub version_mismatches: Vec<(String, String, String)>,
+    /// Tools whose binary no longer matches the recorded checksum
+    pub checksum_mismatches: Vec<String>,
+    /// Backups and retained versions left behind by tools no longer managed
+    pub stale_backups: Vec<PathBuf>,
+}
+
+impl StatusReport {
+    pub fn is_consistent(&self) -> bool {
+        self.missing.is_empty()
+            && self.untracked.is_empty()
+            && self.version_mismatches.is_empty()
+            && self.checksum_mismatches.is_empty()
+            && self.stale_backups.is_empty()
+    }
+}
+
+/// Compares every managed tool against the install directory and
+/// `versions_dir`. When `probe_versions` is set, each binary is run with
+/// `--version`.
+pub fn collect_status(
+    config: &Config,
+    versions_dir: &Path,
+    probe_versions: bool,
+) -> Result<StatusReport> {
+    let install_dir = &config.settings.install_dir;
+    let mut report = StatusReport::default();
+
+    // Tools with an install directory of their own don't account for files here
+    let managed: HashSet<&str> = config
+        .tools
+        .iter()
+        .filter(|t| t.install_dir.is_none())
+        .map(|t| t.installed_name())
+        .collect();
+
+    for tool in &config.tools {
+        let path = config.install_dir_for(tool).join(tool.installed_name());
+
+        match tool::verify_checksum(config, tool) {
+            ChecksumStatus::Missing => {
+                report.missing.push((tool.name.clone(), path));
+                continue;
+            }
+            ChecksumStatus::Mismatch => report.checksum_mismatches.push(tool.name.clone()),
+            ChecksumStatus::Ok | ChecksumStatus::Unknown => {}
+        }
+
+        if probe_versions
+            && let Some(configured) = &tool.version
+            && let Some(reported) = binary::probe_version(&path)
+            && version::compare(configured, &reported) != Ordering::Equal
+        {
+            report
+                .version_mismatches
+                .push((tool.name.clone(), configured.clone(), reported));
+        }
+    }
+
+    if install_dir.is_dir() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let mut entries: Vec<_> = fs::read_dir(install_dir)?.collect::<std::io::Result<_>>()?;
+        entries.sort_by_key(|e| e.file_name());
+
+        for entry in entries {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            let metadata = entry.metadata()?;
+            if !metadata.is_file() {
+                continue;
+            }
+
+            if let Some(name) = file_name.strip_suffix(".previous") {
+                if !managed.contains(name) {
+                    report.stale_backups.push(entry.path());
+                }
+            } else if metadata.permissions().mode() & 0o111 != 0
+                && !managed.contains(file_name.as_str())
+            {
+                report.untracked.push(file_name);
+            }
+        }
+    }
+
+    if versions_dir.is_dir() {
+        for entry in fs::read_dir(versions_dir)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().to_string();
+            if config.get_tool(&name).is_none() {
+                report.stale_backups.push(entry.path());
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+/// An installed version that no longer exists upstream.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct YankedRelease {
+    pub tool: String,
+    pub version: String,
+    /// Closest version still available, if any
+    pub nearest: Option<String>,
+}
+
+/// Checks whether the recorded version of a tool still exists upstream,
+/// returning `None` when it does.
+pub async fn check_yanked(
+    tool: &Tool,
+    settings: &Settings,
+    http: &HttpContext,
+) -> Result<Option<YankedRelease>> {
+    let Some(installed) = &tool.version else {
+        return Ok(None);
+    };
+
+    let source = source::for_tool(tool, settings, http)?;
+    let repo = tool.release_repo();
+
+    match source
+        .release_by_tag(repo, &tool.tag_for_version(installed))
+        .await
+    {
+        Ok(_) => Ok(None),
+        Err(OktofetchError::ReleaseNotFound { .. }) => {
+            let channel = tool.channel.unwrap_or_default();
+            let available: Vec<String> = source
+                .releases(repo, &channel.list_options())
+                .await?
+                .into_iter()
+                .map(|r| tool.version_from_tag(&r.tag_name))
+                .collect();
+
+            Ok(Some(YankedRelease {
+                tool: tool.name.clone(),
+                version: installed.clone(),
+                nearest: version::nearest(installed, &available).map(str::to_string),
+            }))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Tools that look unmaintained upstream: (tool, reason). With `remote`,
+/// whether each GitHub repository is archived is asked afresh rather than
+/// taken from the last update.
+async fn collect_unmaintained(config: &Config, remote: bool) -> Vec<(String, String)> {
+    let client = match remote {
+        true => source::github_client(&config.settings, &config.http()).ok(),
+        false => None,
+    };
+    let now = human::unix_now();
+
+    let mut unmaintained = Vec::new();
+    for tool in &config.tools {
+        let mut tool = tool.clone();
+        if let Some(client) = client.as_ref().filter(|_| tool.is_github()) {
+            match client.get_repository(tool.release_repo()).await {
+                Ok(repository) => tool.archived = repository.archived,
+                Err(e) => eprintln!("Could not look up {}: {}", tool.release_repo(), e),
+            }
+        }
+        if let Some(reason) = tool::unmaintained_reason(&tool, now) {
+            unmaintained.push((tool.name, reason));
+        }
+    }
+    unmaintained
+}
+
+pub async fn show_status(config: &Config, remote: bool) -> Result<()> {
+    let report = collect_status(config, &Config::versions_dir()?, true)?;
+
+    let mut yanked = Vec::new();
+    if remote {
+        for tool in &config.tools {
+            match check_yanked(tool, &config.settings, &config.http()).await {
+                Ok(Some(y)) => yanked.push(y),
+                Ok(None) => {}
+                Err(e) => eprintln!("Could not check {} upstream: {}", tool.name, e),
+            }
+        }
+    }
+
+    // Not an inconsistency, but worth knowing about either way
+    let unmaintained = collect_unmaintained(config, remote).await;
+    if !unmaintained.is_empty() {
+        println!("Possibly unmaintained upstream:");
+        for (name, reason) in &unmaintained {
+            println!("  {:<20} {}", name, reason);
+        }
+    }
+
+    if report.is_consistent() && yanked.is_empty() {
+        println!("All {} tools are consistent.", config.tools.len());
+        return Ok(());
+    }
+
+    if !yanked.is_empty() {
+        println!("Installed release no longer available upstream:");
+        for y in &yanked {
+            match &y.nearest {
+                Some(nearest) => println!(
+                    "  {:<20} {} (nearest available: {}, run: oktofetch update {} --version {})",
+                    y.tool, y.version, nearest, y.tool, nearest
+                ),
+                None => println!("  {:<20} {} (no releases available)", y.tool, y.version),
+            }
+        }
+    }
+
+    if !report.missing.is_empty() {
+        println!("Missing binaries:");
+        for (name, path) in &report.missing {
+            println!("  {:<20} expected at {}", name, path.display());
+        }
+    }
+
+    if !report.version_mismatches.is_empty() {
+        println!("Version mismatches:");
+        for (name, configured, reported) in &report.version_mismatches {
+            println!(
+                "  {:<20} config: {}, binary reports: {}",
+                name, configured, reported
+            );
+        }
+    }
+
+    if !report.checksum_mismatches.is_empty() {
+        println!("Modified since install (checksum mismatch):");
+        for name in &report.checksum_mismatches {
+            println!("  {}", name);
+        }
+    }
+
+    if !report.untracked.is_empty() {
+        println!(
+            "Untracked binaries in {}:",
+            config.settings.install_dir.display()
+        );
+        for name in &report.untracked {
+            println!("  {}", name);
+        }
+        println!("  (run 'oktofetch scan' for repositories to adopt them from)");
+    }
+
+    if !report.stale_backups.is_empty() {
+        println!("Stale backups:");
+        for path in &report.stale_backups {
+            println!("  {}", path.display());
+        }
+    }
+
+    Ok(())
+}
+
+/// Lists the unmanaged executables in the install directory, each with the
+/// repositories a GitHub search by its name turns up and the command to adopt
+/// it from them.
+pub async fn scan(config: &Config, candidates: usize) -> Result<()> {
+    let report = collect_status(config, &Config::versions_dir()?, false)?;
+    let install_dir = config.settings.install_dir.display();
+    if report.untracked.is_empty() {
+        println!("No unmanaged executables in {}", install_dir);
+        return Ok(());
+    }
+
+    let client = source::github_client(&config.settings, &config.http())?;
+    println!(
+        "{} unmanaged executables in {}:",
+        report.untracked.len(),
+        install_dir
+    );
+    for name in &report.untracked {
+        println!("\n{}", name);
+        let results = match client.search_repositories(name, candidates).await {
+            Ok(results) => rank_candidates(name, results),
+            // Searching the rest would fail the same way
+            Err(e @ OktofetchError::RateLimited { .. }) => return Err(e),
+            Err(e) => {
+                eprintln!("  Search failed: {}", e);
+                continue;
+            }
+        };
+        if results.is_empty() {
+            println!("  No matching repositories found");
+        }
+        for repo in results {
+            println!(
+                "  oktofetch adopt {} {:<40} {:>7} stars  {}",
+                name,
+                repo.full_name,
+                repo.stargazers_count,
+                repo.description.as_deref().unwrap_or("")
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Moves repositories named exactly like the binary to the front, keeping the
+/// search order (most starred first) otherwise.
+fn rank_candidates(name: &str, mut results: Vec<RepoSearchResult>) -> Vec<RepoSearchResult> {
+    results.sort_by_key(|repo| {
+        let repo_name = repo.full_name.rsplit('/').next().unwrap_or_default();
+        !repo_name.eq_ignore_ascii_case(name)
+    });
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Tool;
+    use std::os::unix::fs::PermissionsExt;
+    use tempfile::TempDir;
+
+    fn write_executable(path: &std::path::Path, content: &str) {
+        fs::write(path, content).unwrap();
+        let mut perms = fs::metadata(path).unwrap().permissions();
+        perms.set_mode(0o755);
+        fs::set_permissions(path, perms).unwrap();
+    }
+
+    fn config_with_tools(install_dir: &std::path::Path, names: &[&str]) -> Config {
+        let mut config = Config::default();
+        config.settings.install_dir = install_dir.to_path_buf();
+        for name in names {
+            config
+                .add_tool(Tool {
+                    name: name.to_string(),
+                    repo: format!("owner/{}", name),
+                    ..Default::default()
+                })
+                .unwrap();
+        }
+        config
+    }
+
+    #[test]
+    fn test_rank_candidates() {
+        let repo = |full_name: &str, stars: u64| RepoSearchResult {
+            full_name: full_name.to_string(),
+            stargazers_count: stars,
+            description: None,
+        };
+        let results = vec![
+            repo("someone/awesome-k9s", 900),
+            repo("derailed/K9s", 27000),
+            repo("other/k9s-plugins", 40),
+            repo("fork/k9s", 3),
+        ];
+
+        let ranked: Vec<_> = rank_candidates("k9s", results)
+            .into_iter()
+            .map(|r| r.full_name)
+            .collect();
+        assert_eq!(
+            ranked,
+            vec![
+                "derailed/K9s",
+                "fork/k9s",
+                "someone/awesome-k9s",
+                "other/k9s-plugins"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_status_empty_install_dir() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = config_with_tools(temp_dir.path(), &["k9s"]);
+
+        let report = collect_status(&config, &temp_dir.path().join("versions"), false).unwrap();
+        assert!(!report.is_consistent());
+        assert_eq!(report.missing.len(), 1);
+        assert_eq!(report.missing[0].0, "k9s");
+    }
+
+    #[test]
+    fn test_status_untracked_and_stale_backups() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = config_with_tools(temp_dir.path(), &["k9s"]);
+
+        write_executable(&temp_dir.path().join("k9s"), "bin");
+        write_executable(&temp_dir.path().join("k9s.previous"), "old");
+        write_executable(&temp_dir.path().join("stray"), "bin");
+        write_executable(&temp_dir.path().join("gone.previous"), "old");
+        fs::write(temp_dir.path().join("notes.txt"), "not executable").unwrap();
+
+        let report = collect_status(&config, &temp_dir.path().join("versions"), false).unwrap();
+        assert!(report.missing.is_empty());
+        assert_eq!(report.untracked, vec!["stray".to_string()]);
+        assert_eq!(
+            report.stale_backups,
+            vec![temp_dir.path().join("gone.previous")]
+        );
+    }
+
+    #[test]
+    fn test_status_version_mismatch() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = config_with_tools(temp_dir.path(), &["k9s", "jq"]);
+        write_executable(
+            &temp_dir.path().join("k9s"),
+            "#!/bin/sh\necho 'k9s 1.2.3'\n",
+        );
+        write_executable(&temp_dir.path().join("jq"), "#!/bin/sh\necho 'jq-1.7.1'\n");
+        config.tools[0].version = Some("v1.2.30".to_string());
+        config.tools[1].version = Some("jq-1.7.1".to_string());
+
+        let report = collect_status(&config, &temp_dir.path().join("versions"), true).unwrap();
+        assert_eq!(
+            report.version_mismatches,
+            vec![(
+                "k9s".to_string(),
+                "v1.2.30".to_string(),
+                "1.2.3".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_status_stale_versions() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = config_with_tools(temp_dir.path(), &["k9s"]);
+        write_executable(&temp_dir.path().join("k9s"), "bin");
+        let versions_dir = temp_dir.path().join("versions");
+        fs::create_dir_all(versions_dir.join("k9s/v1.0.0")).unwrap();
+        fs::create_dir_all(versions_dir.join("gone/v0.1.0")).unwrap();
+
+        let report = collect_status(&config, &versions_dir, false).unwrap();
+        assert_eq!(report.stale_backups, vec![versions_dir.join("gone")]);
+    }
+
+    #[test]
+    fn test_status_checksum_mismatch() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = config_with_tools(temp_dir.path(), &["k9s"]);
+        write_executable(&temp_dir.path().join("k9s"), "bin");
+        config.tools[0].checksum = Some("0".repeat(64));
+
+        let report = collect_status(&config, &temp_dir.path().join("versions"), false).unwrap();
+        assert_eq!(report.checksum_mismatches, vec!["k9s".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_status_consistent() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = config_with_tools(temp_dir.path(), &["k9s"]);
+        write_executable(&temp_dir.path().join("k9s"), "bin");
+
+        let report = collect_status(&config, &temp_dir.path().join("versions"), false).unwrap();
+        assert!(report.is_consistent());
+        assert!(show_status(&config, false).await.is_ok());
+    }
+
+    fn write_plugin(dir: &std::path::Path) -> String {
+        let path = dir.join("plugin");
+        write_executable(
+            &path,
+            r#"#!/bin/sh
+case "$1" in
+  tag) [ "$3" = "v1.1.0" ] && echo '{"tag_name":"v1.1.0","name":"","assets":[]}' || exit 2 ;;
+  list) echo '[{"tag_name":"v1.3.0","name":"","assets":[]},{"tag_name":"v1.1.0","name":"","assets":[]}]' ;;
+esac
+"#,
+        );
+        format!("command:{}", path.display())
+    }
+
+    #[tokio::test]
+    async fn test_check_yanked_without_version() {
+        let tool = Tool {
+            name: "k9s".to_string(),
+            repo: "owner/k9s".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            check_yanked(&tool, &Settings::default(), &HttpContext::default())
+                .await
+                .unwrap(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_check_yanked_detects_pulled_release() {
+        let temp_dir = TempDir::new().unwrap();
+        let tool = Tool {
+            name: "k9s".to_string(),
+            repo: "owner/k9s".to_string(),
+            version: Some("v1.2.0".to_string()),
+            source: Some(write_plugin(temp_dir.path())),
+            ..Default::default()
+        };
+        assert_eq!(
+            check_yanked(&tool, &Settings::default(), &HttpContext::default())
+                .await
+                .unwrap(),
+            Some(YankedRelease {
+                tool: "k9s".to_string(),
+                version: "v1.2.0".to_string(),
+                nearest: Some("v1.1.0".to_string()),
+            })
+        );
+
+        let tool = Tool {
+            version: Some("v1.1.0".to_string()),
+            ..tool
+        };
+        assert_eq!(
+            check_yanked(&tool, &Settings::default(), &HttpContext::default())
+                .await
+                .unwrap(),
+            None
+        );
+    }
+}
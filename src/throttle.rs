@@ -0,0 +1,66 @@
+use std::time::{Duration, Instant};
+
+/// Paces a byte stream to an average rate by sleeping whenever the bytes
+/// consumed so far are ahead of schedule.
+pub struct Throttle {
+    bytes_per_sec: u64,
+    start: Instant,
+    consumed: u64,
+}
+
+impl Throttle {
+    pub fn new(bytes_per_sec: u64) -> Self {
+        Self {
+            bytes_per_sec: bytes_per_sec.max(1),
+            start: Instant::now(),
+            consumed: 0,
+        }
+    }
+
+    /// How long to wait after `bytes` more have been consumed to stay on schedule.
+    fn delay_after(&mut self, bytes: u64, elapsed: Duration) -> Duration {
+        self.consumed += bytes;
+        let due = Duration::from_secs_f64(self.consumed as f64 / self.bytes_per_sec as f64);
+        due.saturating_sub(elapsed)
+    }
+
+    pub async fn consume(&mut self, bytes: u64) {
+        let delay = self.delay_after(bytes, self.start.elapsed());
+        if !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delay_after() {
+        let mut throttle = Throttle::new(1000);
+        assert_eq!(
+            throttle.delay_after(500, Duration::ZERO),
+            Duration::from_millis(500)
+        );
+        // Behind schedule: no wait
+        assert_eq!(
+            throttle.delay_after(500, Duration::from_secs(2)),
+            Duration::ZERO
+        );
+        assert_eq!(
+            throttle.delay_after(1000, Duration::from_millis(1500)),
+            Duration::from_millis(500)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_consume_paces_stream() {
+        let mut throttle = Throttle::new(10_000);
+        let start = Instant::now();
+        for _ in 0..4 {
+            throttle.consume(500).await;
+        }
+        assert!(start.elapsed() >= Duration::from_millis(190));
+    }
+}
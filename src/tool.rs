@@ -1,18 +1,61 @@
 use crate::archive;
 use crate::binary;
-use crate::config::{Config, Tool};
-use crate::error::{OktofetchError, Result};
-use crate::github::GithubClient;
+use crate::binstall;
+use crate::completions;
+use crate::config::{Channel, Config, Settings, Tool, Verification, expand_path};
+use crate::error::{Context, OktofetchError, Result};
+use crate::events::{self, Event};
+use crate::github::{Asset, Release, RepoSearchResult, RepoSnapshot};
+use crate::human;
+use crate::journal::Journal;
 use crate::platform;
+use crate::prompt;
+use crate::source;
+use crate::source_build;
+use crate::state;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use tempfile::TempDir;
 
-pub async fn add_tool(
-    config: &mut Config,
-    repo: String,
-    name: Option<String>,
-    binary_name: Option<String>,
-) -> Result<()> {
-    let repo = parse_repo(&repo)?;
+/// Optional parts of a new tool entry and how `add` treats it.
+#[derive(Debug, Clone, Default)]
+pub struct AddOptions {
+    pub name: Option<String>,
+    pub binary_name: Option<String>,
+    pub release_repo: Option<String>,
+    pub install_as: Option<String>,
+    /// Skip checking that the repository has an installable release
+    pub no_verify: bool,
+    /// Install the tool right after adding it
+    pub install: bool,
+    /// Install this version instead of the latest release
+    pub version: Option<String>,
+    /// Keep `version` side by side with the installed one instead of
+    /// replacing it; adds it to an existing entry for the same repository
+    pub keep: bool,
+    pub verbose: bool,
+}
+
+pub async fn add_tool(config: &mut Config, repo: String, opts: AddOptions) -> Result<()> {
+    let AddOptions {
+        name,
+        binary_name,
+        release_repo,
+        install_as,
+        no_verify,
+        install,
+        version,
+        keep,
+        verbose,
+    } = opts;
+
+    let repo = if is_bare_name(&repo) {
+        search_repo(config, &repo).await?
+    } else {
+        parse_repo(&repo)?
+    };
+    let release_repo = release_repo.map(|r| parse_repo(&r)).transpose()?;
     let tool_name = name.unwrap_or_else(|| {
         binary_name
             .clone()
@@ -25,20 +68,340 @@ pub async fn add_tool(
         binary_name,
         asset_pattern: None,
         version: None,
+        release_repo,
+        install_as,
+        ..Default::default()
     };
 
+    // Another version of a tool that's already there goes next to it
+    let existing = config.get_tool(&tool_name).map(|t| t.repo == repo);
+    if keep && existing == Some(true) {
+        let opts = UpdateOptions {
+            verbose,
+            force: true,
+            version,
+            keep,
+            ..Default::default()
+        };
+        return update_tool(config, &tool_name, &opts).await;
+    }
+
+    if existing.is_none() && !no_verify {
+        let source = source::for_tool(&tool, &config.settings, &config.http())?;
+        let (release, asset) = check_installable(source.as_ref(), &tool, &config.settings)
+            .await
+            .with_context(|| {
+                format!(
+                    "{} can't be installed (pass --no-verify to add it anyway)",
+                    tool.release_repo()
+                )
+            })?;
+        println!("Found {} in release {}", asset.name, release.tag_name);
+    }
+
+    let installed_name = tool.installed_name().to_string();
     config.add_tool(tool)?;
     config.save()?;
     println!("Added tool '{}' ({})", tool_name, repo);
+
+    if install || version.is_some() {
+        let opts = UpdateOptions {
+            verbose,
+            force: keep,
+            version,
+            keep,
+            ..Default::default()
+        };
+        update_tool(config, &tool_name, &opts).await
+    } else {
+        warn_path_collisions(&config.settings.install_dir, &installed_name);
+        Ok(())
+    }
+}
+
+/// Puts a binary already in the install directory under management, recording
+/// the version it reports and its checksum so it isn't needlessly reinstalled.
+pub async fn adopt_tool(config: &mut Config, name: &str, repo: &str, verbose: bool) -> Result<()> {
+    let repo = parse_repo(repo)?;
+    let path = config.settings.install_dir.join(name);
+    if !path.is_file() {
+        return Err(OktofetchError::BinaryNotFound(format!(
+            "{} doesn't exist; use 'oktofetch add' for tools that aren't installed yet",
+            path.display()
+        )));
+    }
+
+    if config.get_tool(name).is_some() {
+        return Err(OktofetchError::Other(format!(
+            "Tool '{}' is already managed",
+            name
+        )));
+    }
+
+    let mut tool = Tool {
+        name: name.to_string(),
+        repo: repo.clone(),
+        ..Default::default()
+    };
+
+    match binary::probe_version(&path) {
+        Some(probed) => {
+            let source = source::for_tool(&tool, &config.settings, &config.http())?;
+            let version = match_release_tag(source.as_ref(), &tool, &probed).await;
+            if verbose && version != probed {
+                println!(
+                    "{} --version reports {}, release tag {}",
+                    name, probed, version
+                );
+            }
+            tool.version = Some(version);
+        }
+        None => println!(
+            "Couldn't read a version from '{} --version'; the next update will reinstall it",
+            name
+        ),
+    }
+    tool.checksum = Some(binary::sha256_file(&path)?);
+
+    let version = tool.version.clone();
+    config.add_tool(tool)?;
+    config.save()?;
+    match version {
+        Some(version) => println!("Adopted {} ({}) at version {}", name, repo, version),
+        None => println!("Adopted {} ({})", name, repo),
+    }
     Ok(())
 }
 
+/// Turns a version printed by a binary, e.g. `0.32.5`, into the form update
+/// compares against: the version of the matching release tag (`v0.32.5`,
+/// `0.32.5`, or per `tag_format`). Falls back to `probed` when no release
+/// matches or the source can't be reached.
+async fn match_release_tag(
+    source: &dyn source::ReleaseSource,
+    tool: &Tool,
+    probed: &str,
+) -> String {
+    let mut candidates = vec![tool.tag_for_version(probed)];
+    if tool.tag_format.is_none() {
+        candidates.insert(0, format!("v{}", probed));
+    }
+
+    for tag in candidates {
+        if let Ok(release) = source.release_by_tag(tool.release_repo(), &tag).await {
+            return tool.version_from_tag(&release.tag_name);
+        }
+    }
+    probed.to_string()
+}
+
+/// The hex SHA256 in a `sha256:<hex>` digest.
+fn digest_sha256(digest: &str) -> Option<String> {
+    digest.strip_prefix("sha256:").map(str::to_lowercase)
+}
+
+/// A tool list meant to be shared, e.g. committed to a project.
+#[derive(Serialize)]
+struct Toolfile {
+    tools: Vec<Tool>,
+}
+
+/// Renders the tools as a TOML toolfile: the declarative entries with each
+/// installed tool pinned to its version and, where it is known, the asset's
+/// SHA256. Settings, install records and anything naming a script or
+/// command on this machine are left out. It loads like any config file.
+pub fn freeze(config: &Config) -> Result<String> {
+    let tools = config
+        .tools
+        .iter()
+        .map(|tool| {
+            let mut frozen = Tool {
+                pinned: tool.pinned || tool.version.is_some(),
+                sha256: tool
+                    .sha256
+                    .clone()
+                    .or_else(|| tool.asset_digest.as_deref().and_then(digest_sha256)),
+                ..tool.clone()
+            };
+            state::strip(&mut frozen);
+
+            let mut dropped = Vec::new();
+            if frozen.asset_selector.take().is_some() {
+                dropped.push("asset_selector");
+            }
+            if frozen
+                .source
+                .as_deref()
+                .is_some_and(|source| source.starts_with("command:"))
+            {
+                frozen.source = None;
+                dropped.push("source");
+            }
+            if frozen.build.take().is_some() {
+                dropped.push("build");
+            }
+            if !dropped.is_empty() {
+                eprintln!(
+                    "Note: left out {} of {}, which only work on this machine",
+                    dropped.join(", "),
+                    tool.name
+                );
+            }
+            frozen
+        })
+        .collect();
+    toml::to_string(&Toolfile { tools }).map_err(|e| OktofetchError::Other(e.to_string()))
+}
+
+/// Warns about other executables called `name` on PATH, saying which one
+/// a shell will actually run.
+fn warn_path_collisions(install_dir: &Path, name: &str) {
+    let Some(search_path) = std::env::var_os("PATH") else {
+        return;
+    };
+    let ours = install_dir.join(name);
+    for collision in binary::path_collisions(name, install_dir, &search_path) {
+        if collision.shadows_install {
+            eprintln!(
+                "Warning: {} comes first on PATH, so it runs instead of {}",
+                collision.path.display(),
+                ours.display()
+            );
+        } else {
+            eprintln!(
+                "Note: {} shadows {} on PATH",
+                ours.display(),
+                collision.path.display()
+            );
+        }
+    }
+}
+
+/// Confirms that `tool` has a release on its channel with an asset for this
+/// platform, returning both.
+pub async fn check_installable(
+    source: &dyn source::ReleaseSource,
+    tool: &Tool,
+    settings: &Settings,
+) -> Result<(Release, Asset)> {
+    let release = source::newest_release_for(source, tool).await?;
+    let (asset, _) = choose_asset(source, tool, &release, settings).await?;
+    Ok((release, asset))
+}
+
+/// Where the asset [`choose_asset`] picked comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AssetOrigin {
+    Release,
+    /// quickinstall's prebuilt binary of the crate
+    Quickinstall,
+    /// The release's source tarball, to be built with the tool's build command
+    Source,
+}
+
+/// Picks the asset to install. When no binary fits, a GitHub release can
+/// fall back to quickinstall's build of the crate if enabled, and then to
+/// its source tarball if the tool has a build command.
+async fn choose_asset(
+    source: &dyn source::ReleaseSource,
+    tool: &Tool,
+    release: &Release,
+    settings: &Settings,
+) -> Result<(Asset, AssetOrigin)> {
+    if tool.binstall
+        && tool.asset_selector.is_none()
+        && tool.asset_pattern.is_none()
+        && let Some(asset) = binstall_asset(source, tool, release).await?
+    {
+        return Ok((asset, AssetOrigin::Release));
+    }
+
+    let error = match select_asset(tool, release, &settings.prefer) {
+        Ok(asset) => return Ok((asset.clone(), AssetOrigin::Release)),
+        Err(e) => e,
+    };
+    if !matches!(error, OktofetchError::NoSuitableRelease { .. }) || source.name() != "github" {
+        return Err(error);
+    }
+
+    if tool.quickinstall.or(settings.quickinstall).unwrap_or(false)
+        && let Some(asset) = quickinstall_asset(source, tool, release).await?
+    {
+        return Ok((asset, AssetOrigin::Quickinstall));
+    }
+    if tool.build.is_some() {
+        let asset = source_build::source_asset(tool.release_repo(), &release.tag_name);
+        return Ok((asset, AssetOrigin::Source));
+    }
+    Err(error)
+}
+
+/// quickinstall's build of the crate at this release, if it has one. The
+/// crate is named in the repository's Cargo.toml, or else after the repo.
+async fn quickinstall_asset(
+    source: &dyn source::ReleaseSource,
+    tool: &Tool,
+    release: &Release,
+) -> Result<Option<Asset>> {
+    let manifest = source
+        .repo_file(&tool.repo, &release.tag_name, "Cargo.toml")
+        .await?
+        .unwrap_or_default();
+    let repo_name = tool.repo.rsplit('/').next().unwrap_or(&tool.repo);
+    let version = tool.version_from_tag(&release.tag_name);
+    let (tag, names) =
+        binstall::quickinstall_release(&manifest, repo_name, version.trim_start_matches('v'));
+
+    let prebuilt = match source
+        .release_by_tag(binstall::QUICKINSTALL_REPO, &tag)
+        .await
+    {
+        Ok(prebuilt) => prebuilt,
+        Err(OktofetchError::ReleaseNotFound { .. }) => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    Ok(names
+        .iter()
+        .find_map(|name| prebuilt.assets.iter().find(|a| &a.name == name))
+        .cloned())
+}
+
+/// The asset the cargo-binstall metadata in the tool's Cargo.toml at the
+/// release tag names. `None`, with a warning, when there is no metadata or
+/// the release lacks the named asset, so that the usual matching applies.
+async fn binstall_asset(
+    source: &dyn source::ReleaseSource,
+    tool: &Tool,
+    release: &Release,
+) -> Result<Option<Asset>> {
+    let manifest = source
+        .repo_file(&tool.repo, &release.tag_name, "Cargo.toml")
+        .await?
+        .unwrap_or_default();
+    let version = tool.version_from_tag(&release.tag_name);
+    let names = binstall::asset_names(&manifest, version.trim_start_matches('v'));
+
+    let asset = names
+        .iter()
+        .find_map(|name| release.assets.iter().find(|a| &a.name == name));
+    if asset.is_none() {
+        eprintln!(
+            "Warning: no cargo-binstall asset for this platform in {} {}, matching assets by name",
+            tool.name, release.tag_name
+        );
+    }
+    Ok(asset.cloned())
+}
+
 fn asset_priority(name: &str) -> u8 {
     let name = name.to_lowercase();
     if name.ends_with(".tar.gz")
         || name.ends_with(".tgz")
         || name.ends_with(".tar.bz2")
         || name.ends_with(".tbz")
+        || name.ends_with(".tar.xz")
+        || name.ends_with(".txz")
+        || name.ends_with(".tar.zst")
     {
         0 // Highest priority (all tar formats)
     } else if name.ends_with(".zip") {
@@ -48,227 +411,2028 @@ fn asset_priority(name: &str) -> u8 {
     }
 }
 
-pub async fn update_tool(
-    config: &mut Config,
-    tool_name: &str,
-    verbose: bool,
-    force: bool,
-) -> Result<()> {
-    let tool = config
-        .get_tool(tool_name)
-        .ok_or_else(|| OktofetchError::ToolNotFound(tool_name.to_string()))?
-        .clone();
+fn no_suitable_release() -> OktofetchError {
+    OktofetchError::NoSuitableRelease {
+        platform: "Linux".to_string(),
+        arch: "x86_64".to_string(),
+    }
+}
 
-    if verbose {
-        println!("Updating {} from {}", tool.name, tool.repo);
+/// Position of the first `prefer` keyword `name` contains (case-insensitive),
+/// or `prefer.len()` when it has none.
+fn preference_rank(prefer: &[String], name: &str) -> usize {
+    let name = name.to_lowercase();
+    prefer
+        .iter()
+        .position(|keyword| name.contains(&keyword.to_lowercase()))
+        .unwrap_or(prefer.len())
+}
+
+/// Whether `name` contains one of the tool's `asset_exclude` keywords.
+fn is_excluded(tool: &Tool, name: &str) -> bool {
+    let name = name.to_lowercase();
+    tool.asset_exclude
+        .iter()
+        .any(|keyword| name.contains(&keyword.to_lowercase()))
+}
+
+/// Picks the release asset to install: a configured selector script wins,
+/// then an explicit pattern, then the platform heuristics. Assets matching
+/// `asset_exclude` are never picked by the latter two. Among the heuristic
+/// matches, names containing an earlier `prefer` keyword win.
+fn select_asset<'a>(tool: &Tool, release: &'a Release, prefer: &[String]) -> Result<&'a Asset> {
+    if let Some(selector) = &tool.asset_selector {
+        let chosen = run_asset_selector(selector, &release.assets)?;
+        return release
+            .assets
+            .iter()
+            .find(|a| a.name == chosen)
+            .ok_or_else(|| {
+                OktofetchError::Other(format!(
+                    "Asset selector chose '{}', which is not in release {}",
+                    chosen, release.tag_name
+                ))
+            });
     }
 
-    // Show current version if available
-    if let Some(current_version) = &tool.version {
-        println!("Current version: {}", current_version);
-    } else {
-        println!("Current version: unknown");
+    if let Some(pattern) = &tool.asset_pattern {
+        return release
+            .assets
+            .iter()
+            .find(|a| a.name.contains(pattern) && !is_excluded(tool, &a.name))
+            .ok_or_else(no_suitable_release);
     }
 
-    // Validate platform
-    platform::validate_platform()?;
+    // Filter assets matching the platform
+    let mut matching_assets: Vec<_> = release
+        .assets
+        .iter()
+        .filter(|a| platform::matches_asset_name(&a.name) && !is_excluded(tool, &a.name))
+        .collect();
 
-    // Fetch latest release
-    let client = GithubClient::new();
-    let release = client.get_latest_release(&tool.repo).await?;
+    if matching_assets.is_empty() {
+        return Err(no_suitable_release());
+    }
 
-    println!("Latest version: {}", release.tag_name);
+    // Sort by preference, then tar.gz/tgz first, then zip, then others
+    matching_assets.sort_by_key(|a| (preference_rank(prefer, &a.name), asset_priority(&a.name)));
 
-    // Check if binary exists on disk
-    let binary_name = tool.binary_name.as_deref().unwrap_or(&tool.name);
-    let binary_path = config.settings.install_dir.join(binary_name);
-    let binary_exists = binary_path.exists();
+    Ok(matching_assets[0])
+}
 
-    if !binary_exists {
-        println!(
-            "Binary not found at {}, reinstalling...",
-            binary_path.display()
-        );
+/// How one release asset fares against a tool's selection rules.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AssetReport {
+    pub name: String,
+    pub size: u64,
+    /// Named like a build for this platform
+    pub platform: bool,
+    /// Contains the tool's `asset_pattern`, when it has one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<bool>,
+    /// Ruled out by `asset_exclude`
+    pub excluded: bool,
+    /// The asset an update would install
+    pub selected: bool,
+}
+
+/// The tool's newest release, with each of its assets assessed and the one
+/// an update would pick marked.
+pub async fn assess_release(config: &Config, tool: &Tool) -> Result<(Release, Vec<AssetReport>)> {
+    let source = source::for_tool(tool, &config.settings, &config.http())?;
+    let release = source::newest_release_for(source.as_ref(), tool).await?;
+
+    // Quickinstall builds and source tarballs aren't among the assets
+    let chosen = match choose_asset(source.as_ref(), tool, &release, &config.settings).await {
+        Ok((asset, AssetOrigin::Release)) => Some(asset.name),
+        _ => None,
+    };
+    let reports = assess_assets(tool, &release, chosen.as_deref());
+    Ok((release, reports))
+}
+
+fn assess_assets(tool: &Tool, release: &Release, chosen: Option<&str>) -> Vec<AssetReport> {
+    release
+        .assets
+        .iter()
+        .map(|asset| AssetReport {
+            name: asset.name.clone(),
+            size: asset.size,
+            platform: platform::matches_asset_name(&asset.name),
+            pattern: tool
+                .asset_pattern
+                .as_ref()
+                .map(|pattern| asset.name.contains(pattern)),
+            excluded: is_excluded(tool, &asset.name),
+            selected: chosen == Some(asset.name.as_str()),
+        })
+        .collect()
+}
+
+/// Runs a user-provided selector script, passing the assets as JSON on stdin
+/// and reading the chosen asset name from the first line of stdout.
+fn run_asset_selector(selector: &str, assets: &[Asset]) -> Result<String> {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    let program = expand_path(selector);
+    prompt::trust_command("asset selector", &program)?;
+    let input = serde_json::to_vec(assets)
+        .map_err(|e| OktofetchError::Other(format!("Failed to encode assets: {}", e)))?;
+
+    let mut child = Command::new(&program)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .map_err(|e| {
+            OktofetchError::Other(format!("Failed to run asset selector {}: {}", program, e))
+        })?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        // A selector may exit without reading everything; that's not an error
+        let _ = stdin.write_all(&input);
     }
 
-    // Check if update is needed
-    if !force
-        && binary_exists
-        && let Some(current_version) = &tool.version
-        && current_version == &release.tag_name
-    {
-        println!("{} is already up to date", tool.name);
-        return Ok(());
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(OktofetchError::Other(format!(
+            "Asset selector {} exited with {}",
+            program, output.status
+        )));
     }
 
-    if verbose {
-        println!("Found release: {}", release.tag_name);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let chosen = stdout.lines().next().unwrap_or_default().trim();
+    if chosen.is_empty() {
+        return Err(OktofetchError::Other(format!(
+            "Asset selector {} did not print an asset name",
+            program
+        )));
     }
 
-    // Find matching asset
-    let asset = if let Some(pattern) = &tool.asset_pattern {
-        release
-            .assets
-            .iter()
-            .find(|a| a.name.contains(pattern))
-            .ok_or_else(|| OktofetchError::NoSuitableRelease {
-                platform: "Linux".to_string(),
-                arch: "x86_64".to_string(),
-            })?
-    } else {
-        // Filter assets matching the platform
-        let mut matching_assets: Vec<_> = release
-            .assets
-            .iter()
-            .filter(|a| platform::matches_asset_name(&a.name))
-            .collect();
+    Ok(chosen.to_string())
+}
 
-        if matching_assets.is_empty() {
-            return Err(OktofetchError::NoSuitableRelease {
-                platform: "Linux".to_string(),
-                arch: "x86_64".to_string(),
-            });
-        }
+/// Options controlling how `update_tool`, `update_tools` and `update_all_tools` behave.
+#[derive(Debug, Clone, Default)]
+pub struct UpdateOptions {
+    pub verbose: bool,
+    /// Reinstall even if the version already matches
+    pub force: bool,
+    /// Install this version instead of the latest release
+    pub version: Option<String>,
+    /// Skip tools already installed at their recorded version, and install
+    /// that version for the others
+    pub only_missing: bool,
+    /// Tools to leave out of a bulk update
+    pub except: Vec<String>,
+    /// Ask before installing each pending update in a bulk update
+    pub interactive: bool,
+    /// Don't ask for confirmation of large downloads or asset changes
+    pub yes: bool,
+    /// Download speed cap in bytes per second, overriding `max_download_rate`
+    pub limit_rate: Option<u64>,
+    /// Stop a bulk update at the first tool that fails, returning its error
+    pub fail_fast: bool,
+    /// Skip the tools an interrupted `update --all` already finished
+    pub resume: bool,
+    /// Seconds within which a tool's last check is trusted instead of
+    /// asking upstream again, overriding `min_check_interval`
+    pub min_check_interval: Option<u64>,
+    /// Store `version` side by side in its own directory instead of
+    /// replacing the installed binary
+    pub keep: bool,
+}
 
-        // Sort by priority: tar.gz/tgz first, then zip, then others
-        matching_assets.sort_by_key(|a| asset_priority(&a.name));
+/// Stages of an update, in the order they run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    /// Resolve the release to install
+    Fetch,
+    /// Download the asset into the downloads cache
+    Download,
+    /// Check the downloaded file before touching it
+    Verify,
+    /// Unpack the asset and locate the binary
+    Extract,
+    /// Move the binary into place and record it in the config
+    Install,
+}
 
-        matching_assets[0]
-    };
+impl std::fmt::Display for Stage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Fetch => "fetch",
+            Self::Download => "download",
+            Self::Verify => "verify",
+            Self::Extract => "extract",
+            Self::Install => "install",
+        })
+    }
+}
 
-    if verbose {
-        println!("Selected asset: {}", asset.name);
+/// Tracks which stage an update is in so failures can name it.
+struct Pipeline {
+    stage: Stage,
+    verbose: bool,
+}
+
+impl Pipeline {
+    fn enter(&mut self, stage: Stage) {
+        self.stage = stage;
+        if self.verbose {
+            println!("[{}]", stage);
+        }
     }
+}
 
-    // Download to temp directory
-    let temp_dir = TempDir::new()?;
-    let archive_path = temp_dir.path().join(&asset.name);
+/// A release resolved for a tool, waiting to be downloaded and installed.
+struct PendingUpdate {
+    source: Box<dyn source::ReleaseSource>,
+    release: Release,
+    asset: Asset,
+    target_version: String,
+    /// Build command to run on the downloaded source tarball
+    build: Option<String>,
+}
 
-    println!("Downloading {}...", asset.name);
-    client
-        .download_asset(&asset.browser_download_url, &archive_path)
-        .await?;
+/// Answers accepted by the interactive update prompt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Answer {
+    Yes,
+    No,
+    /// Approve this and every remaining update
+    All,
+    /// Stop without updating anything else
+    Quit,
+}
 
-    // Extract archive
-    if verbose {
-        println!("Extracting archive...");
+fn parse_answer(input: &str) -> Option<Answer> {
+    match input.trim().to_lowercase().as_str() {
+        "y" | "yes" => Some(Answer::Yes),
+        "n" | "no" => Some(Answer::No),
+        "a" | "all" => Some(Answer::All),
+        "q" | "quit" => Some(Answer::Quit),
+        _ => None,
     }
-    let extracted_files = archive::extract_archive(&archive_path, temp_dir.path())?;
+}
 
-    // Find binary
-    let binary_name = tool.binary_name.as_deref().unwrap_or(&tool.name);
-    let binary_path = binary::find_binary(&extracted_files, temp_dir.path(), binary_name)?;
+/// Confirmation state carried across the tools of an interactive update.
+#[derive(Debug, Default)]
+struct Review {
+    approve_all: bool,
+    quit: bool,
+}
 
-    if verbose {
-        println!("Found binary: {}", binary_path.display());
+impl Review {
+    /// Starts a review, which `--yes` approves throughout and which can't
+    /// run without prompts.
+    fn start() -> Result<Self> {
+        match prompt::mode() {
+            prompt::Mode::Ask => Ok(Self::default()),
+            prompt::Mode::AssumeYes => Ok(Self {
+                approve_all: true,
+                quit: false,
+            }),
+            prompt::Mode::NonInteractive => Err(OktofetchError::InputRequired(
+                "update --interactive asks about each update".to_string(),
+            )),
+        }
     }
 
-    // Install binary
-    let dest = binary::install_binary(&binary_path, &config.settings.install_dir, binary_name)?;
+    /// Shows a pending update and asks whether to install it.
+    fn approve(&mut self, tool: &Tool, pending: &PendingUpdate) -> Result<bool> {
+        self.approve_with(&mut std::io::stdin().lock(), tool, pending)
+    }
 
-    // Update version in config
-    config.update_tool_version(&tool.name, release.tag_name.clone())?;
-    config.save()?;
+    fn approve_with(
+        &mut self,
+        input: &mut impl std::io::BufRead,
+        tool: &Tool,
+        pending: &PendingUpdate,
+    ) -> Result<bool> {
+        use std::io::Write;
 
-    println!("Installed {} to {}", tool.name, dest.display());
-    Ok(())
-}
+        if self.approve_all {
+            return Ok(true);
+        }
 
-pub async fn update_all_tools(config: &mut Config, verbose: bool, force: bool) -> Result<()> {
-    let mut success = 0;
-    let mut failed = 0;
+        println!(
+            "{}: {} -> {}",
+            tool.name,
+            tool.version.as_deref().unwrap_or("not installed"),
+            pending.target_version
+        );
+        println!(
+            "  asset: {} ({})",
+            pending.asset.name,
+            human::format_size(pending.asset.size)
+        );
+        if let Some(build) = &pending.build {
+            println!("  build: {}", build);
+        }
 
-    let tool_names: Vec<String> = config.tools.iter().map(|t| t.name.clone()).collect();
+        loop {
+            print!("Update {}? [y]es/[n]o/[a]ll/[q]uit: ", tool.name);
+            std::io::stdout().flush()?;
 
-    for tool_name in tool_names {
-        match update_tool(config, &tool_name, verbose, force).await {
-            Ok(_) => success += 1,
-            Err(e) => {
-                eprintln!("Failed to update {}: {}", tool_name, e);
-                failed += 1;
+            let mut line = String::new();
+            if input.read_line(&mut line)? == 0 {
+                // End of input: treat like quit rather than looping forever
+                self.quit = true;
+                return Ok(false);
+            }
+
+            match parse_answer(&line) {
+                Some(Answer::Yes) => return Ok(true),
+                Some(Answer::No) => return Ok(false),
+                Some(Answer::All) => {
+                    self.approve_all = true;
+                    return Ok(true);
+                }
+                Some(Answer::Quit) => {
+                    self.quit = true;
+                    return Ok(false);
+                }
+                None => println!("Please answer y, n, a or q"),
             }
         }
     }
-
-    println!("\nSummary: {} updated, {} failed", success, failed);
-    Ok(())
 }
 
-pub fn remove_tool(config: &mut Config, tool_name: &str) -> Result<()> {
-    config.remove_tool(tool_name)?;
-    config.save()?;
-    println!("Removed tool '{}'", tool_name);
-    println!(
-        "Note: Binary in {} not removed",
-        config.settings.install_dir.display()
+/// Asks before downloading an asset above the configured size threshold.
+/// Anything but an explicit yes, including end of input, declines.
+fn confirm_large_download(input: &mut impl std::io::BufRead, asset: &Asset) -> Result<bool> {
+    let question = format!(
+        "{} is {}. Download it?",
+        asset.name,
+        human::format_size(asset.size)
     );
-    Ok(())
+    prompt::confirm(input, &question)
 }
 
-pub fn list_tools(config: &Config) -> Result<()> {
-    if config.tools.is_empty() {
-        println!("No tools configured.");
-        println!("Add a tool with: oktofetch add <github-repo>");
-        return Ok(());
-    }
+/// Asks before installing an asset shaped differently from the last one.
+/// Anything but an explicit yes, including end of input, declines.
+fn confirm_asset_change(input: &mut impl std::io::BufRead, asset: &Asset) -> Result<bool> {
+    prompt::confirm(input, &format!("Install {} anyway?", asset.name))
+}
 
-    println!("Configured tools:\n");
-    for tool in &config.tools {
-        let version_str = tool
-            .version
-            .as_ref()
-            .map(|v| format!(" ({})", v))
-            .unwrap_or_default();
-        println!("  {:<20} {}{}", tool.name, tool.repo, version_str);
-        if let Some(binary) = &tool.binary_name {
-            println!("  {:<20} binary: {}", "", binary);
-        }
+/// Asks before running a build command that came from a config other than
+/// the user's own. Anything but an explicit yes, including end of input,
+/// declines.
+fn confirm_build(
+    input: &mut impl std::io::BufRead,
+    tool: &Tool,
+    command: &str,
+    config_path: &Path,
+) -> Result<bool> {
+    let question = format!(
+        "{} is built from source with `{}` from {}. Run it?",
+        tool.name,
+        command,
+        config_path.display()
+    );
+    prompt::confirm(input, &question)
+}
+
+/// How the asset named `next` differs from `previous` beyond its version,
+/// e.g. `musl -> gnu`, or None when only version numbers changed.
+fn asset_change(previous: &str, next: &str) -> Option<String> {
+    let previous = asset_parts(previous);
+    let next = asset_parts(next);
+    if previous
+        .iter()
+        .map(|(shape, _)| shape)
+        .eq(next.iter().map(|(shape, _)| shape))
+    {
+        return None;
     }
 
-    Ok(())
+    let missing = |parts: &[(String, String)], other: &[(String, String)]| {
+        parts
+            .iter()
+            .filter(|(shape, _)| !other.iter().any(|(s, _)| s == shape))
+            .map(|(_, part)| part.as_str())
+            .collect::<Vec<_>>()
+            .join("-")
+    };
+    let removed = missing(&previous, &next);
+    let added = missing(&next, &previous);
+    Some(match (removed.is_empty(), added.is_empty()) {
+        (false, false) => format!("{} -> {}", removed, added),
+        (false, true) => format!("no longer {}", removed),
+        (true, false) => format!("now {}", added),
+        (true, true) => "parts reordered".to_string(),
+    })
 }
 
-fn parse_repo(input: &str) -> Result<String> {
-    // Handle full GitHub URLs
-    if input.starts_with("http://") || input.starts_with("https://") {
-        let url = input
-            .trim_start_matches("https://")
-            .trim_start_matches("http://");
-        let parts: Vec<&str> = url.split('/').collect();
+/// Parts of an asset name split at `-`, `_` and `.`, each paired with its
+/// shape: lowercased with digit runs collapsed, so versions compare equal.
+/// A version's numbers stay one part however many there are.
+fn asset_parts(name: &str) -> Vec<(String, String)> {
+    let mut parts: Vec<(String, String)> = Vec::new();
+    for part in name.split(['-', '_', '.']).filter(|part| !part.is_empty()) {
+        let mut shape = String::new();
+        for c in part.to_lowercase().chars() {
+            if !c.is_ascii_digit() {
+                shape.push(c);
+            } else if !shape.ends_with('#') {
+                shape.push('#');
+            }
+        }
 
-        if parts.len() >= 3 && parts[0] == "github.com" {
-            return Ok(format!("{}/{}", parts[1], parts[2]));
+        match parts.last_mut() {
+            Some((last, text)) if shape == "#" && (last == "#" || last == "v#") => {
+                text.push('.');
+                text.push_str(part);
+            }
+            _ => parts.push((shape, part.to_string())),
         }
     }
+    parts
+}
 
-    // Validate owner/repo format
-    if input.split('/').count() == 2 {
-        return Ok(input.to_string());
-    }
+pub async fn update_tool(config: &mut Config, tool_name: &str, opts: &UpdateOptions) -> Result<()> {
+    update_reviewed(config, tool_name, opts, None)
+        .await
+        .map(|_| ())
+}
 
-    Err(OktofetchError::Other(format!(
-        "Invalid repository format: {}. Expected 'owner/repo' or GitHub URL",
-        input
-    )))
+/// What became of a tool that an update run got through without errors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum UpdateOutcome {
+    /// Installed a new release, downloading this many bytes (0 if unknown)
+    Updated(u64),
+    UpToDate,
+    /// Left alone: already installed, or declined when asked
+    Skipped,
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+async fn update_reviewed(
+    config: &mut Config,
+    tool_name: &str,
+    opts: &UpdateOptions,
+    review: Option<&mut Review>,
+) -> Result<UpdateOutcome> {
+    let tool = config
+        .get_tool(tool_name)
+        .ok_or_else(|| OktofetchError::ToolNotFound(tool_name.to_string()))?
+        .clone();
 
-    #[test]
-    fn test_asset_priority() {
-        // Test tar.gz variants (highest priority)
-        assert_eq!(asset_priority("myapp.tar.gz"), 0);
-        assert_eq!(asset_priority("myapp.tgz"), 0);
-        assert_eq!(asset_priority("MYAPP.TAR.GZ"), 0); // Case insensitive
-        assert_eq!(asset_priority("MYAPP.TGZ"), 0);
+    events::emit(&Event::Started {
+        tool: &tool.name,
+        version: tool.version.as_deref(),
+    });
+    let outcome = update_checked(config, &tool, opts, review).await;
 
-        // Test zip (second priority)
-        assert_eq!(asset_priority("myapp.zip"), 1);
-        assert_eq!(asset_priority("MYAPP.ZIP"), 1);
+    let version = config
+        .get_tool(tool_name)
+        .and_then(|t| t.version.as_deref());
+    events::emit(&match &outcome {
+        Ok(UpdateOutcome::Updated(_)) => Event::Installed {
+            tool: &tool.name,
+            version,
+        },
+        Ok(UpdateOutcome::UpToDate) => Event::UpToDate {
+            tool: &tool.name,
+            version,
+        },
+        Ok(UpdateOutcome::Skipped) => Event::Skipped { tool: &tool.name },
+        Err(e) => Event::Failed {
+            tool: &tool.name,
+            error: e.to_string(),
+        },
+    });
+    outcome
+}
 
-        // Test other formats (lowest priority)
+async fn update_checked(
+    config: &mut Config,
+    tool: &Tool,
+    opts: &UpdateOptions,
+    review: Option<&mut Review>,
+) -> Result<UpdateOutcome> {
+    if opts.only_missing && is_installed(config, tool) {
+        println!("{} is already installed, skipping", tool.name);
+        return Ok(UpdateOutcome::Skipped);
+    }
+    if let Some(checked_at) = recently_checked(config, tool, opts) {
+        println!(
+            "{} was checked {}, skipping",
+            tool.name,
+            human::format_age(checked_at, human::unix_now())
+        );
+        return Ok(UpdateOutcome::UpToDate);
+    }
+
+    let mut pipeline = Pipeline {
+        stage: Stage::Fetch,
+        verbose: opts.verbose,
+    };
+
+    run_update(config, tool, opts, &mut pipeline, review)
+        .await
+        .inspect_err(|_| {
+            eprintln!(
+                "Update of {} stopped at the {} stage",
+                tool.name, pipeline.stage
+            )
+        })
+}
+
+async fn run_update(
+    config: &mut Config,
+    tool: &Tool,
+    opts: &UpdateOptions,
+    pipeline: &mut Pipeline,
+    review: Option<&mut Review>,
+) -> Result<UpdateOutcome> {
+    let Some(pending) = plan_update(config, tool, opts, pipeline).await? else {
+        if let Some(entry) = config.get_tool_mut(&tool.name) {
+            entry.checked_at = Some(human::unix_now());
+        }
+        config.save()?;
+        return Ok(UpdateOutcome::UpToDate);
+    };
+
+    // A kept version doesn't replace the installed asset
+    let previous_asset = tool.asset_name.as_deref().filter(|_| !opts.keep);
+    let change = previous_asset.and_then(|previous| asset_change(previous, &pending.asset.name));
+    if let (Some(previous), Some(change)) = (previous_asset, &change) {
+        eprintln!(
+            "Warning: {} now picks {} instead of {} ({}); check asset_pattern if this is not what you want",
+            tool.name, pending.asset.name, previous, change
+        );
+    }
+
+    let approved = match review {
+        // The review prompt already showed the size and the warning
+        Some(review) => review.approve(tool, &pending)?,
+        None => {
+            let stdin = &mut std::io::stdin().lock();
+            let large = matches!(
+                config.settings.confirm_download_size,
+                Some(threshold) if pending.asset.size > threshold
+            );
+            let changed = change.is_some() && config.settings.confirm_asset_change == Some(true);
+            // Only the user's own config is trusted to run shell commands
+            let untrusted = Config::untrusted_path();
+            let build = pending.build.as_deref().zip(untrusted);
+            if opts.yes || prompt::assume_yes() || (!large && !changed && build.is_none()) {
+                true
+            } else if !prompt::can_ask() {
+                // Declining is the safe answer when nobody is there to give one
+                eprintln!(
+                    "Not installing {} without confirmation; pass --yes to allow it",
+                    pending.asset.name
+                );
+                false
+            } else {
+                (!large || confirm_large_download(stdin, &pending.asset)?)
+                    && (!changed || confirm_asset_change(stdin, &pending.asset)?)
+                    && match build {
+                        Some((command, config_path)) => {
+                            confirm_build(stdin, tool, command, config_path)?
+                        }
+                        None => true,
+                    }
+            }
+        }
+    };
+    if !approved {
+        println!("Skipped {}", tool.name);
+        config.save()?;
+        return Ok(UpdateOutcome::Skipped);
+    }
+
+    let downloaded = pending.asset.size;
+    apply_update(config, tool, pending, opts, pipeline).await?;
+    Ok(UpdateOutcome::Updated(downloaded))
+}
+
+/// When the tool was last checked, if that was within the minimum check
+/// interval and the check can be trusted: the installed binary is still
+/// there and no particular version or reinstall was asked for.
+fn recently_checked(config: &Config, tool: &Tool, opts: &UpdateOptions) -> Option<u64> {
+    let interval = opts.min_check_interval.filter(|&secs| secs > 0)?;
+    let checked_at = tool.checked_at?;
+    if opts.force
+        || opts.version.is_some()
+        || human::unix_now().saturating_sub(checked_at) >= interval
+        || !config
+            .install_dir_for(tool)
+            .join(tool.installed_name())
+            .exists()
+    {
+        return None;
+    }
+    Some(checked_at)
+}
+
+/// Resolves the release and asset to install, or `None` when the tool is
+/// already up to date.
+async fn plan_update(
+    config: &mut Config,
+    tool: &Tool,
+    opts: &UpdateOptions,
+    pipeline: &mut Pipeline,
+) -> Result<Option<PendingUpdate>> {
+    let verbose = opts.verbose;
+
+    if verbose {
+        println!("Updating {} from {}", tool.name, tool.release_repo());
+    }
+
+    // Show current version if available
+    if let Some(current_version) = &tool.version {
+        println!("Current version: {}", current_version);
+    } else {
+        println!("Current version: unknown");
+    }
+
+    // Validate platform
+    platform::validate_platform()?;
+
+    pipeline.enter(Stage::Fetch);
+    let mut settings = config.settings.clone();
+    if opts.limit_rate.is_some() {
+        settings.max_download_rate = opts.limit_rate;
+    }
+    let mut source = source::for_tool(tool, &settings, &config.http())?;
+    source.set_verbose(verbose);
+    if verbose {
+        println!("Using release source: {}", source.name());
+    }
+    // The batch lookup already told the tools it covered
+    if !config.latest_tags.contains_key(&tool.name) {
+        record_archived(config, source.as_ref(), tool).await;
+    }
+
+    // Check if binary exists on disk
+    let binary_path = config.install_dir_for(tool).join(tool.installed_name());
+    let binary_exists = binary_path.exists();
+
+    if !binary_exists {
+        println!(
+            "Binary not found at {}, reinstalling...",
+            binary_path.display()
+        );
+    }
+
+    // Skip the release lookup when the batch lookup or the feed shows
+    // nothing new was published
+    if !opts.force
+        && binary_exists
+        && opts.version.is_none()
+        && (unchanged_per_batch(config, tool)
+            || source::unchanged_per_hint(source.as_ref(), tool).await)
+    {
+        println!("{} is already up to date", tool.name);
+        return Ok(None);
+    }
+
+    let release = fetch_release(source.as_ref(), tool, opts).await?;
+    let target_version = tool.version_from_tag(&release.tag_name);
+
+    // Check if update is needed
+    if !opts.force
+        && binary_exists
+        && let Some(current_version) = &tool.version
+        && current_version == &target_version
+    {
+        println!("{} is already up to date", tool.name);
+        return Ok(None);
+    }
+
+    if verbose {
+        println!("Found release: {}", release.tag_name);
+    }
+
+    // Find matching asset
+    let (asset, origin) = choose_asset(source.as_ref(), tool, &release, &config.settings).await?;
+
+    // No need to download an asset the release already says won't match
+    if let (Some(pinned), Some(published)) = (tool.sha256.as_deref(), asset.sha256())
+        && !pinned.eq_ignore_ascii_case(published)
+    {
+        return Err(pin_mismatch(&asset, published, pinned));
+    }
+    check_verification_policy(
+        config.settings.verification.unwrap_or_default(),
+        tool,
+        &asset,
+    )?;
+
+    if verbose {
+        println!("Selected asset: {}", asset.name);
+    }
+    match origin {
+        AssetOrigin::Release => {}
+        AssetOrigin::Quickinstall => println!(
+            "No binary for this platform in {}, using quickinstall's {}",
+            release.tag_name, asset.name
+        ),
+        AssetOrigin::Source => println!(
+            "No binary for this platform in {}, building from source",
+            release.tag_name
+        ),
+    }
+
+    Ok(Some(PendingUpdate {
+        source,
+        release,
+        asset,
+        target_version,
+        build: tool.build.clone().filter(|_| origin == AssetOrigin::Source),
+    }))
+}
+
+/// Downloads, verifies, extracts and installs a planned update.
+async fn apply_update(
+    config: &mut Config,
+    tool: &Tool,
+    pending: PendingUpdate,
+    opts: &UpdateOptions,
+    pipeline: &mut Pipeline,
+) -> Result<()> {
+    let verbose = opts.verbose;
+    let PendingUpdate {
+        source,
+        release,
+        asset,
+        target_version,
+        build,
+    } = pending;
+
+    pipeline.enter(Stage::Download);
+    let cache_dir = download_cache_dir(&Config::downloads_dir()?, tool, &release);
+    let download = download_to_cache(source.as_ref(), &asset, &cache_dir, tool.sha256.as_deref())
+        .await
+        .with_context(|| format!("while downloading {} for {}", asset.name, tool.name))?;
+    let download_path = download.path;
+
+    pipeline.enter(Stage::Verify);
+    let asset_sha256 = verify_download(
+        &asset,
+        &download_path,
+        tool.sha256.as_deref(),
+        download.sha256.as_deref(),
+    )
+    .with_context(|| format!("while verifying {}", asset.name))?;
+
+    pipeline.enter(Stage::Extract);
+    let scratch = TempDir::new_in(&cache_dir)?;
+    let decoded = match build {
+        // Builds and bundles need the extracted tree, kept versions their
+        // own directory
+        Some(_) => None,
+        None if tool.bundle || opts.keep => None,
+        None => {
+            let install_dir = config.install_dir_for(tool);
+            std::fs::create_dir_all(&install_dir)?;
+            archive::decode_single_binary(
+                &download_path,
+                &install_dir,
+                &config.settings.extract_limits(),
+            )
+            .with_context(|| format!("while decoding {}", asset.name))?
+        }
+    };
+    let staged = match decoded {
+        Some(decoded) => {
+            if verbose {
+                println!("Decoded {} straight into the install directory", asset.name);
+            }
+            Staged::Decoded(decoded)
+        }
+        None => {
+            let binary = extract_binary(
+                config,
+                tool,
+                &asset,
+                &download_path,
+                scratch.path(),
+                build.as_deref(),
+                verbose,
+            )?;
+            if tool.bundle {
+                Staged::Bundled {
+                    tree: scratch.path().to_path_buf(),
+                    binary,
+                }
+            } else {
+                Staged::Extracted(binary)
+            }
+        }
+    };
+
+    pipeline.enter(Stage::Install);
+    if opts.keep {
+        let versions_dir = Config::versions_dir()?.join(&tool.name);
+        let path = keep_version(config, tool, staged, &target_version, &versions_dir)
+            .with_context(|| format!("while keeping {} {}", tool.name, target_version))?;
+        config.save()?;
+        drop(scratch);
+        std::fs::remove_dir_all(&cache_dir)?;
+        println!(
+            "Kept {} {} as {}",
+            tool.name,
+            target_version,
+            path.display()
+        );
+        // A bundle's binary sits somewhere inside the release tree
+        if let Some(dir) = path.parent() {
+            println!("Put {} on PATH to run it", dir.display());
+        }
+        return Ok(());
+    }
+
+    let install_dir = config.install_dir_for(tool);
+    let first_install = !install_dir.join(tool.installed_name()).exists();
+    let origin = Origin {
+        version: target_version,
+        asset: &asset,
+        asset_sha256,
+        released_at: release
+            .published_at
+            .as_deref()
+            .and_then(human::parse_timestamp),
+    };
+    let dest = install(config, tool, staged, origin, verbose).with_context(|| {
+        format!(
+            "while installing {} to {}",
+            tool.name,
+            install_dir.display()
+        )
+    })?;
+
+    // The download is only kept around so a failed update can be retried
+    drop(scratch);
+    std::fs::remove_dir_all(&cache_dir)?;
+
+    println!("Installed {} to {}", tool.name, dest.display());
+    if first_install {
+        warn_path_collisions(&install_dir, tool.installed_name());
+    }
+    Ok(())
+}
+
+/// A binary ready to be installed.
+enum Staged {
+    /// Found among the files extracted into a scratch directory
+    Extracted(PathBuf),
+    /// Decoded straight into a temp file in the install directory
+    Decoded(tempfile::NamedTempFile),
+    /// The binary inside an extracted tree that is installed whole
+    Bundled { tree: PathBuf, binary: PathBuf },
+}
+
+/// Extracts a downloaded archive into `scratch`, or builds it from source
+/// with `build`, and returns the binary. Shell completions found along the
+/// way are installed if configured.
+fn extract_binary(
+    config: &Config,
+    tool: &Tool,
+    asset: &Asset,
+    download_path: &Path,
+    scratch: &Path,
+    build: Option<&str>,
+    verbose: bool,
+) -> Result<PathBuf> {
+    let extracted_files =
+        archive::extract_archive(download_path, scratch, &config.settings.extract_limits())
+            .with_context(|| format!("while extracting {}", asset.name))?;
+    let binary_path = match build {
+        Some(command) => {
+            let source_dir = source_build::source_root(scratch)?;
+            let prefix = scratch.join(".oktofetch-prefix");
+            source_build::build(command, &source_dir, &prefix, tool.binary_file_name())
+                .with_context(|| format!("while building {} from source", tool.name))?
+        }
+        None => binary::find_binary(&extracted_files, scratch, tool.binary_file_name())
+            .with_context(|| format!("while looking for the binary in {}", asset.name))?,
+    };
+
+    if verbose {
+        println!("Found binary: {}", binary_path.display());
+    }
+
+    if config.settings.install_completions == Some(true) {
+        let installed = completions::install(
+            &extracted_files,
+            scratch,
+            tool.binary_file_name(),
+            tool.installed_name(),
+            &Config::completions_dir()?,
+        )
+        .with_context(|| format!("while installing shell completions for {}", tool.name))?;
+        for path in installed {
+            println!("Installed completion {}", path.display());
+        }
+    }
+
+    Ok(binary_path)
+}
+
+/// Releases older than this mark a tool as possibly unmaintained.
+const STALE_RELEASE_AGE: u64 = 2 * 365 * 86400;
+
+/// Why a tool looks unmaintained upstream, if it does: its repository is
+/// archived or its newest release is years old.
+pub fn unmaintained_reason(tool: &Tool, now: u64) -> Option<String> {
+    if tool.archived {
+        return Some("repository archived".to_string());
+    }
+    let released_at = tool.released_at?;
+    (now.saturating_sub(released_at) >= STALE_RELEASE_AGE)
+        .then(|| format!("last release {}", human::format_age(released_at, now)))
+}
+
+/// Records what a batch lookup learned about each tool's repository, and
+/// says so when one has been archived since the last look.
+fn record_snapshots(config: &mut Config, snapshots: &HashMap<String, RepoSnapshot>) {
+    for (name, snapshot) in snapshots {
+        let Some(entry) = config.get_tool_mut(name) else {
+            continue;
+        };
+        set_archived(entry, snapshot.archived);
+        entry.released_at = entry.released_at.max(snapshot.released_at);
+    }
+}
+
+/// Asks the tool's source whether its repository is archived and records
+/// the answer, if there is one.
+async fn record_archived(config: &mut Config, source: &dyn source::ReleaseSource, tool: &Tool) {
+    let Some(archived) = source.archived(tool.release_repo()).await else {
+        return;
+    };
+    if let Some(entry) = config.get_tool_mut(&tool.name) {
+        set_archived(entry, archived);
+    }
+}
+
+/// Says so when a tool's repository has been archived since the last look.
+fn set_archived(entry: &mut Tool, archived: bool) {
+    if archived && !entry.archived {
+        println!(
+            "Note: {} is archived upstream and won't get new releases",
+            entry.release_repo()
+        );
+    }
+    entry.archived = archived;
+}
+
+/// Whether the tag looked up for the tool ahead of a bulk update is its
+/// installed version.
+fn unchanged_per_batch(config: &Config, tool: &Tool) -> bool {
+    match (config.latest_tags.get(&tool.name), &tool.version) {
+        (Some(tag), Some(installed)) => {
+            &tool.version_from_tag(tag) == installed && tool.accepts_version(installed)
+        }
+        _ => false,
+    }
+}
+
+/// Looks up the latest releases of the named tools with one GraphQL query,
+/// so that tools which are up to date need no REST call of their own, and
+/// learns which repositories were archived on the way. Only tools installed
+/// from GitHub on the `latest` channel take part. The result, keyed by tool
+/// name, is empty without a token, which GraphQL requires, or if the query
+/// fails, and every tool is checked on its own as before.
+async fn batch_lookup(
+    config: &Config,
+    names: &[String],
+    opts: &UpdateOptions,
+) -> HashMap<String, RepoSnapshot> {
+    // Neither a forced update nor restoring missing binaries asks for the latest
+    if opts.force || opts.only_missing {
+        return HashMap::new();
+    }
+    let tools: Vec<&Tool> = names
+        .iter()
+        .filter_map(|name| config.get_tool(name))
+        .filter(|tool| {
+            tool.version.is_some()
+                && recently_checked(config, tool, opts).is_none()
+                && tool.is_github()
+                && tool.channel.unwrap_or_default() == Channel::Latest
+        })
+        .collect();
+    if tools.len() < 2 {
+        return HashMap::new();
+    }
+    let client = match source::github_client(&config.settings, &config.http()) {
+        Ok(client) if client.has_token() => client,
+        _ => return HashMap::new(),
+    };
+
+    let repos: Vec<&str> = tools.iter().map(|tool| tool.release_repo()).collect();
+    match client.repo_snapshots(&repos).await {
+        Ok(snapshots) => tools
+            .iter()
+            .filter_map(|tool| {
+                let snapshot = snapshots.get(tool.release_repo())?;
+                Some((tool.name.clone(), snapshot.clone()))
+            })
+            .collect(),
+        Err(e) => {
+            if opts.verbose {
+                eprintln!(
+                    "Batch release lookup failed, checking tools one by one: {}",
+                    e
+                );
+            }
+            HashMap::new()
+        }
+    }
+}
+
+/// Resolves the requested version, or the newest release on the tool's channel.
+async fn fetch_release(
+    source: &dyn source::ReleaseSource,
+    tool: &Tool,
+    opts: &UpdateOptions,
+) -> Result<Release> {
+    // Restoring a missing binary brings back the version that was recorded
+    let restore = opts.only_missing.then_some(tool.version.as_ref()).flatten();
+    if let Some(version) = opts.version.as_ref().or(restore) {
+        let tag = tool.tag_for_version(version);
+        let release = source.release_by_tag(tool.release_repo(), &tag).await?;
+        println!("Requested version: {}", release.tag_name);
+        Ok(release)
+    } else {
+        let release = source::newest_release_for(source, tool).await?;
+        match &tool.version_req {
+            Some(requirement) => println!(
+                "Newest version matching {}: {}",
+                requirement, release.tag_name
+            ),
+            None => println!("Latest version: {}", release.tag_name),
+        }
+        Ok(release)
+    }
+}
+
+/// Where a release's downloads are kept: `<downloads>/<tool>/<tag>`, the
+/// tag encoded as for the versions directory.
+fn download_cache_dir(downloads_dir: &Path, tool: &Tool, release: &Release) -> PathBuf {
+    binary::version_dir(&downloads_dir.join(&tool.name), &release.tag_name)
+}
+
+/// A complete download in the cache.
+struct Download {
+    path: PathBuf,
+    /// SHA256 of the file, if it was computed on the way
+    sha256: Option<String>,
+}
+
+/// Downloads `asset` into `cache_dir`, reusing a complete earlier download.
+/// Data is written to a `.part` file and only renamed once it is complete.
+async fn download_to_cache(
+    source: &dyn source::ReleaseSource,
+    asset: &Asset,
+    cache_dir: &Path,
+    pinned: Option<&str>,
+) -> Result<Download> {
+    std::fs::create_dir_all(cache_dir)?;
+    let path = cache_dir.join(&asset.name);
+
+    if path.is_file()
+        && let Ok(sha256) = verify_download(asset, &path, pinned, None)
+    {
+        println!("Using cached download of {}", asset.name);
+        return Ok(Download { path, sha256 });
+    }
+
+    let partial = cache_dir.join(format!("{}.part", asset.name));
+    println!(
+        "Downloading {} ({})...",
+        asset.name,
+        human::format_size(asset.size)
+    );
+    let sha256 = source.download(asset, &partial).await?;
+    std::fs::rename(&partial, &path)?;
+
+    Ok(Download { path, sha256 })
+}
+
+/// Checks a downloaded asset against what the release advertised. A file
+/// that fails is deleted so the next attempt downloads it again.
+/// `pinned` is the SHA256 the tool's config requires, if any, and `sha256`
+/// the one computed while downloading, which spares reading the file again.
+/// Returns the file's SHA256 if it is known by the end.
+fn verify_download(
+    asset: &Asset,
+    path: &Path,
+    pinned: Option<&str>,
+    sha256: Option<&str>,
+) -> Result<Option<String>> {
+    let size = std::fs::metadata(path)?.len();
+
+    if asset.size > 0 && size != asset.size {
+        let _ = std::fs::remove_file(path);
+        return Err(OktofetchError::DownloadFailed(format!(
+            "{} is {} bytes, expected {}",
+            asset.name, size, asset.size
+        )));
+    }
+
+    if asset.sha256().is_none() && pinned.is_none() {
+        return Ok(sha256.map(str::to_string));
+    }
+    let actual = match sha256 {
+        Some(sha256) => sha256.to_string(),
+        None => binary::sha256_file(path)?,
+    };
+
+    if let Some(expected) = asset.sha256()
+        && !actual.eq_ignore_ascii_case(expected)
+    {
+        let _ = std::fs::remove_file(path);
+        return Err(OktofetchError::DownloadFailed(format!(
+            "{} has SHA256 {}, but the release lists {}",
+            asset.name, actual, expected
+        )));
+    }
+
+    if let Some(expected) = pinned
+        && !actual.eq_ignore_ascii_case(expected)
+    {
+        let _ = std::fs::remove_file(path);
+        return Err(pin_mismatch(asset, &actual, expected));
+    }
+
+    Ok(Some(actual))
+}
+
+/// Refuses an asset that can't be verified as strictly as `policy` demands,
+/// before anything is downloaded. oktofetch has no way to configure signing
+/// keys yet, so `signature` fails closed for every tool.
+fn check_verification_policy(policy: Verification, tool: &Tool, asset: &Asset) -> Result<()> {
+    match policy {
+        Verification::Off => Ok(()),
+        Verification::Checksum if asset.sha256().is_some() || tool.sha256.is_some() => Ok(()),
+        Verification::Checksum => Err(OktofetchError::DownloadFailed(format!(
+            "{} has no published SHA256 and {} has no sha256 pinned, but verification \
+             is set to checksum. Pin one with 'oktofetch set {} sha256 <digest>'",
+            asset.name, tool.name, tool.name
+        ))),
+        Verification::Signature => Err(OktofetchError::DownloadFailed(format!(
+            "No signing keys/attestations configured for {}, but verification is set \
+             to signature. Refusing to install {} unverified",
+            tool.name, asset.name
+        ))),
+    }
+}
+
+fn pin_mismatch(asset: &Asset, actual: &str, expected: &str) -> OktofetchError {
+    OktofetchError::DownloadFailed(format!(
+        "{} has SHA256 {}, but the tool is pinned to {}. Update or unset its sha256 \
+         to accept a different asset",
+        asset.name, actual, expected
+    ))
+}
+
+/// Where a binary being installed came from, as recorded with it.
+struct Origin<'a> {
+    version: String,
+    asset: &'a Asset,
+    /// SHA256 computed while downloading, standing in for the asset's
+    /// digest when GitHub didn't publish one
+    asset_sha256: Option<String>,
+    /// Unix time the release was published
+    released_at: Option<u64>,
+}
+
+/// Installs the extracted binary, retains a copy if configured, and records
+/// the new version and checksum along with its origin. A bundle is moved to
+/// the versions directory and run through a wrapper script instead. A binary that fails
+/// its smoke test is replaced by the one it overwrote (or removed on a first
+/// install) and nothing is recorded.
+fn install(
+    config: &mut Config,
+    tool: &Tool,
+    staged: Staged,
+    origin: Origin,
+    verbose: bool,
+) -> Result<PathBuf> {
+    let Origin {
+        version: target_version,
+        asset,
+        asset_sha256,
+        released_at,
+    } = origin;
+    let binary_name = tool.installed_name();
+    let install_dir = &config.install_dir_for(tool);
+    let replacing = install_dir.join(binary_name).is_file();
+    let versions_dir = Config::versions_dir()?.join(&tool.name);
+    // A bundle replacing one of the same version keeps the old tree aside
+    // until the new one has passed its smoke test
+    let mut bundle = None;
+    let dest = match staged {
+        Staged::Extracted(path) => binary::install_binary(&path, install_dir, binary_name)?,
+        Staged::Decoded(decoded) => binary::install_decoded(decoded, install_dir, binary_name)?,
+        Staged::Bundled { tree, binary } => {
+            let bundle_dir = binary::version_dir(&versions_dir, &target_version);
+            let stored = binary::store_bundle(&tree, &binary, &bundle_dir)?;
+            if verbose {
+                println!("Stored bundle in {}", bundle_dir.display());
+            }
+            match binary::install_wrapper(&stored.binary, install_dir, binary_name) {
+                Ok(dest) => {
+                    bundle = Some(stored);
+                    dest
+                }
+                Err(e) => {
+                    stored.roll_back()?;
+                    return Err(e);
+                }
+            }
+        }
+    };
+
+    if let Err(reason) = binary::smoke_test(&dest) {
+        if let Some(stored) = bundle {
+            stored.roll_back()?;
+        }
+        let outcome = if replacing {
+            binary::restore_backup(install_dir, binary_name)?;
+            "restored the previous binary"
+        } else {
+            std::fs::remove_file(&dest)?;
+            "removed it again"
+        };
+        return Err(OktofetchError::Other(format!(
+            "{} {} failed its smoke test ({}); {}",
+            tool.name, target_version, reason, outcome
+        )));
+    }
+    if let Some(stored) = bundle {
+        stored.commit()?;
+    }
+
+    // Retain a copy of this version and drop the oldest ones beyond the limit
+    let keep = if tool.bundle {
+        // The bundle is already among the retained versions. The one the
+        // .previous wrapper runs stays as well.
+        Some(config.settings.keep_versions.unwrap_or(0).max(2))
+    } else {
+        let keep = config.settings.keep_versions.filter(|&n| n > 0);
+        if keep.is_some() {
+            binary::store_version(&dest, &versions_dir, &target_version, binary_name)?;
+        }
+        keep
+    };
+    if let Some(keep) = keep {
+        let removed = binary::prune_versions(&versions_dir, keep, &tool.kept_versions)?;
+        if verbose {
+            for path in removed {
+                println!("Removed old version {}", path.display());
+            }
+        }
+    }
+
+    // Update version and checksum in config
+    let checksum = binary::sha256_file(&dest)?;
+    if verbose {
+        println!("SHA256: {}", checksum);
+    }
+    config.update_tool_version(&tool.name, target_version)?;
+    config.update_tool_checksum(&tool.name, checksum)?;
+    if let Some(entry) = config.get_tool_mut(&tool.name) {
+        entry.asset_digest = asset
+            .digest
+            .clone()
+            .or_else(|| asset_sha256.map(|sha256| format!("sha256:{}", sha256)));
+        entry.asset_size = (asset.size > 0).then_some(asset.size);
+        entry.asset_name = Some(asset.name.clone());
+        entry.released_at = entry.released_at.max(released_at);
+        entry.installed_at = Some(human::unix_now());
+        entry.checked_at = entry.installed_at;
+    }
+    config.save()?;
+
+    Ok(dest)
+}
+
+/// Stores `version` side by side with the installed one, in its own
+/// directory under `versions_dir` (the tool's), and records it so that
+/// pruning and `clean` leave it alone. The caller saves the config. Returns
+/// the binary to run.
+fn keep_version(
+    config: &mut Config,
+    tool: &Tool,
+    staged: Staged,
+    version: &str,
+    versions_dir: &Path,
+) -> Result<PathBuf> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let binary_name = tool.installed_name();
+    let mut bundle = None;
+    let path = match staged {
+        Staged::Extracted(path) => {
+            binary::store_version(&path, versions_dir, version, binary_name)?
+        }
+        Staged::Decoded(decoded) => {
+            binary::store_version(decoded.path(), versions_dir, version, binary_name)?
+        }
+        Staged::Bundled { tree, binary } => {
+            let bundle_dir = binary::version_dir(versions_dir, version);
+            let stored = binary::store_bundle(&tree, &binary, &bundle_dir)?;
+            let path = stored.binary.clone();
+            bundle = Some(stored);
+            path
+        }
+    };
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))?;
+
+    if let Err(reason) = binary::smoke_test(&path) {
+        match bundle {
+            Some(stored) => stored.roll_back()?,
+            None => std::fs::remove_dir_all(binary::version_dir(versions_dir, version))?,
+        }
+        return Err(OktofetchError::Other(format!(
+            "{} {} failed its smoke test ({}); removed it again",
+            tool.name, version, reason
+        )));
+    }
+    if let Some(stored) = bundle {
+        stored.commit()?;
+    }
+
+    if let Some(entry) = config.get_tool_mut(&tool.name)
+        && !entry.kept_versions.iter().any(|kept| kept == version)
+    {
+        entry.kept_versions.push(version.to_string());
+    }
+    Ok(path)
+}
+
+/// Updates every tool, recording progress in a journal so that a run that
+/// is cut short can be resumed. Tools checked within `min_check_interval`
+/// are left alone.
+pub async fn update_all_tools(config: &mut Config, opts: &UpdateOptions) -> Result<()> {
+    let opts = &UpdateOptions {
+        min_check_interval: opts
+            .min_check_interval
+            .or(config.settings.min_check_interval),
+        ..opts.clone()
+    };
+    // Restoring a pinned tool's recorded version doesn't move the pin
+    let mut tool_names = tools_to_update(config, &opts.except, opts.only_missing)?;
+
+    let path = Config::journal_path()?;
+    let mut journal = if opts.resume {
+        let journal = Journal::resume(&path)?;
+        let total = tool_names.len();
+        tool_names.retain(|name| !journal.is_done(name));
+        match total - tool_names.len() {
+            0 => println!("No interrupted update to resume, updating all tools"),
+            done => println!(
+                "Resuming an interrupted update: {} of {} tools already done",
+                done, total
+            ),
+        }
+        journal
+    } else {
+        Journal::start(&path)?
+    };
+
+    let snapshots = batch_lookup(config, &tool_names, opts).await;
+    record_snapshots(config, &snapshots);
+    config.latest_tags = snapshots
+        .into_iter()
+        .filter_map(|(name, snapshot)| Some((name, snapshot.latest_tag?)))
+        .collect();
+    let failed = update_each(config, tool_names, opts, Some(&mut journal)).await?;
+    if failed > 0 {
+        // Kept so that a resumed run retries just the failed tools
+        println!("Run 'oktofetch update --all --resume' to retry the failed tools");
+        return Ok(());
+    }
+    journal.complete()
+}
+
+/// Updates the named tools, pinned ones included, and prints a combined
+/// summary. Every name is checked before anything is updated.
+pub async fn update_tools(
+    config: &mut Config,
+    names: &[String],
+    opts: &UpdateOptions,
+) -> Result<()> {
+    if let Some(unknown) = names.iter().find(|name| config.get_tool(name).is_none()) {
+        return Err(OktofetchError::ToolNotFound(unknown.clone()));
+    }
+
+    let mut tool_names: Vec<String> = Vec::new();
+    for name in names {
+        if !tool_names.contains(name) {
+            tool_names.push(name.clone());
+        }
+    }
+    update_each(config, tool_names, opts, None).await?;
+    Ok(())
+}
+
+/// Updates the tools one after another, marking each finished one in
+/// `journal`. Tools that fail are left for a resumed run to retry. Returns
+/// how many failed.
+async fn update_each(
+    config: &mut Config,
+    tool_names: Vec<String>,
+    opts: &UpdateOptions,
+    mut journal: Option<&mut Journal>,
+) -> Result<usize> {
+    let mut rows = Vec::new();
+    let mut review = if opts.interactive {
+        Some(Review::start()?)
+    } else {
+        None
+    };
+
+    for tool_name in tool_names {
+        if review.as_ref().is_some_and(|r| r.quit) {
+            break;
+        }
+
+        let old_version = config.get_tool(&tool_name).and_then(|t| t.version.clone());
+        let started = std::time::Instant::now();
+        let outcome = update_reviewed(config, &tool_name, opts, review.as_mut()).await;
+        let mut row = SummaryRow {
+            new_version: config.get_tool(&tool_name).and_then(|t| t.version.clone()),
+            name: tool_name,
+            old_version,
+            outcome: None,
+            elapsed: started.elapsed(),
+        };
+        match outcome {
+            Ok(outcome) => {
+                row.outcome = Some(outcome);
+                if let Some(journal) = journal.as_deref_mut()
+                    && let Err(e) = journal.finish(&row.name)
+                {
+                    eprintln!("Warning: failed to record progress for --resume: {}", e);
+                }
+            }
+            // The error is reported once, by the caller
+            Err(e) if opts.fail_fast => {
+                rows.push(row);
+                println!("\n{}", summary_table(&rows));
+                println!("Stopped at the first failure (--fail-fast)");
+                return Err(e);
+            }
+            Err(e) => eprintln!("Failed to update {}: {}", row.name, e.report(opts.verbose)),
+        }
+        rows.push(row);
+    }
+
+    println!("\n{}", summary_table(&rows));
+    Ok(rows.iter().filter(|row| row.outcome.is_none()).count())
+}
+
+/// One tool's line in the table printed after updating several tools.
+struct SummaryRow {
+    name: String,
+    old_version: Option<String>,
+    new_version: Option<String>,
+    /// `None` if the update failed
+    outcome: Option<UpdateOutcome>,
+    elapsed: std::time::Duration,
+}
+
+/// Renders the rows as an aligned table followed by the overall counts.
+fn summary_table(rows: &[SummaryRow]) -> String {
+    let cells: Vec<[String; 5]> = rows
+        .iter()
+        .map(|row| {
+            let old = row.old_version.as_deref().unwrap_or("-");
+            let version = match (row.outcome, &row.new_version) {
+                (Some(UpdateOutcome::Updated(_)), Some(new)) if new != old => {
+                    format!("{} → {}", old, new)
+                }
+                _ => old.to_string(),
+            };
+            let (status, downloaded) = match row.outcome {
+                Some(UpdateOutcome::Updated(bytes)) => (
+                    "updated",
+                    Some(bytes).filter(|b| *b > 0).map(human::format_size),
+                ),
+                Some(UpdateOutcome::UpToDate) => ("up to date", None),
+                Some(UpdateOutcome::Skipped) => ("skipped", None),
+                None => ("failed", None),
+            };
+            let elapsed = if row.elapsed.as_secs() < 60 {
+                format!("{:.1}s", row.elapsed.as_secs_f64())
+            } else {
+                human::format_duration(row.elapsed.as_secs())
+            };
+            [
+                row.name.clone(),
+                version,
+                status.to_string(),
+                elapsed,
+                downloaded.unwrap_or_else(|| "-".to_string()),
+            ]
+        })
+        .collect();
+
+    let header = ["TOOL", "VERSION", "STATUS", "TIME", "DOWNLOADED"];
+    let mut widths = header.map(|h| h.chars().count());
+    for row in &cells {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let mut table = String::new();
+    for row in std::iter::once(header.map(str::to_string)).chain(cells) {
+        let line = format!(
+            "{:<w0$}  {:<w1$}  {:<w2$}  {:>w3$}  {:>w4$}",
+            row[0],
+            row[1],
+            row[2],
+            row[3],
+            row[4],
+            w0 = widths[0],
+            w1 = widths[1],
+            w2 = widths[2],
+            w3 = widths[3],
+            w4 = widths[4],
+        );
+        table.push_str(line.trim_end());
+        table.push('\n');
+    }
+
+    let count = |wanted: fn(&Option<UpdateOutcome>) -> bool| {
+        rows.iter().filter(|r| wanted(&r.outcome)).count()
+    };
+    table.push_str(&format!(
+        "{} updated, {} up to date, {} skipped, {} failed",
+        count(|o| matches!(o, Some(UpdateOutcome::Updated(_)))),
+        count(|o| *o == Some(UpdateOutcome::UpToDate)),
+        count(|o| *o == Some(UpdateOutcome::Skipped)),
+        count(Option::is_none),
+    ));
+    table
+}
+
+/// Names of all configured tools minus the excluded and pinned ones. Excluding
+/// a tool that isn't configured is an error, since it's most likely a typo.
+fn tools_to_update(
+    config: &Config,
+    except: &[String],
+    include_pinned: bool,
+) -> Result<Vec<String>> {
+    if let Some(unknown) = except.iter().find(|name| config.get_tool(name).is_none()) {
+        return Err(OktofetchError::ToolNotFound(unknown.clone()));
+    }
+
+    Ok(config
+        .tools
+        .iter()
+        .filter(|t| (include_pinned || !t.pinned) && !except.contains(&t.name))
+        .map(|t| t.name.clone())
+        .collect())
+}
+
+/// Drops a tool from the config after confirming it, see
+/// [`prompt::confirm_destructive`].
+pub fn remove_tool(config: &mut Config, tool_name: &str, force: bool) -> Result<()> {
+    let tool = config
+        .get_tool(tool_name)
+        .ok_or_else(|| OktofetchError::ToolNotFound(tool_name.to_string()))?;
+    let question = format!(
+        "Remove {} ({}, {}) from the config?",
+        tool.name,
+        tool.repo,
+        tool.version.as_deref().unwrap_or("not installed")
+    );
+    if !prompt::confirm_destructive(&question, force)? {
+        println!("Kept '{}'", tool_name);
+        return Ok(());
+    }
+
+    let tool = tool.clone();
+    config.remove_tool(tool_name)?;
+    config.save()?;
+    println!("Removed tool '{}'", tool_name);
+
+    let install_dir = &config.install_dir_for(&tool);
+    for path in delete_leftovers(install_dir, &Config::completions_dir()?, &tool)? {
+        println!("Removed {}", path.display());
+    }
+    if !tool.bundle {
+        println!("Note: Binary in {} not removed", install_dir.display());
+    }
+    Ok(())
+}
+
+/// Deletes what a tool taken out of the config leaves behind: its shell
+/// completions and, for a bundle, the wrappers, which would point at nothing
+/// once the next `clean` deletes the bundle. A plain binary stays. Returns
+/// the deleted files.
+pub fn delete_leftovers(
+    install_dir: &Path,
+    completions_dir: &Path,
+    tool: &Tool,
+) -> Result<Vec<PathBuf>> {
+    let binary_name = tool.installed_name();
+    let mut deleted = Vec::new();
+    if tool.bundle {
+        for path in [
+            install_dir.join(binary_name),
+            binary::backup_path(install_dir, binary_name),
+        ] {
+            if binary::wrapper_target(&path).is_some() {
+                std::fs::remove_file(&path)?;
+                deleted.push(path);
+            }
+        }
+    }
+    deleted.extend(completions::remove(binary_name, completions_dir)?);
+    Ok(deleted)
+}
+
+/// Deletes a version kept side by side after confirming it, leaving the tool
+/// and its installed binary alone.
+pub fn remove_kept_version(
+    config: &mut Config,
+    tool_name: &str,
+    version: &str,
+    force: bool,
+) -> Result<()> {
+    let tool = config
+        .get_tool(tool_name)
+        .ok_or_else(|| OktofetchError::ToolNotFound(tool_name.to_string()))?;
+    if !tool.kept_versions.iter().any(|kept| kept == version) {
+        let kept = match tool.kept_versions.as_slice() {
+            [] => "none".to_string(),
+            versions => versions.join(", "),
+        };
+        return Err(OktofetchError::Other(format!(
+            "{} has no kept version {} (kept: {})",
+            tool_name, version, kept
+        )));
+    }
+    // The installed bundle lives in the same directory
+    let in_use = tool.bundle && tool.version.as_deref() == Some(version);
+
+    let question = format!("Delete {} {}, kept side by side?", tool_name, version);
+    if !prompt::confirm_destructive(&question, force)? {
+        println!("Kept {} {}", tool_name, version);
+        return Ok(());
+    }
+
+    let dir = binary::version_dir(&Config::versions_dir()?.join(tool_name), version);
+    if !in_use && dir.exists() {
+        std::fs::remove_dir_all(&dir)?;
+    }
+    if let Some(entry) = config.get_tool_mut(tool_name) {
+        entry.kept_versions.retain(|kept| kept != version);
+    }
+    config.save()?;
+    println!("Removed {} {}", tool_name, version);
+    Ok(())
+}
+
+/// Whether a tool's binary is on disk at its recorded version: a version is
+/// recorded and the binary hasn't changed since it was installed.
+pub fn is_installed(config: &Config, tool: &Tool) -> bool {
+    tool.version.is_some()
+        && matches!(
+            verify_checksum(config, tool),
+            ChecksumStatus::Ok | ChecksumStatus::Unknown
+        )
+}
+
+/// Result of comparing an installed binary against its recorded checksum.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChecksumStatus {
+    /// On-disk binary matches the recorded checksum
+    Ok,
+    /// On-disk binary differs from what was installed
+    Mismatch,
+    /// Binary is not present in the install directory, or the bundle its
+    /// wrapper runs is gone
+    Missing,
+    /// No checksum was recorded for this tool
+    Unknown,
+}
+
+pub fn verify_checksum(config: &Config, tool: &Tool) -> ChecksumStatus {
+    let path = config.install_dir_for(tool).join(tool.installed_name());
+    if !path.is_file() {
+        return ChecksumStatus::Missing;
+    }
+    if tool.bundle && binary::wrapper_target(&path).is_some_and(|target| !target.is_file()) {
+        return ChecksumStatus::Missing;
+    }
+
+    let Some(expected) = &tool.checksum else {
+        return ChecksumStatus::Unknown;
+    };
+
+    match binary::sha256_file(&path) {
+        Ok(actual) if actual.eq_ignore_ascii_case(expected) => ChecksumStatus::Ok,
+        _ => ChecksumStatus::Mismatch,
+    }
+}
+
+/// A tool's configuration together with its on-disk state, as printed by `--json`.
+#[derive(Debug, Serialize)]
+pub struct ToolRecord<'a> {
+    #[serde(flatten)]
+    pub tool: &'a Tool,
+    pub install_path: PathBuf,
+    pub installed: bool,
+    /// Size in bytes of the binary in the install directory
+    pub installed_size: Option<u64>,
+    pub checksum_status: ChecksumStatus,
+}
+
+/// Size in bytes of a tool's binary in the install directory, if it's there.
+pub fn installed_size(config: &Config, tool: &Tool) -> Option<u64> {
+    let path = config.install_dir_for(tool).join(tool.installed_name());
+    std::fs::metadata(path)
+        .ok()
+        .filter(|metadata| metadata.is_file())
+        .map(|metadata| metadata.len())
+}
+
+pub fn tool_record<'a>(config: &Config, tool: &'a Tool) -> ToolRecord<'a> {
+    let checksum_status = verify_checksum(config, tool);
+    ToolRecord {
+        tool,
+        install_path: config.install_dir_for(tool).join(tool.installed_name()),
+        installed: checksum_status != ChecksumStatus::Missing,
+        installed_size: installed_size(config, tool),
+        checksum_status,
+    }
+}
+
+/// Pretty-prints a value as JSON on stdout.
+pub fn print_json(value: &impl Serialize) -> Result<()> {
+    let json = serde_json::to_string_pretty(value).map_err(std::io::Error::from)?;
+    println!("{}", json);
+    Ok(())
+}
+
+/// Orders `list` can show tools in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ListSort {
+    Name,
+    /// Most recently updated first
+    Updated,
+    /// Largest installed binary first
+    Size,
+    Repo,
+}
+
+/// A condition a tool must meet to be listed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ListFilter {
+    /// The binary is in the install directory
+    Installed,
+    /// The binary isn't in the install directory
+    Missing,
+    /// The recorded version or its release tag contains the text
+    Tag(String),
+    /// The repository contains the text, ignoring case
+    Repo(String),
+}
+
+/// Parses a `list --filter` value: `installed`, `missing`, `tag=<text>` or
+/// `repo=<text>`.
+pub fn parse_list_filter(value: &str) -> std::result::Result<ListFilter, String> {
+    match value.split_once('=') {
+        None if value == "installed" => Ok(ListFilter::Installed),
+        None if value == "missing" => Ok(ListFilter::Missing),
+        Some(("tag", text)) if !text.is_empty() => Ok(ListFilter::Tag(text.to_string())),
+        Some(("repo", text)) if !text.is_empty() => Ok(ListFilter::Repo(text.to_lowercase())),
+        _ => Err(format!(
+            "invalid filter '{}': expected installed, missing, tag=<text> or repo=<text>",
+            value
+        )),
+    }
+}
+
+/// Which tools `list` shows, and in what order. By default all of them, in
+/// config order.
+#[derive(Debug, Default)]
+pub struct ListQuery {
+    /// Tools must meet every filter
+    pub filters: Vec<ListFilter>,
+    pub sort: Option<ListSort>,
+}
+
+impl ListQuery {
+    fn matches(&self, config: &Config, tool: &Tool) -> bool {
+        self.filters.iter().all(|filter| match filter {
+            ListFilter::Installed => installed_size(config, tool).is_some(),
+            ListFilter::Missing => installed_size(config, tool).is_none(),
+            ListFilter::Tag(text) => tool.version.as_ref().is_some_and(|version| {
+                version.contains(text.as_str())
+                    || tool.tag_for_version(version).contains(text.as_str())
+            }),
+            ListFilter::Repo(text) => tool.repo.to_lowercase().contains(text.as_str()),
+        })
+    }
+
+    /// The configured tools to show, in order.
+    pub fn select<'a>(&self, config: &'a Config) -> Vec<&'a Tool> {
+        let mut tools: Vec<&Tool> = config
+            .tools
+            .iter()
+            .filter(|t| self.matches(config, t))
+            .collect();
+        match self.sort {
+            None => {}
+            Some(ListSort::Name) => tools.sort_by_key(|t| t.name.to_lowercase()),
+            Some(ListSort::Updated) => tools.sort_by_key(|t| std::cmp::Reverse(t.installed_at)),
+            Some(ListSort::Size) => {
+                tools.sort_by_key(|t| std::cmp::Reverse(installed_size(config, t)))
+            }
+            Some(ListSort::Repo) => {
+                tools.sort_by_key(|t| (t.repo.to_lowercase(), t.name.to_lowercase()))
+            }
+        }
+        tools
+    }
+}
+
+/// Prints the selected tool records as a JSON array.
+pub fn list_tools_json(config: &Config, query: &ListQuery) -> Result<()> {
+    let records: Vec<_> = query
+        .select(config)
+        .into_iter()
+        .map(|t| tool_record(config, t))
+        .collect();
+    print_json(&records)
+}
+
+pub fn list_tools(config: &Config, query: &ListQuery) -> Result<()> {
+    if config.tools.is_empty() {
+        println!("No tools configured.");
+        println!("Add a tool with: oktofetch add <github-repo>");
+        return Ok(());
+    }
+
+    let tools = query.select(config);
+    if tools.is_empty() {
+        println!("No tools match the filters.");
+        return Ok(());
+    }
+
+    println!("Configured tools:\n");
+    for tool in tools {
+        let updated = tool
+            .installed_at
+            .map(|at| format!("updated {}", human::format_age(at, human::unix_now())));
+        let version_str = match (&tool.version, updated) {
+            (Some(version), Some(updated)) => format!(" ({}, {})", version, updated),
+            (Some(version), None) => format!(" ({})", version),
+            (None, Some(updated)) => format!(" ({})", updated),
+            (None, None) => String::new(),
+        };
+        let pinned_str = if tool.pinned { " [pinned]" } else { "" };
+        let size = installed_size(config, tool)
+            .map(human::format_size)
+            .unwrap_or_else(|| "-".to_string());
+        println!(
+            "  {:<20} {:>10}  {}{}{}",
+            tool.name, size, tool.repo, version_str, pinned_str
+        );
+        if let Some(binary) = &tool.binary_name {
+            println!("  {:<20} binary: {}", "", binary);
+        }
+        if let Some(install_as) = &tool.install_as {
+            println!("  {:<20} installed as: {}", "", install_as);
+        }
+        if verify_checksum(config, tool) == ChecksumStatus::Mismatch {
+            println!(
+                "  {:<20} warning: binary modified since install (checksum mismatch)",
+                ""
+            );
+        }
+        if let Some(reason) = unmaintained_reason(tool, human::unix_now()) {
+            println!("  {:<20} warning: possibly unmaintained ({})", "", reason);
+        }
+    }
+
+    Ok(())
+}
+
+/// Number of search results offered when `add` is given a bare name.
+const SEARCH_CANDIDATES: usize = 5;
+
+/// Whether `add` was given just a name like `k9s` rather than a repository.
+fn is_bare_name(input: &str) -> bool {
+    !input.is_empty() && !input.contains('/') && !input.contains(':')
+}
+
+/// Searches GitHub for repositories named like `query` and asks which one to add.
+async fn search_repo(config: &Config, query: &str) -> Result<String> {
+    let client = source::github_client(&config.settings, &config.http())?;
+    let candidates = client.search_repositories(query, SEARCH_CANDIDATES).await?;
+
+    if candidates.is_empty() {
+        return Err(OktofetchError::RepoNotFound(query.to_string()));
+    }
+    if !prompt::can_ask() {
+        let names: Vec<&str> = candidates.iter().map(|c| c.full_name.as_str()).collect();
+        return Err(OktofetchError::InputRequired(format!(
+            "'{}' needs a repository chosen from {}; pass owner/repo instead",
+            query,
+            names.join(", ")
+        )));
+    }
+
+    choose_repo(&mut std::io::stdin().lock(), query, &candidates)
+}
+
+fn choose_repo(
+    input: &mut impl std::io::BufRead,
+    query: &str,
+    candidates: &[RepoSearchResult],
+) -> Result<String> {
+    use std::io::Write;
+
+    println!("Repositories matching '{}':", query);
+    for (i, repo) in candidates.iter().enumerate() {
+        println!(
+            "  {}) {:<40} {:>7} stars  {}",
+            i + 1,
+            repo.full_name,
+            repo.stargazers_count,
+            repo.description.as_deref().unwrap_or("")
+        );
+    }
+
+    loop {
+        print!(
+            "Select a repository [1-{}] or q to cancel: ",
+            candidates.len()
+        );
+        std::io::stdout().flush()?;
+
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 || line.trim().eq_ignore_ascii_case("q") {
+            return Err(OktofetchError::Other("No repository selected".to_string()));
+        }
+
+        match line.trim().parse::<usize>() {
+            Ok(n) if (1..=candidates.len()).contains(&n) => {
+                return Ok(candidates[n - 1].full_name.clone());
+            }
+            _ => println!("Please enter a number between 1 and {}", candidates.len()),
+        }
+    }
+}
+
+fn parse_repo(input: &str) -> Result<String> {
+    // Handle full GitHub URLs
+    if input.starts_with("http://") || input.starts_with("https://") {
+        let url = input
+            .trim_start_matches("https://")
+            .trim_start_matches("http://");
+        let parts: Vec<&str> = url.split('/').collect();
+
+        if parts.len() >= 3 && parts[0] == "github.com" {
+            return Ok(format!("{}/{}", parts[1], parts[2]));
+        }
+    }
+
+    // Validate owner/repo format
+    if input.split('/').count() == 2 {
+        return Ok(input.to_string());
+    }
+
+    Err(OktofetchError::Other(format!(
+        "Invalid repository format: {}. Expected 'owner/repo' or GitHub URL",
+        input
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_asset_priority() {
+        // Test tar.gz variants (highest priority)
+        assert_eq!(asset_priority("myapp.tar.gz"), 0);
+        assert_eq!(asset_priority("myapp.tgz"), 0);
+        assert_eq!(asset_priority("MYAPP.TAR.GZ"), 0); // Case insensitive
+        assert_eq!(asset_priority("MYAPP.TGZ"), 0);
+
+        // Test zip (second priority)
+        assert_eq!(asset_priority("myapp.zip"), 1);
+        assert_eq!(asset_priority("MYAPP.ZIP"), 1);
+
+        // Test other formats (lowest priority)
         assert_eq!(asset_priority("myapp.7z"), 2);
         assert_eq!(asset_priority("myapp.rar"), 2);
         assert_eq!(asset_priority("myapp.tar"), 2);
@@ -276,266 +2440,1854 @@ mod tests {
     }
 
     #[test]
-    fn test_parse_repo_simple_format() {
-        assert_eq!(parse_repo("owner/repo").unwrap(), "owner/repo");
-        assert_eq!(parse_repo("derailed/k9s").unwrap(), "derailed/k9s");
-        assert_eq!(parse_repo("vmware/govmomi").unwrap(), "vmware/govmomi");
+    fn test_is_bare_name() {
+        assert!(is_bare_name("k9s"));
+        assert!(!is_bare_name("derailed/k9s"));
+        assert!(!is_bare_name("https://github.com/derailed/k9s"));
+        assert!(!is_bare_name(""));
+    }
+
+    #[test]
+    fn test_choose_repo() {
+        let candidates = vec![
+            RepoSearchResult {
+                full_name: "derailed/k9s".to_string(),
+                stargazers_count: 27000,
+                description: Some("Kubernetes CLI".to_string()),
+            },
+            RepoSearchResult {
+                full_name: "someone/k9s".to_string(),
+                stargazers_count: 3,
+                description: None,
+            },
+        ];
+
+        let mut input = std::io::Cursor::new("7\nfoo\n2\n");
+        assert_eq!(
+            choose_repo(&mut input, "k9s", &candidates).unwrap(),
+            "someone/k9s"
+        );
+
+        let mut input = std::io::Cursor::new("q\n");
+        assert!(choose_repo(&mut input, "k9s", &candidates).is_err());
+
+        let mut input = std::io::Cursor::new("");
+        assert!(choose_repo(&mut input, "k9s", &candidates).is_err());
+    }
+
+    #[test]
+    fn test_parse_repo_simple_format() {
+        assert_eq!(parse_repo("owner/repo").unwrap(), "owner/repo");
+        assert_eq!(parse_repo("derailed/k9s").unwrap(), "derailed/k9s");
+        assert_eq!(parse_repo("vmware/govmomi").unwrap(), "vmware/govmomi");
+    }
+
+    #[test]
+    fn test_parse_repo_https_url() {
+        assert_eq!(
+            parse_repo("https://github.com/owner/repo").unwrap(),
+            "owner/repo"
+        );
+        assert_eq!(
+            parse_repo("https://github.com/derailed/k9s").unwrap(),
+            "derailed/k9s"
+        );
+        assert_eq!(
+            parse_repo("https://github.com/vmware/govmomi/issues").unwrap(),
+            "vmware/govmomi"
+        );
+    }
+
+    #[test]
+    fn test_parse_repo_http_url() {
+        assert_eq!(
+            parse_repo("http://github.com/owner/repo").unwrap(),
+            "owner/repo"
+        );
+    }
+
+    #[test]
+    fn test_parse_repo_invalid_format() {
+        assert!(parse_repo("invalid").is_err());
+        assert!(parse_repo("owner/repo/extra/parts").is_err());
+        assert!(parse_repo("").is_err());
+    }
+
+    #[test]
+    fn test_parse_repo_non_github_url() {
+        let result = parse_repo("https://gitlab.com/owner/repo");
+        // Should fail because it's not github.com
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_parse_repo_error_message() {
+        let result = parse_repo("invalid");
+        assert!(result.is_err());
+        let err_msg = format!("{}", result.unwrap_err());
+        assert!(err_msg.contains("Invalid repository format"));
+        assert!(err_msg.contains("owner/repo"));
+    }
+
+    #[test]
+    fn test_tool_name_derivation_simple() {
+        // Test that we correctly parse repo and derive tool name
+        assert_eq!(parse_repo("owner/repo").unwrap(), "owner/repo");
+
+        // Test URL parsing
+        assert_eq!(
+            parse_repo("https://github.com/owner/myrepo").unwrap(),
+            "owner/myrepo"
+        );
+    }
+
+    #[test]
+    fn test_remove_tool_not_found() {
+        let mut config = Config::default();
+        let result = remove_tool(&mut config, "nonexistent", false);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_remove_kept_version_unknown() {
+        let mut config = Config::default();
+        config
+            .add_tool(Tool {
+                name: "terraform".to_string(),
+                repo: "hashicorp/terraform".to_string(),
+                kept_versions: vec!["v1.4.6".to_string()],
+                ..Default::default()
+            })
+            .unwrap();
+
+        let err = remove_kept_version(&mut config, "terraform", "v1.5.0", true).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "terraform has no kept version v1.5.0 (kept: v1.4.6)"
+        );
+        assert!(remove_kept_version(&mut config, "missing", "v1", true).is_err());
+    }
+
+    #[test]
+    fn test_remove_tool_logic() {
+        // Test the underlying logic without saving
+        let mut config = Config::default();
+        let tool = crate::config::Tool {
+            name: "tool1".to_string(),
+            repo: "owner/repo1".to_string(),
+            binary_name: None,
+            asset_pattern: None,
+            version: None,
+            ..Default::default()
+        };
+        config.add_tool(tool).unwrap();
+
+        // Test the remove logic directly on config
+        let result = config.remove_tool("tool1");
+        assert!(result.is_ok());
+        assert!(config.get_tool("tool1").is_none());
+    }
+
+    #[test]
+    fn test_tool_record_json() {
+        let (temp_dir, config, mut tool) = checksum_fixture();
+        tool.version = Some("v1.0.0".to_string());
+
+        let json = serde_json::to_value(tool_record(&config, &tool)).unwrap();
+        assert_eq!(json["name"], "mytool");
+        assert_eq!(json["version"], "v1.0.0");
+        assert_eq!(json["installed"], false);
+        assert_eq!(json["checksum_status"], "missing");
+        assert_eq!(json["installed_size"], serde_json::Value::Null);
+        assert_eq!(
+            json["install_path"],
+            temp_dir.path().join("mytool").display().to_string()
+        );
+
+        std::fs::write(temp_dir.path().join("mytool"), b"bin").unwrap();
+        let json = serde_json::to_value(tool_record(&config, &tool)).unwrap();
+        assert_eq!(json["installed"], true);
+        assert_eq!(json["installed_size"], 3);
+        assert_eq!(json["checksum_status"], "unknown");
+    }
+
+    #[test]
+    fn test_list_tools_empty() {
+        let config = Config::default();
+        let result = list_tools(&config, &ListQuery::default());
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_list_tools_with_entries() {
+        let mut config = Config::default();
+        let tool = crate::config::Tool {
+            name: "tool1".to_string(),
+            repo: "owner/repo1".to_string(),
+            binary_name: Some("bin1".to_string()),
+            asset_pattern: None,
+            version: Some("v1.0.0".to_string()),
+            ..Default::default()
+        };
+        config.add_tool(tool).unwrap();
+
+        let result = list_tools(&config, &ListQuery::default());
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_list_tools_multiple_entries() {
+        let mut config = Config::default();
+        for i in 1..=3 {
+            let tool = crate::config::Tool {
+                name: format!("tool{}", i),
+                repo: format!("owner/repo{}", i),
+                binary_name: None,
+                asset_pattern: None,
+                version: None,
+                ..Default::default()
+            };
+            config.add_tool(tool).unwrap();
+        }
+
+        let result = list_tools(&config, &ListQuery::default());
+        assert!(result.is_ok());
+        assert_eq!(config.tools.len(), 3);
+    }
+
+    #[test]
+    fn test_summary_table() {
+        use std::time::Duration;
+
+        let row = |name: &str, old: Option<&str>, new: Option<&str>, outcome, secs| SummaryRow {
+            name: name.to_string(),
+            old_version: old.map(str::to_string),
+            new_version: new.map(str::to_string),
+            outcome,
+            elapsed: Duration::from_millis(secs),
+        };
+        let rows = [
+            row(
+                "k9s",
+                Some("v0.32.4"),
+                Some("v0.32.5"),
+                Some(UpdateOutcome::Updated(3 * 1024 * 1024)),
+                2150,
+            ),
+            row(
+                "bat",
+                Some("0.24.0"),
+                Some("0.24.0"),
+                Some(UpdateOutcome::UpToDate),
+                300,
+            ),
+            row("fd", None, None, None, 75_000),
+            row(
+                "jq",
+                Some("1.7"),
+                Some("1.7"),
+                Some(UpdateOutcome::Skipped),
+                0,
+            ),
+        ];
+
+        assert_eq!(
+            summary_table(&rows),
+            "TOOL  VERSION            STATUS        TIME  DOWNLOADED\n\
+             k9s   v0.32.4 → v0.32.5  updated       2.1s     3.0 MiB\n\
+             bat   0.24.0             up to date    0.3s           -\n\
+             fd    -                  failed      1m 15s           -\n\
+             jq    1.7                skipped       0.0s           -\n\
+             1 updated, 1 up to date, 1 skipped, 1 failed"
+        );
+    }
+
+    #[test]
+    fn test_parse_list_filter() {
+        assert_eq!(parse_list_filter("installed"), Ok(ListFilter::Installed));
+        assert_eq!(parse_list_filter("missing"), Ok(ListFilter::Missing));
+        assert_eq!(
+            parse_list_filter("tag=v1."),
+            Ok(ListFilter::Tag("v1.".to_string()))
+        );
+        assert_eq!(
+            parse_list_filter("repo=Derailed"),
+            Ok(ListFilter::Repo("derailed".to_string()))
+        );
+        assert!(parse_list_filter("repo=").is_err());
+        assert!(parse_list_filter("pinned").is_err());
+        assert!(parse_list_filter("name=k9s").is_err());
+    }
+
+    #[test]
+    fn test_list_query_select() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = Config::default();
+        config.settings.install_dir = temp_dir.path().to_path_buf();
+        for (name, repo, version, installed_at, size) in [
+            ("k9s", "derailed/k9s", Some("0.32.5"), Some(300), Some(3)),
+            ("bat", "sharkdp/bat", Some("0.24.0"), Some(100), Some(5)),
+            ("fd", "sharkdp/fd", None, None, None),
+        ] {
+            if let Some(size) = size {
+                std::fs::write(temp_dir.path().join(name), vec![0u8; size]).unwrap();
+            }
+            config
+                .add_tool(Tool {
+                    name: name.to_string(),
+                    repo: repo.to_string(),
+                    version: version.map(str::to_string),
+                    installed_at,
+                    ..Default::default()
+                })
+                .unwrap();
+        }
+        let names = |query: ListQuery| -> Vec<String> {
+            query
+                .select(&config)
+                .iter()
+                .map(|t| t.name.clone())
+                .collect()
+        };
+        let sorted = |sort| ListQuery {
+            sort: Some(sort),
+            ..Default::default()
+        };
+        let filtered = |filters: &[&str]| ListQuery {
+            filters: filters
+                .iter()
+                .map(|f| parse_list_filter(f).unwrap())
+                .collect(),
+            ..Default::default()
+        };
+
+        assert_eq!(names(ListQuery::default()), ["k9s", "bat", "fd"]);
+        assert_eq!(names(sorted(ListSort::Name)), ["bat", "fd", "k9s"]);
+        assert_eq!(names(sorted(ListSort::Updated)), ["k9s", "bat", "fd"]);
+        assert_eq!(names(sorted(ListSort::Size)), ["bat", "k9s", "fd"]);
+        assert_eq!(names(sorted(ListSort::Repo)), ["k9s", "bat", "fd"]);
+
+        assert_eq!(names(filtered(&["missing"])), ["fd"]);
+        assert_eq!(names(filtered(&["installed", "repo=SHARKDP"])), ["bat"]);
+        assert_eq!(names(filtered(&["tag=0.32"])), ["k9s"]);
+        assert!(names(filtered(&["missing", "tag=0"])).is_empty());
+    }
+
+    #[test]
+    fn test_asset_priority_sorting() {
+        // Verify that tar.gz gets lowest value (highest priority)
+        assert!(asset_priority("app.tar.gz") < asset_priority("app.zip"));
+        assert!(asset_priority("app.zip") < asset_priority("app.7z"));
+
+        // Verify tgz also gets highest priority
+        assert_eq!(asset_priority("app.tgz"), asset_priority("app.tar.gz"));
+        assert_eq!(asset_priority("app.tar.xz"), asset_priority("app.tar.gz"));
+        assert!(asset_priority("app.zip") < asset_priority("app.xz"));
+    }
+
+    #[tokio::test]
+    async fn test_add_tool_basic() {
+        let mut config = Config::default();
+
+        // Test adding a tool
+        let result = config.add_tool(crate::config::Tool {
+            name: "testtool".to_string(),
+            repo: "owner/repo".to_string(),
+            binary_name: None,
+            asset_pattern: None,
+            version: None,
+            ..Default::default()
+        });
+
+        assert!(result.is_ok());
+        assert_eq!(config.tools.len(), 1);
+        assert_eq!(config.tools[0].name, "testtool");
+    }
+
+    #[test]
+    fn test_parse_repo_edge_cases() {
+        // Test with trailing slash
+        assert_eq!(
+            parse_repo("https://github.com/owner/repo/").unwrap(),
+            "owner/repo"
+        );
+
+        // Test with .git suffix - this is valid (owner/repo.git is still owner/repo.git)
+        assert_eq!(parse_repo("owner/repo.git").unwrap(), "owner/repo.git");
+
+        // Test empty string
+        assert!(parse_repo("").is_err());
+
+        // Test malformed inputs
+        assert!(parse_repo("onlyonepart").is_err());
+        assert!(parse_repo("too/many/parts/here").is_err());
+
+        // Test with special characters that should work
+        assert_eq!(parse_repo("my-org/my-repo").unwrap(), "my-org/my-repo");
+        assert_eq!(
+            parse_repo("org_name/repo_name").unwrap(),
+            "org_name/repo_name"
+        );
+    }
+
+    #[test]
+    fn test_list_tools_formatting() {
+        let mut config = Config::default();
+
+        // Add tools with various configurations
+        config
+            .add_tool(crate::config::Tool {
+                name: "tool_with_version".to_string(),
+                repo: "owner/repo1".to_string(),
+                binary_name: Some("custom_bin".to_string()),
+                asset_pattern: None,
+                version: Some("v1.0.0".to_string()),
+                ..Default::default()
+            })
+            .unwrap();
+
+        config
+            .add_tool(crate::config::Tool {
+                name: "tool_without_version".to_string(),
+                repo: "owner/repo2".to_string(),
+                binary_name: None,
+                asset_pattern: None,
+                version: None,
+                ..Default::default()
+            })
+            .unwrap();
+
+        let result = list_tools(&config, &ListQuery::default());
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_remove_tool_updates_config() {
+        let mut config = Config::default();
+
+        // Add multiple tools
+        for i in 1..=3 {
+            config
+                .add_tool(crate::config::Tool {
+                    name: format!("tool{}", i),
+                    repo: format!("owner/repo{}", i),
+                    binary_name: None,
+                    asset_pattern: None,
+                    version: None,
+                    ..Default::default()
+                })
+                .unwrap();
+        }
+
+        assert_eq!(config.tools.len(), 3);
+
+        // Remove middle tool
+        config.remove_tool("tool2").unwrap();
+        assert_eq!(config.tools.len(), 2);
+        assert!(config.get_tool("tool1").is_some());
+        assert!(config.get_tool("tool2").is_none());
+        assert!(config.get_tool("tool3").is_some());
+    }
+
+    #[test]
+    fn test_parse_repo_url_variations() {
+        // Test various URL formats
+        let test_cases = vec![
+            ("https://github.com/owner/repo", "owner/repo"),
+            ("http://github.com/owner/repo", "owner/repo"),
+            ("https://github.com/owner/repo/releases", "owner/repo"),
+            ("https://github.com/owner/repo/tree/main", "owner/repo"),
+        ];
+
+        for (input, expected) in test_cases {
+            assert_eq!(
+                parse_repo(input).unwrap(),
+                expected,
+                "Failed for input: {}",
+                input
+            );
+        }
+    }
+
+    fn checksum_fixture() -> (tempfile::TempDir, Config, Tool) {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut config = Config::default();
+        config.settings.install_dir = temp_dir.path().to_path_buf();
+        let tool = Tool {
+            name: "mytool".to_string(),
+            repo: "owner/repo".to_string(),
+            ..Default::default()
+        };
+        (temp_dir, config, tool)
+    }
+
+    #[test]
+    fn test_verify_checksum_missing_binary() {
+        let (_temp_dir, config, tool) = checksum_fixture();
+        assert_eq!(verify_checksum(&config, &tool), ChecksumStatus::Missing);
+    }
+
+    #[test]
+    fn test_verify_checksum_bundle_gone() {
+        let (temp_dir, config, mut tool) = checksum_fixture();
+        tool.bundle = true;
+        let target = temp_dir.path().join("versions/mytool/v1/bin/mytool");
+        std::fs::create_dir_all(target.parent().unwrap()).unwrap();
+        std::fs::write(&target, b"bin").unwrap();
+        let wrapper = binary::install_wrapper(&target, temp_dir.path(), "mytool").unwrap();
+        tool.checksum = Some(binary::sha256_file(&wrapper).unwrap());
+        tool.version = Some("v1".to_string());
+        assert_eq!(verify_checksum(&config, &tool), ChecksumStatus::Ok);
+        assert!(is_installed(&config, &tool));
+
+        std::fs::remove_dir_all(temp_dir.path().join("versions")).unwrap();
+        assert_eq!(verify_checksum(&config, &tool), ChecksumStatus::Missing);
+        assert!(!is_installed(&config, &tool));
+    }
+
+    #[test]
+    fn test_verify_checksum_unknown() {
+        let (temp_dir, config, tool) = checksum_fixture();
+        std::fs::write(temp_dir.path().join("mytool"), b"bin").unwrap();
+        assert_eq!(verify_checksum(&config, &tool), ChecksumStatus::Unknown);
+    }
+
+    #[test]
+    fn test_verify_checksum_match_and_mismatch() {
+        let (temp_dir, config, mut tool) = checksum_fixture();
+        let path = temp_dir.path().join("mytool");
+        std::fs::write(&path, b"bin").unwrap();
+        tool.checksum = Some(binary::sha256_file(&path).unwrap());
+        assert_eq!(verify_checksum(&config, &tool), ChecksumStatus::Ok);
+
+        std::fs::write(&path, b"tampered").unwrap();
+        assert_eq!(verify_checksum(&config, &tool), ChecksumStatus::Mismatch);
+        assert!(
+            list_tools(
+                &Config {
+                    tools: vec![tool],
+                    ..config
+                },
+                &ListQuery::default()
+            )
+            .is_ok()
+        );
+    }
+
+    fn release_with_assets(names: &[&str]) -> Release {
+        Release {
+            id: 0,
+            tag_name: "v1.0.0".to_string(),
+            name: "v1.0.0".to_string(),
+            assets: names
+                .iter()
+                .map(|name| Asset {
+                    name: name.to_string(),
+                    browser_download_url: format!("https://example.com/{}", name),
+                    size: 1,
+                    digest: None,
+                })
+                .collect(),
+            prerelease: false,
+            draft: false,
+            body: None,
+            published_at: None,
+        }
+    }
+
+    fn write_selector(dir: &std::path::Path, body: &str) -> String {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = dir.join("selector");
+        std::fs::write(&path, format!("#!/bin/sh\n{}\n", body)).unwrap();
+        let mut perms = std::fs::metadata(&path).unwrap().permissions();
+        perms.set_mode(0o755);
+        std::fs::set_permissions(&path, perms).unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn test_select_asset_heuristics() {
+        let release = release_with_assets(&[
+            "app-darwin-amd64.tar.gz",
+            "app-linux-amd64.zip",
+            "app-linux-amd64.tar.gz",
+        ]);
+        let tool = Tool {
+            name: "app".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            select_asset(&tool, &release, &[]).unwrap().name,
+            "app-linux-amd64.tar.gz"
+        );
+    }
+
+    #[test]
+    fn test_select_asset_pattern() {
+        let release = release_with_assets(&["app-linux-amd64.tar.gz", "app-custom.bin"]);
+        let tool = Tool {
+            name: "app".to_string(),
+            asset_pattern: Some("custom".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            select_asset(&tool, &release, &[]).unwrap().name,
+            "app-custom.bin"
+        );
+
+        let tool = Tool {
+            asset_pattern: Some("nomatch".to_string()),
+            ..tool
+        };
+        assert!(matches!(
+            select_asset(&tool, &release, &[]),
+            Err(OktofetchError::NoSuitableRelease { .. })
+        ));
+    }
+
+    #[test]
+    fn test_select_asset_exclude() {
+        let release = release_with_assets(&[
+            "app-linux-amd64-musl.tar.gz",
+            "app-linux-amd64-static.tar.gz",
+            "app-linux-amd64-gnu.tar.gz",
+        ]);
+        let tool = Tool {
+            name: "app".to_string(),
+            asset_exclude: vec!["MUSL".to_string(), "static".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            select_asset(&tool, &release, &[]).unwrap().name,
+            "app-linux-amd64-gnu.tar.gz"
+        );
+
+        let tool = Tool {
+            asset_pattern: Some("linux-amd64".to_string()),
+            ..tool
+        };
+        assert_eq!(
+            select_asset(&tool, &release, &[]).unwrap().name,
+            "app-linux-amd64-gnu.tar.gz"
+        );
+    }
+
+    #[test]
+    fn test_assess_assets() {
+        let release = release_with_assets(&[
+            "app-linux-amd64-musl.tar.gz",
+            "app-linux-amd64.tar.gz",
+            "app-darwin-arm64.tar.gz",
+            "checksums.txt",
+        ]);
+        let tool = Tool {
+            name: "app".to_string(),
+            asset_exclude: vec!["musl".to_string()],
+            ..Default::default()
+        };
+        let reports = assess_assets(&tool, &release, Some("app-linux-amd64.tar.gz"));
+        assert_eq!(reports.len(), 4);
+        assert!(reports[0].platform && reports[0].excluded && !reports[0].selected);
+        assert!(reports[1].platform && !reports[1].excluded && reports[1].selected);
+        assert!(!reports[2].platform && !reports[2].selected);
+        assert!(reports.iter().all(|r| r.pattern.is_none()));
+
+        let tool = Tool {
+            asset_pattern: Some("darwin".to_string()),
+            ..tool
+        };
+        let reports = assess_assets(&tool, &release, None);
+        assert_eq!(reports[2].pattern, Some(true));
+        assert_eq!(reports[3].pattern, Some(false));
+        assert!(reports.iter().all(|r| !r.selected));
+    }
+
+    #[test]
+    fn test_select_asset_prefer() {
+        let release = release_with_assets(&[
+            "app-linux-amd64.tar.gz",
+            "app-linux-amd64-musl.zip",
+            "app-linux-amd64-static.tar.gz",
+        ]);
+        let tool = Tool {
+            name: "app".to_string(),
+            ..Default::default()
+        };
+        let prefer =
+            |keywords: &[&str]| -> Vec<String> { keywords.iter().map(|k| k.to_string()).collect() };
+
+        assert_eq!(
+            select_asset(&tool, &release, &prefer(&["musl", "static"]))
+                .unwrap()
+                .name,
+            "app-linux-amd64-musl.zip"
+        );
+        assert_eq!(
+            select_asset(&tool, &release, &prefer(&["Static", "musl"]))
+                .unwrap()
+                .name,
+            "app-linux-amd64-static.tar.gz"
+        );
+        assert_eq!(
+            select_asset(&tool, &release, &prefer(&["none"]))
+                .unwrap()
+                .name,
+            "app-linux-amd64.tar.gz"
+        );
+    }
+
+    #[test]
+    fn test_select_asset_with_selector_script() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        // Pick the last asset name out of the JSON on stdin
+        let selector = write_selector(
+            temp_dir.path(),
+            r#"tr ',' '\n' | grep '"name"' | tail -n1 | cut -d'"' -f4"#,
+        );
+        let release = release_with_assets(&["app-linux-amd64.tar.gz", "app-weird-layout.tgz"]);
+        let tool = Tool {
+            name: "app".to_string(),
+            asset_selector: Some(selector),
+            ..Default::default()
+        };
+        assert_eq!(
+            select_asset(&tool, &release, &[]).unwrap().name,
+            "app-weird-layout.tgz"
+        );
+    }
+
+    #[test]
+    fn test_select_asset_selector_unknown_asset() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let selector = write_selector(temp_dir.path(), "cat >/dev/null; echo nope.zip");
+        let release = release_with_assets(&["app-linux-amd64.tar.gz"]);
+        let tool = Tool {
+            name: "app".to_string(),
+            asset_selector: Some(selector),
+            ..Default::default()
+        };
+        let err = select_asset(&tool, &release, &[]).unwrap_err();
+        assert!(format!("{}", err).contains("'nope.zip'"));
+    }
+
+    #[test]
+    fn test_select_asset_selector_failure() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let selector = write_selector(temp_dir.path(), "exit 3");
+        let release = release_with_assets(&["app-linux-amd64.tar.gz"]);
+        let tool = Tool {
+            name: "app".to_string(),
+            asset_selector: Some(selector),
+            ..Default::default()
+        };
+        assert!(select_asset(&tool, &release, &[]).is_err());
+    }
+
+    #[test]
+    fn test_select_asset_selector_declined() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let marker = temp_dir.path().join("ran");
+        let selector = write_selector(
+            temp_dir.path(),
+            &format!("touch {}; echo app-linux-amd64.tar.gz", marker.display()),
+        );
+        let release = release_with_assets(&["app-linux-amd64.tar.gz"]);
+        let tool = Tool {
+            name: "app".to_string(),
+            asset_selector: Some(selector.clone()),
+            ..Default::default()
+        };
+
+        // As when the user said no to a selector from someone else's config
+        prompt::decide_trust(&selector, false);
+        let err = select_asset(&tool, &release, &[]).unwrap_err();
+        assert!(format!("{}", err).contains("Not running asset selector"));
+        assert!(!marker.exists());
+    }
+
+    #[test]
+    fn test_tools_to_update_except() {
+        let mut config = Config::default();
+        for name in ["k9s", "terraform", "vault"] {
+            config
+                .add_tool(Tool {
+                    name: name.to_string(),
+                    repo: format!("owner/{}", name),
+                    ..Default::default()
+                })
+                .unwrap();
+        }
+
+        assert_eq!(tools_to_update(&config, &[], false).unwrap().len(), 3);
+        assert_eq!(
+            tools_to_update(
+                &config,
+                &["terraform".to_string(), "vault".to_string()],
+                false
+            )
+            .unwrap(),
+            vec!["k9s".to_string()]
+        );
+        assert!(matches!(
+            tools_to_update(
+                &config,
+                &["terraform".to_string(), "nope".to_string()],
+                false
+            ),
+            Err(OktofetchError::ToolNotFound(name)) if name == "nope"
+        ));
+
+        config.get_tool_mut("k9s").unwrap().pinned = true;
+        assert_eq!(
+            tools_to_update(&config, &["vault".to_string()], false).unwrap(),
+            vec!["terraform".to_string()]
+        );
+        assert_eq!(
+            tools_to_update(&config, &["vault".to_string()], true).unwrap(),
+            vec!["k9s".to_string(), "terraform".to_string()]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_update_tools_checks_names_first() {
+        let mut config = Config::default();
+        config
+            .add_tool(Tool {
+                name: "k9s".to_string(),
+                repo: "derailed/k9s".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+
+        let names = ["k9s".to_string(), "nope".to_string()];
+        assert!(matches!(
+            update_tools(&mut config, &names, &UpdateOptions::default()).await,
+            Err(OktofetchError::ToolNotFound(name)) if name == "nope"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_update_tools_fail_fast() {
+        use std::os::unix::fs::PermissionsExt;
+
+        // Each tool's plugin logs that it was asked, then fails
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let log = temp_dir.path().join("requests.log");
+        let mut config = Config::default();
+        for name in ["first", "second"] {
+            let plugin = temp_dir.path().join(name);
+            std::fs::write(
+                &plugin,
+                format!(
+                    "#!/bin/sh
+echo {} >> {}
+exit 1
+",
+                    name,
+                    log.display()
+                ),
+            )
+            .unwrap();
+            std::fs::set_permissions(&plugin, std::fs::Permissions::from_mode(0o755)).unwrap();
+            config
+                .add_tool(Tool {
+                    name: name.to_string(),
+                    repo: format!("owner/{}", name),
+                    source: Some(format!("command:{}", plugin.display())),
+                    ..Default::default()
+                })
+                .unwrap();
+        }
+        let names = ["first".to_string(), "second".to_string()];
+        let requests = || std::fs::read_to_string(&log).unwrap_or_default();
+
+        // Failures are reported, but don't stop the others by default
+        assert!(
+            update_tools(&mut config, &names, &UpdateOptions::default())
+                .await
+                .is_ok()
+        );
+        assert_eq!(requests(), "first\nsecond\n");
+
+        std::fs::remove_file(&log).unwrap();
+        let second = format!("{:?}", config.get_tool("second").unwrap());
+        let opts = UpdateOptions {
+            fail_fast: true,
+            ..Default::default()
+        };
+        assert!(update_tools(&mut config, &names, &opts).await.is_err());
+        // The second tool was never asked about, nor its entry touched
+        assert_eq!(requests(), "first\n");
+        assert_eq!(format!("{:?}", config.get_tool("second").unwrap()), second);
+    }
+
+    #[test]
+    fn test_keep_version_leaves_installed_tool_alone() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let install_dir = temp_dir.path().join("bin");
+        std::fs::create_dir_all(&install_dir).unwrap();
+        std::fs::write(install_dir.join("terraform"), "installed").unwrap();
+        let mut config = Config::default();
+        config.settings.install_dir = install_dir.clone();
+        config
+            .add_tool(Tool {
+                name: "terraform".to_string(),
+                repo: "hashicorp/terraform".to_string(),
+                version: Some("v1.9.0".to_string()),
+                checksum: Some("abc".to_string()),
+                ..Default::default()
+            })
+            .unwrap();
+        let tool = config.get_tool("terraform").unwrap().clone();
+
+        let extracted = temp_dir.path().join("scratch/terraform");
+        std::fs::create_dir_all(extracted.parent().unwrap()).unwrap();
+        std::fs::write(&extracted, "#!/bin/sh\necho 'Terraform v1.4.6'\n").unwrap();
+        std::fs::set_permissions(&extracted, std::fs::Permissions::from_mode(0o644)).unwrap();
+
+        let versions_dir = temp_dir.path().join("versions/terraform");
+        let path = keep_version(
+            &mut config,
+            &tool,
+            Staged::Extracted(extracted),
+            "v1.4.6",
+            &versions_dir,
+        )
+        .unwrap();
+        assert_eq!(path, versions_dir.join("v1.4.6/terraform"));
+        assert_eq!(
+            std::fs::metadata(&path).unwrap().permissions().mode() & 0o777,
+            0o755
+        );
+
+        // The installed binary and its record are untouched
+        assert_eq!(
+            std::fs::read_to_string(install_dir.join("terraform")).unwrap(),
+            "installed"
+        );
+        let entry = config.get_tool("terraform").unwrap();
+        assert_eq!(entry.version.as_deref(), Some("v1.9.0"));
+        assert_eq!(entry.checksum.as_deref(), Some("abc"));
+        assert_eq!(entry.kept_versions, vec!["v1.4.6".to_string()]);
+
+        // Pruning after the next install leaves the kept version alone
+        for version in ["v1.8.0", "v1.9.0"] {
+            std::fs::create_dir_all(versions_dir.join(version)).unwrap();
+        }
+        let removed = binary::prune_versions(&versions_dir, 1, &entry.kept_versions).unwrap();
+        assert_eq!(removed.len(), 1);
+        assert!(path.exists());
+    }
+
+    #[test]
+    fn test_keep_version_smoke_test_failure() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut config = Config::default();
+        let tool = Tool {
+            name: "broken".to_string(),
+            repo: "owner/broken".to_string(),
+            ..Default::default()
+        };
+        config.add_tool(tool.clone()).unwrap();
+
+        // Not runnable: an ELF header with nothing behind it
+        let extracted = temp_dir.path().join("broken");
+        std::fs::write(&extracted, [0x7f, b'E', b'L', b'F', 2, 1, 1, 0]).unwrap();
+
+        let versions_dir = temp_dir.path().join("versions/broken");
+        let err = keep_version(
+            &mut config,
+            &tool,
+            Staged::Extracted(extracted),
+            "v1.0.0",
+            &versions_dir,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("failed its smoke test"));
+        assert!(!versions_dir.join("v1.0.0").exists());
+        assert!(config.get_tool("broken").unwrap().kept_versions.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_update_each_leaves_failures_in_journal() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut config = Config::default();
+        config
+            .add_tool(Tool {
+                name: "broken".to_string(),
+                repo: "owner/broken".to_string(),
+                source: Some("command:/nonexistent/plugin".to_string()),
+                ..Default::default()
+            })
+            .unwrap();
+        let path = temp_dir.path().join("journal.json");
+        let mut journal = Journal::start(&path).unwrap();
+
+        let failed = update_each(
+            &mut config,
+            vec!["broken".to_string()],
+            &UpdateOptions::default(),
+            Some(&mut journal),
+        )
+        .await
+        .unwrap();
+        assert_eq!(failed, 1);
+        assert!(!journal.is_done("broken"));
+    }
+
+    #[test]
+    fn test_parse_answer() {
+        assert_eq!(parse_answer("y\n"), Some(Answer::Yes));
+        assert_eq!(parse_answer(" No "), Some(Answer::No));
+        assert_eq!(parse_answer("a"), Some(Answer::All));
+        assert_eq!(parse_answer("quit"), Some(Answer::Quit));
+        assert_eq!(parse_answer("maybe"), None);
+    }
+
+    fn pending_update() -> PendingUpdate {
+        PendingUpdate {
+            source: Box::new(source::CommandSource::new("/bin/true")),
+            release: Release {
+                id: 0,
+                tag_name: "v2.0.0".to_string(),
+                name: String::new(),
+                assets: vec![],
+                prerelease: false,
+                draft: false,
+                body: None,
+                published_at: None,
+            },
+            asset: make_asset("tool-linux-amd64.tar.gz", 2048),
+            target_version: "v2.0.0".to_string(),
+            build: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_choose_asset_falls_back_to_source() {
+        let github = crate::github::GithubClient::new(&crate::http::HttpContext::default());
+        let command = source::CommandSource::new("/bin/true");
+        let mut tool = Tool {
+            name: "tool".to_string(),
+            repo: "owner/tool".to_string(),
+            ..Default::default()
+        };
+        let mut release = pending_update().release;
+        release.assets = vec![make_asset("tool-windows-amd64.zip", 10)];
+
+        assert!(
+            choose_asset(&github, &tool, &release, &Settings::default())
+                .await
+                .is_err()
+        );
+
+        tool.build = Some("make".to_string());
+        let (asset, origin) = choose_asset(&github, &tool, &release, &Settings::default())
+            .await
+            .unwrap();
+        assert_eq!(origin, AssetOrigin::Source);
+        assert_eq!(asset.name, "tool-v2.0.0-source.tar.gz");
+        // Other sources have no source tarball to fall back to
+        assert!(
+            choose_asset(&command, &tool, &release, &Settings::default())
+                .await
+                .is_err()
+        );
+
+        release
+            .assets
+            .push(make_asset("tool-linux-amd64.tar.gz", 10));
+        let (asset, origin) = choose_asset(&github, &tool, &release, &Settings::default())
+            .await
+            .unwrap();
+        assert_eq!(origin, AssetOrigin::Release);
+        assert_eq!(asset.name, "tool-linux-amd64.tar.gz");
+    }
+
+    #[tokio::test]
+    async fn test_choose_asset_from_binstall_metadata() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/repos/owner/tool/contents/Cargo.toml"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                "[package]\nname = \"tool\"\n\n[package.metadata.binstall]\n\
+                 pkg-url = \"{ repo }/releases/download/v{ version }/{ name }-{ version }-{ target }.tgz\"\n",
+            ))
+            .mount(&mock_server)
+            .await;
+        let github = crate::github::GithubClient::new(&crate::http::HttpContext::default())
+            .with_api_base(&mock_server.uri());
+
+        let mut tool = Tool {
+            name: "tool".to_string(),
+            repo: "owner/tool".to_string(),
+            ..Default::default()
+        };
+        let mut release = pending_update().release;
+        release.assets = vec![
+            make_asset("tool-linux-amd64.tar.gz", 10),
+            make_asset("tool-2.0.0-x86_64-unknown-linux-musl.tgz", 10),
+        ];
+
+        let (asset, _) = choose_asset(&github, &tool, &release, &Settings::default())
+            .await
+            .unwrap();
+        assert_eq!(asset.name, "tool-linux-amd64.tar.gz");
+
+        tool.binstall = true;
+        let (asset, _) = choose_asset(&github, &tool, &release, &Settings::default())
+            .await
+            .unwrap();
+        assert_eq!(asset.name, "tool-2.0.0-x86_64-unknown-linux-musl.tgz");
+
+        // The manifest is read from the code repository, not the one the
+        // binaries are published in
+        tool.release_repo = Some("owner/tool-binaries".to_string());
+        let (asset, _) = choose_asset(&github, &tool, &release, &Settings::default())
+            .await
+            .unwrap();
+        assert_eq!(asset.name, "tool-2.0.0-x86_64-unknown-linux-musl.tgz");
+
+        // Falls back to matching by name when the named asset is missing
+        release.assets.remove(1);
+        let (asset, _) = choose_asset(&github, &tool, &release, &Settings::default())
+            .await
+            .unwrap();
+        assert_eq!(asset.name, "tool-linux-amd64.tar.gz");
+    }
+
+    #[tokio::test]
+    async fn test_choose_asset_falls_back_to_quickinstall() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path(
+                "/repos/cargo-bins/cargo-quickinstall/releases/tags/tool-2.0.0",
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "tag_name": "tool-2.0.0",
+                "name": "tool-2.0.0",
+                "assets": [{
+                    "name": "tool-2.0.0-x86_64-unknown-linux-musl.tar.gz",
+                    "browser_download_url": "https://example.com/tool.tar.gz",
+                    "size": 10
+                }]
+            })))
+            .mount(&mock_server)
+            .await;
+        let github = crate::github::GithubClient::new(&crate::http::HttpContext::default())
+            .with_api_base(&mock_server.uri());
+
+        let mut tool = Tool {
+            name: "tool".to_string(),
+            repo: "owner/tool".to_string(),
+            build: Some("make".to_string()),
+            ..Default::default()
+        };
+        let mut release = pending_update().release;
+        release.assets = vec![make_asset("tool-windows-amd64.zip", 10)];
+        let settings = Settings {
+            quickinstall: Some(true),
+            ..Default::default()
+        };
+
+        let (asset, origin) = choose_asset(&github, &tool, &release, &settings)
+            .await
+            .unwrap();
+        assert_eq!(origin, AssetOrigin::Quickinstall);
+        assert_eq!(asset.name, "tool-2.0.0-x86_64-unknown-linux-musl.tar.gz");
+
+        // The tool's own setting wins
+        tool.quickinstall = Some(false);
+        let (_, origin) = choose_asset(&github, &tool, &release, &settings)
+            .await
+            .unwrap();
+        assert_eq!(origin, AssetOrigin::Source);
+
+        // No quickinstall build for this version: the build command applies
+        tool.quickinstall = Some(true);
+        release.tag_name = "v3.0.0".to_string();
+        let (_, origin) = choose_asset(&github, &tool, &release, &settings)
+            .await
+            .unwrap();
+        assert_eq!(origin, AssetOrigin::Source);
+    }
+
+    #[test]
+    fn test_review_answers() {
+        let tool = Tool {
+            name: "mytool".to_string(),
+            version: Some("v1.0.0".to_string()),
+            ..Default::default()
+        };
+        let pending = pending_update();
+
+        let mut review = Review::default();
+        let mut input = std::io::Cursor::new("bogus\nn\ny\n");
+        assert!(!review.approve_with(&mut input, &tool, &pending).unwrap());
+        assert!(review.approve_with(&mut input, &tool, &pending).unwrap());
+        assert!(!review.quit);
+
+        let mut input = std::io::Cursor::new("a\n");
+        assert!(review.approve_with(&mut input, &tool, &pending).unwrap());
+        // Once everything is approved no more input is read
+        assert!(review.approve_with(&mut input, &tool, &pending).unwrap());
+
+        let mut review = Review::default();
+        let mut input = std::io::Cursor::new("q\n");
+        assert!(!review.approve_with(&mut input, &tool, &pending).unwrap());
+        assert!(review.quit);
+
+        let mut review = Review::default();
+        let mut input = std::io::Cursor::new("");
+        assert!(!review.approve_with(&mut input, &tool, &pending).unwrap());
+        assert!(review.quit);
+    }
+
+    #[test]
+    fn test_confirm_large_download() {
+        let asset = make_asset("huge.tar.gz", 3 * 1024 * 1024 * 1024);
+
+        let mut input = std::io::Cursor::new("y\n");
+        assert!(confirm_large_download(&mut input, &asset).unwrap());
+
+        for answer in ["n\n", "\n", "whatever\n", ""] {
+            let mut input = std::io::Cursor::new(answer);
+            assert!(!confirm_large_download(&mut input, &asset).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_asset_change() {
+        // Version bumps, including ones with more numbers, aren't changes
+        assert_eq!(
+            asset_change(
+                "k9s_v0.32.4_Linux_amd64.tar.gz",
+                "k9s_v0.32.5_Linux_amd64.tar.gz"
+            ),
+            None
+        );
+        assert_eq!(
+            asset_change(
+                "tool-1.2-x86_64-unknown-linux-gnu.tar.gz",
+                "tool-1.10.1-x86_64-unknown-linux-gnu.tar.gz"
+            ),
+            None
+        );
+
+        assert_eq!(
+            asset_change(
+                "ripgrep-14.0.0-x86_64-unknown-linux-gnu.tar.gz",
+                "ripgrep-14.1.0-x86_64-unknown-linux-musl.tar.gz"
+            )
+            .as_deref(),
+            Some("gnu -> musl")
+        );
+        assert_eq!(
+            asset_change("k9s_Linux_amd64.tar.gz", "k9s_Linux_arm64.tar.gz").as_deref(),
+            Some("amd64 -> arm64")
+        );
+        assert_eq!(
+            asset_change("fd-linux-amd64.tar.gz", "fd-linux-amd64-static.tar.gz").as_deref(),
+            Some("now static")
+        );
+        assert_eq!(
+            asset_change("bat-linux.tar.gz", "bat-linux.zip").as_deref(),
+            Some("tar-gz -> zip")
+        );
+    }
+
+    #[test]
+    fn test_confirm_asset_change() {
+        let asset = make_asset("tool-linux-musl.tar.gz", 1024);
+        assert!(confirm_asset_change(&mut std::io::Cursor::new("yes\n"), &asset).unwrap());
+        assert!(!confirm_asset_change(&mut std::io::Cursor::new(""), &asset).unwrap());
+    }
+
+    #[test]
+    fn test_confirm_build() {
+        let tool = Tool {
+            name: "tool".to_string(),
+            ..Default::default()
+        };
+        let config_path = Path::new("/srv/project/oktofetch.toml");
+        let mut input = std::io::Cursor::new("y\n");
+        assert!(confirm_build(&mut input, &tool, "make install", config_path).unwrap());
+        for answer in ["n\n", ""] {
+            let mut input = std::io::Cursor::new(answer);
+            assert!(!confirm_build(&mut input, &tool, "make install", config_path).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_is_installed() {
+        let (temp_dir, config, mut tool) = checksum_fixture();
+        assert!(!is_installed(&config, &tool));
+
+        std::fs::write(temp_dir.path().join("mytool"), b"bin").unwrap();
+        tool.version = Some("v1.0.0".to_string());
+        assert!(is_installed(&config, &tool));
+
+        tool.checksum = Some("0".repeat(64));
+        assert!(!is_installed(&config, &tool));
+
+        tool.checksum = None;
+        tool.version = None;
+        assert!(!is_installed(&config, &tool));
+
+        tool.version = Some("v1.0.0".to_string());
+        tool.install_as = Some("elsewhere".to_string());
+        assert!(!is_installed(&config, &tool));
+    }
+
+    #[tokio::test]
+    async fn test_check_installable() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let mock_server = MockServer::start().await;
+        let release = |assets: &str| {
+            ResponseTemplate::new(200).set_body_string(format!(
+                r#"{{"id": 1, "tag_name": "v1.0.0", "name": "v1.0.0", "assets": [{}]}}"#,
+                assets
+            ))
+        };
+        Mock::given(method("GET"))
+            .and(path("/repos/owner/good/releases/latest"))
+            .respond_with(release(
+                r#"{"name": "good_linux_amd64.tar.gz", "browser_download_url": "https://example.com/a", "size": 1}"#,
+            ))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/repos/owner/mac-only/releases/latest"))
+            .respond_with(release(
+                r#"{"name": "tool_darwin_arm64.tar.gz", "browser_download_url": "https://example.com/b", "size": 1}"#,
+            ))
+            .mount(&mock_server)
+            .await;
+
+        let client = crate::github::GithubClient::new(&crate::http::HttpContext::default())
+            .with_api_base(&mock_server.uri());
+        let tool = |repo: &str| Tool {
+            name: "tool".to_string(),
+            repo: repo.to_string(),
+            ..Default::default()
+        };
+
+        let (release, asset) =
+            check_installable(&client, &tool("owner/good"), &Settings::default())
+                .await
+                .unwrap();
+        assert_eq!(release.tag_name, "v1.0.0");
+        assert_eq!(asset.name, "good_linux_amd64.tar.gz");
+
+        assert!(matches!(
+            check_installable(&client, &tool("owner/mac-only"), &Settings::default()).await,
+            Err(OktofetchError::NoSuitableRelease { .. })
+        ));
+        assert!(matches!(
+            check_installable(&client, &tool("owner/missing"), &Settings::default()).await,
+            Err(OktofetchError::RepoNotFound(_))
+        ));
     }
 
-    #[test]
-    fn test_parse_repo_https_url() {
+    #[tokio::test]
+    async fn test_match_release_tag() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let mock_server = MockServer::start().await;
+        for (repo, tag) in [("owner/prefixed", "v1.2.3"), ("owner/plain", "1.2.3")] {
+            Mock::given(method("GET"))
+                .and(path(format!("/repos/{}/releases/tags/{}", repo, tag)))
+                .respond_with(ResponseTemplate::new(200).set_body_string(format!(
+                    r#"{{"id": 1, "tag_name": "{}", "name": "{}", "assets": []}}"#,
+                    tag, tag
+                )))
+                .mount(&mock_server)
+                .await;
+        }
+
+        let client = crate::github::GithubClient::new(&crate::http::HttpContext::default())
+            .with_api_base(&mock_server.uri());
+        let tool = |repo: &str| Tool {
+            name: "tool".to_string(),
+            repo: repo.to_string(),
+            ..Default::default()
+        };
+
         assert_eq!(
-            parse_repo("https://github.com/owner/repo").unwrap(),
-            "owner/repo"
+            match_release_tag(&client, &tool("owner/prefixed"), "1.2.3").await,
+            "v1.2.3"
         );
         assert_eq!(
-            parse_repo("https://github.com/derailed/k9s").unwrap(),
-            "derailed/k9s"
+            match_release_tag(&client, &tool("owner/plain"), "1.2.3").await,
+            "1.2.3"
         );
         assert_eq!(
-            parse_repo("https://github.com/vmware/govmomi/issues").unwrap(),
-            "vmware/govmomi"
+            match_release_tag(&client, &tool("owner/other"), "9.9.9").await,
+            "9.9.9"
         );
     }
 
+    #[tokio::test]
+    async fn test_adopt_tool_requires_binary() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = Config::default();
+        config.settings.install_dir = temp_dir.path().to_path_buf();
+
+        let err = adopt_tool(&mut config, "k9s", "derailed/k9s", false)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, OktofetchError::BinaryNotFound(_)));
+        assert!(config.tools.is_empty());
+    }
+
     #[test]
-    fn test_parse_repo_http_url() {
+    fn test_freeze() {
+        let mut config = Config::default();
+        config.settings.install_dir = PathBuf::from("/home/someone/bin");
+        config
+            .add_tool(Tool {
+                name: "k9s".to_string(),
+                repo: "derailed/k9s".to_string(),
+                asset_pattern: Some("Linux_amd64".to_string()),
+                version: Some("v0.32.5".to_string()),
+                checksum: Some("abc".to_string()),
+                asset_digest: Some("sha256:def".to_string()),
+                kept_versions: vec!["v0.31.0".to_string()],
+                ..Default::default()
+            })
+            .unwrap();
+        config
+            .add_tool(Tool {
+                name: "local".to_string(),
+                repo: "me/local".to_string(),
+                source: Some("command:/home/someone/plugin".to_string()),
+                asset_selector: Some("/home/someone/pick.sh".to_string()),
+                build: Some("make install".to_string()),
+                ..Default::default()
+            })
+            .unwrap();
+
+        let frozen = freeze(&config).unwrap();
+        assert!(!frozen.contains("install_dir"));
+        assert!(!frozen.contains("kept_versions"));
+        assert!(!frozen.contains("/home/someone"));
+        assert!(!frozen.contains("make install"));
+        assert!(!frozen.contains("checksum"));
+        assert!(!frozen.contains("asset_digest"));
+        assert!(frozen.contains("sha256 = \"def\""));
+
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("oktofetch.toml");
+        std::fs::write(&path, frozen).unwrap();
+        let loaded = Config::load_from(&path).unwrap();
+        assert_eq!(loaded.tools[0].repo, "derailed/k9s");
         assert_eq!(
-            parse_repo("http://github.com/owner/repo").unwrap(),
-            "owner/repo"
+            loaded.tools[0].asset_pattern.as_deref(),
+            Some("Linux_amd64")
         );
+        assert_eq!(loaded.tools[0].version.as_deref(), Some("v0.32.5"));
+        assert!(loaded.tools[0].pinned);
     }
 
-    #[test]
-    fn test_parse_repo_invalid_format() {
-        assert!(parse_repo("invalid").is_err());
-        assert!(parse_repo("owner/repo/extra/parts").is_err());
-        assert!(parse_repo("").is_err());
+    fn make_asset(name: &str, size: u64) -> Asset {
+        Asset {
+            name: name.to_string(),
+            browser_download_url: format!("https://example.com/{}", name),
+            size,
+            digest: None,
+        }
     }
 
-    #[test]
-    fn test_parse_repo_non_github_url() {
-        let result = parse_repo("https://gitlab.com/owner/repo");
-        // Should fail because it's not github.com
-        assert!(result.is_err());
+    fn write_download_plugin(dir: &std::path::Path) -> source::CommandSource {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = dir.join("plugin");
+        std::fs::write(
+            &path,
+            format!(
+                "#!/bin/sh\necho x >> {}\nprintf abc > \"$3\"\n",
+                dir.join("downloads.log").display()
+            ),
+        )
+        .unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        source::CommandSource::new(path)
     }
 
-    #[test]
-    fn test_parse_repo_error_message() {
-        let result = parse_repo("invalid");
-        assert!(result.is_err());
-        let err_msg = format!("{}", result.unwrap_err());
-        assert!(err_msg.contains("Invalid repository format"));
-        assert!(err_msg.contains("owner/repo"));
+    fn download_count(dir: &std::path::Path) -> usize {
+        std::fs::read_to_string(dir.join("downloads.log"))
+            .map(|log| log.lines().count())
+            .unwrap_or(0)
     }
 
-    #[test]
-    fn test_tool_name_derivation_simple() {
-        // Test that we correctly parse repo and derive tool name
-        assert_eq!(parse_repo("owner/repo").unwrap(), "owner/repo");
+    #[tokio::test]
+    async fn test_download_to_cache_reuses_complete_download() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let source = write_download_plugin(temp_dir.path());
+        let asset = make_asset("tool.tar.gz", 3);
+        let cache_dir = temp_dir.path().join("cache");
 
-        // Test URL parsing
-        assert_eq!(
-            parse_repo("https://github.com/owner/myrepo").unwrap(),
-            "owner/myrepo"
-        );
+        let path = download_to_cache(&source, &asset, &cache_dir, None)
+            .await
+            .unwrap()
+            .path;
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "abc");
+        assert!(!cache_dir.join("tool.tar.gz.part").exists());
+        assert_eq!(download_count(temp_dir.path()), 1);
+
+        download_to_cache(&source, &asset, &cache_dir, None)
+            .await
+            .unwrap();
+        assert_eq!(download_count(temp_dir.path()), 1);
+
+        // A truncated leftover is downloaded again
+        std::fs::write(&path, "a").unwrap();
+        download_to_cache(&source, &asset, &cache_dir, None)
+            .await
+            .unwrap();
+        assert_eq!(download_count(temp_dir.path()), 2);
     }
 
     #[test]
-    fn test_remove_tool_not_found() {
-        let mut config = Config::default();
-        let result = remove_tool(&mut config, "nonexistent");
-        assert!(result.is_err());
+    fn test_verify_download_size_mismatch() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("tool.tar.gz");
+        std::fs::write(&path, "abc").unwrap();
+
+        assert!(verify_download(&make_asset("tool.tar.gz", 3), &path, None, None).is_ok());
+        assert!(verify_download(&make_asset("tool.tar.gz", 0), &path, None, None).is_ok());
+
+        let err = verify_download(&make_asset("tool.tar.gz", 10), &path, None, None).unwrap_err();
+        assert!(matches!(err, OktofetchError::DownloadFailed(_)));
+        assert!(!path.exists());
     }
 
     #[test]
-    fn test_remove_tool_logic() {
-        // Test the underlying logic without saving
-        let mut config = Config::default();
-        let tool = crate::config::Tool {
-            name: "tool1".to_string(),
-            repo: "owner/repo1".to_string(),
-            binary_name: None,
-            asset_pattern: None,
-            version: None,
-        };
-        config.add_tool(tool).unwrap();
+    fn test_verify_download_digest() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("tool.tar.gz");
+        std::fs::write(&path, "abc").unwrap();
 
-        // Test the remove logic directly on config
-        let result = config.remove_tool("tool1");
-        assert!(result.is_ok());
-        assert!(config.get_tool("tool1").is_none());
+        let mut asset = make_asset("tool.tar.gz", 3);
+        asset.digest = Some(
+            "sha256:BA7816BF8F01CFEA414140DE5DAE2223B00361A396177A9CB410FF61F20015AD".to_string(),
+        );
+        assert!(verify_download(&asset, &path, None, None).is_ok());
+
+        asset.digest = Some(format!("sha256:{}", "0".repeat(64)));
+        let err = verify_download(&asset, &path, None, None).unwrap_err();
+        assert!(err.to_string().contains("but the release lists"));
+        assert!(!path.exists());
     }
 
     #[test]
-    fn test_list_tools_empty() {
-        let config = Config::default();
-        let result = list_tools(&config);
-        assert!(result.is_ok());
+    fn test_verify_download_pinned() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("tool.tar.gz");
+        std::fs::write(&path, "abc").unwrap();
+        let asset = make_asset("tool.tar.gz", 3);
+
+        let pinned = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+        assert!(verify_download(&asset, &path, Some(pinned), None).is_ok());
+
+        let err = verify_download(&asset, &path, Some(&"0".repeat(64)), None).unwrap_err();
+        assert!(err.to_string().contains("pinned to"));
+        assert!(!path.exists());
     }
 
     #[test]
-    fn test_list_tools_with_entries() {
+    fn test_recently_checked() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("k9s"), "bin").unwrap();
         let mut config = Config::default();
-        let tool = crate::config::Tool {
-            name: "tool1".to_string(),
-            repo: "owner/repo1".to_string(),
-            binary_name: Some("bin1".to_string()),
-            asset_pattern: None,
-            version: Some("v1.0.0".to_string()),
+        config.settings.install_dir = temp_dir.path().to_path_buf();
+        let checked_at = human::unix_now() - 3600;
+        let tool = Tool {
+            name: "k9s".to_string(),
+            repo: "derailed/k9s".to_string(),
+            checked_at: Some(checked_at),
+            ..Default::default()
+        };
+        let opts = UpdateOptions {
+            min_check_interval: Some(6 * 3600),
+            ..Default::default()
         };
-        config.add_tool(tool).unwrap();
 
-        let result = list_tools(&config);
-        assert!(result.is_ok());
+        assert_eq!(recently_checked(&config, &tool, &opts), Some(checked_at));
+
+        let stale = UpdateOptions {
+            min_check_interval: Some(1800),
+            ..opts.clone()
+        };
+        assert_eq!(recently_checked(&config, &tool, &stale), None);
+        let forced = UpdateOptions {
+            force: true,
+            ..opts.clone()
+        };
+        assert_eq!(recently_checked(&config, &tool, &forced), None);
+        assert_eq!(
+            recently_checked(&config, &tool, &UpdateOptions::default()),
+            None
+        );
+
+        // A missing binary is reinstalled regardless
+        std::fs::remove_file(temp_dir.path().join("k9s")).unwrap();
+        assert_eq!(recently_checked(&config, &tool, &opts), None);
     }
 
     #[test]
-    fn test_list_tools_multiple_entries() {
+    fn test_unchanged_per_batch() {
+        let tool = Tool {
+            name: "k9s".to_string(),
+            repo: "derailed/k9s".to_string(),
+            version: Some("v0.32.5".to_string()),
+            ..Default::default()
+        };
         let mut config = Config::default();
-        for i in 1..=3 {
-            let tool = crate::config::Tool {
-                name: format!("tool{}", i),
-                repo: format!("owner/repo{}", i),
-                binary_name: None,
-                asset_pattern: None,
-                version: None,
-            };
-            config.add_tool(tool).unwrap();
-        }
+        assert!(!unchanged_per_batch(&config, &tool));
 
-        let result = list_tools(&config);
-        assert!(result.is_ok());
-        assert_eq!(config.tools.len(), 3);
+        config
+            .latest_tags
+            .insert("k9s".to_string(), "v0.32.5".to_string());
+        assert!(unchanged_per_batch(&config, &tool));
+
+        config
+            .latest_tags
+            .insert("k9s".to_string(), "v0.33.0".to_string());
+        assert!(!unchanged_per_batch(&config, &tool));
     }
 
     #[test]
-    fn test_asset_priority_sorting() {
-        // Verify that tar.gz gets lowest value (highest priority)
-        assert!(asset_priority("app.tar.gz") < asset_priority("app.zip"));
-        assert!(asset_priority("app.zip") < asset_priority("app.7z"));
+    fn test_unmaintained_reason() {
+        let now = 1_800_000_000;
+        let mut tool = Tool {
+            name: "old".to_string(),
+            released_at: Some(now - 400 * 86400),
+            ..Default::default()
+        };
+        assert_eq!(unmaintained_reason(&tool, now), None);
 
-        // Verify tgz also gets highest priority
-        assert_eq!(asset_priority("app.tgz"), asset_priority("app.tar.gz"));
+        tool.released_at = Some(now - 3 * 365 * 86400);
+        assert_eq!(
+            unmaintained_reason(&tool, now).as_deref(),
+            Some("last release 3 years ago")
+        );
+
+        tool.archived = true;
+        assert_eq!(
+            unmaintained_reason(&tool, now).as_deref(),
+            Some("repository archived")
+        );
+        assert_eq!(unmaintained_reason(&Tool::default(), now), None);
     }
 
-    #[tokio::test]
-    async fn test_add_tool_basic() {
+    #[test]
+    fn test_record_snapshots() {
         let mut config = Config::default();
+        config
+            .add_tool(Tool {
+                name: "k9s".to_string(),
+                repo: "derailed/k9s".to_string(),
+                released_at: Some(1_700_000_000),
+                ..Default::default()
+            })
+            .unwrap();
 
-        // Test adding a tool
-        let result = config.add_tool(crate::config::Tool {
-            name: "testtool".to_string(),
-            repo: "owner/repo".to_string(),
-            binary_name: None,
-            asset_pattern: None,
-            version: None,
-        });
+        let snapshot = RepoSnapshot {
+            latest_tag: Some("v0.32.5".to_string()),
+            released_at: Some(1_600_000_000),
+            archived: true,
+        };
+        record_snapshots(&mut config, &HashMap::from([("k9s".to_string(), snapshot)]));
 
-        assert!(result.is_ok());
-        assert_eq!(config.tools.len(), 1);
-        assert_eq!(config.tools[0].name, "testtool");
+        let tool = config.get_tool("k9s").unwrap();
+        assert!(tool.archived);
+        // An older publish date doesn't replace a newer one
+        assert_eq!(tool.released_at, Some(1_700_000_000));
     }
 
-    #[test]
-    fn test_parse_repo_edge_cases() {
-        // Test with trailing slash
-        assert_eq!(
-            parse_repo("https://github.com/owner/repo/").unwrap(),
-            "owner/repo"
-        );
+    #[tokio::test]
+    async fn test_record_archived() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
 
-        // Test with .git suffix - this is valid (owner/repo.git is still owner/repo.git)
-        assert_eq!(parse_repo("owner/repo.git").unwrap(), "owner/repo.git");
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/repos/owner/old"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string(r#"{"full_name": "owner/old", "archived": true}"#),
+            )
+            .mount(&mock_server)
+            .await;
+        let github = crate::github::GithubClient::new(&crate::http::HttpContext::default())
+            .with_api_base(&mock_server.uri());
 
-        // Test empty string
-        assert!(parse_repo("").is_err());
+        let mut config = Config::default();
+        let tool = Tool {
+            name: "old".to_string(),
+            repo: "owner/old".to_string(),
+            ..Default::default()
+        };
+        config.add_tool(tool.clone()).unwrap();
+        record_archived(&mut config, &github, &tool).await;
+        assert!(config.get_tool("old").unwrap().archived);
 
-        // Test malformed inputs
-        assert!(parse_repo("onlyonepart").is_err());
-        assert!(parse_repo("too/many/parts/here").is_err());
+        // A failed lookup leaves what was known alone
+        let gone = Tool {
+            name: "gone".to_string(),
+            repo: "owner/gone".to_string(),
+            archived: true,
+            ..Default::default()
+        };
+        config.add_tool(gone.clone()).unwrap();
+        record_archived(&mut config, &github, &gone).await;
+        assert!(config.get_tool("gone").unwrap().archived);
+    }
 
-        // Test with special characters that should work
-        assert_eq!(parse_repo("my-org/my-repo").unwrap(), "my-org/my-repo");
+    #[test]
+    fn test_verify_download_uses_streamed_sha256() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("tool.tar.gz");
+        std::fs::write(&path, "abc").unwrap();
+        let abc = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+        let mut asset = make_asset("tool.tar.gz", 3);
+
+        // Nothing to check against: the streamed hash is passed through
         assert_eq!(
-            parse_repo("org_name/repo_name").unwrap(),
-            "org_name/repo_name"
+            verify_download(&asset, &path, None, Some(abc)).unwrap(),
+            Some(abc.to_string())
         );
+        assert_eq!(verify_download(&asset, &path, None, None).unwrap(), None);
+
+        // The file isn't read again, so only the streamed hash counts
+        asset.digest = Some(format!("sha256:{}", abc));
+        let wrong = "0".repeat(64);
+        let err = verify_download(&asset, &path, None, Some(&wrong)).unwrap_err();
+        assert!(err.to_string().contains("but the release lists"));
     }
 
     #[test]
-    fn test_list_tools_formatting() {
-        let mut config = Config::default();
+    fn test_check_verification_policy() {
+        let mut tool = Tool {
+            name: "mytool".to_string(),
+            ..Default::default()
+        };
+        let mut asset = make_asset("tool.tar.gz", 3);
 
-        // Add tools with various configurations
-        config
-            .add_tool(crate::config::Tool {
-                name: "tool_with_version".to_string(),
-                repo: "owner/repo1".to_string(),
-                binary_name: Some("custom_bin".to_string()),
-                asset_pattern: None,
-                version: Some("v1.0.0".to_string()),
-            })
-            .unwrap();
+        assert!(check_verification_policy(Verification::Off, &tool, &asset).is_ok());
+        let err = check_verification_policy(Verification::Checksum, &tool, &asset).unwrap_err();
+        assert!(err.to_string().contains("no published SHA256"));
 
-        config
-            .add_tool(crate::config::Tool {
-                name: "tool_without_version".to_string(),
-                repo: "owner/repo2".to_string(),
-                binary_name: None,
-                asset_pattern: None,
-                version: None,
-            })
-            .unwrap();
+        asset.digest = Some(format!("sha256:{}", "0".repeat(64)));
+        assert!(check_verification_policy(Verification::Checksum, &tool, &asset).is_ok());
+        asset.digest = None;
+        tool.sha256 = Some("0".repeat(64));
+        assert!(check_verification_policy(Verification::Checksum, &tool, &asset).is_ok());
 
-        let result = list_tools(&config);
-        assert!(result.is_ok());
+        // Nothing can satisfy a signature requirement without configured keys
+        let err = check_verification_policy(Verification::Signature, &tool, &asset).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("No signing keys/attestations configured for")
+        );
     }
 
-    #[test]
-    fn test_remove_tool_updates_config() {
-        let mut config = Config::default();
+    #[tokio::test]
+    async fn test_update_refused_under_signature_policy() {
+        use std::os::unix::fs::PermissionsExt;
 
-        // Add multiple tools
-        for i in 1..=3 {
-            config
-                .add_tool(crate::config::Tool {
-                    name: format!("tool{}", i),
-                    repo: format!("owner/repo{}", i),
-                    binary_name: None,
-                    asset_pattern: None,
-                    version: None,
-                })
-                .unwrap();
-        }
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let plugin = temp_dir.path().join("plugin");
+        std::fs::write(
+            &plugin,
+            format!(
+                r#"#!/bin/sh
+case "$1" in
+  latest) echo '{{"tag_name":"v1.0.0","name":"v1.0.0","assets":[{{"name":"tool-linux-amd64.tar.gz","browser_download_url":"mem://a","size":3}}]}}' ;;
+  download) echo x >> {}; printf abc > "$3" ;;
+  *) exit 1 ;;
+esac
+"#,
+                temp_dir.path().join("downloads.log").display()
+            ),
+        )
+        .unwrap();
+        std::fs::set_permissions(&plugin, std::fs::Permissions::from_mode(0o755)).unwrap();
 
-        assert_eq!(config.tools.len(), 3);
+        let mut config = Config::default();
+        config.settings.install_dir = temp_dir.path().join("bin");
+        config.settings.verification = Some(Verification::Signature);
+        config
+            .add_tool(Tool {
+                name: "tool".to_string(),
+                repo: "owner/tool".to_string(),
+                source: Some(format!("command:{}", plugin.display())),
+                ..Default::default()
+            })
+            .unwrap();
 
-        // Remove middle tool
-        config.remove_tool("tool2").unwrap();
-        assert_eq!(config.tools.len(), 2);
-        assert!(config.get_tool("tool1").is_some());
-        assert!(config.get_tool("tool2").is_none());
-        assert!(config.get_tool("tool3").is_some());
+        let err = update_tool(&mut config, "tool", &UpdateOptions::default())
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("No signing keys/attestations configured for tool")
+        );
+        assert_eq!(download_count(temp_dir.path()), 0);
+        assert!(!temp_dir.path().join("bin/tool").exists());
+        assert_eq!(config.get_tool("tool").unwrap().version, None);
     }
 
     #[test]
-    fn test_parse_repo_url_variations() {
-        // Test various URL formats
-        let test_cases = vec![
-            ("https://github.com/owner/repo", "owner/repo"),
-            ("http://github.com/owner/repo", "owner/repo"),
-            ("https://github.com/owner/repo/releases", "owner/repo"),
-            ("https://github.com/owner/repo/tree/main", "owner/repo"),
-        ];
-
-        for (input, expected) in test_cases {
-            assert_eq!(
-                parse_repo(input).unwrap(),
-                expected,
-                "Failed for input: {}",
-                input
-            );
-        }
+    fn test_download_cache_dir_sanitizes_tag() {
+        let tool = Tool {
+            name: "mytool".to_string(),
+            ..Default::default()
+        };
+        let release = Release {
+            id: 0,
+            tag_name: "mytool/v1.0.0".to_string(),
+            name: String::new(),
+            assets: vec![],
+            prerelease: false,
+            draft: false,
+            body: None,
+            published_at: None,
+        };
+        assert_eq!(
+            download_cache_dir(Path::new("/cache"), &tool, &release),
+            PathBuf::from("/cache/mytool/mytool%2Fv1.0.0")
+        );
+        // Tags that only differ in `/` and `_` don't share a directory
+        let underscored = Release {
+            tag_name: "mytool_v1.0.0".to_string(),
+            ..release.clone()
+        };
+        assert_eq!(
+            download_cache_dir(Path::new("/cache"), &tool, &underscored),
+            PathBuf::from("/cache/mytool/mytool_v1.0.0")
+        );
+        let dots = Release {
+            tag_name: "..".to_string(),
+            ..release
+        };
+        assert_eq!(
+            download_cache_dir(Path::new("/cache"), &tool, &dots),
+            PathBuf::from("/cache/mytool/%2E%2E")
+        );
+        assert_eq!(Stage::Verify.to_string(), "verify");
     }
 }
@@ -0,0 +1,576 @@
+//! Interactive terminal dashboard (`oktofetch ui`).
+//!
+//! Upstream checks run as background tasks and report back over a channel.
+//! Updates run as a child `oktofetch update <tool>` process so their output
+//! can be streamed into the log pane without disturbing the screen.
+
+use crate::config::{Config, Tool};
+use crate::error::{OktofetchError, Result};
+use crate::{lock, prompt, source, tool};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, List, ListItem, Paragraph, Row, Table, TableState};
+use ratatui::{DefaultTerminal, Frame};
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel};
+
+/// Lines of update output kept for the log pane.
+const LOG_LINES: usize = 200;
+
+/// How long to wait for a key press before redrawing.
+const TICK: Duration = Duration::from_millis(100);
+
+/// Upstream state of a tool as far as the dashboard knows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Upstream {
+    Checking,
+    UpToDate,
+    Available(String),
+    Failed(String),
+}
+
+impl Upstream {
+    fn label(&self) -> String {
+        match self {
+            Self::Checking => "checking...".to_string(),
+            Self::UpToDate => "up to date".to_string(),
+            Self::Available(version) => format!("update: {}", version),
+            Self::Failed(err) => format!("error: {}", err),
+        }
+    }
+}
+
+/// Events sent to the UI loop from background tasks.
+enum Message {
+    Upstream(String, Upstream),
+    Log(String),
+    Finished { tool: String, success: bool },
+}
+
+/// Something the user asked for that the event loop has to carry out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Action {
+    Update(String),
+    Remove(String),
+    TogglePin(String),
+    Refresh,
+}
+
+struct App {
+    config: Config,
+    upstream: HashMap<String, Upstream>,
+    table: TableState,
+    log: Vec<String>,
+    /// Tool currently being updated; only one update runs at a time
+    busy: Option<String>,
+    /// Tool awaiting confirmation of removal
+    confirm_remove: Option<String>,
+    quit: bool,
+}
+
+impl App {
+    fn new(config: Config) -> Self {
+        let mut table = TableState::default();
+        if !config.tools.is_empty() {
+            table.select(Some(0));
+        }
+
+        Self {
+            config,
+            upstream: HashMap::new(),
+            table,
+            log: Vec::new(),
+            busy: None,
+            confirm_remove: None,
+            quit: false,
+        }
+    }
+
+    fn selected_tool(&self) -> Option<String> {
+        self.table
+            .selected()
+            .and_then(|i| self.config.tools.get(i))
+            .map(|t| t.name.clone())
+    }
+
+    fn push_log(&mut self, line: String) {
+        self.log.push(line);
+        if self.log.len() > LOG_LINES {
+            self.log.remove(0);
+        }
+    }
+
+    fn handle_key(&mut self, key: KeyCode) -> Option<Action> {
+        if let Some(tool) = self.confirm_remove.take() {
+            return matches!(key, KeyCode::Char('y')).then_some(Action::Remove(tool));
+        }
+
+        let count = self.config.tools.len();
+        match key {
+            KeyCode::Char('q') | KeyCode::Esc => {
+                self.quit = true;
+                None
+            }
+            KeyCode::Down | KeyCode::Char('j') if count > 0 => {
+                let next = self.table.selected().map_or(0, |i| (i + 1).min(count - 1));
+                self.table.select(Some(next));
+                None
+            }
+            KeyCode::Up | KeyCode::Char('k') if count > 0 => {
+                let prev = self.table.selected().map_or(0, |i| i.saturating_sub(1));
+                self.table.select(Some(prev));
+                None
+            }
+            KeyCode::Char('u') => {
+                if let Some(busy) = &self.busy {
+                    self.push_log(format!("Already updating {}", busy));
+                    return None;
+                }
+                self.selected_tool().map(Action::Update)
+            }
+            KeyCode::Char('p') => self.selected_tool().map(Action::TogglePin),
+            KeyCode::Char('d') => {
+                if let Some(tool) = self.selected_tool() {
+                    self.push_log(format!("Remove {}? Press y to confirm", tool));
+                    self.confirm_remove = Some(tool);
+                }
+                None
+            }
+            KeyCode::Char('r') => Some(Action::Refresh),
+            _ => None,
+        }
+    }
+
+    /// Drops upstream results and the selection for tools no longer configured.
+    fn sync_selection(&mut self) {
+        let count = self.config.tools.len();
+        self.upstream
+            .retain(|name, _| self.config.get_tool(name).is_some());
+        match self.table.selected() {
+            _ if count == 0 => self.table.select(None),
+            Some(i) if i >= count => self.table.select(Some(count - 1)),
+            None => self.table.select(Some(0)),
+            _ => {}
+        }
+    }
+}
+
+/// Checks each named tool upstream in the background.
+fn spawn_checks(app: &mut App, names: &[String], tx: &UnboundedSender<Message>) {
+    for name in names {
+        let Some(tool) = app.config.get_tool(name).cloned() else {
+            continue;
+        };
+        let settings = app.config.settings.clone();
+        let http = app.config.http();
+        let tx = tx.clone();
+
+        app.upstream.insert(tool.name.clone(), Upstream::Checking);
+        tokio::spawn(async move {
+            let status = match check_upstream(&tool, &settings, &http).await {
+                Ok(status) => status,
+                Err(e) => Upstream::Failed(e.to_string()),
+            };
+            let _ = tx.send(Message::Upstream(tool.name, status));
+        });
+    }
+}
+
+async fn check_upstream(
+    tool: &crate::config::Tool,
+    settings: &crate::config::Settings,
+    http: &crate::http::HttpContext,
+) -> Result<Upstream> {
+    let source = source::for_tool(tool, settings, http)?;
+    if source::unchanged_per_hint(source.as_ref(), tool).await {
+        return Ok(Upstream::UpToDate);
+    }
+
+    let channel = tool.channel.unwrap_or_default();
+    let release = source::newest_release(source.as_ref(), tool.release_repo(), channel).await?;
+    let latest = tool.version_from_tag(&release.tag_name);
+
+    if tool.version.as_deref() == Some(latest.as_str()) {
+        Ok(Upstream::UpToDate)
+    } else {
+        Ok(Upstream::Available(latest))
+    }
+}
+
+/// Runs `oktofetch update <tool> --yes` and streams its output into the log.
+/// Pressing the key is the confirmation; the child has no terminal to ask on.
+fn spawn_update(tool: String, tx: UnboundedSender<Message>) -> Result<()> {
+    let mut command = tokio::process::Command::new(std::env::current_exe()?);
+    if let Some(path) = Config::path_override() {
+        command.arg("--config").arg(path);
+    }
+    let mut child = command
+        .args(["update", &tool, "--yes"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    let forward = |stream: Option<Box<dyn tokio::io::AsyncRead + Unpin + Send>>| {
+        let tx = tx.clone();
+        async move {
+            if let Some(stream) = stream {
+                let mut lines = BufReader::new(stream).lines();
+                while let Ok(Some(line)) = lines.next_line().await {
+                    let _ = tx.send(Message::Log(line));
+                }
+            }
+        }
+    };
+    let stdout = forward(child.stdout.take().map(|s| Box::new(s) as _));
+    let stderr = forward(child.stderr.take().map(|s| Box::new(s) as _));
+
+    tokio::spawn(async move {
+        tokio::join!(stdout, stderr);
+        let success = child.wait().await.is_ok_and(|status| status.success());
+        let _ = tx.send(Message::Finished { tool, success });
+    });
+
+    Ok(())
+}
+
+/// Applies `change` to the config and saves it while holding the lock, like
+/// the CLI's commands do. The config is read afresh under the lock so that
+/// a change saved meanwhile, e.g. by an update, isn't lost.
+fn change_config<T>(app: &mut App, change: impl FnOnce(&mut Config) -> Result<T>) -> Result<T> {
+    let _lock = lock::acquire(&Config::lock_path()?, false)?;
+    let mut config = Config::load()?;
+    let result = change(&mut config)?;
+    config.save()?;
+    app.config = config;
+    Ok(result)
+}
+
+/// Deletes what `oktofetch remove` would after a tool left the config, and
+/// logs it.
+fn clean_up_removed(app: &mut App, removed: &Tool, completions_dir: &Path) -> Result<()> {
+    let install_dir = app.config.install_dir_for(removed);
+    let deleted = tool::delete_leftovers(&install_dir, completions_dir, removed)?;
+    app.sync_selection();
+    app.push_log(format!("Removed {}", removed.name));
+    for path in deleted {
+        app.push_log(format!("Deleted {}", path.display()));
+    }
+    if !removed.bundle {
+        app.push_log(format!("Binary {} left in place", removed.installed_name()));
+    }
+    Ok(())
+}
+
+fn perform(app: &mut App, action: Action, tx: &UnboundedSender<Message>) -> Result<()> {
+    match action {
+        Action::Update(tool) => {
+            app.push_log(format!("Updating {}...", tool));
+            spawn_update(tool.clone(), tx.clone())?;
+            app.busy = Some(tool);
+        }
+        Action::Remove(name) => {
+            let removed = change_config(app, |config| {
+                let tool = config
+                    .get_tool(&name)
+                    .cloned()
+                    .ok_or_else(|| OktofetchError::ToolNotFound(name.clone()))?;
+                config.remove_tool(&name)?;
+                Ok(tool)
+            })?;
+            clean_up_removed(app, &removed, &Config::completions_dir()?)?;
+        }
+        Action::TogglePin(tool) => {
+            let pinned = change_config(app, |config| {
+                Ok(config.get_tool_mut(&tool).map(|entry| {
+                    entry.pinned = !entry.pinned;
+                    entry.pinned
+                }))
+            })?;
+            if let Some(pinned) = pinned {
+                let state = if pinned { "Pinned" } else { "Unpinned" };
+                app.push_log(format!("{} {}", state, tool));
+            }
+        }
+        Action::Refresh => {
+            let names: Vec<String> = app.config.tools.iter().map(|t| t.name.clone()).collect();
+            spawn_checks(app, &names, tx);
+        }
+    }
+    Ok(())
+}
+
+fn receive(app: &mut App, message: Message, tx: &UnboundedSender<Message>) {
+    match message {
+        Message::Upstream(tool, status) => {
+            if app.config.get_tool(&tool).is_some() {
+                app.upstream.insert(tool, status);
+            }
+        }
+        Message::Log(line) => app.push_log(line),
+        Message::Finished { tool, success } => {
+            app.busy = None;
+            app.push_log(if success {
+                format!("Finished updating {}", tool)
+            } else {
+                format!("Update of {} failed", tool)
+            });
+
+            // The child process saved the new version; pick it up
+            match Config::load() {
+                Ok(config) => {
+                    app.config = config;
+                    app.sync_selection();
+                    spawn_checks(app, &[tool], tx);
+                }
+                Err(e) => app.push_log(format!("Could not reload config: {}", e)),
+            }
+        }
+    }
+}
+
+fn draw(frame: &mut Frame, app: &mut App) {
+    let [table_area, log_area, help_area] = Layout::vertical([
+        Constraint::Min(5),
+        Constraint::Length(10),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+
+    let rows = app.config.tools.iter().map(|tool| {
+        let upstream = app.upstream.get(&tool.name);
+        let style = match upstream {
+            Some(Upstream::Available(_)) => Style::default().fg(Color::Yellow),
+            Some(Upstream::Failed(_)) => Style::default().fg(Color::Red),
+            _ => Style::default(),
+        };
+        Row::new(vec![
+            tool.name.clone(),
+            tool.version.clone().unwrap_or_else(|| "-".to_string()),
+            upstream.map(Upstream::label).unwrap_or_default(),
+            if tool.pinned { "pinned" } else { "" }.to_string(),
+        ])
+        .style(style)
+    });
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Percentage(25),
+            Constraint::Percentage(20),
+            Constraint::Percentage(45),
+            Constraint::Percentage(10),
+        ],
+    )
+    .header(
+        Row::new(["Tool", "Installed", "Upstream", ""])
+            .style(Style::default().add_modifier(Modifier::BOLD)),
+    )
+    .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(format!(" oktofetch: {} tools ", app.config.tools.len())),
+    );
+    frame.render_stateful_widget(table, table_area, &mut app.table);
+
+    let visible = log_area.height.saturating_sub(2) as usize;
+    let items: Vec<ListItem> = app
+        .log
+        .iter()
+        .skip(app.log.len().saturating_sub(visible))
+        .map(|line| ListItem::new(line.as_str()))
+        .collect();
+    let title = match &app.busy {
+        Some(tool) => format!(" Log (updating {}) ", tool),
+        None => " Log ".to_string(),
+    };
+    frame.render_widget(
+        List::new(items).block(Block::default().borders(Borders::ALL).title(title)),
+        log_area,
+    );
+
+    frame.render_widget(
+        Paragraph::new(Line::from(
+            " j/k move  u update  p pin/unpin  d remove  r refresh  q quit",
+        )),
+        help_area,
+    );
+}
+
+async fn event_loop(
+    terminal: &mut DefaultTerminal,
+    app: &mut App,
+    tx: &UnboundedSender<Message>,
+    rx: &mut UnboundedReceiver<Message>,
+) -> Result<()> {
+    while !app.quit {
+        terminal.draw(|frame| draw(frame, app))?;
+
+        while let Ok(message) = rx.try_recv() {
+            receive(app, message, tx);
+        }
+
+        let ready = tokio::task::block_in_place(|| event::poll(TICK))?;
+        if ready
+            && let Event::Key(key) = event::read()?
+            && key.kind == KeyEventKind::Press
+            && let Some(action) = app.handle_key(key.code)
+            && let Err(e) = perform(app, action, tx)
+        {
+            app.push_log(e.to_string());
+        }
+    }
+
+    Ok(())
+}
+
+pub async fn run(config: Config) -> Result<()> {
+    let (tx, mut rx) = unbounded_channel();
+    // Asked now, while the terminal is line-based: the checks started below
+    // run `command:` sources from a config that isn't the user's own only
+    // once they agreed
+    for tool in &config.tools {
+        if let Some(program) = tool
+            .source
+            .as_deref()
+            .and_then(|s| s.strip_prefix(source::COMMAND_PREFIX))
+        {
+            let _ = prompt::trust_command("release source", program);
+        }
+    }
+    let mut app = App::new(config);
+    perform(&mut app, Action::Refresh, &tx)?;
+
+    let mut terminal = ratatui::init();
+    let result = event_loop(&mut terminal, &mut app, &tx, &mut rx).await;
+    ratatui::restore();
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Tool;
+
+    fn app_with_tools(names: &[&str]) -> App {
+        let mut config = Config::default();
+        for name in names {
+            config
+                .add_tool(Tool {
+                    name: name.to_string(),
+                    repo: format!("owner/{}", name),
+                    ..Default::default()
+                })
+                .unwrap();
+        }
+        App::new(config)
+    }
+
+    #[test]
+    fn test_navigation_stays_in_bounds() {
+        let mut app = app_with_tools(&["a", "b"]);
+        assert_eq!(app.selected_tool(), Some("a".to_string()));
+
+        app.handle_key(KeyCode::Up);
+        assert_eq!(app.selected_tool(), Some("a".to_string()));
+        app.handle_key(KeyCode::Char('j'));
+        app.handle_key(KeyCode::Down);
+        assert_eq!(app.selected_tool(), Some("b".to_string()));
+
+        let mut empty = app_with_tools(&[]);
+        assert_eq!(empty.handle_key(KeyCode::Down), None);
+        assert_eq!(empty.handle_key(KeyCode::Char('u')), None);
+    }
+
+    #[test]
+    fn test_key_actions() {
+        let mut app = app_with_tools(&["a"]);
+        assert_eq!(
+            app.handle_key(KeyCode::Char('u')),
+            Some(Action::Update("a".to_string()))
+        );
+        assert_eq!(
+            app.handle_key(KeyCode::Char('p')),
+            Some(Action::TogglePin("a".to_string()))
+        );
+        assert_eq!(app.handle_key(KeyCode::Char('r')), Some(Action::Refresh));
+
+        app.busy = Some("a".to_string());
+        assert_eq!(app.handle_key(KeyCode::Char('u')), None);
+
+        app.handle_key(KeyCode::Char('q'));
+        assert!(app.quit);
+    }
+
+    #[test]
+    fn test_remove_requires_confirmation() {
+        let mut app = app_with_tools(&["a"]);
+
+        assert_eq!(app.handle_key(KeyCode::Char('d')), None);
+        assert_eq!(app.handle_key(KeyCode::Char('n')), None);
+        assert_eq!(app.confirm_remove, None);
+
+        app.handle_key(KeyCode::Char('d'));
+        assert_eq!(
+            app.handle_key(KeyCode::Char('y')),
+            Some(Action::Remove("a".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_sync_selection_after_removal() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let install_dir = temp_dir.path().join("bin");
+        let completions_dir = temp_dir.path().join("completions");
+        let mut app = app_with_tools(&["a", "b"]);
+        app.config.settings.install_dir = install_dir.clone();
+        app.config.get_tool_mut("b").unwrap().bundle = true;
+        app.handle_key(KeyCode::Down);
+        app.upstream.insert("b".to_string(), Upstream::UpToDate);
+
+        // Installed as a bundle's wrapper, with a completion
+        let wrapper =
+            crate::binary::install_wrapper(&temp_dir.path().join("bundle/b"), &install_dir, "b")
+                .unwrap();
+        std::fs::create_dir_all(completions_dir.join("bash")).unwrap();
+        std::fs::write(completions_dir.join("bash/b"), "complete").unwrap();
+
+        let removed = app.config.get_tool("b").unwrap().clone();
+        app.config.remove_tool("b").unwrap();
+        clean_up_removed(&mut app, &removed, &completions_dir).unwrap();
+        assert_eq!(app.selected_tool(), Some("a".to_string()));
+        assert!(app.upstream.is_empty());
+        assert!(!wrapper.exists());
+        assert!(!completions_dir.join("bash/b").exists());
+
+        app.config.remove_tool("a").unwrap();
+        app.sync_selection();
+        assert_eq!(app.table.selected(), None);
+    }
+
+    #[test]
+    fn test_log_is_bounded() {
+        let mut app = app_with_tools(&[]);
+        for i in 0..LOG_LINES + 5 {
+            app.push_log(i.to_string());
+        }
+        assert_eq!(app.log.len(), LOG_LINES);
+        assert_eq!(app.log[0], "5");
+    }
+
+    #[test]
+    fn test_upstream_labels() {
+        assert_eq!(Upstream::UpToDate.label(), "up to date");
+        assert_eq!(
+            Upstream::Available("v2.0.0".to_string()).label(),
+            "update: v2.0.0"
+        );
+    }
+}
@@ -0,0 +1,214 @@
+//! Disk space taken by installed tools and oktofetch's own caches.
+
+use crate::binary;
+use crate::clean::disk_usage;
+use crate::config::Config;
+use crate::error::Result;
+use crate::human;
+use crate::tool;
+use std::fs;
+use std::path::Path;
+
+/// Space one tool takes up, in bytes.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ToolUsage {
+    pub name: String,
+    /// The installed binary, or wrapper and bundle
+    pub binary: u64,
+    /// Its `.previous` backup and retained copies in the versions directory
+    pub old_versions: u64,
+    /// Leftover downloads in the download cache
+    pub downloads: u64,
+    /// No longer in the config; only leftovers remain
+    pub unmanaged: bool,
+}
+
+impl ToolUsage {
+    pub fn total(&self) -> u64 {
+        self.binary + self.old_versions + self.downloads
+    }
+}
+
+/// Names of the subdirectories of `dir`, which may not exist.
+fn subdirectory_names(dir: &Path) -> Vec<String> {
+    fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .filter(|e| e.file_type().is_ok_and(|t| t.is_dir()))
+                .map(|e| e.file_name().to_string_lossy().to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Measures every configured tool, followed by tools that were removed from
+/// the config but still have retained versions or downloads, by name.
+pub fn measure(config: &Config, versions_dir: &Path, downloads_dir: &Path) -> Vec<ToolUsage> {
+    let mut usage: Vec<ToolUsage> = config
+        .tools
+        .iter()
+        .map(|t| {
+            let backup = binary::backup_path(&config.install_dir_for(t), t.installed_name());
+            // The installed bundle sits among the retained versions
+            let bundle = t.bundle_dir(versions_dir).map_or(0, |dir| disk_usage(&dir));
+            ToolUsage {
+                name: t.name.clone(),
+                binary: tool::installed_size(config, t).unwrap_or(0) + bundle,
+                old_versions: disk_usage(&backup) + disk_usage(&versions_dir.join(&t.name))
+                    - bundle,
+                downloads: disk_usage(&downloads_dir.join(&t.name)),
+                unmanaged: false,
+            }
+        })
+        .collect();
+
+    let mut leftovers: Vec<String> = subdirectory_names(versions_dir)
+        .into_iter()
+        .chain(subdirectory_names(downloads_dir))
+        .filter(|name| config.get_tool(name).is_none())
+        .collect();
+    leftovers.sort();
+    leftovers.dedup();
+
+    usage.extend(leftovers.into_iter().map(|name| ToolUsage {
+        old_versions: disk_usage(&versions_dir.join(&name)),
+        downloads: disk_usage(&downloads_dir.join(&name)),
+        name,
+        unmanaged: true,
+        ..Default::default()
+    }));
+    usage
+}
+
+pub fn show_usage(config: &Config) -> Result<()> {
+    let usage = measure(config, &Config::versions_dir()?, &Config::downloads_dir()?);
+    let metadata = disk_usage(&Config::cache_dir()?.join("http"));
+
+    println!(
+        "{:<24} {:>10} {:>12} {:>10} {:>10}",
+        "TOOL", "BINARY", "OLD VERSIONS", "DOWNLOADS", "TOTAL"
+    );
+    let mut totals = ToolUsage::default();
+    for entry in &usage {
+        let name = if entry.unmanaged {
+            format!("{} (removed)", entry.name)
+        } else {
+            entry.name.clone()
+        };
+        println!(
+            "{:<24} {:>10} {:>12} {:>10} {:>10}",
+            name,
+            human::format_size(entry.binary),
+            human::format_size(entry.old_versions),
+            human::format_size(entry.downloads),
+            human::format_size(entry.total())
+        );
+        totals.binary += entry.binary;
+        totals.old_versions += entry.old_versions;
+        totals.downloads += entry.downloads;
+    }
+    println!(
+        "{:<24} {:>10} {:>12} {:>10} {:>10}",
+        "Total",
+        human::format_size(totals.binary),
+        human::format_size(totals.old_versions),
+        human::format_size(totals.downloads),
+        human::format_size(totals.total())
+    );
+
+    println!("\nCached API responses: {}", human::format_size(metadata));
+    println!("Overall: {}", human::format_size(totals.total() + metadata));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Tool;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_measure() {
+        let temp_dir = TempDir::new().unwrap();
+        let install_dir = temp_dir.path().join("bin");
+        let versions_dir = temp_dir.path().join("versions");
+        let downloads_dir = temp_dir.path().join("downloads");
+        fs::create_dir_all(&install_dir).unwrap();
+        fs::write(install_dir.join("kube9"), "12345").unwrap();
+        fs::write(install_dir.join("kube9.previous"), "123").unwrap();
+        fs::create_dir_all(versions_dir.join("k9s/v1.0.0")).unwrap();
+        fs::write(versions_dir.join("k9s/v1.0.0/kube9"), "1234").unwrap();
+        fs::create_dir_all(downloads_dir.join("k9s/v2.0.0")).unwrap();
+        fs::write(downloads_dir.join("k9s/v2.0.0/k9s.tar.gz"), "12").unwrap();
+        fs::create_dir_all(versions_dir.join("gone/v0.1.0")).unwrap();
+        fs::write(versions_dir.join("gone/v0.1.0/gone"), "1").unwrap();
+
+        let mut config = Config::default();
+        config.settings.install_dir = install_dir;
+        for (name, install_as) in [("k9s", Some("kube9")), ("jq", None)] {
+            config
+                .add_tool(Tool {
+                    name: name.to_string(),
+                    repo: format!("owner/{}", name),
+                    install_as: install_as.map(str::to_string),
+                    ..Default::default()
+                })
+                .unwrap();
+        }
+
+        let usage = measure(&config, &versions_dir, &downloads_dir);
+        assert_eq!(
+            usage,
+            vec![
+                ToolUsage {
+                    name: "k9s".to_string(),
+                    binary: 5,
+                    old_versions: 7,
+                    downloads: 2,
+                    unmanaged: false,
+                },
+                ToolUsage {
+                    name: "jq".to_string(),
+                    ..Default::default()
+                },
+                ToolUsage {
+                    name: "gone".to_string(),
+                    old_versions: 1,
+                    unmanaged: true,
+                    ..Default::default()
+                },
+            ]
+        );
+        assert_eq!(usage[0].total(), 14);
+    }
+
+    #[test]
+    fn test_measure_bundle() {
+        let temp_dir = TempDir::new().unwrap();
+        let install_dir = temp_dir.path().join("bin");
+        let versions_dir = temp_dir.path().join("versions");
+        fs::create_dir_all(&install_dir).unwrap();
+        fs::write(install_dir.join("app"), "12").unwrap();
+        fs::create_dir_all(versions_dir.join("app/2.0/lib")).unwrap();
+        fs::write(versions_dir.join("app/2.0/lib/app.jar"), "12345").unwrap();
+        fs::create_dir_all(versions_dir.join("app/1.0")).unwrap();
+        fs::write(versions_dir.join("app/1.0/app.jar"), "123").unwrap();
+
+        let mut config = Config::default();
+        config.settings.install_dir = install_dir;
+        config
+            .add_tool(Tool {
+                name: "app".to_string(),
+                repo: "owner/app".to_string(),
+                version: Some("2.0".to_string()),
+                bundle: true,
+                ..Default::default()
+            })
+            .unwrap();
+
+        let usage = measure(&config, &versions_dir, temp_dir.path());
+        assert_eq!(usage[0].binary, 7);
+        assert_eq!(usage[0].old_versions, 3);
+    }
+}
@@ -0,0 +1,197 @@
+use std::cmp::Ordering;
+
+/// Splits a version or tag such as `v1.2.3-rc1` into its numeric components
+/// (`[1, 2, 3]`) and optional pre-release suffix (`rc1`). Any leading
+/// non-digit prefix like `v` or `release-` is skipped.
+fn parse(version: &str) -> (Vec<u64>, Option<&str>) {
+    let start = version
+        .find(|c: char| c.is_ascii_digit())
+        .unwrap_or(version.len());
+    let version = &version[start..];
+
+    let (core, pre) = match version.split_once(['-', '+']) {
+        Some((core, pre)) => (core, Some(pre)),
+        None => (version, None),
+    };
+
+    let numbers = core
+        .split('.')
+        .map_while(|part| part.parse::<u64>().ok())
+        .collect();
+
+    (numbers, pre.filter(|p| !p.is_empty()))
+}
+
+/// Orders two version strings numerically; a pre-release sorts before the
+/// release it precedes (`1.0.0-rc1 < 1.0.0`).
+pub fn compare(a: &str, b: &str) -> Ordering {
+    let (a_nums, a_pre) = parse(a);
+    let (b_nums, b_pre) = parse(b);
+
+    match compare_numbers(&a_nums, &b_nums) {
+        Ordering::Equal => {}
+        other => return other,
+    }
+
+    match (a_pre, b_pre) {
+        (None, None) => Ordering::Equal,
+        (None, Some(_)) => Ordering::Greater,
+        (Some(_), None) => Ordering::Less,
+        (Some(x), Some(y)) => x.cmp(y),
+    }
+}
+
+/// Orders numeric components, missing ones counting as zero.
+fn compare_numbers(a: &[u64], b: &[u64]) -> Ordering {
+    let len = a.len().max(b.len());
+    (0..len)
+        .map(|i| {
+            let x = a.get(i).copied().unwrap_or(0);
+            let y = b.get(i).copied().unwrap_or(0);
+            x.cmp(&y)
+        })
+        .find(|order| order.is_ne())
+        .unwrap_or(Ordering::Equal)
+}
+
+/// Whether `version` satisfies `requirement`: comma-separated comparisons
+/// that must all hold, e.g. `>=1.2, <2`. Each is an operator (`=`, `>`,
+/// `>=`, `<`, `<=`, `~` or `^`) and a possibly partial version; a version
+/// on its own means `^`, as in Cargo. Also as in Cargo, pre-releases only
+/// satisfy requirements that name a pre-release. An invalid requirement
+/// matches nothing.
+pub fn satisfies(version: &str, requirement: &str) -> bool {
+    let comparisons: Option<Vec<_>> = requirement.split(',').map(comparison).collect();
+    let Some(comparisons) = comparisons else {
+        return false;
+    };
+    let names_pre = comparisons
+        .iter()
+        .any(|(_, bound)| parse(bound).1.is_some());
+    (parse(version).1.is_none() || names_pre)
+        && comparisons
+            .iter()
+            .all(|(op, bound)| holds(version, op, bound))
+}
+
+/// Checks that `requirement` is one [`satisfies`] understands.
+pub fn check_requirement(requirement: &str) -> Result<(), String> {
+    match requirement
+        .split(',')
+        .find(|part| comparison(part).is_none())
+    {
+        Some(part) => Err(format!("can't parse '{}' as a comparison", part.trim())),
+        None => Ok(()),
+    }
+}
+
+/// Splits one comparison into its operator and version.
+fn comparison(part: &str) -> Option<(&str, &str)> {
+    let part = part.trim();
+    let op = [">=", "<=", "=", ">", "<", "~", "^"]
+        .into_iter()
+        .find(|op| part.starts_with(op))
+        .unwrap_or("");
+    let bound = part[op.len()..].trim();
+    let digits = bound.strip_prefix('v').unwrap_or(bound);
+    if !digits.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+    Some((if op.is_empty() { "^" } else { op }, bound))
+}
+
+fn holds(version: &str, op: &str, bound: &str) -> bool {
+    let order = compare(version, bound);
+    match op {
+        ">" => order.is_gt(),
+        ">=" => order.is_ge(),
+        "<" => order.is_lt(),
+        "<=" => order.is_le(),
+        // `=`, `~` and `^` allow everything from the bound up to the next
+        // change in the components they fix
+        _ => {
+            let (bound, _) = parse(bound);
+            let fixed = match op {
+                "=" => bound.len(),
+                "~" => bound.len().min(2),
+                _ => bound
+                    .iter()
+                    .position(|&n| n != 0)
+                    .map_or(bound.len(), |i| i + 1),
+            };
+            let mut upper = bound[..fixed].to_vec();
+            if let Some(last) = upper.last_mut() {
+                *last += 1;
+            }
+            order.is_ge() && compare_numbers(&parse(version).0, &upper).is_lt()
+        }
+    }
+}
+
+/// Picks the candidate closest to `target`: the newest one not newer than
+/// it, or failing that the oldest one newer than it.
+pub fn nearest<'a>(target: &str, candidates: &'a [String]) -> Option<&'a str> {
+    let older = candidates
+        .iter()
+        .filter(|c| compare(c, target) != Ordering::Greater)
+        .max_by(|a, b| compare(a, b));
+
+    older
+        .or_else(|| candidates.iter().min_by(|a, b| compare(a, b)))
+        .map(String::as_str)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare_versions() {
+        assert_eq!(compare("v1.2.3", "1.2.3"), Ordering::Equal);
+        assert_eq!(compare("1.10.0", "1.9.0"), Ordering::Greater);
+        assert_eq!(compare("1.2", "1.2.0"), Ordering::Equal);
+        assert_eq!(compare("v2.0.0", "v10.0.0"), Ordering::Less);
+        assert_eq!(compare("1.0.0-rc1", "1.0.0"), Ordering::Less);
+        assert_eq!(compare("release-1.0.1", "tool/v1.0.0"), Ordering::Greater);
+    }
+
+    #[test]
+    fn test_satisfies() {
+        assert!(satisfies("1.4.2", "1.2"));
+        assert!(!satisfies("2.0.0", "^1.2"));
+        assert!(!satisfies("1.1.9", "^1.2"));
+        assert!(satisfies("0.2.5", "^0.2"));
+        assert!(!satisfies("0.3.0", "^0.2"));
+        assert!(satisfies("1.2.9", "~1.2.3"));
+        assert!(!satisfies("1.3.0", "~1.2.3"));
+        assert!(satisfies("v1.2.7", "=1.2"));
+        assert!(!satisfies("1.3.0", "=1.2"));
+        assert!(satisfies("1.9.0", ">=1.2, <2"));
+        assert!(!satisfies("2.0.0-rc1", "^1"));
+        assert!(!satisfies("1.1.0-rc1", "<1.1"));
+        assert!(satisfies("1.1.0-rc2", ">=1.1.0-rc1"));
+        assert!(!satisfies("2.1.0", ">=1.2, <2"));
+        assert!(!satisfies("1.0.0", "latest"));
+    }
+
+    #[test]
+    fn test_check_requirement() {
+        assert!(check_requirement(">=1.2, <2").is_ok());
+        assert!(check_requirement("v1").is_ok());
+        let err = check_requirement("1.2, newest").unwrap_err();
+        assert!(err.contains("'newest'"));
+    }
+
+    #[test]
+    fn test_nearest_prefers_older() {
+        let candidates = vec![
+            "v1.0.0".to_string(),
+            "v1.1.0".to_string(),
+            "v1.3.0".to_string(),
+        ];
+        assert_eq!(nearest("v1.2.0", &candidates), Some("v1.1.0"));
+        assert_eq!(nearest("v0.9.0", &candidates), Some("v1.0.0"));
+        assert_eq!(nearest("v2.0.0", &candidates), Some("v1.3.0"));
+        assert_eq!(nearest("v1.0.0", &[]), None);
+    }
+}